@@ -0,0 +1,164 @@
+//! Benchmarks of common editing patterns.
+//!
+//! These run on stable Rust with [criterion]:
+//!
+//! ```text
+//! cargo bench --features test-support
+//! ```
+//!
+//! Each scenario is run against `Rope`s built with several leaf sizes, so
+//! that the effect of `RopeBuilder`'s chunk size can be compared.
+//! The shape of each tree is checked before the scenarios which use it, and
+//! `edited_lookups` checks that its trees are still shallow after editing;
+//! to print the shapes instead, see the `tree_stats` example.
+//!
+//! [criterion]: https://docs.rs/crate/criterion
+extern crate an_rope;
+#[macro_use] extern crate criterion;
+
+use an_rope::{Rope, RopeBuilder};
+use an_rope::raw::TreeStats;
+use an_rope::test_support::{random_ops, sample_text, typing_ops, Op};
+
+use criterion::{black_box, BatchSize, BenchmarkId, Criterion, Throughput};
+
+/// The leaf sizes to build each `Rope` with.
+const CHUNK_SIZES: &'static [usize] = &[64, 1024, 16 * 1024];
+
+/// The length of the document used by most scenarios.
+const DOC_LEN: usize = 1024 * 1024;
+
+/// The length of the document used by the huge file scenario.
+const HUGE_LEN: usize = 64 * 1024 * 1024;
+
+fn build(text: &str, chunk_size: usize) -> Rope {
+    let mut builder = RopeBuilder::with_capacity_hints(text.len(), chunk_size);
+    builder.push_str(text);
+    builder.finish()
+}
+
+/// Panics unless `stats` describes a balanced tree.
+fn assert_shallow(stats: &TreeStats, what: &str) {
+    // balanced trees are at most log_φ(len) + 2 deep
+    assert!( stats.depth as f64 <= (stats.len as f64).log(1.618) + 2.0
+           , "{}: {}", what, stats);
+}
+
+fn ropes(text: &str) -> Vec<(usize, Rope)> {
+    CHUNK_SIZES.iter().map(|&chunk_size| {
+        let rope = build(text, chunk_size);
+        let stats = TreeStats::of(&rope);
+        let what = format!("chunk size {}", chunk_size);
+        assert!(stats.max_leaf <= chunk_size, "{}: {}", what, stats);
+        assert_shallow(&stats, &what);
+        (chunk_size, rope)
+    }).collect()
+}
+
+fn typing(c: &mut Criterion) {
+    let text = sample_text(DOC_LEN, 0);
+    let ops = typing_ops(text.len() / 2, &sample_text(1000, 1));
+    let mut group = c.benchmark_group("typing");
+    group.throughput(Throughput::Elements(ops.len() as u64));
+    for (chunk_size, rope) in ropes(&text) {
+        group.bench_with_input( BenchmarkId::from_parameter(chunk_size)
+                              , &rope
+                              , |b, rope| b.iter(|| {
+            ops.iter().fold(rope.clone(), |rope, op| op.apply(&rope))
+        }));
+    }
+    group.finish();
+}
+
+fn random_edits(c: &mut Criterion) {
+    let text = sample_text(DOC_LEN, 0);
+    let ops = random_ops(&text, 1000, 2);
+    let mut group = c.benchmark_group("random_edits");
+    group.throughput(Throughput::Elements(ops.len() as u64));
+    for (chunk_size, rope) in ropes(&text) {
+        group.bench_with_input( BenchmarkId::from_parameter(chunk_size)
+                              , &rope
+                              , |b, rope| b.iter(|| {
+            ops.iter().fold(rope.clone(), |rope, op| op.apply(&rope))
+        }));
+    }
+    group.finish();
+}
+
+fn edited_lookups(c: &mut Criterion) {
+    // appending in small pieces is the worst case for the depth of an
+    // unbalanced tree, so this checks that rebalancing keeps the depth of
+    // the edited ropes down, and measures what each lookup costs
+    let text = sample_text(DOC_LEN, 0);
+    let appended = sample_text(64 * 1024, 5);
+    let ops = random_ops(&text, 1000, 4).into_iter()
+        .chain(appended.split(' ').map(|word| Op::Append(format!("{} ", word))))
+        .collect::<Vec<_>>();
+    let mut group = c.benchmark_group("edited_lookups");
+    for (chunk_size, rope) in ropes(&text) {
+        let edited = ops.iter().fold(rope, |rope, op| op.apply(&rope));
+        assert_shallow( &TreeStats::of(&edited)
+                      , &format!("edited, chunk size {}", chunk_size));
+        let offsets = (0..1000).map(|i| i * edited.len() / 1000)
+                               .collect::<Vec<_>>();
+        group.throughput(Throughput::Elements(offsets.len() as u64));
+        group.bench_with_input( BenchmarkId::from_parameter(chunk_size)
+                              , &edited
+                              , |b, rope| b.iter(|| {
+            offsets.iter().map(|&i| rope.line_of_offset(i)).sum::<usize>()
+        }));
+    }
+    group.finish();
+}
+
+fn huge_file_load(c: &mut Criterion) {
+    let text = sample_text(HUGE_LEN, 3);
+    let mut group = c.benchmark_group("huge_file_load");
+    group.sample_size(10).throughput(Throughput::Bytes(text.len() as u64));
+    for &chunk_size in CHUNK_SIZES {
+        group.bench_with_input( BenchmarkId::new("builder", chunk_size)
+                              , &chunk_size
+                              , |b, &chunk_size| {
+            b.iter(|| build(black_box(&text), chunk_size))
+        });
+    }
+    group.bench_function("from_string", |b| b.iter_batched(
+        || text.clone(), Rope::from, BatchSize::LargeInput));
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let text = sample_text(DOC_LEN, 0);
+    let mut group = c.benchmark_group("search");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for (chunk_size, rope) in ropes(&text) {
+        // the needle never occurs, so the whole buffer is searched
+        group.bench_with_input( BenchmarkId::new("rfind_str", chunk_size)
+                              , &rope
+                              , |b, rope| {
+            b.iter(|| rope.rfind_str(rope.len(), black_box("needle")))
+        });
+        group.bench_with_input( BenchmarkId::new("split_terminator", chunk_size)
+                              , &rope
+                              , |b, rope| {
+            b.iter(|| rope.split_terminator(black_box("rope")).count())
+        });
+    }
+    group.finish();
+}
+
+fn lines(c: &mut Criterion) {
+    let text = sample_text(DOC_LEN, 0);
+    let mut group = c.benchmark_group("lines");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for (chunk_size, rope) in ropes(&text) {
+        group.bench_with_input( BenchmarkId::from_parameter(chunk_size)
+                              , &rope
+                              , |b, rope| b.iter(|| rope.lines().count()));
+    }
+    group.finish();
+}
+
+criterion_group!( benches, typing, random_edits, edited_lookups
+                , huge_file_load, search, lines);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+//! Prints the shape of the tree built for each file named on the command
+//! line, with each of several leaf sizes.
+//!
+//! ```text
+//! cargo run --example tree_stats -- src/lib.rs
+//! ```
+extern crate an_rope;
+
+use an_rope::RopeBuilder;
+use an_rope::raw::TreeStats;
+
+use std::env;
+use std::fs;
+use std::process;
+
+/// The leaf sizes to build each `Rope` with.
+const CHUNK_SIZES: &'static [usize] = &[64, 1024, 16 * 1024];
+
+fn main() {
+    let paths = env::args().skip(1).collect::<Vec<_>>();
+    if paths.is_empty() {
+        eprintln!("usage: tree_stats FILE...");
+        process::exit(2);
+    }
+    for path in &paths {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text
+          , Err(e) => {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
+            }
+        };
+        println!("{}:", path);
+        for &chunk_size in CHUNK_SIZES {
+            let mut builder =
+                RopeBuilder::with_capacity_hints(text.len(), chunk_size);
+            builder.push_str(&text);
+            let rope = builder.finish();
+            println!( "  chunk size {}: {}"
+                    , chunk_size, TreeStats::of(&rope));
+        }
+    }
+}
@@ -182,3 +182,28 @@ insert_benches! {
         at_3quarter: 0.75,
         at_end: 1
 }
+
+macro_rules! slice_benches {
+    ( leaves: $leaves:expr, $($name:ident: $range:expr),* ) => {
+        mod slice {
+            $(
+                #[bench]
+                fn $name(b: &mut ::bench::test::Bencher) {
+                    let rope = (0..$leaves).map(|_| String::from("abcdefgh"))
+                                           .collect::<::Rope>();
+                    let len = rope.len();
+                    let range = $range(len);
+                    b.iter(|| rope.slice(range.clone()).len())
+                }
+            )*
+        }
+    }
+}
+#[cfg(all( test, feature = "unstable") )]
+slice_benches! {
+    leaves: 10_000,
+        within_leaf: |_| 40_001..40_005,
+        across_two_leaves: |_| 40_006..40_010,
+        across_all_leaves: |len| 1..len - 1,
+        whole_rope: |len| 0..len
+}
@@ -0,0 +1,202 @@
+//! Building `Rope`s incrementally.
+//!
+//! A [`RopeBuilder`] accumulates text into fixed-size chunks, and builds a
+//! balanced tree from them once all the text has been pushed. Loaders which
+//! know roughly how large a document will be can choose the leaf size up
+//! front, rather than building a `Rope` one append at a time and paying for
+//! a degenerate tree.
+//!
+//! [`RopeBuilder`]: struct.RopeBuilder.html
+
+use std::fmt;
+use std::iter;
+
+use super::Rope;
+use super::internals::{Node, NodeLink};
+
+/// The leaf size used by `RopeBuilder::new`, in bytes.
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+
+/// Builds a `Rope` from text pushed in pieces.
+///
+/// Text is collected into leaves of at most `chunk_size` bytes (a leaf may
+/// be longer only if it holds a single `char` longer than `chunk_size`).
+/// As when converting a `String` into a `Rope`, leaves are also split after
+/// each newline.
+///
+/// # Examples
+///
+/// ```
+/// use an_rope::RopeBuilder;
+/// let mut builder = RopeBuilder::with_capacity_hints(26, 8);
+/// builder.push_str("abcdefghijklm");
+/// builder.push_str("nopqrstuvwxyz");
+/// let rope = builder.finish();
+/// assert_eq!(&rope, "abcdefghijklmnopqrstuvwxyz");
+/// assert_eq!( rope.strings().collect::<Vec<_>>()
+///           , vec!["abcdefgh", "ijklmnop", "qrstuvwx", "yz"]);
+/// ```
+#[derive(Debug)]
+pub struct RopeBuilder { leaves: Vec<NodeLink>
+                       , buf: String
+                       , chunk_size: usize
+                       }
+
+impl RopeBuilder {
+    /// Returns a new `RopeBuilder` with the default chunk size.
+    #[inline]
+    pub fn new() -> Self {
+        RopeBuilder::with_capacity_hints(0, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Returns a new `RopeBuilder` which will build leaves of `chunk_size`
+    /// bytes, with room for a `Rope` of `total_len` bytes.
+    ///
+    /// `total_len` is only a hint, used to allocate space for the leaves up
+    /// front; pushing more or less text than `total_len` is fine.
+    ///
+    /// # Panics
+    /// If `chunk_size` is 0.
+    pub fn with_capacity_hints(total_len: usize, chunk_size: usize) -> Self {
+        assert!( chunk_size > 0
+               , "RopeBuilder::with_capacity_hints: chunk size must be > 0");
+        let leaves = (total_len + chunk_size - 1) / chunk_size;
+        RopeBuilder { leaves: Vec::with_capacity(leaves)
+                    , buf: String::with_capacity(chunk_size)
+                    , chunk_size: chunk_size
+                    }
+    }
+
+    /// Push the text in `s` onto the end of the `Rope` being built.
+    pub fn push_str(&mut self, mut s: &str) {
+        while !s.is_empty() {
+            let room = self.chunk_size - self.buf.len();
+            if s.len() <= room {
+                self.buf.push_str(s);
+                return
+            }
+            // find the longest prefix of `s` that fits in the current chunk
+            // without splitting a character
+            let mut i = room;
+            while !s.is_char_boundary(i) { i -= 1; }
+            if i == 0 && self.buf.is_empty() {
+                // the first char is longer than a whole chunk; it gets a
+                // leaf of its own
+                i = s.chars().next().map(char::len_utf8).unwrap_or(0);
+            }
+            self.buf.push_str(&s[..i]);
+            self.flush();
+            s = &s[i..];
+        }
+    }
+
+    /// Push `ch` onto the end of the `Rope` being built.
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        let mut bytes = [0; 4];
+        self.push_str(ch.encode_utf8(&mut bytes))
+    }
+
+    /// Finish building, returning the `Rope`.
+    ///
+    /// # Time complexity
+    /// O(_n_) in the number of leaves
+    pub fn finish(mut self) -> Rope {
+        self.flush();
+        Rope::from(Node::from_nodes(self.leaves))
+    }
+
+    /// Move the current chunk into a new leaf.
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            let chunk = ::std::mem::replace( &mut self.buf
+                                           , String::with_capacity(
+                                                self.chunk_size));
+            self.leaves.push(NodeLink::from(chunk));
+        }
+    }
+}
+
+impl Default for RopeBuilder {
+    #[inline] fn default() -> Self { RopeBuilder::new() }
+}
+
+impl fmt::Write for RopeBuilder {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl iter::Extend<char> for RopeBuilder {
+    fn extend<I>(&mut self, iter: I)
+    where I: IntoIterator<Item=char> {
+        for ch in iter { self.push(ch) }
+    }
+}
+
+impl<'a> iter::Extend<&'a str> for RopeBuilder {
+    fn extend<I>(&mut self, iter: I)
+    where I: IntoIterator<Item=&'a str> {
+        for s in iter { self.push_str(s) }
+    }
+}
+
+impl iter::Extend<String> for RopeBuilder {
+    fn extend<I>(&mut self, iter: I)
+    where I: IntoIterator<Item=String> {
+        for s in iter { self.push_str(&s) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RopeBuilder;
+
+    #[test]
+    fn chunks_respect_char_boundaries() {
+        let mut builder = RopeBuilder::with_capacity_hints(0, 3);
+        builder.push_str("a\u{3c0}\u{3c0}b");
+        let rope = builder.finish();
+        assert_eq!(&rope, "a\u{3c0}\u{3c0}b");
+        assert_eq!( rope.strings().collect::<Vec<_>>()
+                  , vec!["a\u{3c0}", "\u{3c0}b"]);
+    }
+
+    #[test]
+    fn char_longer_than_chunk() {
+        let mut builder = RopeBuilder::with_capacity_hints(0, 2);
+        builder.push_str("\u{1f192}\u{1f195}a");
+        let rope = builder.finish();
+        assert_eq!( rope.strings().collect::<Vec<_>>()
+                  , vec!["\u{1f192}", "\u{1f195}", "a"]);
+    }
+
+    #[test]
+    fn leaves_split_after_newlines() {
+        let mut builder = RopeBuilder::with_capacity_hints(0, 8);
+        builder.push_str("ab\ncd\nefghijk");
+        let rope = builder.finish();
+        #[cfg(not(feature = "line-leaves"))]
+        let expected = vec!["ab\n", "cd\n", "ef", "ghijk"];
+        // leaves which index their lines hold as many lines as fit
+        #[cfg(feature = "line-leaves")]
+        let expected = vec!["ab\ncd\nef", "ghijk"];
+        assert_eq!(rope.strings().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn balanced() {
+        let mut builder = RopeBuilder::with_capacity_hints(4096, 4);
+        for _ in 0..1024 { builder.push_str("abcd"); }
+        let rope = builder.finish();
+        assert_eq!(rope.len(), 4096);
+        assert_eq!(rope.root.depth(), 10);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(RopeBuilder::new().finish().is_empty());
+    }
+}
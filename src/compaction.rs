@@ -0,0 +1,196 @@
+//! Compacting fragmented `Rope`s.
+//!
+//! Most edits split a leaf of the `Rope` they are applied to, so a buffer
+//! which has been edited many times ends up with many short leaves. Each of
+//! them costs a node, and iterating over the `Rope` visits every one, so a
+//! long editing session gradually makes the buffer slower to read.
+//! [`Rope::compact`] merges runs of short leaves back into full-sized ones.
+//!
+//! A program which does not want to decide when to compact can instead call
+//! [`Rope::compacted`] after each edit, with the mean leaf length below which
+//! it considers a `Rope` fragmented. Whether a `Rope` is fragmented is cached
+//! in each node, so checking it only visits the nodes an edit created, and a
+//! `Rope` which is not fragmented is returned unchanged, sharing all of its
+//! nodes.
+//!
+//! Compaction is never done implicitly: building a `Rope`, including with
+//! `append`, `prepend` and `+`, never copies the leaves it is built from, so
+//! `Rope`s keep sharing their nodes with the versions they were edited from,
+//! such as those held by a `History`. A program which builds a `Rope` from
+//! many short pieces can instead use [`Rope::append_compact`], which merges
+//! short leaves where the two `Rope`s meet.
+//!
+//! [`Rope::compact`]: ../struct.Rope.html#method.compact
+//! [`Rope::compacted`]: ../struct.Rope.html#method.compacted
+//! [`Rope::append_compact`]: ../struct.Rope.html#method.append_compact
+use super::Rope;
+use internals::Node;
+
+impl Rope {
+    /// Returns the number of leaves in this `Rope`'s tree.
+    ///
+    /// Together with the length of the `Rope`, this measures how fragmented
+    /// it is.
+    ///
+    /// # Time complexity
+    /// O(1) if the `Rope` has been measured before; otherwise, O(_n_) in
+    /// the number of nodes which have not.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("abcdef");
+    /// assert_eq!(rope.leaf_count(), 1);
+    /// assert_eq!(rope.insert_str(3, "\u{3c0}").leaf_count(), 3);
+    /// ```
+    #[inline]
+    pub fn leaf_count(&self) -> usize { self.root.leaf_count() }
+
+    /// Returns a copy of this `Rope` with runs of short adjacent leaves
+    /// merged into leaves of up to `DEFAULT_CHUNK_SIZE` bytes.
+    ///
+    /// Leaves which are already at least that long are shared with this
+    /// `Rope`, rather than copied.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = (0..10).fold(Rope::from("0123456789"), |rope, i| {
+    ///     rope.insert_str(i * 2, "x")
+    /// });
+    /// assert!(rope.leaf_count() > 1);
+    /// let compacted = rope.compact();
+    /// assert_eq!(compacted.leaf_count(), 1);
+    /// assert_eq!(compacted, rope);
+    /// ```
+    #[inline]
+    pub fn compact(&self) -> Rope { Rope { root: self.root.compact() } }
+
+    /// Returns this `Rope`, compacted as by `compact()` if its leaves are
+    /// shorter than `threshold` bytes on average.
+    ///
+    /// This is meant to be called after each edit, so that a buffer is
+    /// compacted once it becomes fragmented. A `Rope` which is not
+    /// fragmented is returned unchanged, sharing all of its nodes. Since the
+    /// leaves of a compacted `Rope` are only guaranteed to be a third of
+    /// `DEFAULT_CHUNK_SIZE` long on average, a `threshold` greater than
+    /// about a quarter of it may compact after every edit.
+    ///
+    /// # Time complexity
+    /// O(_n_) if the `Rope` is compacted; otherwise, O(1) if it has been
+    /// measured before, or O(_m_) in the number of nodes which have not
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let typed = |rope: Rope, threshold| (0..100).fold(rope, |rope, i| {
+    ///     rope.insert_str(i * 20, "x").compacted(threshold)
+    /// });
+    /// let rope = Rope::from("-".repeat(2000));
+    /// assert_eq!(typed(rope.clone(), 0).leaf_count(), 200);
+    ///
+    /// let typed = typed(rope, 64);
+    /// assert!(typed.len() / typed.leaf_count() >= 64);
+    /// ```
+    pub fn compacted(&self, threshold: usize) -> Rope {
+        if self.root.is_fragmented(threshold) { self.compact() }
+        else { self.clone() }
+    }
+
+    /// Appends `other` to the end of this `Rope`, merging the leaves where
+    /// they meet if they are together no longer than `DEFAULT_CHUNK_SIZE`.
+    ///
+    /// Unlike `append`, this copies the text of the merged leaves, so they
+    /// are no longer shared with `self` or `other`; in exchange, a `Rope`
+    /// built by appending short strings does not fragment into many tiny
+    /// leaves.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let typed = "typed".chars().fold(Rope::new(), |rope, c| {
+    ///     rope.append_compact(&Rope::from(c.to_string()))
+    /// });
+    /// assert_eq!(&typed, "typed");
+    /// assert_eq!(typed.leaf_count(), 1);
+    /// ```
+    pub fn append_compact(&self, other: &Rope) -> Rope {
+        Rope::from(Node::concat(&self.root, &other.root))
+    }
+
+    /// Prepends `other` to the beginning of this `Rope`, merging the leaves
+    /// where they meet as by `append_compact`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("b").prepend_compact(&Rope::from("a"));
+    /// assert_eq!(&rope, "ab");
+    /// assert_eq!(rope.leaf_count(), 1);
+    /// ```
+    pub fn prepend_compact(&self, other: &Rope) -> Rope {
+        Rope::from(Node::concat(&other.root, &self.root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{Rope, DEFAULT_CHUNK_SIZE};
+    use ::test::leaves;
+
+    #[test]
+    fn compact_shares_long_leaves() {
+        let long = "x".repeat(DEFAULT_CHUNK_SIZE);
+        let rope = leaves(&["a", "", &long, "b", "c"]);
+        let compacted = rope.compact();
+        assert_eq!(compacted, rope);
+        assert_eq!( compacted.leaf_ranges().map(|(_, s)| s.len())
+                              .collect::<Vec<_>>()
+                  , vec![1, DEFAULT_CHUNK_SIZE, 2]);
+        assert!(compacted.root.leaf_at(1).0.as_ptr()
+                == rope.root.leaf_at(1).0.as_ptr());
+    }
+
+    #[test]
+    fn fragmented_below_mean_leaf() {
+        let rope = leaves(&["ab", "cd", "ef"]);
+        assert!(rope.root.is_fragmented(3));
+        assert!(!rope.root.is_fragmented(2));
+        assert!(!Rope::from("a").root.is_fragmented(64));
+        assert!(!rope.compact().root.is_fragmented(64));
+    }
+
+    #[test]
+    fn append_compact_merges_short_leaves() {
+        let long = "x".repeat(DEFAULT_CHUNK_SIZE);
+        let r = Rope::from(&long[..]).append_compact(&Rope::from("y"));
+        assert_eq!(r.leaf_count(), 2);
+        // a short leaf is merged with the adjacent leaf of a branch
+        let r = r.append_compact(&Rope::from("z"));
+        assert_eq!( r.leaf_ranges().last()
+                  , Some((DEFAULT_CHUNK_SIZE..r.len(), "yz")));
+        let r = Rope::from("b") + Rope::from(&long[..]);
+        let r = r.prepend_compact(&Rope::from("a"));
+        assert_eq!(r.leaf_ranges().next(), Some((0..2, "ab")));
+        assert_eq!(r.root.depth(), 1);
+        // `append` keeps the leaves separate
+        assert_eq!((Rope::from("a") + Rope::from("b")).leaf_count(), 2);
+    }
+
+    #[test]
+    fn compacted_shares_unfragmented_ropes() {
+        let rope = leaves(&["ab", "cd", "ef"]);
+        assert!(rope.compacted(2).root.ptr_eq(&rope.root));
+        assert_eq!(rope.compacted(3).leaf_count(), 1);
+        assert_eq!(rope.compacted(3), rope);
+    }
+}
@@ -0,0 +1,137 @@
+//! Cursors for moving through a `Rope` by grapheme cluster.
+//!
+//! A [`GraphemeCursor`] holds a byte offset into a `Rope`, and can step it
+//! forwards and backwards by one grapheme cluster at a time. Each step only
+//! examines the leaves around the cursor, so moving a cursor does not require
+//! iterating over the graphemes from the start of the line or the `Rope`.
+//!
+//! [`GraphemeCursor`]: struct.GraphemeCursor.html
+
+use super::Rope;
+
+/// A cursor which moves through a `Rope` one grapheme cluster at a time.
+///
+/// # Examples
+///
+/// ```
+/// use an_rope::Rope;
+/// let rope = Rope::from("a\u{310}e\u{301}") + Rope::from("\r\nx");
+/// let mut cursor = rope.grapheme_cursor(0);
+/// assert_eq!(cursor.next_boundary(), Some(3));
+/// assert_eq!(cursor.next_boundary(), Some(6));
+/// assert_eq!(cursor.peek_next(), Some(8));
+/// assert_eq!(cursor.next_boundary(), Some(8));
+/// assert_eq!(cursor.prev_boundary(), Some(6));
+/// assert_eq!(cursor.offset(), 6);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GraphemeCursor<'a> { rope: &'a Rope
+                              , offset: usize
+                              }
+
+impl<'a> GraphemeCursor<'a> {
+    /// Returns a new cursor at byte index `offset` in `rope`.
+    ///
+    /// `offset` need not fall on a grapheme boundary; the first step in
+    /// either direction will move the cursor to the nearest one.
+    ///
+    /// # Panics
+    /// If `offset` is greater than the length of `rope`, or if `offset` does
+    /// not fall on a character boundary.
+    pub fn new(rope: &'a Rope, offset: usize) -> Self {
+        assert!( offset <= rope.len()
+               , "GraphemeCursor::new: offset {} was > length {}"
+               , offset, rope.len());
+        let (leaf, leaf_start) = rope.root.leaf_at(offset);
+        assert!( leaf.is_char_boundary(offset - leaf_start)
+               , "GraphemeCursor::new: offset {} is not a char boundary"
+               , offset);
+        GraphemeCursor { rope: rope, offset: offset }
+    }
+
+    /// Returns the byte index of this cursor in its `Rope`.
+    #[inline] pub fn offset(&self) -> usize { self.offset }
+
+    /// Returns the `Rope` this cursor moves through.
+    #[inline] pub fn rope(&self) -> &'a Rope { self.rope }
+
+    /// Moves this cursor to byte index `offset`.
+    ///
+    /// # Panics
+    /// Under the same conditions as `GraphemeCursor::new`.
+    #[inline]
+    pub fn set_offset(&mut self, offset: usize) {
+        *self = GraphemeCursor::new(self.rope, offset);
+    }
+
+    /// Returns the byte index of the next grapheme boundary after this
+    /// cursor, without moving it.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the next boundary
+    /// - `None` if the cursor is at the end of the `Rope`
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme cluster)
+    #[inline]
+    pub fn peek_next(&self) -> Option<usize> {
+        self.rope.next_grapheme_boundary(self.offset)
+    }
+
+    /// Returns the byte index of the previous grapheme boundary before this
+    /// cursor, without moving it.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the previous boundary
+    /// - `None` if the cursor is at the start of the `Rope`
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme cluster)
+    #[inline]
+    pub fn peek_prev(&self) -> Option<usize> {
+        self.rope.prev_grapheme_boundary(self.offset)
+    }
+
+    /// Moves this cursor forward to the next grapheme boundary, returning
+    /// its byte index.
+    ///
+    /// If the cursor is already at the end of the `Rope`, it does not move,
+    /// and `None` is returned.
+    pub fn next_boundary(&mut self) -> Option<usize> {
+        let next = self.peek_next();
+        if let Some(i) = next { self.offset = i; }
+        next
+    }
+
+    /// Moves this cursor back to the previous grapheme boundary, returning
+    /// its byte index.
+    ///
+    /// If the cursor is already at the start of the `Rope`, it does not
+    /// move, and `None` is returned.
+    pub fn prev_boundary(&mut self) -> Option<usize> {
+        let prev = self.peek_prev();
+        if let Some(i) = prev { self.offset = i; }
+        prev
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+
+    #[test]
+    fn from_inside_cluster_across_leaves() {
+        let rope = Rope::from("ae") + Rope::from("\u{301}\u{301}b");
+        let mut cursor = rope.grapheme_cursor(4);
+        assert_eq!(cursor.peek_prev(), Some(1));
+        assert_eq!(cursor.next_boundary(), Some(6));
+        assert_eq!(cursor.prev_boundary(), Some(1));
+        assert_eq!(cursor.prev_boundary(), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "offset 2 is not a char boundary")]
+    fn not_char_boundary() {
+        Rope::from("a\u{3c0}").grapheme_cursor(2);
+    }
+}
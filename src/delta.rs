@@ -0,0 +1,198 @@
+//! Self-contained edits to a `Rope`.
+//!
+//! An [`EditInfo`] records where an edit happened, but not the text it
+//! inserted, so it can't be applied again. A [`Delta`] carries that text as
+//! well, so a sequence of them, applied in order to the `Rope` they were
+//! recorded from, reproduces the final `Rope` exactly. This makes them
+//! suitable for persisting edit streams, e.g. for session replay or crash
+//! recovery.
+//!
+//! When built with the `serde` feature, `Delta` and `EditInfo` implement
+//! `Serialize` and `Deserialize`. When built with the `arbitrary` feature,
+//! they implement `arbitrary::Arbitrary`, so edit streams can be generated
+//! by fuzzers and property tests. An arbitrary `Delta` knows nothing of the
+//! `Rope` it will be applied to, so it should be applied with
+//! [`Delta::try_apply`], and discarded if that fails.
+//!
+//! [`EditInfo`]: struct.EditInfo.html
+//! [`Delta`]: struct.Delta.html
+//! [`Delta::try_apply`]: struct.Delta.html#method.try_apply
+use std::ops;
+
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+#[cfg(feature = "arbitrary")] use arbitrary::{Arbitrary, Unstructured};
+
+use super::{EditInfo, Error, Rope};
+
+/// The replacement of a byte range of a `Rope` with some text.
+///
+/// Insertions replace an empty range, and deletions insert no text.
+///
+/// # Examples
+/// ```
+/// use an_rope::{Delta, Rope};
+/// let rope = Rope::from("hello world");
+/// let deltas = [Delta::new(6..11, "there"), Delta::insert(0, "oh, ")];
+/// let edited = deltas.iter().fold(rope, |rope, delta| delta.apply(&rope));
+/// assert_eq!(&edited, "oh, hello there");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Delta { range: ops::Range<usize>
+                 , text: String
+                 }
+
+impl Delta {
+    /// Returns a new `Delta` replacing the byte range `range` with `text`.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than its end.
+    pub fn new<S>(range: ops::Range<usize>, text: S) -> Self
+    where S: Into<String> {
+        range_len!("Delta::new", range.start, range.end);
+        Delta { range: range, text: text.into() }
+    }
+
+    /// Returns a new `Delta` inserting `text` at byte index `i`.
+    #[inline]
+    pub fn insert<S>(i: usize, text: S) -> Self
+    where S: Into<String> {
+        Delta::new(i..i, text)
+    }
+
+    /// Returns a new `Delta` deleting the byte range `range`.
+    #[inline]
+    pub fn delete(range: ops::Range<usize>) -> Self { Delta::new(range, "") }
+
+    /// Returns the byte range of the old text which is replaced.
+    #[inline]
+    pub fn range(&self) -> ops::Range<usize> { self.range.clone() }
+
+    /// Returns the text which replaces it.
+    #[inline]
+    pub fn text(&self) -> &str { &self.text }
+
+    /// Returns an `EditInfo` describing this `Delta`, without its text.
+    #[inline]
+    pub fn info(&self) -> EditInfo {
+        EditInfo::new(self.range(), self.text.len())
+    }
+
+    /// Returns the result of applying this `Delta` to `rope`.
+    ///
+    /// # Panics
+    /// As for `Rope::edit`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn apply(&self, rope: &Rope) -> Rope {
+        rope.edit(self.range(), &self.text)
+    }
+
+    /// Returns the result of applying this `Delta` to `rope`, or an `Error`
+    /// if its range can't be used to slice `rope`.
+    ///
+    /// # Errors
+    /// As for `Rope::try_edit`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Delta, Error, Rope};
+    /// let rope = Rope::from("abc");
+    /// assert_eq!(&Delta::delete(1..2).try_apply(&rope).unwrap(), "ac");
+    /// assert_eq!( Delta::insert(4, "d").try_apply(&rope)
+    ///           , Err(Error::IndexOutOfBounds { index: 4, len: 3 }));
+    /// ```
+    #[inline]
+    pub fn try_apply(&self, rope: &Rope) -> Result<Rope, Error> {
+        rope.try_edit(self.range(), &self.text)
+    }
+}
+
+/// Returns an arbitrary range, whose start is not after its end.
+#[cfg(feature = "arbitrary")]
+fn arbitrary_range(u: &mut Unstructured)
+                   -> arbitrary::Result<ops::Range<usize>> {
+    let (a, b) = (usize::arbitrary(u)?, usize::arbitrary(u)?);
+    Ok(if a <= b { a..b } else { b..a })
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Delta {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Delta { range: arbitrary_range(u)?, text: String::arbitrary(u)? })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for EditInfo {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(EditInfo::new(arbitrary_range(u)?, usize::arbitrary(u)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::Delta;
+
+    /// The deltas recorded while editing `"hello world"` into
+    /// `"oh, hello there\n"`.
+    fn recorded() -> Vec<Delta> {
+        vec![ Delta::new(6..11, "there"), Delta::insert(0, "oh, ")
+            , Delta::insert(15, "!\n"), Delta::delete(15..16) ]
+    }
+
+    #[test]
+    fn replaying_reproduces_rope() {
+        let mut rope = Rope::from("hello world");
+        for delta in recorded() {
+            let edited = delta.apply(&rope);
+            assert_eq!( edited.len() as isize
+                      , rope.len() as isize + delta.info().delta());
+            rope = edited;
+        }
+        assert_eq!(&rope, "oh, hello there\n");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        use serde_json;
+        let deltas = recorded();
+        let json = serde_json::to_string(&deltas).unwrap();
+        let read = serde_json::from_str::<Vec<Delta>>(&json).unwrap();
+        assert_eq!(read, deltas);
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary_streams_replay() {
+        use arbitrary::{Arbitrary, Unstructured};
+        let data = (0..4096u32).map(|i| (i * 7919 % 251) as u8)
+                               .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&data);
+        let mut rope = Rope::from("some text\nto edit");
+        let start = rope.clone();
+        let mut applied = Vec::new();
+        while let Ok(delta) = Delta::arbitrary(&mut u) {
+            // most arbitrary ranges are out of bounds, so fit them to the
+            // `Rope` before trying them
+            let len = rope.len() + 1;
+            let range = delta.range().start % len..delta.range().end % len;
+            let delta = Delta { range: range, text: delta.text };
+            if let Ok(edited) = delta.try_apply(&rope) {
+                rope = edited;
+                applied.push(delta);
+            }
+            if u.is_empty() { break }
+        }
+        assert!(!applied.is_empty());
+        let replayed = applied.iter().fold(start, |r, d| d.apply(&r));
+        assert_eq!(replayed, rope);
+    }
+}
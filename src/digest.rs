@@ -0,0 +1,199 @@
+//! Digests of the chunks of a `Rope`, for synchronizing copies of it.
+//!
+//! Two processes which each hold a copy of a buffer, such as an editor's
+//! UI and a headless backend, can bring one copy up to date by sending only
+//! the parts of it which differ, as rsync does with files. Each side splits
+//! its copy into chunks with [`Rope::chunk_digests`], the digests are
+//! exchanged, and only the chunks whose digests the other side lacks are
+//! sent.
+//!
+//! Chunks are defined by their content, as in rsync and other
+//! deduplicating tools: a chunk ends after a byte where a rolling hash of
+//! the bytes before it matches a mask. Inserting or deleting text therefore
+//! only changes the chunks around the edit, rather than moving the
+//! boundaries of every chunk after it, as cutting chunks at fixed offsets
+//! would. Chunks depend only on the text, not on how either `Rope`'s text
+//! is divided into leaves, so equal text always has equal digests.
+//!
+//! The rolling hash is a [Gear] hash, and the digest of each chunk is its
+//! 64-bit [FNV-1a] hash; both are the same on every platform and in every
+//! version of this crate. They are not cryptographic, so they should not be
+//! relied on to detect deliberate tampering.
+//!
+//! [`Rope::chunk_digests`]: ../struct.Rope.html#method.chunk_digests
+//! [Gear]: https://www.usenix.org/conference/atc16/technical-sessions/presentation/xia
+//! [FNV-1a]: http://www.isthe.com/chongo/tech/comp/fnv/
+use std::cmp;
+use std::mem;
+use std::ops;
+
+use super::Rope;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Multiplies each byte into the value it adds to the rolling hash, in
+/// place of the table of random values a Gear hash usually uses.
+const GEAR_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Returns the value which the byte `b` adds to the rolling hash.
+#[inline]
+fn gear(b: u8) -> u64 { (b as u64 + 1).wrapping_mul(GEAR_MULTIPLIER) }
+
+/// The digest of a chunk of a `Rope`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkDigest { /// The byte offset at which the chunk starts.
+                         pub offset: usize
+                       , /// The length of the chunk, in bytes.
+                         pub len: usize
+                       , /// The FNV-1a hash of the bytes of the chunk.
+                         pub hash: u64
+                       }
+
+impl ChunkDigest {
+    /// Returns the byte range of the chunk.
+    #[inline]
+    pub fn range(&self) -> ops::Range<usize> {
+        self.offset..self.offset + self.len
+    }
+}
+
+impl Rope {
+    /// Returns the digests of the content-defined chunks which make up this
+    /// `Rope`, in order.
+    ///
+    /// Chunks are about `chunk_size` bytes long on average, rounded down to
+    /// a power of two, but never shorter than a quarter of `chunk_size`,
+    /// except at the end of the `Rope`, or longer than four times it. An
+    /// empty `Rope` has no chunks. Chunks are cut between bytes, so a chunk
+    /// may begin or end part way through a `char`.
+    ///
+    /// Since an edit moves the chunks after it, the digests of two copies
+    /// should be compared by their lengths and hashes, not their offsets.
+    ///
+    /// # Panics
+    /// If `chunk_size` is 0.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// use std::collections::HashSet;
+    /// let ours = (0..400).map(|i| format!("line {}\n", i * i))
+    ///                    .collect::<Rope>();
+    /// let theirs = ours.insert_str(0, "a new first line\n");
+    /// let known = theirs.chunk_digests(64).iter()
+    ///                   .map(|d| (d.len, d.hash))
+    ///                   .collect::<HashSet<_>>();
+    /// let ours = ours.chunk_digests(64);
+    /// let missing = ours.iter().filter(|d| !known.contains(&(d.len, d.hash)))
+    ///                   .count();
+    /// // only the chunk which the new line was inserted into differs
+    /// assert_eq!(missing, 1);
+    /// assert!(ours.len() > 20);
+    /// ```
+    pub fn chunk_digests(&self, chunk_size: usize) -> Vec<ChunkDigest> {
+        assert!( chunk_size > 0
+               , "Rope::chunk_digests: chunk size must be > 0");
+        // a chunk may end after a byte where the top `bits` bits of the
+        // rolling hash are 0, which happens every 2^`bits` bytes on average
+        let bits = (mem::size_of::<usize>() * 8) as u32 - 1
+                 - chunk_size.leading_zeros();
+        let min = cmp::max(chunk_size / 4, 1);
+        let max = chunk_size.saturating_mul(4);
+        let mut digests = Vec::new();
+        let mut chunk = ChunkDigest { offset: 0, len: 0
+                                    , hash: FNV_OFFSET_BASIS };
+        let mut rolling: u64 = 0;
+        for leaf in self.strings() {
+            for &b in leaf.as_bytes() {
+                chunk.hash = (chunk.hash ^ b as u64).wrapping_mul(FNV_PRIME);
+                chunk.len += 1;
+                // each byte is shifted out of the hash 64 bytes later
+                rolling = (rolling << 1).wrapping_add(gear(b));
+                let boundary = bits == 0 || rolling >> (64 - bits) == 0;
+                if chunk.len >= max || chunk.len >= min && boundary {
+                    digests.push(chunk);
+                    chunk = ChunkDigest { offset: chunk.offset + chunk.len
+                                        , len: 0
+                                        , hash: FNV_OFFSET_BASIS };
+                }
+            }
+        }
+        if chunk.len > 0 { digests.push(chunk); }
+        digests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use ::test::leaves;
+    use super::ChunkDigest;
+
+    #[test]
+    fn independent_of_leaves() {
+        let text = "one two\nthree \u{3c0} four\n";
+        let split = leaves(&["one t", "wo\nthree \u{3c0}", "", " four\n"]);
+        for size in 1..text.len() + 2 {
+            assert_eq!( split.chunk_digests(size)
+                      , Rope::from(text).chunk_digests(size));
+        }
+        assert!(Rope::new().chunk_digests(4).is_empty());
+    }
+
+    #[test]
+    fn fnv1a_test_vectors() {
+        assert_eq!( Rope::from("a").chunk_digests(1)
+                  , vec![ChunkDigest { offset: 0, len: 1
+                                     , hash: 0xaf63_dc4c_8601_ec8c }]);
+        // "foobar" is shorter than the least chunk size, so it is one chunk
+        assert_eq!( Rope::from("foobar").chunk_digests(64)
+                  , vec![ChunkDigest { offset: 0, len: 6
+                                     , hash: 0x8594_4171_f739_67e8 }]);
+    }
+
+    /// Returns `n` bytes of text which doesn't repeat.
+    fn varied_text(n: usize) -> String {
+        let mut state: u32 = 1;
+        (0..n).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (b'a' + (state >> 16) as u8 % 26) as char
+        }).collect()
+    }
+
+    #[test]
+    fn chunks_are_bounded() {
+        let rope = Rope::from(varied_text(10_000));
+        let digests = rope.chunk_digests(64);
+        let (last, rest) = digests.split_last().unwrap();
+        assert!(rest.iter().all(|d| d.len >= 16 && d.len <= 256));
+        assert_eq!(last.offset + last.len, rope.len());
+        // the average is near 64 bytes
+        assert!(digests.len() > 10_000 / 128 && digests.len() < 10_000 / 32);
+    }
+
+    #[test]
+    fn edits_only_change_nearby_chunks() {
+        let rope = Rope::from(varied_text(10_000));
+        for edited in &[ rope.insert_str(5_000, "inserted")
+                       , rope.delete(5_000..5_010)
+                       , rope.insert_str(0, "x") ] {
+            let ours = rope.chunk_digests(64);
+            let theirs = edited.chunk_digests(64);
+            let missing = ours.iter().filter(|a| {
+                !theirs.iter().any(|b| (a.len, a.hash) == (b.len, b.hash))
+            }).count();
+            assert!(missing <= 2, "{} of {} chunks changed"
+                   , missing, ours.len());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be > 0")]
+    fn zero_chunk_size() {
+        Rope::from("abc").chunk_digests(0);
+    }
+}
@@ -0,0 +1,112 @@
+//! Bounded edit distances between `Rope`s and strings.
+//!
+//! [`Rope::edit_distance`] computes the Levenshtein distance between the
+//! text of a `Rope` and a string, for fuzzy matching such as in a command
+//! palette. Callers are usually only interested in whether two texts are
+//! similar, so the computation is bounded: only the cells of the dynamic
+//! programming table within `max` of its diagonal are computed, and it
+//! gives up as soon as every cell in a row exceeds `max`. The `Rope`'s text
+//! is streamed a `char` at a time, and only one row of the table is kept,
+//! so its text is never collected into a `String`.
+//!
+//! [`Rope::edit_distance`]: ../struct.Rope.html#method.edit_distance
+use std::cmp;
+use std::mem;
+
+use super::Rope;
+use super::metric::{CharIndex, Measured};
+
+impl Rope {
+    /// Returns the Levenshtein distance between the text of this `Rope`
+    /// and `other`, if it is at most `max`.
+    ///
+    /// The distance is the least number of `char`s which must be inserted,
+    /// deleted or substituted to turn one text into the other.
+    ///
+    /// # Returns
+    /// - `Some` with the distance, if it is at most `max`
+    /// - `None` if it is greater than `max`
+    ///
+    /// # Time complexity
+    /// O(_n_ _k_), for a bound _k_; a `Rope` whose length differs from that
+    /// of `other` by more than `max` `char`s is rejected in O(log _n_).
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("kitten");
+    /// assert_eq!(rope.edit_distance("sitting", 3), Some(3));
+    /// assert_eq!(rope.edit_distance("sitting", 2), None);
+    /// assert_eq!(rope.edit_distance("kitten", 0), Some(0));
+    /// ```
+    pub fn edit_distance(&self, other: &str, max: usize) -> Option<usize> {
+        let other = other.chars().collect::<Vec<_>>();
+        let CharIndex(len) = self.measure();
+        let longer = cmp::max(len, other.len());
+        if longer - cmp::min(len, other.len()) > max { return None }
+        // cells outside the band are never read, except at its edges,
+        // where they must be larger than any distance within it
+        let over = max + 1;
+        let mut prev = (0..other.len() + 1).map(|j| cmp::min(j, over))
+                                           .collect::<Vec<_>>();
+        let mut row = vec![over; other.len() + 1];
+        for (i, c) in (1..).zip(self.chars()) {
+            let lo = if i > max { i - max } else { 1 };
+            let hi = cmp::min(other.len(), i + max);
+            row[0] = if i > max { over } else { i };
+            if lo > 1 { row[lo - 1] = over; }
+            let mut least = row[0];
+            for j in lo..hi + 1 {
+                let substitute = prev[j - 1] + (other[j - 1] != c) as usize;
+                let insert_or_delete = cmp::min(prev[j], row[j - 1]) + 1;
+                row[j] = cmp::min(cmp::min(substitute, insert_or_delete), over);
+                least = cmp::min(least, row[j]);
+            }
+            if hi < other.len() { row[hi + 1] = over; }
+            if least > max { return None }
+            mem::swap(&mut prev, &mut row);
+        }
+        Some(prev[other.len()]).filter(|&d| d <= max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use ::test::leaves;
+
+    /// The full, unbounded Levenshtein distance, computed naïvely.
+    fn levenshtein(a: &str, b: &str) -> usize {
+        let b = b.chars().collect::<Vec<_>>();
+        let mut prev = (0..b.len() + 1).collect::<Vec<_>>();
+        for (i, c) in a.chars().enumerate() {
+            let mut row = vec![i + 1];
+            for j in 1..b.len() + 1 {
+                let cost = (prev[j - 1] + (b[j - 1] != c) as usize)
+                    .min(prev[j] + 1)
+                    .min(row[j - 1] + 1);
+                row.push(cost);
+            }
+            prev = row;
+        }
+        prev[b.len()]
+    }
+
+    #[test]
+    fn bounded_matches_unbounded() {
+        let rope = leaves(&["open f", "\u{3c0}le", "", " in split"]);
+        let text = rope.to_string();
+        let others = [ "open file in split", "", "open \u{3c0}", "split"
+                     , "opne f\u{3c0}le in splti", "close file", &text[..] ];
+        for other in &others {
+            let distance = levenshtein(&text, other);
+            for max in 0..distance + 3 {
+                assert_eq!( rope.edit_distance(other, max)
+                          , if distance <= max { Some(distance) } else { None }
+                          , "{:?} with max {}", other, max);
+            }
+        }
+        assert_eq!(Rope::new().edit_distance("abc", 3), Some(3));
+        assert_eq!(Rope::new().edit_distance("", 0), Some(0));
+    }
+}
@@ -0,0 +1,409 @@
+//! Errors returned by fallible `Rope` operations.
+//!
+//! Most methods of `Rope` panic when given an index which is out of bounds,
+//! as indexing a `str` does. Code handling indices from an untrusted
+//! source, such as a network protocol or a plugin, can use the `try_`
+//! variants of those methods instead, which return an [`Error`] describing
+//! what was wrong with the request.
+//!
+//! The constructors which decode text, `Rope::from_utf8`,
+//! `Rope::from_utf8_bytes` and `Rope::from_utf16`, instead return the same
+//! error types as the `String` constructors they mirror, so that code using
+//! `String` can be ported to `Rope` unchanged, and so that `from_utf8` can
+//! give back the vector it was passed. Each of those errors converts into an
+//! `Error`, so all of them may be propagated with `?` from a function which
+//! returns an `Error`.
+//!
+//! [`Error`]: enum.Error.html
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::io;
+use std::ops;
+use std::str;
+use std::string;
+
+use super::{Rope, RopeSlice};
+
+/// An error returned by a fallible `Rope` operation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// Bytes which were to be converted into a `Rope` were not valid UTF-8.
+    InvalidUtf8 { /// The number of bytes before the invalid sequence.
+                  valid_up_to: usize }
+  , /// UTF-16 which was to be converted into a `Rope` was not valid.
+    InvalidUtf16
+  , /// A byte index was greater than the length of the `Rope`.
+    IndexOutOfBounds { /// The index.
+                       index: usize
+                     , /// The length of the `Rope`.
+                       len: usize }
+  , /// A byte index fell within a `char`, rather than between two.
+    NotCharBoundary { /// The index.
+                      index: usize }
+  , /// The start of a range was greater than its end.
+    InvalidRange { /// The start of the range.
+                   start: usize
+                 , /// The end of the range.
+                   end: usize }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidUtf8 { valid_up_to } =>
+                write!( f, "invalid UTF-8 after the first {} bytes"
+                      , valid_up_to)
+          , Error::InvalidUtf16 => write!(f, "invalid UTF-16")
+          , Error::IndexOutOfBounds { index, len } =>
+                write!(f, "index {} was > length {}", index, len)
+          , Error::NotCharBoundary { index } =>
+                write!(f, "index {} is not a char boundary", index)
+          , Error::InvalidRange { start, end } =>
+                write!(f, "start {} > end {}", start, end)
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::InvalidUtf8 { .. } => "invalid UTF-8"
+          , Error::InvalidUtf16 => "invalid UTF-16"
+          , Error::IndexOutOfBounds { .. } => "index out of bounds"
+          , Error::NotCharBoundary { .. } => "index is not a char boundary"
+          , Error::InvalidRange { .. } => "range start was > end"
+        }
+    }
+}
+
+impl From<str::Utf8Error> for Error {
+    #[inline] fn from(e: str::Utf8Error) -> Self {
+        Error::InvalidUtf8 { valid_up_to: e.valid_up_to() }
+    }
+}
+
+impl From<string::FromUtf8Error> for Error {
+    #[inline] fn from(e: string::FromUtf8Error) -> Self {
+        Error::from(e.utf8_error())
+    }
+}
+
+impl From<string::FromUtf16Error> for Error {
+    #[inline] fn from(_: string::FromUtf16Error) -> Self { Error::InvalidUtf16 }
+}
+
+impl From<Error> for io::Error {
+    /// Converts an `Error` into an `io::Error` of kind `InvalidData`, if the
+    /// error is invalid UTF-8 or UTF-16, or `InvalidInput` otherwise.
+    fn from(e: Error) -> Self {
+        let kind = match e {
+            Error::InvalidUtf8 { .. } | Error::InvalidUtf16 =>
+                io::ErrorKind::InvalidData
+          , _ => io::ErrorKind::InvalidInput
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+impl Rope {
+    /// Returns `Ok` if byte index `i` may be used to split this `Rope`.
+    fn check_index(&self, i: usize) -> Result<(), Error> {
+        let len = self.len();
+        if i > len {
+            return Err(Error::IndexOutOfBounds { index: i, len: len })
+        }
+        if i == 0 || i == len { return Ok(()) }
+        let (leaf, offset) = self.root.leaf_at(i);
+        if leaf.is_char_boundary(i - offset) { Ok(()) }
+        else { Err(Error::NotCharBoundary { index: i }) }
+    }
+
+    /// Returns `Ok` if `range` may be used to slice this `Rope`.
+    fn check_range(&self, range: &ops::Range<usize>) -> Result<(), Error> {
+        if range.start > range.end {
+            return Err(Error::InvalidRange { start: range.start
+                                           , end: range.end })
+        }
+        self.check_index(range.end)?;
+        self.check_index(range.start)
+    }
+
+    /// Re-validates the text of this `Rope` between the given byte indices,
+    /// returning an `Error` locating the first problem found.
+    ///
+    /// The text of a `Rope` is always valid UTF-8 unless it was built with
+    /// `from_utf8_unchecked`, or from text passed in through the C API, with
+    /// invalid input. This checks the UTF-8 of each leaf in `range`, and that
+    /// each boundary between leaves in it falls between two `char`s, to
+    /// verify text ingested in those ways.
+    ///
+    /// # Errors
+    /// - `InvalidRange` if the start of `range` is greater than its end
+    /// - `IndexOutOfBounds` if the end of `range` is greater than the length
+    ///   of this `Rope`
+    /// - `NotCharBoundary` if either end of `range`, or a boundary between
+    ///   two leaves, falls within a `char`
+    /// - `InvalidUtf8` if a leaf contains an invalid sequence, with
+    ///   `valid_up_to` set to the byte index in this `Rope` at which it
+    ///   starts
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, Error};
+    /// let rope = Rope::from("a\u{3c0}b");
+    /// assert_eq!(rope.validate_utf8(0..4), Ok(()));
+    /// assert_eq!( rope.validate_utf8(2..4)
+    ///           , Err(Error::NotCharBoundary { index: 2 }));
+    /// ```
+    pub fn validate_utf8(&self, range: ops::Range<usize>)
+                        -> Result<(), Error> {
+        if range.start > range.end {
+            return Err(Error::InvalidRange { start: range.start
+                                           , end: range.end })
+        }
+        if range.end > self.len() {
+            return Err(Error::IndexOutOfBounds { index: range.end
+                                               , len: self.len() })
+        }
+        if range.start == range.end { return self.check_index(range.start) }
+        let leaves = self.leaf_ranges()
+                         .skip_while(|&(ref leaf, _)| leaf.end <= range.start)
+                         .take_while(|&(ref leaf, _)| leaf.start < range.end);
+        for (leaf, text) in leaves {
+            let start = cmp::max(leaf.start, range.start);
+            let end = cmp::min(leaf.end, range.end);
+            let bytes = &text.as_bytes()[start - leaf.start..end - leaf.start];
+            // a continuation byte can only follow the start of a `char`
+            if bytes.first().map_or(false, |&b| b & 0xc0 == 0x80) {
+                return Err(Error::NotCharBoundary { index: start })
+            }
+            if let Err(e) = str::from_utf8(bytes) {
+                // a `char` which is cut off by the end of the text is split
+                // between leaves, or by the end of `range`
+                return Err(if e.error_len().is_none() {
+                    Error::NotCharBoundary { index: end }
+                } else {
+                    Error::InvalidUtf8 { valid_up_to: start + e.valid_up_to() }
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the text of this `Rope` between the given byte
+    /// indices is valid UTF-8, split into leaves only between `char`s.
+    ///
+    /// This is [`validate_utf8()`], for callers which only need to know
+    /// whether the text is valid.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\u{3c0}b");
+    /// assert!(rope.is_valid_utf8_at(1..3));
+    /// assert!(!rope.is_valid_utf8_at(1..2));
+    /// assert!(!rope.is_valid_utf8_at(0..9));
+    /// ```
+    ///
+    /// [`validate_utf8()`]: struct.Rope.html#method.validate_utf8
+    #[inline]
+    pub fn is_valid_utf8_at(&self, range: ops::Range<usize>) -> bool {
+        self.validate_utf8(range).is_ok()
+    }
+
+    /// Converts a vector of bytes to a `Rope`.
+    ///
+    /// This is like [`from_utf8()`], but returns this crate's [`Error`].
+    ///
+    /// # Errors
+    /// `InvalidUtf8` if `vec` is not valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, Error};
+    /// assert_eq!(&Rope::try_from_utf8(b"abc".to_vec()).unwrap(), "abc");
+    /// assert_eq!( Rope::try_from_utf8(vec![b'a', 0xff])
+    ///           , Err(Error::InvalidUtf8 { valid_up_to: 1 }));
+    /// ```
+    ///
+    /// [`from_utf8()`]: struct.Rope.html#method.from_utf8
+    /// [`Error`]: enum.Error.html
+    #[inline]
+    pub fn try_from_utf8(vec: Vec<u8>) -> Result<Rope, Error> {
+        Rope::from_utf8(vec).map_err(Error::from)
+    }
+
+    /// Returns an immutable slice of this `Rope` between the given byte
+    /// indices, or an `Error` if they can't be used to slice it.
+    ///
+    /// # Errors
+    /// - `InvalidRange` if the start of `range` is greater than its end
+    /// - `IndexOutOfBounds` if the end of `range` is greater than the length
+    ///   of this `Rope`
+    /// - `NotCharBoundary` if either end of `range` falls within a `char`
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, Error};
+    /// let rope = Rope::from("a\u{3c0}b");
+    /// assert_eq!(&rope.try_slice(1..3).unwrap(), "\u{3c0}");
+    /// assert_eq!( rope.try_slice(1..2).unwrap_err()
+    ///           , Error::NotCharBoundary { index: 2 });
+    /// assert_eq!( rope.try_slice(2..9).unwrap_err()
+    ///           , Error::IndexOutOfBounds { index: 9, len: 4 });
+    /// ```
+    pub fn try_slice(&self, range: ops::Range<usize>)
+                    -> Result<RopeSlice, Error> {
+        self.check_range(&range)?;
+        Ok(self.slice(range))
+    }
+
+    /// Insert `s` at byte index `i` in this `Rope`, returning a new `Rope`,
+    /// or an `Error` if `i` is not a valid index.
+    ///
+    /// # Errors
+    /// - `IndexOutOfBounds` if `i` is greater than the length of this `Rope`
+    /// - `NotCharBoundary` if `i` falls within a `char`
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, Error};
+    /// let rope = Rope::from("ad");
+    /// assert_eq!(&rope.try_insert_str(1, "bc").unwrap(), "abcd");
+    /// assert_eq!( rope.try_insert_str(3, "e")
+    ///           , Err(Error::IndexOutOfBounds { index: 3, len: 2 }));
+    /// ```
+    pub fn try_insert_str(&self, i: usize, s: &str) -> Result<Rope, Error> {
+        self.check_index(i)?;
+        Ok(self.insert_str(i, s))
+    }
+
+    /// Delete the text between the given byte indices from this `Rope`,
+    /// returning a new `Rope`, or an `Error` if they can't be used to slice
+    /// it.
+    ///
+    /// # Errors
+    /// As for [`try_slice()`].
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, Error};
+    /// let rope = Rope::from("this is not fine");
+    /// assert_eq!(&rope.try_delete(8..12).unwrap(), "this is fine");
+    /// assert_eq!( rope.try_delete(12..8)
+    ///           , Err(Error::InvalidRange { start: 12, end: 8 }));
+    /// ```
+    ///
+    /// [`try_slice()`]: struct.Rope.html#method.try_slice
+    pub fn try_delete(&self, range: ops::Range<usize>) -> Result<Rope, Error> {
+        self.check_range(&range)?;
+        Ok(self.delete(range))
+    }
+
+    /// Replace the text between the given byte indices with `replacement`,
+    /// returning a new `Rope`, or an `Error` if they can't be used to slice
+    /// this `Rope`.
+    ///
+    /// # Errors
+    /// As for [`try_slice()`].
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, Error};
+    /// let rope = Rope::from("\u{3c0} is not fine");
+    /// assert_eq!( &rope.try_edit(6..9, "very").unwrap()
+    ///           , "\u{3c0} is very fine");
+    /// assert_eq!( rope.try_edit(1..2, "")
+    ///           , Err(Error::NotCharBoundary { index: 1 }));
+    /// ```
+    ///
+    /// [`try_slice()`]: struct.Rope.html#method.try_slice
+    pub fn try_edit(&self, range: ops::Range<usize>, replacement: &str)
+                   -> Result<Rope, Error> {
+        self.check_range(&range)?;
+        Ok(self.edit(range, replacement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use ::test::leaves;
+    use super::Error;
+    use std::io;
+
+    #[test]
+    fn char_boundaries_across_leaves() {
+        let rope = Rope::from("a\u{3c0}") + Rope::from("\u{3c4}b");
+        let s = rope.to_string();
+        for i in 0..s.len() + 2 {
+            let expected = if i > s.len() {
+                Err(Error::IndexOutOfBounds { index: i, len: s.len() })
+            } else if !s.is_char_boundary(i) {
+                Err(Error::NotCharBoundary { index: i })
+            } else {
+                Ok(format!("{}x{}", &s[..i], &s[i..]))
+            };
+            assert_eq!( rope.try_insert_str(i, "x").map(|r| r.to_string())
+                      , expected);
+        }
+    }
+
+    #[test]
+    fn validate_utf8_across_leaves() {
+        let rope = leaves(&["a\u{3c0}", "\u{3c4}b", "", "c"]);
+        let s = rope.to_string();
+        for start in 0..s.len() + 1 {
+            for end in start..s.len() + 1 {
+                let expected = if !s.is_char_boundary(start) {
+                    Err(Error::NotCharBoundary { index: start })
+                } else if !s.is_char_boundary(end) {
+                    Err(Error::NotCharBoundary { index: end })
+                } else {
+                    Ok(())
+                };
+                assert_eq!(rope.validate_utf8(start..end), expected);
+            }
+        }
+        assert_eq!( rope.validate_utf8(3..2)
+                  , Err(Error::InvalidRange { start: 3, end: 2 }));
+        assert_eq!( rope.validate_utf8(0..8)
+                  , Err(Error::IndexOutOfBounds { index: 8, len: 7 }));
+    }
+
+    #[test]
+    fn converts_to_io_error() {
+        let e: io::Error = Error::InvalidUtf8 { valid_up_to: 0 }.into();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let e: io::Error = Error::InvalidRange { start: 2, end: 1 }.into();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(e.to_string(), "start 2 > end 1");
+    }
+
+    #[test]
+    fn decoding_errors_convert() {
+        fn decode(utf8: Vec<u8>, utf16: &[u16]) -> Result<Rope, Error> {
+            Ok(Rope::from_utf8(utf8)? + Rope::from_utf16(utf16)?)
+        }
+        assert_eq!(&decode(b"ab".to_vec(), &[0x63]).unwrap(), "abc");
+        assert_eq!( decode(vec![b'a', 0xff], &[])
+                  , Err(Error::InvalidUtf8 { valid_up_to: 1 }));
+        assert_eq!(decode(vec![], &[0xd800]), Err(Error::InvalidUtf16));
+    }
+}
@@ -0,0 +1,348 @@
+//! A C interface to `Rope`s.
+//!
+//! When built with the `ffi` feature, this module exports a set of
+//! `extern "C"` functions for using `Rope`s from C, C++, Swift, and other
+//! languages which can call C functions. A `Rope` is passed across the
+//! boundary as an opaque pointer, created by [`an_rope_new`] or
+//! [`an_rope_from_utf8`] and released with [`an_rope_free`].
+//!
+//! All offsets are byte offsets into the UTF-8 text of the `Rope`, and all
+//! text passed in or out is UTF-8, described by a pointer and a length in
+//! bytes; it need not be NUL-terminated.
+//!
+//! Where the corresponding `Rope` method would panic on a bad argument, such
+//! as an index which is past the end of the `Rope` or which is not on a
+//! character boundary, these functions instead return `false` or a null
+//! pointer, and leave the `Rope` unchanged. Unwinding across the boundary
+//! is undefined behaviour, so they never panic on bad input.
+//!
+//! Edits replace the `Rope` behind a handle with the edited `Rope`. Since
+//! `Rope`s are persistent, a handle made with [`an_rope_clone`] before an
+//! edit is a snapshot which the edit does not affect.
+//!
+//! A C declaration of this interface might look like:
+//!
+//! ```c
+//! typedef struct AnRope AnRope;
+//! typedef struct AnRopeChunks AnRopeChunks;
+//!
+//! AnRope *an_rope_new(void);
+//! AnRope *an_rope_from_utf8(const uint8_t *text, size_t len);
+//! AnRope *an_rope_clone(const AnRope *rope);
+//! void an_rope_free(AnRope *rope);
+//! size_t an_rope_len(const AnRope *rope);
+//! bool an_rope_insert(AnRope *rope, size_t index,
+//!                     const uint8_t *text, size_t len);
+//! bool an_rope_delete(AnRope *rope, size_t start, size_t end);
+//! AnRope *an_rope_slice(const AnRope *rope, size_t start, size_t end);
+//! AnRopeChunks *an_rope_chunks(const AnRope *rope);
+//! bool an_rope_chunks_next(AnRopeChunks *chunks,
+//!                          const uint8_t **text, size_t *len);
+//! void an_rope_chunks_free(AnRopeChunks *chunks);
+//! ```
+//!
+//! [`an_rope_new`]: fn.an_rope_new.html
+//! [`an_rope_from_utf8`]: fn.an_rope_from_utf8.html
+//! [`an_rope_free`]: fn.an_rope_free.html
+//! [`an_rope_clone`]: fn.an_rope_clone.html
+
+use std::ptr;
+use std::slice;
+use std::str;
+
+use super::Rope;
+
+/// An iterator over the chunks of text in a `Rope`, for use from C.
+///
+/// The iterator holds its own snapshot of the `Rope`, so the `Rope` it was
+/// made from may be edited or freed while it is in use.
+pub struct AnRopeChunks { rope: Rope
+                        , offset: usize
+                        }
+
+/// Returns `true` if byte index `i` is within `rope` and falls on a
+/// character boundary.
+fn is_char_boundary(rope: &Rope, i: usize) -> bool {
+    if i > rope.len() { return false }
+    let (leaf, offset) = rope.root.leaf_at(i);
+    leaf.is_char_boundary(i - offset)
+}
+
+/// Returns the UTF-8 text of length `len` at `text`, or `None` if it is not
+/// valid UTF-8.
+unsafe fn text_arg<'a>(text: *const u8, len: usize) -> Option<&'a str> {
+    if len == 0 { return Some("") }
+    if text.is_null() { return None }
+    str::from_utf8(slice::from_raw_parts(text, len)).ok()
+}
+
+#[inline]
+fn into_handle(rope: Rope) -> *mut Rope { Box::into_raw(Box::new(rope)) }
+
+/// Returns a new, empty `Rope`.
+///
+/// The returned handle must be released with `an_rope_free`.
+#[no_mangle]
+pub extern "C" fn an_rope_new() -> *mut Rope { into_handle(Rope::new()) }
+
+/// Returns a new `Rope` containing the `len` bytes of UTF-8 text at `text`.
+///
+/// The text is copied, so it need not outlive the `Rope`. The returned
+/// handle must be released with `an_rope_free`.
+///
+/// # Returns
+/// - a handle to the new `Rope`
+/// - null if the text is not valid UTF-8
+///
+/// # Safety
+/// `text` must point to at least `len` readable bytes, or may be null if
+/// `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_from_utf8(text: *const u8, len: usize)
+                                          -> *mut Rope {
+    match text_arg(text, len) {
+        Some(s) => into_handle(Rope::from(s))
+      , None => ptr::null_mut()
+    }
+}
+
+/// Returns a new handle to a snapshot of `rope`.
+///
+/// The snapshot shares `rope`'s text, so this does not copy it, and later
+/// edits through either handle do not affect the other. The returned handle
+/// must be released with `an_rope_free`.
+///
+/// # Safety
+/// `rope` must be a live handle returned by one of these functions.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_clone(rope: *const Rope) -> *mut Rope {
+    into_handle((*rope).clone())
+}
+
+/// Releases a handle to a `Rope`.
+///
+/// Passing null does nothing.
+///
+/// # Safety
+/// `rope` must be null or a live handle returned by one of these functions,
+/// and must not be used again after it is released.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_free(rope: *mut Rope) {
+    if !rope.is_null() { drop(Box::from_raw(rope)) }
+}
+
+/// Returns the length of `rope`, in bytes.
+///
+/// # Safety
+/// `rope` must be a live handle returned by one of these functions.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_len(rope: *const Rope) -> usize {
+    (*rope).len()
+}
+
+/// Inserts the `len` bytes of UTF-8 text at `text` into `rope` at byte
+/// index `index`.
+///
+/// # Returns
+/// - `true` if the text was inserted
+/// - `false`, leaving `rope` unchanged, if `index` is greater than the
+///   length of `rope` or not on a character boundary, or if the text is not
+///   valid UTF-8
+///
+/// # Safety
+/// `rope` must be a live handle returned by one of these functions, and
+/// `text` must point to at least `len` readable bytes, or may be null if
+/// `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_insert( rope: *mut Rope, index: usize
+                                       , text: *const u8, len: usize)
+                                       -> bool {
+    let rope = &mut *rope;
+    match text_arg(text, len) {
+        Some(s) if is_char_boundary(rope, index) => {
+            *rope = rope.insert_str(index, s);
+            true
+        }
+      , _ => false
+    }
+}
+
+/// Deletes the bytes from `start` up to (but not including) `end` from
+/// `rope`.
+///
+/// # Returns
+/// - `true` if the text was deleted
+/// - `false`, leaving `rope` unchanged, if `start` is greater than `end`,
+///   `end` is greater than the length of `rope`, or either is not on a
+///   character boundary
+///
+/// # Safety
+/// `rope` must be a live handle returned by one of these functions.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_delete( rope: *mut Rope
+                                       , start: usize, end: usize)
+                                       -> bool {
+    let rope = &mut *rope;
+    if start > end || !is_char_boundary(rope, start)
+                   || !is_char_boundary(rope, end) {
+        return false
+    }
+    *rope = rope.delete(start..end);
+    true
+}
+
+/// Returns a new `Rope` containing the bytes of `rope` from `start` up to
+/// (but not including) `end`.
+///
+/// The new `Rope` shares `rope`'s text where it can, rather than copying
+/// it. The returned handle must be released with `an_rope_free`.
+///
+/// # Returns
+/// - a handle to the new `Rope`
+/// - null if `start` is greater than `end`, `end` is greater than the
+///   length of `rope`, or either is not on a character boundary
+///
+/// # Safety
+/// `rope` must be a live handle returned by one of these functions.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_slice( rope: *const Rope
+                                      , start: usize, end: usize)
+                                      -> *mut Rope {
+    let rope = &*rope;
+    if start > end || !is_char_boundary(rope, start)
+                   || !is_char_boundary(rope, end) {
+        return ptr::null_mut()
+    }
+    let (prefix, _) = rope.split(end);
+    let (_, slice) = prefix.split(start);
+    into_handle(slice)
+}
+
+/// Returns an iterator over the chunks of text in `rope`, in order.
+///
+/// The iterator holds a snapshot of `rope`, so `rope` may be edited or
+/// released while the iterator is in use. The returned iterator must be
+/// released with `an_rope_chunks_free`.
+///
+/// # Safety
+/// `rope` must be a live handle returned by one of these functions.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_chunks(rope: *const Rope)
+                                       -> *mut AnRopeChunks {
+    let chunks = AnRopeChunks { rope: (*rope).clone(), offset: 0 };
+    Box::into_raw(Box::new(chunks))
+}
+
+/// Advances `chunks` to the next chunk of text.
+///
+/// The chunk is written to `*text` and `*len` as a pointer to its UTF-8
+/// bytes and their length. The chunk is never empty, and remains valid
+/// until `chunks` is released.
+///
+/// # Returns
+/// - `true` if a chunk was written
+/// - `false` if there are no more chunks
+///
+/// # Safety
+/// `chunks` must be a live iterator returned by `an_rope_chunks`, and
+/// `text` and `len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_chunks_next( chunks: *mut AnRopeChunks
+                                            , text: *mut *const u8
+                                            , len: *mut usize)
+                                            -> bool {
+    let chunks = &mut *chunks;
+    if chunks.offset >= chunks.rope.len() { return false }
+    let chunk = chunks.rope.chunk_at(chunks.offset);
+    chunks.offset += chunk.len();
+    *text = chunk.as_ptr();
+    *len = chunk.len();
+    true
+}
+
+/// Releases an iterator returned by `an_rope_chunks`.
+///
+/// Passing null does nothing.
+///
+/// # Safety
+/// `chunks` must be null or a live iterator returned by `an_rope_chunks`,
+/// and must not be used again after it is released.
+#[no_mangle]
+pub unsafe extern "C" fn an_rope_chunks_free(chunks: *mut AnRopeChunks) {
+    if !chunks.is_null() { drop(Box::from_raw(chunks)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+    use std::slice;
+    use std::str;
+
+    use ::Rope;
+    use super::*;
+
+    unsafe fn contents(rope: *const Rope) -> String {
+        let chunks = an_rope_chunks(rope);
+        let (mut text, mut len) = (ptr::null(), 0);
+        let mut s = String::new();
+        while an_rope_chunks_next(chunks, &mut text, &mut len) {
+            assert!(len > 0);
+            s.push_str(str::from_utf8(slice::from_raw_parts(text, len))
+                           .unwrap());
+        }
+        an_rope_chunks_free(chunks);
+        s
+    }
+
+    #[test]
+    fn edit_and_read_back() {
+        unsafe {
+            let rope = an_rope_from_utf8("hello world".as_ptr(), 11);
+            assert!(an_rope_insert(rope, 5, ",".as_ptr(), 1));
+            let snapshot = an_rope_clone(rope);
+            assert!(an_rope_delete(rope, 6, 12));
+            assert_eq!(contents(rope), "hello,");
+            assert_eq!(contents(snapshot), "hello, world");
+            let slice = an_rope_slice(snapshot, 7, 12);
+            assert_eq!(contents(slice), "world");
+            assert_eq!(an_rope_len(slice), 5);
+            an_rope_free(slice);
+            an_rope_free(snapshot);
+            an_rope_free(rope);
+        }
+    }
+
+    #[test]
+    fn chunks_outlive_rope() {
+        unsafe {
+            let rope = an_rope_new();
+            assert!(an_rope_insert(rope, 0, "def".as_ptr(), 3));
+            assert!(an_rope_insert(rope, 0, "abc".as_ptr(), 3));
+            let chunks = an_rope_chunks(rope);
+            an_rope_free(rope);
+            let (mut text, mut len) = (ptr::null(), 0);
+            let mut s = Vec::new();
+            while an_rope_chunks_next(chunks, &mut text, &mut len) {
+                s.extend_from_slice(slice::from_raw_parts(text, len));
+            }
+            an_rope_chunks_free(chunks);
+            assert_eq!(s, b"abcdef");
+        }
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        unsafe {
+            let rope = an_rope_from_utf8("a\u{3c0}b".as_ptr(), 4);
+            assert!(an_rope_from_utf8(b"\xff".as_ptr(), 1).is_null());
+            assert!(!an_rope_insert(rope, 2, "x".as_ptr(), 1));
+            assert!(!an_rope_insert(rope, 5, "x".as_ptr(), 1));
+            assert!(!an_rope_insert(rope, 0, b"\xff".as_ptr(), 1));
+            assert!(!an_rope_delete(rope, 3, 1));
+            assert!(!an_rope_delete(rope, 0, 2));
+            assert!(an_rope_slice(rope, 0, 5).is_null());
+            assert_eq!(contents(rope), "a\u{3c0}b");
+            an_rope_free(rope);
+        }
+    }
+}
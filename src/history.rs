@@ -0,0 +1,689 @@
+//! Undo and redo over snapshots of a `Rope`.
+//!
+//! Since `Rope`s are persistent, keeping an old version of a buffer only
+//! costs the nodes which later edits replaced; everything else is shared.
+//! A [`History`] keeps a sequence of such snapshots, and can move back and
+//! forth between them.
+//!
+//! Counting snapshots is a poor way to bound the memory a history uses,
+//! since a snapshot taken after a one-character edit costs a few nodes,
+//! while one taken after replacing the whole buffer costs a full copy. A
+//! `History` can instead be given a memory budget, in which case it counts
+//! the bytes retained by every distinct node reachable from its snapshots,
+//! counting each shared node only once, and discards the oldest snapshots
+//! when that exceeds the budget. The count is kept up to date as snapshots
+//! are recorded and discarded, so recording a snapshot only visits the nodes
+//! it does not share with those already in the `History`.
+//!
+//! Snapshots may also be tagged with labels, and [`diff`] computes the
+//! changes between any two of them, to support "local history" features.
+//!
+//! [`History`]: struct.History.html
+//! [`diff`]: fn.diff.html
+use std::collections::{btree_map, BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops;
+
+use super::Rope;
+use super::internals::{Node, NodeLink, Value};
+
+/// The bytes retained by a node, not counting its children.
+#[inline]
+fn node_bytes(node: &NodeLink) -> usize {
+    let (node, text) = node.allocated_bytes();
+    node + text
+}
+
+/// Returns an estimate of the bytes retained by `ropes`, counting each node
+/// which is shared between them only once.
+///
+/// # Time complexity
+/// O(_n_) in the number of distinct nodes.
+///
+/// # Examples
+/// ```
+/// use an_rope::{history, Rope};
+/// let rope = Rope::from("a".repeat(4096));
+/// let edited = rope.append(&Rope::from("b"));
+/// let alone = history::retained_bytes(&[rope.clone()]);
+/// let both = history::retained_bytes(&[rope, edited]);
+/// assert!(both < 2 * alone);
+/// ```
+pub fn retained_bytes<'a, I>(ropes: I) -> usize
+where I: IntoIterator<Item=&'a Rope> {
+    let mut seen = HashSet::new();
+    ropes.into_iter().map(|rope| unseen_bytes(rope, &mut seen)).sum()
+}
+
+/// Returns the bytes retained by the nodes of `rope` which are not in
+/// `seen`, adding those nodes to `seen`.
+fn unseen_bytes(rope: &Rope, seen: &mut HashSet<*const Node>) -> usize {
+    let mut stack = vec![&rope.root];
+    let mut bytes = 0;
+    while let Some(node) = stack.pop() {
+        // a node which has already been counted is shared, and so are all
+        // of its children
+        if !seen.insert(node.as_ptr()) { continue }
+        bytes += node_bytes(node);
+        if let Value::Branch { ref left, ref right } = node.value {
+            stack.push(left);
+            stack.push(right);
+        }
+    }
+    bytes
+}
+
+/// The distinct nodes retained by a collection of `Rope`s, and the bytes
+/// they retain.
+///
+/// Each node is counted by the number of `Rope`s and retained nodes which
+/// refer to it, so that adding or removing a `Rope` only visits the nodes
+/// which it alone retains.
+#[derive(Clone, Debug, Default)]
+struct Retained { counts: HashMap<usize, usize>
+                , bytes: usize
+                }
+
+impl Retained {
+    /// Counts a reference to `rope`, adding the bytes retained by any of its
+    /// nodes which were not already retained.
+    fn add(&mut self, rope: &Rope) {
+        let mut stack = vec![&rope.root];
+        while let Some(node) = stack.pop() {
+            let count = self.counts.entry(node.as_ptr() as usize)
+                                   .or_insert(0);
+            *count += 1;
+            // the children of a node which was already retained are
+            // already counted
+            if *count > 1 { continue }
+            self.bytes += node_bytes(node);
+            if let Value::Branch { ref left, ref right } = node.value {
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+    }
+
+    /// Removes a reference to `rope`, which must have been added, taking
+    /// away the bytes retained by any of its nodes which are no longer
+    /// retained.
+    fn remove(&mut self, rope: &Rope) {
+        let mut stack = vec![&rope.root];
+        while let Some(node) = stack.pop() {
+            let key = node.as_ptr() as usize;
+            {
+                let count = self.counts.get_mut(&key)
+                                .expect("removed a node which wasn't added");
+                *count -= 1;
+                if *count > 0 { continue }
+            }
+            self.counts.remove(&key);
+            self.bytes -= node_bytes(node);
+            if let Value::Branch { ref left, ref right } = node.value {
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+    }
+}
+
+/// A sequence of snapshots of a `Rope`, with undo and redo.
+///
+/// # Examples
+///
+/// ```
+/// use an_rope::{History, Rope};
+/// let mut history = History::new(Rope::from("hello"));
+/// history.record(history.current().insert_str(5, " world"));
+/// history.record(history.current().delete(0..6));
+/// assert_eq!(history.current(), &Rope::from("world"));
+///
+/// assert_eq!(history.undo(), Some(&Rope::from("hello world")));
+/// assert_eq!(history.undo(), Some(&Rope::from("hello")));
+/// assert_eq!(history.undo(), None);
+/// assert_eq!(history.redo(), Some(&Rope::from("hello world")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct History { snapshots: VecDeque<Rope>
+                   , current: usize
+                   , budget: Option<usize>
+                   , tags: BTreeMap<String, Rope>
+                   , retained: Retained
+                   }
+
+impl History {
+    /// Returns a new `History` whose only snapshot is `rope`, with no
+    /// memory budget.
+    #[inline]
+    pub fn new(rope: Rope) -> Self {
+        let mut retained = Retained::default();
+        retained.add(&rope);
+        let mut snapshots = VecDeque::new();
+        snapshots.push_back(rope);
+        History { snapshots: snapshots
+                , current: 0
+                , budget: None
+                , tags: BTreeMap::new()
+                , retained: retained
+                }
+    }
+
+    /// Returns a new `History` whose only snapshot is `rope`, which will
+    /// discard old snapshots to keep the memory they retain under `budget`
+    /// bytes.
+    ///
+    /// The current snapshot is never discarded, even if it alone is over
+    /// budget.
+    #[inline]
+    pub fn with_memory_budget(rope: Rope, budget: usize) -> Self {
+        let mut history = History::new(rope);
+        history.budget = Some(budget);
+        history
+    }
+
+    /// Returns the memory budget of this `History`, in bytes, if it has one.
+    #[inline] pub fn memory_budget(&self) -> Option<usize> { self.budget }
+
+    /// Sets the memory budget of this `History`, discarding old snapshots if
+    /// they are now over budget. `None` removes the budget.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+        self.prune();
+    }
+
+    /// Returns the current snapshot.
+    #[inline]
+    pub fn current(&self) -> &Rope { &self.snapshots[self.current] }
+
+    /// Returns the number of snapshots in this `History`, including the
+    /// current snapshot and any which may be redone.
+    #[inline] pub fn len(&self) -> usize { self.snapshots.len() }
+
+    /// Returns `true` if there is a snapshot before the current one.
+    #[inline] pub fn can_undo(&self) -> bool { self.current > 0 }
+
+    /// Returns `true` if there is a snapshot after the current one.
+    #[inline]
+    pub fn can_redo(&self) -> bool { self.current + 1 < self.snapshots.len() }
+
+    /// Record `rope` as a new snapshot, after the current one.
+    ///
+    /// Any snapshots which could have been redone are discarded, as are the
+    /// oldest snapshots if this `History` is then over its memory budget.
+    ///
+    /// # Time complexity
+    /// O(_m_) in the number of nodes of `rope` which are not shared with
+    /// the snapshots already in this `History`, and the number of nodes
+    /// which are no longer retained once snapshots are discarded.
+    pub fn record(&mut self, rope: Rope) {
+        for redo in self.snapshots.drain(self.current + 1..) {
+            self.retained.remove(&redo);
+        }
+        self.retained.add(&rope);
+        self.snapshots.push_back(rope);
+        self.current += 1;
+        self.prune();
+    }
+
+    /// Move back to the previous snapshot, returning it.
+    ///
+    /// # Returns
+    /// - `Some` with the previous snapshot
+    /// - `None`, without moving, if the current snapshot is the oldest
+    pub fn undo(&mut self) -> Option<&Rope> {
+        if !self.can_undo() { return None }
+        self.current -= 1;
+        Some(self.current())
+    }
+
+    /// Move forward to the next snapshot, returning it.
+    ///
+    /// # Returns
+    /// - `Some` with the next snapshot
+    /// - `None`, without moving, if the current snapshot is the newest
+    pub fn redo(&mut self) -> Option<&Rope> {
+        if !self.can_redo() { return None }
+        self.current += 1;
+        Some(self.current())
+    }
+
+    /// Tag the current snapshot with `label`.
+    ///
+    /// If another snapshot already has that label, the label is moved. A
+    /// tagged snapshot is kept until it is untagged, even if it is undone
+    /// past and discarded, or pruned to meet the memory budget.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{History, Rope};
+    /// let mut history = History::new(Rope::from("one\ntwo\n"));
+    /// history.tag("before");
+    /// history.record(history.current().edit(4..7, "three"));
+    /// history.tag("after");
+    ///
+    /// let hunks = history.diff("before", "after").unwrap();
+    /// assert_eq!(hunks.len(), 1);
+    /// assert_eq!(hunks[0].old_range(), 4..8);
+    /// assert_eq!(hunks[0].new_range(), 4..10);
+    /// ```
+    pub fn tag<S>(&mut self, label: S)
+    where S: Into<String> {
+        let current = self.current().clone();
+        self.retained.add(&current);
+        if let Some(old) = self.tags.insert(label.into(), current) {
+            self.retained.remove(&old);
+        }
+    }
+
+    /// Remove the tag `label`.
+    ///
+    /// # Returns
+    /// `true` if a tag was removed, `false` if there was no such tag.
+    #[inline]
+    pub fn untag(&mut self, label: &str) -> bool {
+        match self.tags.remove(label) {
+            Some(rope) => { self.retained.remove(&rope); true }
+          , None => false
+        }
+    }
+
+    /// Returns the snapshot tagged with `label`, if there is one.
+    #[inline]
+    pub fn tagged(&self, label: &str) -> Option<&Rope> { self.tags.get(label) }
+
+    /// Returns an iterator over the labels of the tagged snapshots, in
+    /// order.
+    #[inline]
+    pub fn tags(&self) -> btree_map::Keys<String, Rope> { self.tags.keys() }
+
+    /// Returns the changes which turn the snapshot tagged `from` into the
+    /// snapshot tagged `to`.
+    ///
+    /// # Returns
+    /// - `Some` with the changes, as for [`diff`]
+    /// - `None` if either label is not a tag
+    ///
+    /// [`diff`]: fn.diff.html
+    pub fn diff(&self, from: &str, to: &str) -> Option<Vec<Hunk>> {
+        match (self.tagged(from), self.tagged(to)) {
+            (Some(old), Some(new)) => Some(diff(old, new))
+          , _ => None
+        }
+    }
+
+    /// Returns an estimate of the bytes retained by all the snapshots in
+    /// this `History`, including tagged snapshots, counting nodes shared
+    /// between snapshots only once.
+    ///
+    /// # Time complexity
+    /// O(1), since the count is kept up to date as snapshots are recorded
+    /// and discarded.
+    #[inline] pub fn retained_bytes(&self) -> usize { self.retained.bytes }
+
+    /// Discard the oldest snapshots until this `History` is within its
+    /// memory budget, or until only the current snapshot and those after it
+    /// remain.
+    ///
+    /// # Time complexity
+    /// O(_m_) in the number of nodes which are no longer retained once the
+    /// snapshots are discarded.
+    fn prune(&mut self) {
+        let budget = match self.budget {
+            Some(budget) => budget
+          , None => return
+        };
+        while self.current > 0 && self.retained.bytes > budget {
+            let oldest = self.snapshots.pop_front()
+                             .expect("a snapshot before the current one");
+            self.retained.remove(&oldest);
+            self.current -= 1;
+        }
+    }
+}
+
+/// A change between two versions of a `Rope`: the byte range `old_range()`
+/// of the old version was replaced by the byte range `new_range()` of the
+/// new version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk { old: ops::Range<usize>
+                , new: ops::Range<usize>
+                }
+
+impl Hunk {
+    /// Returns the byte range of the old version which was replaced.
+    #[inline] pub fn old_range(&self) -> ops::Range<usize> { self.old.clone() }
+
+    /// Returns the byte range of the new version which replaced it.
+    #[inline] pub fn new_range(&self) -> ops::Range<usize> { self.new.clone() }
+}
+
+/// Returns the changes which turn `old` into `new`, compared line by line.
+///
+/// Each `Hunk` replaces whole lines of `old` with whole lines of `new`. The
+/// hunks are in order, and do not overlap, so applying them from last to
+/// first turns `old` into `new`.
+///
+/// Lines which are shared with `old` at the start and end of `new` are
+/// skipped quickly; the lines between are compared using the linear space
+/// variant of Myers' algorithm, which is O((_n_ + _m_) _d_) in time and
+/// O(_n_ + _m_) in space for _n_ and _m_ lines differing in _d_ lines.
+///
+/// # Examples
+/// ```
+/// use an_rope::Rope;
+/// use an_rope::history;
+/// let old = Rope::from("a\nb\nc\nd\n");
+/// let new = Rope::from("a\nx\nc\nd\ne\n");
+/// let hunks = history::diff(&old, &new);
+/// let patched = hunks.iter().rev().fold(old.clone(), |rope, hunk| {
+///     let text = new.slice(hunk.new_range()).to_string();
+///     rope.edit(hunk.old_range(), &text)
+/// });
+/// assert_eq!(patched, new);
+/// assert_eq!(hunks.len(), 2);
+/// ```
+pub fn diff(old: &Rope, new: &Rope) -> Vec<Hunk> {
+    let old_lines = old.lines_inclusive().map(|l| l.to_string())
+                       .collect::<Vec<_>>();
+    let new_lines = new.lines_inclusive().map(|l| l.to_string())
+                       .collect::<Vec<_>>();
+    let (a, b) = (&old_lines[..], &new_lines[..]);
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev())
+                             .take_while(|&(x, y)| x == y).count();
+    let (deleted, inserted) = myers( &a[prefix..a.len() - suffix]
+                                   , &b[prefix..b.len() - suffix]);
+
+    // byte offsets of the start of each line
+    let starts = |lines: &[String]| lines.iter().scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some(start)
+    }).chain(Some(lines.iter().map(String::len).sum())).collect::<Vec<_>>();
+    let (a_starts, b_starts) = (starts(a), starts(b));
+
+    // walk both sides together; unchanged lines pair up in order, so each
+    // run of changed lines between them is a hunk
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < deleted.len() || j < inserted.len() {
+        let del = i < deleted.len() && deleted[i];
+        let ins = j < inserted.len() && inserted[j];
+        if !del && !ins {
+            i += 1;
+            j += 1;
+            continue
+        }
+        let (old_start, new_start) = (i, j);
+        while i < deleted.len() && deleted[i] { i += 1; }
+        while j < inserted.len() && inserted[j] { j += 1; }
+        hunks.push(Hunk {
+            old: a_starts[prefix + old_start]..a_starts[prefix + i]
+          , new: b_starts[prefix + new_start]..b_starts[prefix + j]
+        });
+    }
+    hunks
+}
+
+/// Myers' diff algorithm, in linear space.
+///
+/// Returns, for each line of `a`, whether it was deleted, and for each line
+/// of `b`, whether it was inserted.
+///
+/// Rather than keeping the furthest point reached on each diagonal after
+/// every number of edits, which takes O((_n_ + _m_) _d_) space, this finds
+/// the middle snake of an edit script searching from both ends at once, and
+/// then diffs the lines before and after it in the same way, so only two
+/// vectors of O(_n_ + _m_) are kept.
+fn myers(a: &[String], b: &[String]) -> (Vec<bool>, Vec<bool>) {
+    let mut deleted = vec![false; a.len()];
+    let mut inserted = vec![false; b.len()];
+    let max = (a.len() + b.len() + 1) / 2 + 1;
+    let mut forward = vec![0isize; 2 * max + 2];
+    let mut backward = vec![0isize; 2 * max + 2];
+    compare( a, b, &mut deleted, &mut inserted
+           , &mut forward, &mut backward);
+    (deleted, inserted)
+}
+
+/// Marks the lines of `a` which are deleted and the lines of `b` which are
+/// inserted in an edit script from `a` to `b`.
+///
+/// `forward` and `backward` are scratch space for `middle_snake`.
+fn compare( a: &[String], b: &[String]
+          , deleted: &mut [bool], inserted: &mut [bool]
+          , forward: &mut [isize], backward: &mut [isize]) {
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (deleted, inserted) = (&mut deleted[prefix..], &mut inserted[prefix..]);
+    let suffix = a.iter().rev().zip(b.iter().rev())
+                  .take_while(|&(x, y)| x == y).count();
+    let (n, m) = (a.len() - suffix, b.len() - suffix);
+    let (a, b) = (&a[..n], &b[..m]);
+    if n == 0 || m == 0 {
+        for line in &mut deleted[..n] { *line = true; }
+        for line in &mut inserted[..m] { *line = true; }
+        return
+    }
+    let (x, y) = middle_snake(a, b, forward, backward);
+    let (deleted_before, deleted_after) = deleted[..n].split_at_mut(x);
+    let (inserted_before, inserted_after) = inserted[..m].split_at_mut(y);
+    compare( &a[..x], &b[..y], deleted_before, inserted_before
+           , forward, backward);
+    compare( &a[x..], &b[y..], deleted_after, inserted_after
+           , forward, backward);
+}
+
+/// Returns a point on an edit script from `a` to `b` with the fewest edits,
+/// which splits it in two halves with at most one edit between them.
+///
+/// `a` and `b` must both be non-empty, and must neither start nor end with
+/// the same line, so that the point is neither at the start nor at the end.
+/// The furthest point reached on each diagonal _k_ by the searches from the
+/// start and from the end is kept in `vf` and `vb` respectively.
+fn middle_snake( a: &[String], b: &[String]
+               , vf: &mut [isize], vb: &mut [isize])
+               -> (usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let offset = (vf.len() / 2 - 1) as isize;
+    let at = |k: isize| (k + offset) as usize;
+    let delta = n - m;
+    let odd = delta & 1 == 1;
+    vf[at(1)] = 0;
+    vb[at(1)] = 0;
+    for d in 0..(n + m + 1) / 2 + 1 {
+        let mut k = -d;
+        while k <= d {
+            let mut x = if k == -d || (k != d && vf[at(k - 1)] < vf[at(k + 1)])
+                        { vf[at(k + 1)] } else { vf[at(k - 1)] + 1 };
+            let (start_x, start_y) = (x, x - k);
+            let mut y = start_y;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            vf[at(k)] = x;
+            // the backward search reached this diagonal with one fewer edit
+            if odd && (k - delta).abs() < d
+               && x + vb[at(delta - k)] >= n {
+                return (start_x as usize, start_y as usize)
+            }
+            k += 2;
+        }
+        let mut k = -d;
+        while k <= d {
+            // `x` and `y` count lines from the ends of `a` and `b`
+            let mut x = if k == -d || (k != d && vb[at(k - 1)] < vb[at(k + 1)])
+                        { vb[at(k + 1)] } else { vb[at(k - 1)] + 1 };
+            let mut y = x - k;
+            while x < n && y < m
+                  && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            vb[at(k)] = x;
+            if !odd && (k - delta).abs() <= d
+               && x + vf[at(delta - k)] >= n {
+                return ((n - x) as usize, (m - y) as usize)
+            }
+            k += 2;
+        }
+    }
+    unreachable!("an edit script from `a` to `b` always meets in the middle")
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use internals::Node;
+    use super::{diff, myers, retained_bytes, History};
+
+    #[test]
+    fn shared_nodes_are_counted_once() {
+        let rope = Rope::from("abc") + Rope::from("def");
+        let alone = retained_bytes(&[rope.clone()]);
+        assert_eq!(retained_bytes(&[rope.clone(), rope.clone()]), alone);
+        let edited = rope.insert_str(6, "g");
+        let both = retained_bytes(&[rope, edited.clone()]);
+        assert!(both > alone);
+        assert!(both < alone + retained_bytes(&[edited]));
+    }
+
+    #[test]
+    fn budget_prunes_oldest_snapshots() {
+        let big = "x".repeat(10_000);
+        let mut history = History::new(Rope::from("start"));
+        history.record(Rope::from(big.as_str()));
+        for i in 0..100 {
+            // small edits share almost everything with the previous snapshot
+            let next = history.current().insert_str(0, &i.to_string());
+            history.record(next);
+        }
+        assert_eq!(history.len(), 102);
+        // each edit adds a leaf and a branch, and rebalancing a few more
+        // branches, whose size depends on the features enabled
+        let (node, _) = Node::empty().allocated_bytes();
+        let retained = history.retained_bytes();
+        assert!(retained < 2 * 10_000 + 100 * 4 * node);
+
+        // replacing the whole buffer costs a full copy, so the old text
+        // is pruned once it no longer fits
+        history.set_memory_budget(Some(15_000));
+        history.record(Rope::from(big.replace('x', "y")));
+        assert!(history.retained_bytes() <= 15_000);
+        assert_eq!(history.len(), 1);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn running_count_matches_walk() {
+        let walked = |history: &History| retained_bytes(
+            history.snapshots.iter().chain(history.tags.values()));
+        let mut history = History::new(Rope::from("one\ntwo\n"));
+        history.tag("start");
+        for i in 0..20 {
+            let next = history.current().insert_str(i % 5, "x");
+            history.record(next);
+            if i % 3 == 0 { history.undo(); }
+            if i % 7 == 0 { history.tag("seven"); }
+            assert_eq!(history.retained_bytes(), walked(&history));
+        }
+        assert!(history.untag("start"));
+        history.set_memory_budget(Some(walked(&history) / 2));
+        assert_eq!(history.retained_bytes(), walked(&history));
+        assert!(history.untag("seven"));
+        history.set_memory_budget(Some(0));
+        assert_eq!(history.retained_bytes(), walked(&history));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn prune_keeps_every_snapshot_that_fits() {
+        // snapshots which share nothing, so each costs the same
+        let ropes = (0..10).map(|i| Rope::from(i.to_string().repeat(1_000)))
+                           .collect::<Vec<_>>();
+        let one = retained_bytes(&ropes[..1]);
+        let mut history = History::new(ropes[0].clone());
+        for rope in &ropes[1..] { history.record(rope.clone()); }
+        history.set_memory_budget(Some(3 * one + one / 2));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.current(), &ropes[9]);
+        assert_eq!(history.undo(), Some(&ropes[8]));
+
+        // the current snapshot and those after it are never discarded
+        history.undo();
+        history.set_memory_budget(Some(0));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.current(), &ropes[7]);
+        assert!(!history.can_undo());
+        assert_eq!(history.redo(), Some(&ropes[8]));
+    }
+
+    fn patch(old: &Rope, new: &Rope) -> Rope {
+        diff(old, new).iter().rev().fold(old.clone(), |rope, hunk| {
+            let text = new.slice(hunk.new_range()).to_string();
+            rope.edit(hunk.old_range(), &text)
+        })
+    }
+
+    #[test]
+    fn diff_round_trips() {
+        let texts = [ "", "a\n", "a\nb\nc\n", "c\nb\na\n", "a\nb\nc"
+                    , "x\na\ny\nb\nz\nc\n", "\u{3c0}\n\n\na\n", "a\nc\n"];
+        for old in texts.iter() {
+            for new in texts.iter() {
+                let (old, new) = (Rope::from(*old), Rope::from(*new));
+                assert_eq!(patch(&old, &new), new, "{:?} -> {:?}", old, new);
+            }
+        }
+        assert!(diff(&Rope::from("a\nb\n"), &Rope::from("a\nb\n")).is_empty());
+    }
+
+    #[test]
+    fn diff_large_with_scattered_changes() {
+        let (mut old, mut new) = (String::new(), String::new());
+        for i in 0..20_000 {
+            old.push_str(&format!("line {}\n", i));
+            if i % 100 == 50 { new.push_str(&format!("changed {}\n", i)); }
+            else if i % 250 != 1 { new.push_str(&format!("line {}\n", i)); }
+            if i % 333 == 7 { new.push_str(&format!("new {}\n", i)); }
+        }
+        let (old, new) = (Rope::from(old), Rope::from(new));
+        assert_eq!(patch(&old, &new), new);
+
+        // every line is distinct, so the fewest edits touch only the lines
+        // which were changed, deleted or inserted
+        let lines = |r: &Rope| r.lines_inclusive().map(|l| l.to_string())
+                                .collect::<Vec<_>>();
+        let (deleted, inserted) = myers(&lines(&old), &lines(&new));
+        assert_eq!(deleted.iter().filter(|&&d| d).count(), 200 + 80);
+        assert_eq!(inserted.iter().filter(|&&i| i).count(), 200 + 61);
+    }
+
+    #[test]
+    fn tags_outlive_pruning() {
+        let mut history = History::with_memory_budget(Rope::from("a\n"), 0);
+        history.tag("first");
+        history.record(Rope::from("a\nb\n"));
+        history.record(Rope::from("b\n"));
+        history.tag("last");
+        assert!(!history.can_undo());
+        assert_eq!(history.tags().collect::<Vec<_>>(), vec!["first", "last"]);
+        let hunks = history.diff("first", "last").unwrap();
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].old_range(), 0..2);
+        assert!(history.diff("first", "missing").is_none());
+        assert!(history.untag("first"));
+        assert!(history.tagged("first").is_none());
+    }
+
+    #[test]
+    fn record_discards_redo() {
+        let mut history = History::new(Rope::from("a"));
+        history.record(Rope::from("b"));
+        history.undo();
+        history.record(Rope::from("c"));
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(), Some(&Rope::from("a")));
+        assert_eq!(history.len(), 2);
+    }
+}
@@ -0,0 +1,237 @@
+//! Secondary indexes kept consistent with a `Rope` as it is edited.
+//!
+//! Most programs which edit text keep some structures derived from it: the
+//! offsets at which lines start, the locations of symbols, diagnostics from
+//! a linter. Each of these must be updated whenever the text changes. The
+//! [`RopeIndex`] trait describes such a structure, and an [`IndexedRope`]
+//! owns a `Rope` along with any number of `RopeIndex`es, and tells each of
+//! them about every edit as it is made.
+//!
+//! [`RopeIndex`]: trait.RopeIndex.html
+//! [`IndexedRope`]: struct.IndexedRope.html
+use std::any::Any;
+use std::fmt;
+use std::ops;
+
+use super::Rope;
+use super::observed::EditInfo;
+
+/// A structure derived from the text of a `Rope`, which can be updated
+/// incrementally as the `Rope` is edited.
+pub trait RopeIndex {
+    /// Update this index after an edit.
+    ///
+    /// `edit` describes the edit which was made, and `rope` is the text
+    /// after the edit.
+    fn on_edit(&mut self, edit: &EditInfo, rope: &Rope);
+}
+
+/// A `RopeIndex` which can be downcast to its concrete type.
+trait AnyIndex: RopeIndex {
+    fn as_any(&self) -> &Any;
+    fn as_any_mut(&mut self) -> &mut Any;
+}
+
+impl<I> AnyIndex for I
+where I: RopeIndex + Any {
+    #[inline] fn as_any(&self) -> &Any { self }
+    #[inline] fn as_any_mut(&mut self) -> &mut Any { self }
+}
+
+/// A `Rope`, edited in place, along with a set of `RopeIndex`es which are
+/// kept consistent with it.
+///
+/// # Examples
+///
+/// ```
+/// use an_rope::{Rope, IndexedRope, LineStarts};
+/// let rope = Rope::from("one\ntwo\n");
+/// let mut buffer = IndexedRope::new(rope.clone());
+/// buffer.add_index(LineStarts::new(&rope));
+///
+/// buffer.insert_str(4, "one and a half\n");
+/// let lines = buffer.index::<LineStarts>().unwrap();
+/// assert_eq!(lines.starts(), &[0, 4, 19, 23]);
+/// ```
+pub struct IndexedRope { rope: Rope
+                       , indexes: Vec<Box<AnyIndex>>
+                       }
+
+impl IndexedRope {
+    /// Returns a new `IndexedRope` holding `rope`, with no indexes.
+    #[inline]
+    pub fn new(rope: Rope) -> Self {
+        IndexedRope { rope: rope, indexes: Vec::new() }
+    }
+
+    /// Returns the current `Rope`.
+    #[inline] pub fn rope(&self) -> &Rope { &self.rope }
+
+    /// Returns the current `Rope`, consuming this `IndexedRope`.
+    #[inline] pub fn into_rope(self) -> Rope { self.rope }
+
+    /// Register `index` to be updated on every edit.
+    ///
+    /// `index` should already be consistent with the current `Rope`.
+    pub fn add_index<I>(&mut self, index: I)
+    where I: RopeIndex + Any {
+        self.indexes.push(Box::new(index))
+    }
+
+    /// Returns the first registered index of type `I`, if there is one.
+    pub fn index<I>(&self) -> Option<&I>
+    where I: RopeIndex + Any {
+        self.indexes.iter()
+            .filter_map(|index| index.as_any().downcast_ref::<I>())
+            .next()
+    }
+
+    /// Returns a mutable reference to the first registered index of type
+    /// `I`, if there is one.
+    pub fn index_mut<I>(&mut self) -> Option<&mut I>
+    where I: RopeIndex + Any {
+        self.indexes.iter_mut()
+            .filter_map(|index| index.as_any_mut().downcast_mut::<I>())
+            .next()
+    }
+
+    /// Replace the byte range `range` with `replacement`, and update every
+    /// registered index.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than the end, or if the end of
+    /// `range` is greater than the length of the `Rope`.
+    pub fn edit(&mut self, range: ops::Range<usize>, replacement: &str) {
+        self.rope = self.rope.edit(range.clone(), replacement);
+        let edit = EditInfo::new(range, replacement.len());
+        for index in &mut self.indexes {
+            index.on_edit(&edit, &self.rope)
+        }
+    }
+
+    /// Insert `s` at byte index `index`, and update every registered index.
+    ///
+    /// # Panics
+    /// If `index` is greater than the length of the `Rope`.
+    #[inline]
+    pub fn insert_str(&mut self, index: usize, s: &str) {
+        self.edit(index..index, s)
+    }
+
+    /// Delete the byte range `range`, and update every registered index.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than the end, or if the end of
+    /// `range` is greater than the length of the `Rope`.
+    #[inline]
+    pub fn delete(&mut self, range: ops::Range<usize>) {
+        self.edit(range, "")
+    }
+}
+
+impl fmt::Debug for IndexedRope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!( f, "IndexedRope {{ rope: {:?}, indexes: {} }}"
+              , self.rope, self.indexes.len())
+    }
+}
+
+/// A `RopeIndex` of the byte offsets at which each line of a `Rope` starts.
+///
+/// The first line always starts at 0, and each `'\n'` starts a new line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineStarts(Vec<usize>);
+
+impl LineStarts {
+    /// Returns the line starts of `rope`.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    pub fn new(rope: &Rope) -> Self {
+        let mut starts = vec![0];
+        starts.extend(line_starts_in(rope, 0..rope.len()));
+        LineStarts(starts)
+    }
+
+    /// Returns the byte offset at which each line starts, in order.
+    #[inline] pub fn starts(&self) -> &[usize] { &self.0 }
+
+    /// Returns the zero-based line containing byte offset `i`.
+    #[inline]
+    pub fn line_of(&self, i: usize) -> usize {
+        match self.0.binary_search(&i) {
+            Ok(line) => line
+          , Err(line) => line - 1
+        }
+    }
+}
+
+/// Returns the offsets of the line starts produced by the newlines in the
+/// byte range `range` of `rope`.
+fn line_starts_in(rope: &Rope, range: ops::Range<usize>) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut i = range.start;
+    let slice = rope.slice(range);
+    for c in slice.chars() {
+        i += c.len_utf8();
+        if c == '\n' { starts.push(i) }
+    }
+    starts
+}
+
+impl RopeIndex for LineStarts {
+    fn on_edit(&mut self, edit: &EditInfo, rope: &Rope) {
+        let old = edit.old_range();
+        // the lines started by newlines in the removed text are gone, and the
+        // lines after them move by the change in length
+        let after = |i: usize| match self.0.binary_search(&i) {
+            Ok(n) => n + 1
+          , Err(n) => n
+        };
+        let (first, last) = (after(old.start), after(old.end));
+        let shifted = self.0[last..].iter()
+                          .map(|&s| (s as isize + edit.delta()) as usize)
+                          .collect::<Vec<_>>();
+        self.0.truncate(first);
+        self.0.extend(line_starts_in(rope, edit.new_range()));
+        self.0.extend(shifted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::{IndexedRope, LineStarts, RopeIndex};
+    use observed::EditInfo;
+
+    struct EditCount(usize);
+
+    impl RopeIndex for EditCount {
+        fn on_edit(&mut self, _: &EditInfo, _: &Rope) { self.0 += 1 }
+    }
+
+    #[test]
+    fn line_starts_match_rebuilt() {
+        let rope = Rope::from("a\nbc\n\ndef\ng");
+        let mut buffer = IndexedRope::new(rope.clone());
+        buffer.add_index(LineStarts::new(&rope));
+        buffer.add_index(EditCount(0));
+        let edits = [ (0..0, "\n"), (3..7, "x\ny\nz"), (2..2, "")
+                    , (5..9, ""), (0..3, "\u{3c0}\n\n")];
+        for &(ref range, text) in edits.iter() {
+            buffer.edit(range.clone(), text);
+            assert_eq!( buffer.index::<LineStarts>().unwrap()
+                      , &LineStarts::new(buffer.rope()));
+        }
+        assert_eq!(buffer.index::<EditCount>().unwrap().0, edits.len());
+    }
+
+    #[test]
+    fn line_of() {
+        let lines = LineStarts::new(&Rope::from("ab\ncd\n"));
+        assert_eq!(lines.line_of(0), 0);
+        assert_eq!(lines.line_of(2), 0);
+        assert_eq!(lines.line_of(3), 1);
+        assert_eq!(lines.line_of(6), 2);
+    }
+}
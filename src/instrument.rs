@@ -0,0 +1,118 @@
+//! Counts of the work done by `Rope` operations.
+//!
+//! The documentation of each `Rope` method states its time complexity. When
+//! built with the `complexity` feature, this crate counts the tree nodes
+//! allocated by each thread, and the leaves among them along with the bytes
+//! of text those leaves hold, so that those claims can be checked by tests
+//! rather than taken on trust: an edit which is O(log _n_) may only
+//! allocate O(log _n_) new nodes, and copy a bounded amount of text, however
+//! long the `Rope` is. The crate's own complexity tests, which run when it
+//! is tested with the feature enabled, use these counts to check how the
+//! cost of each operation grows as `Rope`s get longer.
+//!
+//! Counting costs a thread-local update for every node allocated, so the
+//! feature is meant for tests, not for release builds.
+use std::ops;
+
+use super::internals;
+
+/// Counts of the allocations made on a thread.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Allocations {
+    /// The number of tree nodes allocated, both branches and leaves.
+    pub nodes: usize
+  , /// The number of leaves allocated.
+    pub leaves: usize
+  , /// The number of bytes of text held by the leaves allocated.
+    ///
+    /// This is the length of the `String` (or other leaf storage) behind
+    /// each new leaf, so it counts the text copied into new leaves. With
+    /// leaf storage which shares its buffer, such as the `bytes` and
+    /// `tendril` features', it is an upper bound on the text copied.
+    pub leaf_bytes: usize
+}
+
+impl ops::Sub for Allocations {
+    type Output = Allocations;
+
+    #[inline]
+    fn sub(self, rhs: Allocations) -> Allocations {
+        Allocations { nodes: self.nodes - rhs.nodes
+                    , leaves: self.leaves - rhs.leaves
+                    , leaf_bytes: self.leaf_bytes - rhs.leaf_bytes
+                    }
+    }
+}
+
+/// Returns the counts of the allocations made on the current thread since
+/// it started.
+///
+/// # Examples
+/// ```
+/// use an_rope::{Rope, instrument};
+/// let before = instrument::allocations();
+/// let rope = Rope::from("abc");
+/// let allocated = instrument::allocations() - before;
+/// assert_eq!(allocated.leaves, 1);
+/// assert_eq!(allocated.leaf_bytes, 3);
+/// # drop(rope);
+/// ```
+#[inline]
+pub fn allocations() -> Allocations {
+    let (nodes, leaves, leaf_bytes) = internals::allocated();
+    Allocations { nodes: nodes, leaves: leaves, leaf_bytes: leaf_bytes }
+}
+
+/// Returns the number of tree nodes allocated on the current thread since
+/// it started.
+///
+/// # Examples
+/// ```
+/// use an_rope::{Rope, instrument};
+/// let before = instrument::node_allocations();
+/// let rope = Rope::from("abc");
+/// assert!(instrument::node_allocations() > before);
+/// # drop(rope);
+/// ```
+#[inline]
+pub fn node_allocations() -> usize { allocations().nodes }
+
+/// Calls `f`, returning its result along with the counts of the allocations
+/// it made on the current thread.
+///
+/// # Examples
+/// ```
+/// use an_rope::{Rope, instrument};
+/// let rope = Rope::from("hello world");
+/// let (longer, allocated) = instrument::count_allocations(|| {
+///     rope.insert_str(5, ",")
+/// });
+/// assert!(allocated.leaf_bytes <= rope.len() + 1);
+/// assert_eq!(longer, "hello, world");
+/// ```
+pub fn count_allocations<F, T>(f: F) -> (T, Allocations)
+where F: FnOnce() -> T {
+    let before = allocations();
+    let result = f();
+    (result, allocations() - before)
+}
+
+/// Calls `f`, returning its result along with the number of tree nodes it
+/// allocated on the current thread.
+///
+/// # Examples
+/// ```
+/// use an_rope::{Rope, instrument};
+/// let rope = Rope::from("hello world");
+/// let (copy, allocated) = instrument::count_node_allocations(|| {
+///     rope.clone()
+/// });
+/// assert_eq!(allocated, 0);
+/// assert_eq!(copy, rope);
+/// ```
+#[inline]
+pub fn count_node_allocations<F, T>(f: F) -> (T, usize)
+where F: FnOnce() -> T {
+    let (result, allocated) = count_allocations(f);
+    (result, allocated.nodes)
+}
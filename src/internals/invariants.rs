@@ -0,0 +1,247 @@
+//! Checking the internal invariants of a tree of `Node`s.
+//!
+//! Every `Node` caches the measurements of its subrope the first time they
+//! are requested. A bug which builds a `Node` from the wrong children, or
+//! which reuses a `Node` after its text has changed, shows up as a cached
+//! measurement that no longer matches the text beneath it. These checks
+//! recompute each measurement from the node's children and compare.
+//!
+//! The shape of the tree is checked too: no leaf may be empty, except the
+//! single leaf of an empty `Rope`, no leaf may start or end within a
+//! `char`, and the tree must be balanced, so that its depth is logarithmic
+//! in its length.
+use metric::{Measured, Metric, Line, CharIndex};
+#[cfg(feature = "unicode")] use metric::Grapheme;
+
+use super::{Node, Value};
+use super::node::Value::*;
+
+use std::error;
+use std::fmt;
+
+/// An error describing a broken invariant in the tree of a `Rope`.
+///
+/// This is returned by [`Rope::check_invariants`]. If you see one of these,
+/// you have found a bug in this crate. Each variant holds the path from the
+/// root of the tree to the node which broke the invariant, such as
+/// `"root.left.right"`.
+///
+/// [`Rope::check_invariants`]: struct.Rope.html#method.check_invariants
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvariantError {
+    /// A measurement cached by a node, such as its length, its weight or
+    /// its line count, differs from the measurement recomputed from its
+    /// children or its text.
+    Measurement { /// The path to the node
+                  path: String
+                , /// A description of the cached and actual measurements
+                  message: String }
+  , /// A leaf holds no text. Only the single leaf of an empty `Rope` may be
+    /// empty; a `Rope` with any text has no empty leaves.
+    EmptyLeaf { /// The path to the leaf
+                path: String }
+  , /// The text of a leaf starts or ends part way through a `char`, so that
+    /// the `char` is split between two leaves.
+    NotCharBoundary { /// The path to the leaf
+                      path: String }
+  , /// The tree is deeper than a balanced tree of its length may be: a
+    /// tree of depth _d_ must hold at least _F(d + 2)_ bytes, where _F(i)_
+    /// is the _i_th Fibonacci number.
+    Unbalanced { /// The path to the root of the tree
+                 path: String
+               , /// The depth of the tree
+                 depth: usize
+               , /// The length of the tree's text, in bytes
+                 len: usize }
+}
+
+impl InvariantError {
+    /// Returns the path from the root of the tree to the node which broke
+    /// the invariant, such as `"root.left.right"`.
+    #[inline]
+    pub fn path(&self) -> &str {
+        match *self {
+            InvariantError::Measurement { ref path, .. }
+          | InvariantError::EmptyLeaf { ref path }
+          | InvariantError::NotCharBoundary { ref path }
+          | InvariantError::Unbalanced { ref path, .. } => path
+        }
+    }
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invariant broken at {}: ", self.path())?;
+        match *self {
+            InvariantError::Measurement { ref message, .. } =>
+                write!(f, "{}", message)
+          , InvariantError::EmptyLeaf { .. } => write!(f, "empty leaf")
+          , InvariantError::NotCharBoundary { .. } =>
+                write!(f, "leaf starts or ends within a char")
+          , InvariantError::Unbalanced { depth, len, .. } =>
+                write!(f, "depth {} is too deep for length {}", depth, len)
+        }
+    }
+}
+
+impl error::Error for InvariantError {
+    fn description(&self) -> &str {
+        match *self {
+            InvariantError::Measurement { ref message, .. } => message
+          , InvariantError::EmptyLeaf { .. } => "empty leaf"
+          , InvariantError::NotCharBoundary { .. } =>
+                "leaf starts or ends within a char"
+          , InvariantError::Unbalanced { .. } => "unbalanced tree"
+        }
+    }
+}
+
+/// Returns true if `bytes`, which are otherwise UTF-8, start and end on
+/// `char` boundaries.
+fn on_char_boundaries(bytes: &[u8]) -> bool {
+    let is_continuation = |b: u8| b & 0xC0 == 0x80;
+    if bytes.first().map_or(false, |&b| is_continuation(b)) { return false }
+    // the last `char` is whole if as many bytes follow its first byte as
+    // that byte says it has
+    match bytes.iter().rposition(|&b| !is_continuation(b)) {
+        None => bytes.is_empty()
+      , Some(start) => {
+            let width = match bytes[start] {
+                b if b < 0x80 => 1
+              , b if b < 0xE0 => 2
+              , b if b < 0xF0 => 3
+              , _ => 4
+            };
+            bytes.len() - start == width
+        }
+    }
+}
+
+/// Check that the cached measurement of `node` in the metric `M` matches the
+/// measurement recomputed from its value.
+fn check_metric<M>(node: &Node, path: &str, name: &str)
+                  -> Result<(), InvariantError>
+where M: Metric
+    , Node: Measured<M>
+    , Value: Measured<M>
+    {
+    let (cached, actual): (M, M) = (node.measure(), node.value.measure());
+    if cached != actual {
+        return Err(InvariantError::Measurement {
+            path: path.to_owned()
+          , message: format!( "cached {} {:?} != actual {} {:?}"
+                            , name, cached, name, actual)
+        })
+    }
+    let (cached, actual): (M, M) =
+        (node.measure_weight(), node.value.measure_weight());
+    if cached != actual {
+        return Err(InvariantError::Measurement {
+            path: path.to_owned()
+          , message: format!( "cached {} weight {:?} != actual {} weight {:?}"
+                            , name, cached, name, actual)
+        })
+    }
+    Ok(())
+}
+
+impl Node {
+    /// Check the internal invariants of the tree rooted at this `Node`.
+    ///
+    /// `path` names this node in any error which is returned.
+    pub fn check_invariants(&self, path: &str) -> Result<(), InvariantError> {
+        match self.value {
+            // the empty `Rope` is a single empty leaf
+            Leaf(_) if self.is_empty() => return Ok(())
+          , _ => self.check_subtree(path)?
+        }
+        if !self.is_balanced() {
+            return Err(InvariantError::Unbalanced { path: path.to_owned()
+                                                  , depth: self.depth()
+                                                  , len: self.len()
+                                                  })
+        }
+        Ok(())
+    }
+
+    /// Check the invariants of each node in this `Node`'s subtree.
+    fn check_subtree(&self, path: &str) -> Result<(), InvariantError> {
+        match self.value {
+            Branch { ref left, ref right } => {
+                // check the children first, so that the cached measurements
+                // of this node can be recomputed from theirs
+                left.check_subtree(&format!("{}.left", path))?;
+                right.check_subtree(&format!("{}.right", path))?;
+            }
+          , Leaf(_) if self.is_empty() =>
+                return Err(InvariantError::EmptyLeaf { path: path.to_owned() })
+          , Leaf(ref s) if !on_char_boundaries(s.as_bytes()) =>
+                return Err(InvariantError::NotCharBoundary {
+                    path: path.to_owned()
+                })
+          , Leaf(_) => {}
+        }
+        check_metric::<usize>(self, path, "length")?;
+        check_metric::<CharIndex>(self, path, "char count")?;
+        check_metric::<Line>(self, path, "line count")?;
+        #[cfg(feature = "unicode")]
+        check_metric::<Grapheme>(self, path, "grapheme count")?;
+        if self.is_ascii() != self.value.is_ascii() {
+            return Err(InvariantError::Measurement {
+                path: path.to_owned()
+              , message: format!( "cached ASCII flag {} is wrong"
+                                , self.is_ascii())
+            })
+        }
+        if self.newline_count() != self.value.newline_count() {
+            return Err(InvariantError::Measurement {
+                path: path.to_owned()
+              , message: format!( "cached newline count {} != actual {}"
+                                , self.newline_count()
+                                , self.value.newline_count())
+            })
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::{InvariantError, on_char_boundaries};
+    use super::super::{Node, NodeLink, Value};
+
+    #[test]
+    fn empty_leaves_are_found() {
+        // `new_branch` drops empty nodes, so build the branch by hand
+        let root = NodeLink::new(
+            Value::new_branch(NodeLink::from("ab"), Node::empty()));
+        assert_eq!( Rope { root: root }.check_invariants()
+                  , Err(InvariantError::EmptyLeaf {
+                        path: "root.right".to_owned()
+                    }));
+        assert_eq!(Rope::new().check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn split_chars_are_found() {
+        assert!(on_char_boundaries(b""));
+        assert!(on_char_boundaries("a\u{3c0}\u{1f192}".as_bytes()));
+        let pi = "\u{3c0}".as_bytes();
+        assert!(!on_char_boundaries(&pi[..1]));
+        assert!(!on_char_boundaries(&pi[1..]));
+        assert!(!on_char_boundaries(&"a\u{1f192}".as_bytes()[..4]));
+    }
+
+    #[test]
+    fn unbalanced_trees_are_found() {
+        let root = (1..8).map(|_| NodeLink::from("a"))
+                         .fold(NodeLink::from("a"), Node::new_branch);
+        assert_eq!( Rope { root: root }.check_invariants()
+                  , Err(InvariantError::Unbalanced { path: "root".to_owned()
+                                                   , depth: 7
+                                                   , len: 8
+                                                   }));
+        assert_eq!(Rope::from("a".repeat(8)).check_invariants(), Ok(()));
+    }
+}
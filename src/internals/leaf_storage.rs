@@ -0,0 +1,32 @@
+//! The storage of the text in each leaf.
+//!
+//! The type which holds a leaf's text, `LeafRepr`, is chosen by the cargo
+//! features the crate is built with. The tree only reads a leaf's text as a
+//! `str`, and asks a few questions about the lines in it through the
+//! [`LeafStorage`] trait, so a storage type which indexes its text can
+//! answer them without scanning it. The default answers scan the text.
+//!
+//! [`LeafStorage`]: trait.LeafStorage.html
+use std::ops;
+
+/// A type which can hold the text of a leaf.
+pub trait LeafStorage: ops::Deref<Target=str> {
+    /// Returns the number of `'\n'`s in this leaf.
+    #[inline]
+    fn newline_count(&self) -> usize {
+        self.as_bytes().iter().filter(|&&b| b == b'\n').count()
+    }
+
+    /// Returns the byte index just after the `n`th `'\n'` (counting from
+    /// zero) in this leaf, if it has that many.
+    #[inline]
+    fn after_newline(&self, n: usize) -> Option<usize> {
+        self.match_indices('\n').nth(n).map(|(i, _)| i + 1)
+    }
+
+    /// Returns the number of `'\n'`s before byte index `i` in this leaf.
+    #[inline]
+    fn newlines_before(&self, i: usize) -> usize {
+        self.as_bytes()[..i].iter().filter(|&&b| b == b'\n').count()
+    }
+}
@@ -0,0 +1,167 @@
+//! Leaves which index their lines.
+//!
+//! When built with the `line-leaves` feature, each leaf holds a run of
+//! whole lines, along with the byte index at which each of them ends, so a
+//! rope is a tree of blocks of lines. Finding a line within a leaf is then a
+//! binary search rather than a scan, which suits workloads dominated by
+//! line operations, such as processing logs, at the cost of a `usize` per
+//! line.
+use metric::{Measured, Line, ByteIndex, CharIndex};
+#[cfg(feature = "unicode")] use metric::Grapheme;
+use super::leaf_storage::LeafStorage;
+
+use std::borrow::Borrow;
+use std::convert;
+use std::fmt;
+use std::ops;
+
+/// The text of a leaf, with the byte index after each `'\n'` in it.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct LineLeaf { text: String
+                    , line_ends: Vec<usize>
+                    }
+
+impl LineLeaf {
+    /// Returns a new, empty `LineLeaf`.
+    #[inline]
+    pub fn new() -> Self { LineLeaf::default() }
+}
+
+impl LeafStorage for LineLeaf {
+    #[inline]
+    fn newline_count(&self) -> usize { self.line_ends.len() }
+
+    #[inline]
+    fn after_newline(&self, n: usize) -> Option<usize> {
+        self.line_ends.get(n).cloned()
+    }
+
+    #[inline]
+    fn newlines_before(&self, i: usize) -> usize {
+        // a newline is before `i` if the line it ends ends at or before `i`
+        match self.line_ends.binary_search(&i) {
+            Ok(n) => n + 1
+          , Err(n) => n
+        }
+    }
+}
+
+impl ops::Deref for LineLeaf {
+    type Target = str;
+    #[inline] fn deref(&self) -> &str { &self.text }
+}
+
+impl AsRef<str> for LineLeaf {
+    #[inline] fn as_ref(&self) -> &str { &self.text }
+}
+
+impl Borrow<str> for LineLeaf {
+    #[inline] fn borrow(&self) -> &str { &self.text }
+}
+
+impl convert::From<String> for LineLeaf {
+    fn from(text: String) -> Self {
+        let line_ends = text.match_indices('\n').map(|(i, _)| i + 1)
+                            .collect();
+        LineLeaf { text: text, line_ends: line_ends }
+    }
+}
+
+impl<'a> convert::From<&'a str> for LineLeaf {
+    #[inline] fn from(text: &'a str) -> Self {
+        LineLeaf::from(text.to_owned())
+    }
+}
+
+impl fmt::Debug for LineLeaf {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.text, f)
+    }
+}
+
+impl fmt::Display for LineLeaf {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.text, f)
+    }
+}
+
+macro_rules! forward_measured {
+    ($($(#[$attr:meta])* $metric:ty),*) => { $(
+        $(#[$attr])*
+        impl Measured<$metric> for LineLeaf {
+            #[inline] fn to_byte_index(&self, index: $metric) -> Option<usize> {
+                self.text.as_str().to_byte_index(index)
+            }
+            #[inline] fn measure(&self) -> $metric {
+                self.text.as_str().measure()
+            }
+            #[inline] fn measure_weight(&self) -> $metric {
+                self.text.as_str().measure_weight()
+            }
+        }
+    )* }
+}
+
+forward_measured!(usize, ByteIndex, CharIndex
+                 , #[cfg(feature = "unicode")] Grapheme);
+
+// `str` only counts a line ending at its end, since leaves split from a
+// `String` end at a newline; a `LineLeaf` may hold many lines.
+impl Measured<Line> for LineLeaf {
+    fn to_byte_index(&self, index: Line) -> Option<usize>  {
+        let Line(n) = index;
+        self.after_newline(n).or_else(|| if n == self.newline_count() {
+            Some(self.len())
+        } else {
+            None
+        })
+    }
+
+    #[inline]
+    fn measure(&self) -> Line { Line(self.newline_count()) }
+
+    #[inline] fn measure_weight(&self) -> Line { self.measure() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineLeaf;
+    use super::super::leaf_storage::LeafStorage;
+    use metric::{Line, Measured};
+
+    /// The default implementations of `LeafStorage`'s methods, which scan
+    /// the text.
+    struct Scanned<'a>(&'a str);
+
+    impl<'a> ::std::ops::Deref for Scanned<'a> {
+        type Target = str;
+        fn deref(&self) -> &str { self.0 }
+    }
+
+    impl<'a> LeafStorage for Scanned<'a> {}
+
+    #[test]
+    fn index_matches_scanning() {
+        for text in &["", "a", "\n", "ab\ncd\n\nef", "\n\u{3c0}\n"] {
+            let leaf = LineLeaf::from(*text);
+            let scanned = Scanned(text);
+            assert_eq!(leaf.newline_count(), scanned.newline_count());
+            for n in 0..5 {
+                assert_eq!(leaf.after_newline(n), scanned.after_newline(n));
+            }
+            for i in 0..text.len() + 1 {
+                assert_eq!( leaf.newlines_before(i)
+                          , scanned.newlines_before(i));
+            }
+        }
+    }
+
+    #[test]
+    fn measures_every_line() {
+        let leaf = LineLeaf::from("ab\ncd\n\nef");
+        assert_eq!(Measured::<Line>::measure(&leaf), Line(3));
+        assert_eq!(leaf.to_byte_index(Line(1)), Some(6));
+        assert_eq!(leaf.to_byte_index(Line(3)), Some(9));
+        assert_eq!(leaf.to_byte_index(Line(4)), None);
+    }
+}
@@ -1,21 +1,70 @@
+#[cfg(feature = "unicode")]
 use unicode_segmentation::UnicodeSegmentation;
+#[cfg(feature = "unicode")]
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
+#[cfg(feature = "unicode")]
 use unicode_segmentation::{ GraphemeIndices as StrGraphemeIndices
                           , UWordBoundIndices as StrUWordBoundIndices
                           };
-use metric::{Metric, Measured};
+use metric::{Metric, Measured, CharIndex};
+use builder::DEFAULT_CHUNK_SIZE;
 
 use std::ops;
 use std::fmt;
 use std::convert;
-use std::borrow::{Borrow, ToOwned};
+use std::borrow::{Borrow, Cow, ToOwned};
+#[cfg(feature = "complexity")] use std::cell::Cell;
 
 #[cfg(test)] mod test;
 
 mod node;
 pub use self::node::*;
 
-#[cfg(feature = "atomic")]      use std::sync::Arc;
-#[cfg(not(feature = "atomic"))] use std::rc::Rc;
+mod invariants;
+pub use self::invariants::InvariantError;
+
+mod leaf_storage;
+pub use self::leaf_storage::LeafStorage;
+
+#[cfg(not(any( feature = "tendril", feature = "bytes", feature = "smallstr"
+             , feature = "line-leaves")))]
+mod str_leaf;
+#[cfg(not(any( feature = "tendril", feature = "bytes", feature = "smallstr"
+             , feature = "line-leaves")))]
+pub use self::str_leaf::StrLeaf;
+
+#[cfg(feature = "bytes")] mod utf8_bytes;
+#[cfg(feature = "bytes")] pub use self::utf8_bytes::Utf8Bytes;
+
+#[cfg(feature = "line-leaves")] mod line_leaf;
+#[cfg(feature = "line-leaves")] pub use self::line_leaf::LineLeaf;
+
+#[cfg(feature = "node-pool")] #[allow(unsafe_code)] mod pool;
+#[cfg(feature = "node-pool")] pub use self::pool::NodePool;
+
+#[cfg(feature = "atomic")]      use std::sync::{Arc, Weak};
+#[cfg(not(feature = "atomic"))] use std::rc::{Rc, Weak};
+
+/// The reference-counted pointer type used to link `Node`s together.
+///
+/// By default, this is `Rc`, which is faster, but not thread-safe. When built
+/// with the `atomic` feature, this is `Arc` instead, so that `Rope`s may be
+/// shared between threads. When built with the `node-pool` feature, nodes
+/// are allocated with a `NodePool`.
+#[cfg(not(any(feature = "atomic", feature = "node-pool")))]
+type Link<T> = Rc<T>;
+#[cfg(all(feature = "atomic", not(feature = "node-pool")))]
+type Link<T> = Arc<T>;
+#[cfg(all(not(feature = "atomic"), feature = "node-pool"))]
+type Link<T> = Rc<T, NodePool>;
+#[cfg(all(feature = "atomic", feature = "node-pool"))]
+type Link<T> = Arc<T, NodePool>;
+
+/// The weak counterpart of `Link`.
+#[cfg(not(feature = "node-pool"))]
+type WeakLink<T> = Weak<T>;
+#[cfg(feature = "node-pool")]
+type WeakLink<T> = Weak<T, NodePool>;
 
 #[cfg(feature = "tendril")]
 use tendril;
@@ -26,8 +75,50 @@ use tendril::{Atomic, fmt as tendril_fmt};
 
 use self::node::Value::*;
 
-#[cfg(not(feature = "tendril"))]
-type LeafRepr = String;
+#[cfg(feature = "smallstr")]
+use smallstr::SmallString;
+
+#[cfg(any( all(feature = "tendril", feature = "bytes")
+         , all(feature = "tendril", feature = "smallstr")
+         , all(feature = "bytes", feature = "smallstr")
+         , all(feature = "line-leaves", feature = "tendril")
+         , all(feature = "line-leaves", feature = "bytes")
+         , all(feature = "line-leaves", feature = "smallstr") ))]
+compile_error!("only one of the `tendril`, `bytes`, `smallstr` and \
+                `line-leaves` features may be enabled");
+
+#[cfg(not(any( feature = "tendril", feature = "bytes", feature = "smallstr"
+             , feature = "line-leaves")))]
+type LeafRepr = StrLeaf;
+
+#[cfg(feature = "line-leaves")]
+type LeafRepr = LineLeaf;
+
+// every storage type but `LineLeaf` scans its text to find lines
+#[cfg(not(feature = "line-leaves"))]
+impl LeafStorage for LeafRepr {}
+
+/// Leaves of up to 24 bytes are stored inline in the `Node`, rather than in
+/// a separate heap allocation.
+#[cfg(feature = "smallstr")]
+type LeafRepr = SmallString<[u8; 24]>;
+
+#[cfg(feature = "smallstr")]
+impl<M> Measured<M> for LeafRepr
+where M: Metric
+    , str: Measured<M>
+    {
+        #[inline] fn to_byte_index(&self, index: M) -> Option<usize> {
+            self.as_str().to_byte_index(index)
+        }
+        #[inline] fn measure(&self) -> M { self.as_str().measure() }
+        #[inline] fn measure_weight(&self) -> M {
+             self.as_str().measure_weight()
+         }
+    }
+
+#[cfg(feature = "bytes")]
+type LeafRepr = Utf8Bytes;
 
 #[cfg(all(feature = "tendril", not(feature = "atomic") ))]
 type LeafRepr = StrTendril;
@@ -35,13 +126,34 @@ type LeafRepr = StrTendril;
 #[cfg(all(feature = "tendril", feature = "atomic"))]
 type LeafRepr = tendril::Tendril<tendril_fmt::UTF8, Atomic>;
 
-#[cfg(not(feature = "atomic"))]
+/// A reference-counted link to a `Node`.
+///
+/// Cloning a `NodeLink` only increments the reference count of the `Node` it
+/// points to; the `Node` itself is never copied.
 #[derive(Clone)]
-pub struct NodeLink(Rc<Node>);
+pub struct NodeLink(Link<Node>);
 
-#[cfg(feature = "atomic")]
+/// A weak link to a `Node`, which does not keep the `Node` alive.
 #[derive(Clone)]
-pub struct NodeLink(Arc<Node>);
+pub struct WeakNodeLink(WeakLink<Node>);
+
+impl WeakNodeLink {
+    /// Returns a `NodeLink` to the `Node`, if it has not been dropped.
+    #[inline]
+    pub fn upgrade(&self) -> Option<NodeLink> { self.0.upgrade().map(NodeLink) }
+
+    /// Returns true if this link points to the same `Node` as `other`.
+    #[inline]
+    pub fn ptr_eq(&self, other: &NodeLink) -> bool {
+        WeakLink::as_ptr(&self.0) == Link::as_ptr(&other.0)
+    }
+}
+
+impl fmt::Debug for WeakNodeLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WeakNodeLink({:p})", WeakLink::as_ptr(&self.0))
+    }
+}
 
 // impl<T> convert::From<T> for NodeLink
 // where Node: convert::From<T> {
@@ -50,37 +162,111 @@ pub struct NodeLink(Arc<Node>);
 //     }
 // }
 
-#[cfg(feature = "tendril")]
+#[cfg(not(any(feature = "bytes", feature = "line-leaves")))]
 impl convert::From<String> for NodeLink {
-    #[inline] fn from(string: String) -> Self {
-        if string.is_empty() {
-            NodeLink::default()
-        } else {
-            let mut strings = string.rsplit('\n');
-            let last = Node::new_leaf(strings.next().unwrap());
-            strings.map(|s| {
-                        let mut r = LeafRepr::from_slice(s);
-                        r.push_char('\n');
-                        Node::new_leaf(r)
-                    })
-                 .fold(last, |r, l| Node::new_branch(l, r))
+    fn from(string: String) -> Self {
+        if string.is_empty() { return NodeLink::default() }
+        // one leaf per line, built into a balanced tree: a tree with a leaf
+        // per line is far too deep to build by folding, or to rebalance
+        let mut leaves = Vec::new();
+        let mut start = 0;
+        for (i, _) in string.match_indices('\n') {
+            leaves.push(Node::new_leaf(&string[start..i + 1]));
+            start = i + 1;
+        }
+        // text ending with a newline has no leaf after it
+        if start < string.len() {
+            leaves.push(Node::new_leaf(&string[start..]));
         }
+        Node::from_nodes(leaves)
     }
 }
-#[cfg(not(feature = "tendril")) ]
+
+#[cfg(feature = "line-leaves")]
+impl convert::From<String> for NodeLink {
+    fn from(string: String) -> Self {
+        if string.is_empty() { return NodeLink::default() }
+        // collect whole lines into leaves of up to `DEFAULT_CHUNK_SIZE`
+        // bytes, so that each leaf indexes many lines; a longer line gets a
+        // leaf of its own
+        let mut leaves = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+        for line in string.split_terminator('\n') {
+            let line_end = ::std::cmp::min( end + line.len() + 1
+                                          , string.len());
+            if line_end - start > DEFAULT_CHUNK_SIZE && end > start {
+                leaves.push(Node::new_leaf(&string[start..end]));
+                start = end;
+            }
+            end = line_end;
+        }
+        leaves.push(Node::new_leaf(&string[start..]));
+        Node::from_nodes(leaves)
+    }
+}
+
+#[cfg(feature = "bytes")]
 impl convert::From<String> for NodeLink {
     #[inline] fn from(string: String) -> Self {
-        if string.is_empty() {
+        NodeLink::from(Utf8Bytes::from(string))
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl convert::From<Utf8Bytes> for NodeLink {
+    fn from(bytes: Utf8Bytes) -> Self {
+        if bytes.is_empty() {
             NodeLink::default()
         } else {
-            let mut strings = string.rsplit('\n');
-            let last = Node::new_leaf(strings.next().unwrap());
-            strings.map(|s| Node::new_leaf(LeafRepr::from(s) + "\n"))
-                   .fold(last, |r, l| Node::new_branch(l, r))
+            // split the buffer after each newline, as for `String`s, but
+            // make each leaf a view into the original buffer
+            // text ending with a newline has no leaf after it
+            let mut starts = vec![0];
+            starts.extend(bytes.match_indices('\n').map(|(i, _)| i + 1)
+                               .filter(|&start| start < bytes.len()));
+            starts.push(bytes.len());
+            Node::from_nodes(starts.windows(2).map(|w| {
+                Node::new_leaf(bytes.slice(w[0]..w[1]))
+            }))
         }
     }
 }
 
+impl NodeLink {
+    /// Returns a `NodeLink` to the static text `s`, split after each
+    /// newline, as for `String`s, with leaves which borrow `s` rather than
+    /// copying it.
+    #[cfg(not(any( feature = "tendril", feature = "bytes"
+                 , feature = "smallstr", feature = "line-leaves")))]
+    pub fn from_static(s: &'static str) -> Self {
+        if s.is_empty() { return NodeLink::default() }
+        // text ending with a newline has no leaf after it
+        let mut starts = vec![0];
+        starts.extend(s.match_indices('\n').map(|(i, _)| i + 1)
+                       .filter(|&start| start < s.len()));
+        starts.push(s.len());
+        Node::from_nodes(starts.windows(2).map(|w| {
+            Node::new_leaf(StrLeaf::from_static(&s[w[0]..w[1]]))
+        }))
+    }
+
+    /// Returns a `NodeLink` to the static text `s`, with leaves which are
+    /// views into it rather than copies.
+    #[cfg(feature = "bytes")]
+    #[inline]
+    pub fn from_static(s: &'static str) -> Self {
+        NodeLink::from(Utf8Bytes::from_static(s))
+    }
+
+    /// Returns a `NodeLink` to a copy of the text `s`, since this leaf
+    /// storage type can't borrow it.
+    #[cfg(any( feature = "tendril", feature = "smallstr"
+             , feature = "line-leaves"))]
+    #[inline]
+    pub fn from_static(s: &'static str) -> Self { NodeLink::from(s) }
+}
+
 impl<'a, S: ?Sized> convert::From<&'a S> for NodeLink
 where String: Borrow<S>
     , S: ToOwned<Owned=String> {
@@ -93,30 +279,263 @@ where String: Borrow<S>
 
 #[cfg(feature = "tendril")]
 impl convert::From<LeafRepr> for NodeLink {
-    #[inline] fn from(string: LeafRepr) -> Self {
-        if string.is_empty() {
-            NodeLink::default()
-        } else {
-            let mut strings = string.rsplit('\n');
-            let last = Node::new_leaf(strings.next().unwrap());
-            strings.map(|s| {
-                    let mut r = LeafRepr::from_slice(s);
-                    r.push_char('\n');
-                    Node::new_leaf(r)
-                })
-                  .fold(last, |r, l| Node::new_branch(l, r))
+    fn from(string: LeafRepr) -> Self {
+        if string.is_empty() { return NodeLink::default() }
+        // as for `String`s, one leaf per line, built into a balanced tree
+        let mut leaves = Vec::new();
+        let mut start = 0;
+        for (i, _) in string.match_indices('\n') {
+            leaves.push(Node::new_leaf(&string[start..i + 1]));
+            start = i + 1;
         }
+        // text ending with a newline has no leaf after it
+        if start < string.len() {
+            leaves.push(Node::new_leaf(&string[start..]));
+        }
+        Node::from_nodes(leaves)
     }
 }
 
+#[cfg(feature = "complexity")]
+thread_local! {
+    /// The numbers of `Node`s and of leaves allocated on this thread, and
+    /// the number of bytes of text held by those leaves.
+    static ALLOCATED: Cell<(usize, usize, usize)> = Cell::new((0, 0, 0));
+}
+
+/// Returns the numbers of `Node`s and of leaves allocated on this thread so
+/// far, and the number of bytes of text held by those leaves.
+#[cfg(feature = "complexity")]
+#[inline]
+pub fn allocated() -> (usize, usize, usize) { ALLOCATED.with(Cell::get) }
+
+/// Counts the allocation of `node` on this thread.
+#[cfg(feature = "complexity")]
+#[inline]
+fn count_allocation(node: &Node) {
+    let (leaves, bytes) = match node.value {
+        Leaf(ref s) => (1, s.len())
+      , Branch { .. } => (0, 0)
+    };
+    ALLOCATED.with(|n| {
+        let (nodes, all_leaves, all_bytes) = n.get();
+        n.set((nodes + 1, all_leaves + leaves, all_bytes + bytes))
+    });
+}
+
 impl NodeLink {
-    #[cfg(not(feature = "atomic"))]
+    #[cfg(not(feature = "node-pool"))]
     pub fn new<N>(node: N) -> Self
-    where N: convert::Into<Node> { NodeLink(Rc::new(node.into())) }
+    where N: convert::Into<Node> {
+        let node = node.into();
+        #[cfg(feature = "complexity")]
+        count_allocation(&node);
+        NodeLink(Link::new(node))
+    }
 
-    #[cfg(feature = "atomic")]
+    #[cfg(feature = "node-pool")]
     pub fn new<N>(node: N) -> Self
-    where N: convert::Into<Node> { NodeLink(Arc::new(node.into())) }
+    where N: convert::Into<Node> {
+        let node = node.into();
+        #[cfg(feature = "complexity")]
+        count_allocation(&node);
+        NodeLink(Link::new_in(node, NodePool))
+    }
+
+    /// Returns true if `self` and `other` point to the same `Node`.
+    #[inline]
+    pub fn ptr_eq(&self, other: &NodeLink) -> bool {
+        Link::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns true if `self` and `other` are trees of the same shape, whose
+    /// corresponding leaves have the same text.
+    ///
+    /// Shared subtrees are not descended into, and subtrees whose cached
+    /// lengths, depths or leaf counts differ are rejected without visiting
+    /// their leaves.
+    pub fn structural_eq(&self, other: &NodeLink) -> bool {
+        if self.ptr_eq(other) { return true }
+        if self.len() != other.len() || self.depth() != other.depth()
+            || self.leaf_count() != other.leaf_count() {
+            return false
+        }
+        match (&self.value, &other.value) {
+            (&Leaf(ref a), &Leaf(ref b)) => {
+                let (a, b): (&str, &str) = (a.as_ref(), b.as_ref());
+                a == b
+            }
+          , ( &Branch { left: ref a_left, right: ref a_right }
+            , &Branch { left: ref b_left, right: ref b_right }) =>
+                a_left.structural_eq(b_left) && a_right.structural_eq(b_right)
+          , _ => false
+        }
+    }
+
+    /// Returns the address of the `Node` this link points to, which
+    /// identifies it for as long as the link is alive.
+    #[inline]
+    pub fn as_ptr(&self) -> *const Node { Link::as_ptr(&self.0) }
+
+    /// Returns the number of strong links to this `Node`.
+    #[inline]
+    pub fn strong_count(&self) -> usize { Link::strong_count(&self.0) }
+
+    /// Returns the bytes allocated for this `Node`, not counting its
+    /// children, as the bytes of the node itself (including its reference
+    /// counts) and the bytes of its text, if it is a leaf.
+    pub fn allocated_bytes(&self) -> (usize, usize) {
+        let node = ::std::mem::size_of::<Node>()
+                 + 2 * ::std::mem::size_of::<usize>();
+        match self.value {
+            Leaf(_) => (node, self.len())
+          , Branch { .. } => (node, 0)
+        }
+    }
+
+    /// Returns a weak link to this `Node`.
+    #[inline]
+    pub fn downgrade(&self) -> WeakNodeLink {
+        WeakNodeLink(Link::downgrade(&self.0))
+    }
+
+    /// Returns true if this subrope has more than one leaf, and its leaves
+    /// are shorter than `mean_leaf` bytes on average.
+    #[inline]
+    pub fn is_fragmented(&self, mean_leaf: usize) -> bool {
+        let leaves = self.leaf_count();
+        leaves > 1 && self.len() / leaves < mean_leaf
+    }
+
+    /// Returns a copy of this subrope with runs of short adjacent leaves
+    /// merged into leaves of up to `DEFAULT_CHUNK_SIZE` bytes.
+    ///
+    /// Leaves are merged greedily from left to right, so any two adjacent
+    /// leaves of the result are together longer than `DEFAULT_CHUNK_SIZE`.
+    /// Leaves which are already at least that long are shared with `self`,
+    /// and empty leaves are dropped. The result is balanced.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    pub fn compact(&self) -> NodeLink {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        let mut compacted = Vec::new();
+        let mut buf = String::new();
+        for leaf in leaves {
+            let text = leaf.leaf_at(0).0;
+            if !buf.is_empty() && buf.len() + text.len() > DEFAULT_CHUNK_SIZE {
+                let chunk = ::std::mem::replace(&mut buf, String::new());
+                compacted.push(NodeLink::from(chunk));
+            }
+            if text.len() >= DEFAULT_CHUNK_SIZE {
+                compacted.push(leaf.clone());
+            } else {
+                buf.push_str(text);
+            }
+        }
+        if !buf.is_empty() { compacted.push(NodeLink::from(buf)); }
+        Node::from_nodes(compacted)
+    }
+
+    /// Returns a copy of this subrope with some of its leaves rewritten.
+    ///
+    /// `f` is called on the text of each leaf, from left to right. If it
+    /// returns `Some`, the leaf is replaced with the returned text; if it
+    /// returns `None`, the leaf is kept. Subtrees in which no leaf was
+    /// replaced are shared with `self`, rather than copied.
+    pub fn map_leaves<F>(&self, f: &mut F) -> NodeLink
+    where F: FnMut(&str) -> Option<String> {
+        match self.value {
+            Leaf(ref s) => match f(s.as_ref()) {
+                Some(string) => NodeLink::from(string)
+              , None => self.clone()
+            }
+          , Branch { ref left, ref right } => {
+                let new_left = left.map_leaves(f);
+                let new_right = right.map_leaves(f);
+                if new_left.ptr_eq(left) && new_right.ptr_eq(right) {
+                    self.clone()
+                } else {
+                    Node::new_branch(new_left, new_right)
+                }
+            }
+        }
+    }
+
+    /// Returns the subrope of this subrope between byte indices `start` and
+    /// `end`.
+    ///
+    /// Subtrees which lie wholly within the range are shared with `self`;
+    /// only the leaves at either end of the range which it covers in part
+    /// are copied.
+    ///
+    /// # Panics
+    /// If either index does not fall on a character boundary.
+    pub fn subrope(&self, start: usize, end: usize) -> NodeLink {
+        if start == 0 && end == self.len() { return self.clone() }
+        if start == end { return NodeLink::default() }
+        match self.value {
+            Leaf(ref s) => {
+                let s: &str = s.as_ref();
+                assert!( s.is_char_boundary(start) && s.is_char_boundary(end)
+                       , "Node::subrope: range {}..{} of leaf is not on char \
+                          boundaries", start, end);
+                NodeLink::from(&s[start..end])
+            }
+          , Branch { ref left, ref right } => {
+                let mid = left.len();
+                if end <= mid {
+                    left.subrope(start, end)
+                } else if start >= mid {
+                    right.subrope(start - mid, end - mid)
+                } else {
+                    Node::new_branch( left.subrope(start, mid)
+                                    , right.subrope(0, end - mid))
+                }
+            }
+        }
+    }
+
+    /// Split this subrope after each occurrence of `delimiter`, pushing each
+    /// completed piece, including its delimiter, onto `pieces`.
+    ///
+    /// `current` holds the subtrees of the piece which is still being built,
+    /// which continues into the text after this subrope. Subtrees for which
+    /// `may_contain` returns false are not searched for `delimiter`, but are
+    /// added to the current piece whole, sharing them with `self`.
+    pub fn split_inclusive_into<F>( &self, delimiter: char, may_contain: &F
+                                  , current: &mut Vec<NodeLink>
+                                  , pieces: &mut Vec<NodeLink>)
+    where F: Fn(&Node) -> bool {
+        if !may_contain(self) {
+            current.push(self.clone());
+            return
+        }
+        match self.value {
+            Branch { ref left, ref right } => {
+                left.split_inclusive_into( delimiter, may_contain
+                                         , current, pieces);
+                right.split_inclusive_into( delimiter, may_contain
+                                          , current, pieces);
+            }
+          , Leaf(ref s) => {
+                let s: &str = s.as_ref();
+                let part = |start: usize, end: usize| {
+                    if start == 0 && end == s.len() { self.clone() }
+                    else { NodeLink::from(&s[start..end]) }
+                };
+                let mut start = 0;
+                for (i, _) in s.match_indices(delimiter) {
+                    let end = i + delimiter.len_utf8();
+                    current.push(part(start, end));
+                    pieces.push(Node::from_nodes(current.drain(..)));
+                    start = end;
+                }
+                if start < s.len() { current.push(part(start, s.len())); }
+            }
+        }
+    }
 
     /// Rebalance the subrope starting at this `Node`, returning a new `Node`
     ///
@@ -128,7 +547,7 @@ impl NodeLink {
     ///
     /// > The concatenation of the sequence of ropes in order of decreasing
     /// > length is equivalent to the prefix of the rope we have traversed so
-    /// > far. Each new leaf _x_ is with_insert_ropeed into the appropriate entry of the
+    /// > far. Each new leaf _x_ is inserted into the appropriate entry of the
     /// > sequence. Assume that _x_’s length is in the interval [_Fn_, _Fn_+1),
     /// > and thus it should be put in slot _n_ (which also corresponds to
     /// > maximum depth _n_ − 2). If all lower and equal numbered levels are
@@ -138,32 +557,94 @@ impl NodeLink {
     /// > concatenate ropes from the sequence in increasing order to the left
     /// > of this result, until the result fits into an empty slot in the
     /// > sequence."
+    ///
+    /// Subtrees which are already balanced are kept whole, rather than split
+    /// into their leaves, so they are shared with `self`, unless they are
+    /// much deeper than the number of their leaves requires. A long leaf
+    /// allows a balanced subtree to be deep, so without that limit, a chain
+    /// of short leaves prepended to one would be kept whole, and could only
+    /// be rebalanced by rebuilding the whole tree. Concatenating the ropes
+    /// in the sequence still does not always produce a balanced rope; if it
+    /// does not, the tree is instead rebuilt from its leaves with
+    /// `from_nodes`, whose depth is logarithmic in the number of leaves.
     pub fn rebalance(self) -> Self {
-        // TODO: this is a huge mess, I based it on the IBM Java implementation
-        //       please refactor until it stops being ugly!
-        //        - eliza, 12/17/2016
+        if self.is_balanced() { return self }
+        // `forest[i]` holds a balanced rope whose length is in the interval
+        // [F(i + 2), F(i + 3)), and the ropes in the forest, from the last
+        // to the first, hold the text visited so far
+        let mut forest = Vec::new();
+        self.add_to_forest(&mut forest);
+        let node = forest.into_iter().filter_map(|node| node)
+                         .fold(None, |right, left| Some(match right {
+                             Some(right) => Node::new_branch(left, right)
+                           , None => left
+                         }))
+                         .unwrap_or_else(Node::empty);
+        if node.is_balanced() { return node }
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        Node::from_nodes(leaves)
+    }
 
-        if self.is_balanced() {
-            // the subrope is already balanced, do nothing
-            self
-        } else {
-            // let mut leaves: Vec<Option<Node>> =
-            //     self.into_leaves().map(Option::Some).collect();
-            // let len = leaves.len();
-            // fn _rebalance(l: &mut Vec<Option<Node>>, start: usize, end: usize)
-            //               -> Node {
-            //     match end - start {
-            //         1 => l[start].take().unwrap()
-            //       , 2 => l[start].take().unwrap() + l[start + 1].take().unwrap()
-            //       , n => {
-            //             let mid = start + (n / 2);
-            //             _rebalance(l, start, mid) + _rebalance(l, mid, end)
-            //
-            //         }
-            //     }
-            // };
-            // _rebalance(&mut leaves, 0, len)
-            self
+    /// Pushes links to the leaves of this subrope onto `leaves`, in order.
+    fn collect_leaves(&self, leaves: &mut Vec<NodeLink>) {
+        match self.value {
+            Leaf(_) => leaves.push(self.clone())
+          , Branch { ref left, ref right } => {
+                left.collect_leaves(leaves);
+                right.collect_leaves(leaves);
+            }
+        }
+    }
+
+    /// Adds the balanced subtrees of this subrope to `forest`, from left to
+    /// right, as described in `rebalance`.
+    fn add_to_forest(&self, forest: &mut Vec<Option<NodeLink>>) {
+        // the number of bits in the leaf count, so a subtree is kept whole
+        // if it is no more than about twice as deep as `from_nodes` would
+        // make it
+        let bits = 64 - (self.leaf_count() as u64).leading_zeros() as usize;
+        let whole = self.is_balanced() && self.depth() < 2 * bits;
+        match self.value {
+            Branch { ref left, ref right } if !whole => {
+                left.add_to_forest(forest);
+                right.add_to_forest(forest);
+                return
+            }
+          , _ if self.is_empty() => return
+          , _ => {}
+        }
+        let len = self.len();
+        // concatenate the ropes in the slots for shorter lengths...
+        let mut i = 0;
+        let mut prefix = None;
+        while fibonacci(i + 3) <= len && i < forest.len() {
+            if let Some(node) = forest[i].take() {
+                prefix = Some(match prefix {
+                    Some(prefix) => Node::new_branch(node, prefix)
+                  , None => node
+                });
+            }
+            i += 1;
+        }
+        // ...onto the left of this one...
+        let mut node = match prefix {
+            Some(prefix) => Node::new_branch(prefix, self.clone())
+          , None => self.clone()
+        };
+        while fibonacci(i + 3) <= len { i += 1; }
+        // ...then keep concatenating onto the left until it fits in an
+        // empty slot
+        loop {
+            if forest.len() <= i { forest.resize(i + 1, None); }
+            if let Some(left) = forest[i].take() {
+                node = Node::new_branch(left, node);
+            }
+            if node.len() < fibonacci(i + 3) {
+                forest[i] = Some(node);
+                return
+            }
+            i += 1;
         }
     }
 
@@ -190,7 +671,9 @@ impl NodeLink {
             Leaf(_) if self.is_empty() =>
                 // splitting an empty leaf node returns two empty leaf nodes
                 (Node::empty(), Node::empty())
-          , Leaf(_) if self.measure().into() == 1 =>
+          , Leaf(_) if index.into() != 0 && self.measure().into() == 1 =>
+                // splitting a leaf of length 1 anywhere but the beginning
+                // returns the whole leaf on the left
                 (self.clone(), Node::empty())
           , Leaf(ref s) => {
                 // splitting a leaf node with length >= 2 returns two new Leaf
@@ -198,8 +681,19 @@ impl NodeLink {
                 // the right
                 // TODO: make this properly respect metric index boundaries
                 let index = self.to_byte_index(index).expect("invalid index!");
-                let left = Leaf(s[..index].into());
-                let right = Leaf(s[index..].into());
+                // never cut a leaf inside a UTF-8 sequence, whatever the
+                // metric; this is checked here so that every operation
+                // built on `split` rejects such indices the same way
+                assert!( s.is_char_boundary(index)
+                       , "Node::split: byte index {} of leaf is not a char \
+                          boundary", index);
+                #[cfg(not(feature = "bytes"))]
+                let (left, right) = ( Leaf(s[..index].into())
+                                    , Leaf(s[index..].into()) );
+                // splitting a `Bytes`-backed leaf shares its buffer
+                #[cfg(feature = "bytes")]
+                let (left, right) = ( Leaf(s.slice(0..index))
+                                    , Leaf(s.slice(index..s.len())) );
                 (NodeLink::new(left), NodeLink::new(right))
             }
           , Branch { ref left, ref right }
@@ -301,33 +795,134 @@ where M: Metric
         #[inline] fn measure_weight(&self) -> M { self.0.measure_weight() }
     }
 
-#[cfg(feature = "rebalance")]
-const FIB_LOOKUP: [usize; 93] = [
+const FIB_LOOKUP: [u64; 93] = [
  0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144, 233, 377, 610, 987, 1597, 2584, 4181, 6765, 10946, 17711, 28657, 46368, 75025, 121393, 196418, 317811, 514229, 832040, 1346269, 2178309, 3524578, 5702887, 9227465, 14930352, 24157817, 39088169, 63245986, 102334155, 165580141, 267914296, 433494437, 701408733, 1134903170, 1836311903, 2971215073, 4807526976, 7778742049, 12586269025, 20365011074, 32951280099, 53316291173, 86267571272, 139583862445, 225851433717, 365435296162, 591286729879, 956722026041, 1548008755920, 2504730781961, 4052739537881, 6557470319842, 10610209857723, 17167680177565, 27777890035288, 44945570212853, 72723460248141, 117669030460994, 190392490709135, 308061521170129, 498454011879264, 806515533049393, 1304969544928657, 2111485077978050, 3416454622906707, 5527939700884757, 8944394323791464, 14472334024676221, 23416728348467685, 37889062373143906, 61305790721611591, 99194853094755497, 160500643816367088, 259695496911122585, 420196140727489673, 679891637638612258, 1100087778366101931, 1779979416004714189, 2880067194370816120, 4660046610375530309, 7540113804746346429 ];
 
 
-/// Returns the _n_th fibonacci number.
+/// Returns the _n_th fibonacci number, or `usize::MAX` if it is too large
+/// to be the length of a `Rope`.
 #[inline]
-#[cfg(feature = "rebalance")]
 fn fibonacci(n: usize) -> usize {
-    if n <= 93 { FIB_LOOKUP[n] }
-    else { fibonacci(n - 1) + fibonacci(n - 2) }
+    match FIB_LOOKUP.get(n) {
+        Some(&f) if f <= usize::max_value() as u64 => f as usize
+      , _ => usize::max_value()
+    }
+}
+
+/// Returns true if there is a UAX#29 word boundary between `before` and
+/// `after` whatever text surrounds them, and no rule looks across it.
+///
+/// These are the boundaries around a line break (WB3a and WB3b), and before
+/// a space which doesn't follow another space (spaces only join each other,
+/// under WB3d). Text on either side of one of these can be segmented on its
+/// own.
+#[cfg(feature = "unicode")]
+fn is_word_anchor(before: Option<char>, after: Option<char>) -> bool {
+    match (before, after) {
+        (None, _) | (_, None) => true
+      , (Some('\r'), Some('\n')) => false
+      , (Some('\r'), _) | (Some('\n'), _) => true
+      , (_, Some('\r')) | (_, Some('\n')) => true
+      , (Some(' '), Some(' ')) => false
+      , (_, Some(' ')) => true
+      , _ => false
+    }
+}
+
+thread_local! {
+    /// An empty leaf, shared by every empty `Node` created on this thread.
+    static EMPTY: NodeLink = NodeLink::new(Leaf(LeafRepr::new()));
 }
 
 impl Node {
 
+    /// Returns an empty leaf node.
+    ///
+    /// Each thread keeps a single empty leaf, which is shared by every call
+    /// to this function, so creating an empty `Rope` does not allocate.
     #[inline]
     pub fn empty() -> NodeLink {
-        NodeLink::new(Leaf(LeafRepr::new()))
+        EMPTY.with(NodeLink::clone)
+    }
+
+    /// Build a tree concatenating a sequence of `Node`s, in order.
+    ///
+    /// Rather than folding the nodes together one at a time, which would
+    /// produce a degenerate tree, adjacent nodes are merged pairwise, level by
+    /// level. The resulting tree is at most ⌈log₂ _n_⌉ levels deeper than
+    /// the deepest of the _n_ nodes. Empty nodes are skipped.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    pub fn from_nodes<I>(nodes: I) -> NodeLink
+    where I: IntoIterator<Item=NodeLink> {
+        let mut level = nodes.into_iter()
+                             .filter(|node| !node.is_empty())
+                             .collect::<Vec<_>>();
+        while level.len() > 1 {
+            let mut next = Vec::with_capacity((level.len() + 1) / 2);
+            let mut nodes = level.into_iter();
+            while let Some(left) = nodes.next() {
+                next.push(match nodes.next() {
+                    Some(right) => Node::new_branch(left, right)
+                  , None => left
+                });
+            }
+            level = next;
+        }
+        level.pop().unwrap_or_else(Node::empty)
     }
 
     /// Concatenate two `Node`s to return a new `Branch` node.
+    ///
+    /// If either `Node` is empty, the other is returned instead, so that
+    /// the tree of a `Rope` never holds an empty leaf.
     #[inline]
     pub fn new_branch<A, B>(left: A, right: B) -> NodeLink
     where A: convert::Into<NodeLink>
         , B: convert::Into<NodeLink>
         {
-        NodeLink::new(Value::new_branch(left.into(), right.into()))
+        let (left, right) = (left.into(), right.into());
+        if left.is_empty() { return right }
+        if right.is_empty() { return left }
+        NodeLink::new(Value::new_branch(left, right))
+    }
+
+    /// Concatenate two `Node`s, merging short leaves rather than branching.
+    ///
+    /// If `left` and `right` are both leaves which together are at most
+    /// `DEFAULT_CHUNK_SIZE` bytes long, their text is copied into a single
+    /// new leaf. If only one of them is such a leaf, it is merged with the
+    /// adjacent leaf of the other, if that is short enough. Otherwise, this
+    /// is the same as `new_branch`. This is used by `Rope::append_compact`,
+    /// so building a `Rope` that way one character at a time does not
+    /// produce a tree of one-character leaves.
+    ///
+    /// Leaves may be shared with other `Rope`s, so they are never extended
+    /// in place; since only short leaves are merged, the copying is bounded
+    /// by the chunk size.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn concat(left: &NodeLink, right: &NodeLink) -> NodeLink {
+        if left.is_empty() { return right.clone() }
+        if right.is_empty() { return left.clone() }
+        let len = left.len() + right.len();
+        match (&left.value, &right.value) {
+            (&Leaf(ref l), &Leaf(ref r)) if len <= DEFAULT_CHUNK_SIZE => {
+                let mut merged = String::with_capacity(len);
+                merged.push_str(l.as_ref());
+                merged.push_str(r.as_ref());
+                NodeLink::from(merged)
+            }
+          , (&Branch { left: ref ll, right: ref lr }, &Leaf(_))
+                if lr.len() + right.len() <= DEFAULT_CHUNK_SIZE =>
+                Node::new_branch(ll.clone(), Node::concat(lr, right))
+          , (&Leaf(_), &Branch { left: ref rl, right: ref rr })
+                if left.len() + rl.len() <= DEFAULT_CHUNK_SIZE =>
+                Node::new_branch(Node::concat(left, rl), rr.clone())
+          , _ => Node::new_branch(left.clone(), right.clone())
+        }
     }
 
     #[inline]
@@ -346,38 +941,14 @@ impl Node {
     /// > have length at least 2. Note that balanced ropes may contain
     /// > unbalanced subropes.
     /// – from "Ropes: An Alternative to Strings"
-    #[inline]
-    #[cfg(feature = "rebalance")]
-    pub fn is_balanced(&self) -> bool {
-        self.len() >= fibonacci(self.depth() + 2)
-        // true
-    }
-
-    /// Returns true if this node is balanced
     ///
-    /// > We define the depth of a leaf to be 0, and the depth of a
-    /// > concatenation to be one plus the maximum depth of its children. Let
-    /// > _Fn_ be the _n_th Fibonacci number. A rope of depth _n_ is balanced if
-    /// > its length is at least _Fn_+2, e.g. a balanced rope of depth 1 must
-    /// > have length at least 2. Note that balanced ropes may contain
-    /// > unbalanced subropes.
-    /// – from "Ropes: An Alternative to Strings"
+    /// A leaf, including an empty one, is always balanced. Since the depth
+    /// of each node is cached, this is O(1) for a subrope which has already
+    /// been checked, and only visits the new nodes of an edited one.
     #[inline]
-    #[cfg(not(feature = "rebalance"))]
     pub fn is_balanced(&self) -> bool {
-        true
-    }
-
-    /// Returns the depth in the tree of a node
-    #[inline]
-    #[cfg(feature = "rebalance")]
-    fn depth(&self) -> usize {
-        use std::cmp::max;
-
-        match *self { Leaf(_) => 0
-                    , Branch(BranchNode { ref left, ref right, .. }) =>
-                        max(left.depth(), right.depth()) + 1
-                    }
+        let depth = self.depth();
+        depth == 0 || self.len() >= fibonacci(depth + 2)
     }
 
 
@@ -390,56 +961,6 @@ impl Node {
     #[inline] pub fn is_empty(&self) -> bool { self.len() == 0 }
 
 
-    /// Rebalance the subrope starting at this `Node`, returning a new `Node`
-    ///
-    /// From "Ropes: An Alternative to Strings":
-    /// > "The rebalancing operation maintains an ordered sequence of (empty
-    /// > or) balanced ropes, one for each length interval [_Fn_, _Fn_+1), for
-    /// > _n_ >= 2. We traverse the rope from left to right, inserting each
-    /// > leaf into the appropriate sequence position, depending on its length.
-    ///
-    /// > The concatenation of the sequence of ropes in order of decreasing
-    /// > length is equivalent to the prefix of the rope we have traversed so
-    /// > far. Each new leaf _x_ is with_insert_ropeed into the appropriate entry of the
-    /// > sequence. Assume that _x_’s length is in the interval [_Fn_, _Fn_+1),
-    /// > and thus it should be put in slot _n_ (which also corresponds to
-    /// > maximum depth _n_ − 2). If all lower and equal numbered levels are
-    /// > empty, then this can be done directly. If not, then we concatenate
-    /// > ropes in slots 2, ... ,(_n_ − 1) (concatenating onto the left), and
-    /// > concatenate _x_ to the right of the result. We then continue to
-    /// > concatenate ropes from the sequence in increasing order to the left
-    /// > of this result, until the result fits into an empty slot in the
-    /// > sequence."
-    pub fn rebalance(self) -> Self {
-        // TODO: this is a huge mess, I based it on the IBM Java implementation
-        //       please refactor until it stops being ugly!
-        //        - eliza, 12/17/2016
-
-        if self.is_balanced() {
-            // the subrope is already balanced, do nothing
-            self
-        } else {
-            // let mut leaves: Vec<Option<Node>> =
-            //     self.into_leaves().map(Option::Some).collect();
-            // let len = leaves.len();
-            // #[inline]
-            // fn _rebalance(l: &mut Vec<Option<Node>>, start: usize, end: usize)
-            //               -> Node {
-            //     match end - start {
-            //         1 => l[start].take().unwrap()
-            //       , 2 => l[start].take().unwrap() + l[start + 1].take().unwrap()
-            //       , n => {
-            //             let mid = start + (n / 2);
-            //             _rebalance(l, start, mid) + _rebalance(l, mid, end)
-            //
-            //         }
-            //     }
-            // };
-            // _rebalance(&mut leaves, 0, len)
-            self
-        }
-    }
-
     /// Returns an iterator that performs an in-order traversal over all the
     /// `Nodes` in this `Node`'s subtree
     #[inline]
@@ -453,6 +974,13 @@ impl Node {
         Leaves(vec![self])
     }
 
+    /// Returns an iterator over all leaf nodes in this `Node`'s subrope, in
+    /// reverse order
+    #[inline]
+    fn leaves_rev(&self) -> LeavesRev {
+        LeavesRev(vec![self])
+    }
+
     // /// Returns a move iterator over all leaf nodes in this `Node`'s subrope
     // #[inline]
     // fn into_leaves(self) -> IntoLeaves {
@@ -472,6 +1000,18 @@ impl Node {
             })
         }
 
+        #[doc=
+            "Returns an iterator over all the strings in this `Node`s subrope, \
+             starting from the last one."]
+        #[inline]
+        pub fn strings_rev<'a>(&'a self) -> impl Iterator<Item=&'a str> + 'a {
+            self.leaves_rev().map(|n| match **n {
+                Leaf(ref s) => s.as_ref()
+              , _ => unreachable!("Node.leaves_rev() iterator contained \
+                                   something that wasn't a leaf.")
+            })
+        }
+
         #[inline]
         pub fn char_indices<'a>(&'a self)
                                -> impl Iterator<Item=(usize, char)> + 'a {
@@ -592,6 +1132,7 @@ impl Node {
              (http://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)\
              recommends extended grapheme cluster boundaries for general \
              processing."]
+        #[cfg(feature = "unicode")]
         #[inline]
         impl graphemes for Node { extend }
     }
@@ -607,6 +1148,7 @@ impl Node {
             [Alphabetic](http://unicode.org/reports/tr44/#Alphabetic) \
             property, or with [General_Category=Number]\
             (http://unicode.org/reports/tr44/#General_Category_Values)."]
+        #[cfg(feature = "unicode")]
         #[inline]
         impl unicode_words for Node {}
         #[doc=
@@ -615,10 +1157,519 @@ impl Node {
             (http://www.unicode.org/reports/tr29/#Word_Boundaries). \n\n\
             The concatenation of the substrings returned by this function is \
             just the original string."]
+        #[cfg(feature = "unicode")]
         #[inline]
         impl split_word_bounds for Node {}
     }
 
+    /// Returns the byte index just after the `n`th `'\n'` (counting from
+    /// zero) in this `Node`'s subrope.
+    ///
+    /// # Returns
+    /// - `Some` with the index after the newline
+    /// - `None` if there are not more than `n` newlines
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn after_newline(&self, n: usize) -> Option<usize> {
+        if n >= self.newline_count() { return None }
+        let (mut node, mut n, mut offset) = (self, n, 0);
+        loop {
+            match node.value {
+                Leaf(ref s) =>
+                    return s.after_newline(n).map(|i| offset + i)
+              , Branch { ref left, ref right } => {
+                    let in_left = left.newline_count();
+                    if n < in_left {
+                        node = left;
+                    } else {
+                        n -= in_left;
+                        offset += left.len();
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the number of `'\n'`s before byte index `i` in this `Node`'s
+    /// subrope.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn newlines_before(&self, i: usize) -> usize {
+        let (mut node, mut i, mut count) = (self, i, 0);
+        loop {
+            match node.value {
+                Leaf(ref s) => return count + s.newlines_before(i)
+              , Branch { ref left, ref right } => {
+                    let weight = left.len();
+                    if i <= weight {
+                        node = left;
+                    } else {
+                        i -= weight;
+                        count += left.newline_count();
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the byte index of the `n`th `char` (counting from zero) in
+    /// this `Node`'s subrope.
+    ///
+    /// # Returns
+    /// - `Some` with the byte index, or the length of the subrope if `n` is
+    ///   equal to the number of `char`s in it
+    /// - `None` if `n` is greater than the number of `char`s
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn char_to_byte(&self, n: usize) -> Option<usize> {
+        let (mut node, mut n, mut offset) = (self, n, 0);
+        loop {
+            if node.is_ascii() {
+                // every char is one byte, so we can stop descending
+                return if n <= node.len() { Some(offset + n) } else { None }
+            }
+            match node.value {
+                Leaf(ref s) => {
+                    let s: &str = s.as_ref();
+                    return s.char_indices().map(|(i, _)| i)
+                            .chain(Some(s.len()))
+                            .nth(n)
+                            .map(|i| offset + i)
+                }
+              , Branch { ref left, ref right } => {
+                    let CharIndex(in_left) = left.measure();
+                    if n < in_left {
+                        node = left;
+                    } else {
+                        n -= in_left;
+                        offset += left.len();
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Measures the prefix of this `Node`'s subrope before each byte offset
+    /// in `offsets`, by metric `M`, storing each measurement in `out`.
+    ///
+    /// Each element of `offsets` is an index into `out` and a byte offset,
+    /// relative to the start of the `Rope`, which is `start` bytes before
+    /// the start of this subrope. `offsets` must be sorted by offset, and
+    /// `before` is the measure of the text before this subrope.
+    ///
+    /// Only the subtrees containing an offset are visited, so this is
+    /// O(_k_ log _n_) for _k_ offsets, plus the time to measure the part of
+    /// each leaf before each offset.
+    pub fn measure_prefixes<M>( &self, start: usize, before: M
+                              , offsets: &[(usize, usize)], out: &mut [M])
+    where M: Metric
+        , Node: Measured<M>
+        , str: Measured<M>
+        {
+        if offsets.is_empty() { return }
+        match self.value {
+            Leaf(ref s) => {
+                let s: &str = s.as_ref();
+                for &(i, offset) in offsets {
+                    out[i] = before + s[..offset - start].measure();
+                }
+            }
+          , Branch { ref left, ref right } => {
+                let mid = start + left.len();
+                let split = offsets.iter().position(|&(_, o)| o >= mid)
+                                   .unwrap_or(offsets.len());
+                let (in_left, in_right) = offsets.split_at(split);
+                left.measure_prefixes(start, before, in_left, out);
+                right.measure_prefixes( mid, before + left.measure()
+                                      , in_right, out);
+            }
+        }
+    }
+
+    /// Returns the string in the leaf node containing byte index `i`, along
+    /// with the byte offset of the start of that leaf in this `Node`'s subrope.
+    ///
+    /// If `i` is equal to the length of this `Node`, the last leaf is
+    /// returned.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn leaf_at(&self, i: usize) -> (&str, usize) {
+        let mut node = self;
+        let mut offset = 0;
+        loop {
+            match node.value {
+                Leaf(ref s) => return (s.as_ref(), offset)
+              , Branch { ref left, ref right } => {
+                    // walk left if the index falls within the left subtree's
+                    // weight, otherwise walk right and shift the offset.
+                    let weight = left.len();
+                    if i - offset < weight {
+                        node = left;
+                    } else {
+                        offset += weight;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns an iterator over the text of the leaves of this `Node`'s
+    /// subrope from byte index `start`, from left to right, along with the
+    /// byte offset of the start of each leaf's text.
+    ///
+    /// The text of the leaf containing `start` is cut off before `start`.
+    ///
+    /// # Time complexity
+    /// O(log _n_) to find the leaf containing `start`, and O(1) on average
+    /// for each leaf after that.
+    pub fn strings_from(&self, start: usize) -> StringsFrom {
+        let mut stack = Vec::new();
+        let (mut node, mut offset) = (self, 0);
+        // walk down to the leaf holding the byte at `start`, keeping the
+        // right subtree of each node passed on the left, to visit later
+        while start < self.len() {
+            match node.value {
+                Branch { ref left, ref right } => {
+                    let mid = offset + left.len();
+                    if start < mid {
+                        stack.push((&**right, mid));
+                        node = left;
+                    } else {
+                        offset = mid;
+                        node = right;
+                    }
+                }
+              , Leaf(_) => { stack.push((node, offset)); break }
+            }
+        }
+        StringsFrom { stack: stack, start: start }
+    }
+
+    /// Returns an iterator over the text of the leaves of this `Node`'s
+    /// subrope before byte index `end`, from right to left, along with the
+    /// byte offset of the start of each leaf.
+    ///
+    /// The text of the leaf containing `end` is cut off at `end`.
+    ///
+    /// # Time complexity
+    /// O(log _n_) to find the leaf containing `end`, and O(1) on average for
+    /// each leaf after that.
+    pub fn strings_before(&self, end: usize) -> StringsBefore {
+        let mut stack = Vec::new();
+        let (mut node, mut start) = (self, 0);
+        // walk down to the leaf holding the byte before `end`, keeping the
+        // left subtree of each node passed on the right, to visit later
+        while end > start {
+            match node.value {
+                Branch { ref left, ref right } if end - start > left.len() => {
+                    stack.push((&**left, start));
+                    start += left.len();
+                    node = right;
+                }
+              , Branch { ref left, .. } => node = left
+              , Leaf(_) => { stack.push((node, start)); break }
+            }
+        }
+        StringsBefore { stack: stack, end: end }
+    }
+
+    /// Returns the byte index of the next grapheme cluster boundary after
+    /// byte index `i` in this `Node`'s subrope.
+    ///
+    /// Only the leaves containing the grapheme cluster at `i` are examined,
+    /// so clusters which span multiple leaves are handled correctly.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the next boundary
+    /// - `None` if `i` is at the end of this subrope
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme cluster)
+    #[cfg(feature = "unicode")]
+    pub fn next_grapheme_boundary(&self, i: usize) -> Option<usize> {
+        let mut cursor = GraphemeCursor::new(i, self.len(), true);
+        let (mut chunk, mut chunk_start) = self.leaf_at(i);
+        loop {
+            match cursor.next_boundary(chunk, chunk_start) {
+                Ok(boundary) => return boundary
+              , Err(GraphemeIncomplete::NextChunk) => {
+                    let (next, next_start) =
+                        self.leaf_at(chunk_start + chunk.len());
+                    chunk = next;
+                    chunk_start = next_start;
+                }
+              , Err(GraphemeIncomplete::PreContext(n)) => {
+                    let (ctx, ctx_start) = self.leaf_at(n - 1);
+                    cursor.provide_context(&ctx[..n - ctx_start], ctx_start);
+                }
+              , Err(e) => panic!( "next_grapheme_boundary: invalid index {} \
+                                   ({:?})", i, e)
+            }
+        }
+    }
+
+    /// Returns true if byte index `i` in this `Node`'s subrope falls on a
+    /// grapheme cluster boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[cfg(feature = "unicode")]
+    pub fn is_grapheme_boundary(&self, i: usize) -> bool {
+        let len = self.len();
+        if i == 0 || i == len { return true }
+        let mut cursor = GraphemeCursor::new(i, len, true);
+        let (leaf, leaf_start) = self.leaf_at(i);
+        let joined;
+        let (chunk, chunk_start) = if i == leaf_start {
+            // if `i` is the first index in a leaf, the cursor needs to see the
+            // character before `i` in the same chunk as the character after
+            // it, so join the two together.
+            let (prev, prev_start) = self.leaf_at(i - 1);
+            let before = prev[..i - prev_start].chars().next_back()
+                             .expect("is_grapheme_boundary: empty leaf!");
+            let after = leaf.chars().next()
+                            .expect("is_grapheme_boundary: empty leaf!");
+            joined = format!("{}{}", before, after);
+            (&joined[..], i - before.len_utf8())
+        } else {
+            (leaf, leaf_start)
+        };
+        loop {
+            match cursor.is_boundary(chunk, chunk_start) {
+                Ok(is_boundary) => return is_boundary
+              , Err(GraphemeIncomplete::PreContext(n)) => {
+                    let (ctx, ctx_start) = self.leaf_at(n - 1);
+                    cursor.provide_context(&ctx[..n - ctx_start], ctx_start);
+                }
+              , Err(e) => panic!( "is_grapheme_boundary: invalid index {} \
+                                   ({:?})", i, e)
+            }
+        }
+    }
+
+    /// Returns the byte index of the previous grapheme cluster boundary
+    /// before byte index `i` in this `Node`'s subrope.
+    ///
+    /// Only the leaves containing the grapheme cluster before `i` are
+    /// examined, so clusters which span multiple leaves are handled correctly.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the previous boundary
+    /// - `None` if `i` is at the beginning of this subrope
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme cluster)
+    #[cfg(feature = "unicode")]
+    pub fn prev_grapheme_boundary(&self, i: usize) -> Option<usize> {
+        if i == 0 { return None }
+        let mut cursor = GraphemeCursor::new(i, self.len(), true);
+        // the cursor needs a chunk which starts before `i`, so start with
+        // the leaf holding the character before it
+        let (leaf, leaf_start) = self.leaf_at(i - 1);
+        let before = leaf[..i - leaf_start].chars().next_back()
+                         .expect("prev_grapheme_boundary: empty leaf!");
+        let joined;
+        let (mut chunk, mut chunk_start) =
+            if i - before.len_utf8() == leaf_start && leaf_start > 0 {
+                // if that is the first character in its leaf, the cursor
+                // can't ask for the previous leaf after stepping over it
+                // without first seeing the character before it in the same
+                // chunk, so join the two together.
+                let (prev, prev_start) = self.leaf_at(leaf_start - 1);
+                let first = prev[..leaf_start - prev_start].chars()
+                                .next_back()
+                                .expect("prev_grapheme_boundary: empty leaf!");
+                joined = format!("{}{}", first, before);
+                (&joined[..], leaf_start - first.len_utf8())
+            } else {
+                (leaf, leaf_start)
+            };
+        loop {
+            match cursor.prev_boundary(chunk, chunk_start) {
+                Ok(boundary) => return boundary
+              , Err(GraphemeIncomplete::PrevChunk) => {
+                    let (prev, prev_start) = self.leaf_at(chunk_start - 1);
+                    chunk = prev;
+                    chunk_start = prev_start;
+                }
+              , Err(GraphemeIncomplete::PreContext(n)) => {
+                    let (ctx, ctx_start) = self.leaf_at(n - 1);
+                    cursor.provide_context(&ctx[..n - ctx_start], ctx_start);
+                }
+              , Err(e) => panic!( "prev_grapheme_boundary: invalid index {} \
+                                   ({:?})", i, e)
+            }
+        }
+    }
+
+    /// Returns the text between byte indices `start` and `end` in this
+    /// `Node`'s subrope.
+    ///
+    /// If the range falls within a single leaf, the text is borrowed from that
+    /// leaf; otherwise, the text of the leaves spanned by the range is copied
+    /// into a new `String`.
+    pub fn text_between(&self, start: usize, end: usize) -> Cow<str> {
+        let (leaf, leaf_start) = self.leaf_at(start);
+        if end - leaf_start <= leaf.len() {
+            Cow::Borrowed(&leaf[start - leaf_start..end - leaf_start])
+        } else {
+            let mut text = String::with_capacity(end - start);
+            let mut i = start;
+            while i < end {
+                let (leaf, leaf_start) = self.leaf_at(i);
+                let leaf_end = ::std::cmp::min(leaf.len(), end - leaf_start);
+                text.push_str(&leaf[i - leaf_start..leaf_end]);
+                i = leaf_start + leaf_end;
+            }
+            Cow::Owned(text)
+        }
+    }
+
+    /// Returns true if the text beginning at byte index `i` in this `Node`'s
+    /// subrope starts with `s`.
+    ///
+    /// Only the leaves spanned by `s` are examined.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of `s`)
+    pub fn eq_str_at(&self, i: usize, s: &str) -> bool {
+        if i + s.len() > self.len() { return false }
+        let mut i = i;
+        let mut rest = s.as_bytes();
+        while !rest.is_empty() {
+            let (leaf, leaf_start) = self.leaf_at(i);
+            let chunk = &leaf.as_bytes()[i - leaf_start..];
+            let n = ::std::cmp::min(chunk.len(), rest.len());
+            if chunk[..n] != rest[..n] { return false }
+            rest = &rest[n..];
+            i += n;
+        }
+        true
+    }
+
+    /// Returns the byte indices of the UAX#29 word boundaries around byte
+    /// index `i`, from the last word anchor before `i` to the first one after
+    /// it, including both anchors.
+    ///
+    /// Only the text between the two anchors is segmented, so this is usually
+    /// just the word containing `i`, however long its line is.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the distance between the anchors)
+    #[cfg(feature = "unicode")]
+    pub fn word_bounds_around(&self, i: usize) -> Vec<usize> {
+        let start = if i == 0 { 0 } else { self.word_anchor_before(i) };
+        let end = if i == self.len() { i } else { self.word_anchor_after(i) };
+        let text = self.text_between(start, end);
+        let mut bounds = text.split_word_bound_indices()
+                             .map(|(offset, _)| start + offset)
+                             .collect::<Vec<_>>();
+        bounds.push(end);
+        bounds
+    }
+
+    /// Returns the greatest word anchor before byte index `i`, walking
+    /// backwards through the leaves until one is found.
+    #[cfg(feature = "unicode")]
+    fn word_anchor_before(&self, i: usize) -> usize {
+        let mut after = self.char_after(i);
+        let mut j = i;
+        while j > 0 {
+            let (leaf, leaf_start) = self.leaf_at(j - 1);
+            let text = &leaf[..j - leaf_start];
+            for (offset, before) in text.char_indices().rev() {
+                if j < i && is_word_anchor(Some(before), after) { return j }
+                after = Some(before);
+                j = leaf_start + offset;
+            }
+        }
+        0
+    }
+
+    /// Returns the least word anchor after byte index `i`, walking forwards
+    /// through the leaves until one is found.
+    #[cfg(feature = "unicode")]
+    fn word_anchor_after(&self, i: usize) -> usize {
+        let len = self.len();
+        let mut before = self.char_before(i);
+        let mut j = i;
+        while j < len {
+            let (leaf, leaf_start) = self.leaf_at(j);
+            for after in leaf[j - leaf_start..].chars() {
+                if j > i && is_word_anchor(before, Some(after)) { return j }
+                before = Some(after);
+                j += after.len_utf8();
+            }
+        }
+        len
+    }
+
+    /// Returns the character ending at byte index `i`, if there is one.
+    #[cfg(feature = "unicode")]
+    fn char_before(&self, i: usize) -> Option<char> {
+        if i == 0 { return None }
+        let (leaf, leaf_start) = self.leaf_at(i - 1);
+        leaf[..i - leaf_start].chars().next_back()
+    }
+
+    /// Returns the character beginning at byte index `i`, if there is one.
+    #[cfg(feature = "unicode")]
+    fn char_after(&self, i: usize) -> Option<char> {
+        if i == self.len() { return None }
+        let (leaf, leaf_start) = self.leaf_at(i);
+        leaf[i - leaf_start..].chars().next()
+    }
+
+    /// Returns the byte index of the next UAX#29 word boundary after byte
+    /// index `i` in this `Node`'s subrope.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the next word boundary
+    /// - `None` if `i` is at the end of this subrope
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the words around `i`)
+    #[cfg(feature = "unicode")]
+    pub fn next_word_boundary(&self, i: usize) -> Option<usize> {
+        if i == self.len() { return None }
+        self.word_bounds_around(i).into_iter().find(|&b| b > i)
+    }
+
+    /// Returns the byte index of the previous UAX#29 word boundary before
+    /// byte index `i` in this `Node`'s subrope.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the previous word boundary
+    /// - `None` if `i` is at the beginning of this subrope
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the words around `i`)
+    #[cfg(feature = "unicode")]
+    pub fn prev_word_boundary(&self, i: usize) -> Option<usize> {
+        if i == 0 { return None }
+        self.word_bounds_around(i).into_iter().rev().find(|&b| b < i)
+    }
+
+    /// Returns true if byte index `i` is a UAX#29 word boundary in this
+    /// `Node`'s subrope.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the words around `i`)
+    #[cfg(feature = "unicode")]
+    pub fn is_word_boundary(&self, i: usize) -> bool {
+        i == 0 || i == self.len()
+            || self.word_bounds_around(i).binary_search(&i).is_ok()
+    }
+
+    #[cfg(feature = "unicode")]
     pub fn grapheme_indices(&self) -> GraphemeIndices {
         let mut strings = self.strings();
         let first_string = strings.next()
@@ -629,6 +1680,7 @@ impl Node {
                         , curr_length: first_string.len() }
     }
 
+    #[cfg(feature = "unicode")]
     pub fn split_word_bound_indices(&self) -> UWordBoundIndices {
         let mut strings = self.strings();
         let first_string = strings.next()
@@ -680,6 +1732,85 @@ impl<'a> Iterator for Leaves<'a> {
     }
 }
 
+/// An iterator over a series of leaf `Node`s, from right to left
+struct LeavesRev<'a>(Vec<&'a Node>);
+
+impl<'a> Iterator for LeavesRev<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.0.pop() {
+                None => return None
+              , Some(&Node { value: Leaf(ref s), .. }) if s.is_empty() => {}
+              , leaf @ Some(&Node { value: Leaf(_), .. })=> return leaf
+              , Some(&Node { value: Branch { ref left, ref right }, .. }) => {
+                    self.0.push(left);
+                    self.0.push(right);
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the text of the leaves of a `Node` from a byte index,
+/// from left to right.
+///
+/// This is the iterator returned by `Node::strings_from`.
+pub struct StringsFrom<'a> { stack: Vec<(&'a Node, usize)>
+                           , start: usize
+                           }
+
+impl<'a> Iterator for StringsFrom<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        while let Some((node, offset)) = self.stack.pop() {
+            match node.value {
+                Leaf(ref s) => {
+                    let s: &str = s.as_ref();
+                    let cut = self.start.saturating_sub(offset);
+                    if cut < s.len() { return Some((offset + cut, &s[cut..])) }
+                }
+              , Branch { ref left, ref right } => {
+                    self.stack.push((right, offset + left.len()));
+                    self.stack.push((left, offset));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over the text of the leaves of a `Node` before a byte index,
+/// from right to left.
+///
+/// This is the iterator returned by `Node::strings_before`.
+pub struct StringsBefore<'a> { stack: Vec<(&'a Node, usize)>
+                             , end: usize
+                             }
+
+impl<'a> Iterator for StringsBefore<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        while let Some((node, start)) = self.stack.pop() {
+            match node.value {
+                Leaf(ref s) => {
+                    let s: &str = s.as_ref();
+                    let len = ::std::cmp::min(s.len(), self.end - start);
+                    if len > 0 { return Some((start, &s[..len])) }
+                }
+              , Branch { ref left, ref right } => {
+                    self.stack.push((left, start));
+                    self.stack.push((right, start + left.len()));
+                }
+            }
+        }
+        None
+    }
+}
+
 // /// A move iterator over a series of leaf `Node`s
 // struct IntoLeaves(Vec<Node>);
 //
@@ -701,6 +1832,7 @@ impl<'a> Iterator for Leaves<'a> {
 //     }
 // }
 
+#[cfg(feature = "unicode")]
 pub struct GraphemeIndices<'a> {
     strings: Box<Iterator<Item = &'a str> + 'a >
   , graphemes: StrGraphemeIndices<'a>
@@ -708,6 +1840,7 @@ pub struct GraphemeIndices<'a> {
   , curr_length: usize
 }
 
+#[cfg(feature = "unicode")]
 impl<'a> Iterator for GraphemeIndices<'a> {
     type Item = (usize, &'a str);
 
@@ -724,6 +1857,7 @@ impl<'a> Iterator for GraphemeIndices<'a> {
     }
 }
 
+#[cfg(feature = "unicode")]
 pub struct UWordBoundIndices<'a> {
     strings: Box<Iterator<Item = &'a str> + 'a >
   , bounds: StrUWordBoundIndices<'a>
@@ -731,6 +1865,7 @@ pub struct UWordBoundIndices<'a> {
   , curr_length: usize
 }
 
+#[cfg(feature = "unicode")]
 impl<'a> Iterator for UWordBoundIndices<'a> {
     type Item = (usize, &'a str);
 
@@ -761,24 +1896,3 @@ impl<'a> ops::Add for &'a NodeLink {
     }
 }
 
-pub trait IsLineEnding { fn is_line_ending(&self) -> bool; }
-
-impl IsLineEnding for char {
-    #[inline]
-    fn is_line_ending(self: &char) -> bool {
-        match *self {
-            '\u{000A}' => true,
-            _ => false
-        }
-    }
-}
-
-impl IsLineEnding for str {
-    #[inline]
-    fn is_line_ending(self: &Self) -> bool {
-        match self {
-            "\u{000A}" => true,
-            _ => false
-        }
-    }
-}
@@ -1,20 +1,37 @@
-use metric::{Measured, Line, Grapheme, Metric};
-use super::{NodeLink, LeafRepr };
+use metric::{Measured, Line, Metric, ByteIndex, CharIndex};
+#[cfg(feature = "unicode")] use metric::Grapheme;
+use super::{Link, NodeLink, LeafRepr, LeafStorage};
 
 use self::Value::*;
 
-use std::cell::Cell;
+#[cfg(not(feature = "atomic"))] use std::cell::Cell;
+#[cfg(feature = "atomic")]      use std::sync::RwLock;
 use std::convert;
 use std::default::Default;
 use std::fmt;
+use std::mem;
 use std::ops;
 
 
 
 /// A lazily-evaluated field
+#[cfg(not(feature = "atomic"))]
 #[derive(Clone)]
 struct Lazy<T: Copy>(Cell<Option<T>>);
 
+/// A lazily-evaluated field
+///
+/// When built with the `atomic` feature, the field is guarded by a lock, so
+/// that `Node`s may be shared between threads.
+#[cfg(feature = "atomic")]
+struct Lazy<T: Copy>(RwLock<Option<T>>);
+
+#[cfg(feature = "atomic")]
+impl<T> Clone for Lazy<T>
+where T: Copy {
+    #[inline] fn clone(&self) -> Self { Lazy(RwLock::new(self.get())) }
+}
+
 impl<T> Lazy<T>
 where T: Copy {
 
@@ -24,8 +41,30 @@ where T: Copy {
     /// - `Some(T)` if the value of this field has been computed
     /// - `None` if the field has yet to be computed
     #[inline]
+    #[cfg(not(feature = "atomic"))]
     pub fn get(&self) -> Option<T> { self.0.get() }
 
+    /// Get the value of the field if it has been computed
+    ///
+    /// # Returns
+    /// - `Some(T)` if the value of this field has been computed
+    /// - `None` if the field has yet to be computed
+    #[inline]
+    #[cfg(feature = "atomic")]
+    pub fn get(&self) -> Option<T> {
+        *self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[inline]
+    #[cfg(not(feature = "atomic"))]
+    fn set(&self, value: T) { self.0.set(Some(value)) }
+
+    #[inline]
+    #[cfg(feature = "atomic")]
+    fn set(&self, value: T) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Some(value)
+    }
+
     /// Get the value of the field, or compute it
     ///
     /// # Arguments
@@ -38,20 +77,27 @@ where T: Copy {
     #[inline]
     pub fn get_or_else<F>(&self, f: F) -> T
     where F: FnOnce() -> T {
-        if let Some(value) = self.0.get() {
+        if let Some(value) = self.get() {
             value
         } else {
             let value = f();
-            self.0.set(Some(value));
+            self.set(value);
             value
         }
     }
 
     #[inline]
+    #[cfg(not(feature = "atomic"))]
     pub fn new() -> Self {
         Lazy(Cell::new(None))
     }
 
+    #[inline]
+    #[cfg(feature = "atomic")]
+    pub fn new() -> Self {
+        Lazy(RwLock::new(None))
+    }
+
 }
 
 impl<T> Default for Lazy<T>
@@ -65,9 +111,118 @@ impl<T> fmt::Debug for Lazy<T>
 where T: fmt::Debug
     , T: Copy {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.0.get() { Some(value) => value.fmt(f)
-                           , None => write!(f, "?")
+        match self.get() { Some(value) => value.fmt(f)
+                         , None => write!(f, "?")
+
+        }
+    }
+}
+
+/// Returns the number of grapheme clusters in an ASCII string.
+///
+/// In ASCII text, every character is its own grapheme cluster except for
+/// `"\r\n"`, so the UAX#29 segmentation can be skipped.
+#[cfg(feature = "unicode")]
+#[inline]
+fn ascii_grapheme_count(s: &str) -> usize {
+    s.len() - s.matches("\r\n").count()
+}
+
+/// The widths, in `char`s, of the lines in a subrope, from which the widths
+/// of the lines in a concatenation of subropes can be computed.
+///
+/// A line's width does not count its line terminator, `"\n"` or `"\r\n"`.
+/// The first and last lines of a subrope may continue into its neighbours,
+/// so they are kept separately, along with whether the subrope begins with
+/// `'\n'` and ends with `'\r'`, since a `"\r\n"` may be split between two
+/// leaves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineWidths { /// The width of the text before the first `'\n'`,
+                        /// or of the whole subrope if it has none.
+                        first: usize
+                      , /// The width of the text after the last `'\n'`,
+                        /// counting a `'\r'` at its end.
+                        last: usize
+                      , /// The width of the widest line ended by a `'\n'`
+                        /// after the first.
+                        max: usize
+                      , /// Whether the subrope contains a `'\n'`.
+                        newline: bool
+                      , /// Whether the subrope begins with `'\n'`.
+                        starts_lf: bool
+                      , /// Whether the subrope ends with `'\r'`.
+                        ends_cr: bool
+                      }
+
+impl LineWidths {
+    /// Returns the `LineWidths` of the text `s`.
+    pub fn of(s: &str) -> Self {
+        let mut lines = s.split('\n');
+        let last = lines.next_back().unwrap_or("");
+        // every line but the last is ended by a `'\n'`
+        let width = |line: &str| {
+            let line = if line.ends_with('\r') { &line[..line.len() - 1] }
+                       else { line };
+            line.chars().count()
+        };
+        let first = lines.next().map(&width);
+        LineWidths { first: first.unwrap_or_else(|| last.chars().count())
+                   , last: last.chars().count()
+                   , max: lines.map(width).max().unwrap_or(0)
+                   , newline: first.is_some()
+                   , starts_lf: s.starts_with('\n')
+                   , ends_cr: s.ends_with('\r')
+                   }
+    }
 
+    /// Returns the `LineWidths` of the text of `self` followed by that of
+    /// `right`.
+    pub fn join(&self, right: &LineWidths) -> Self {
+        // the width of the line made by joining a line of `self` of width
+        // `left` to the first line of `right`, when the line is ended
+        // by a `'\n'` in `right`
+        let joined = |left: usize| {
+            let split_crlf = self.ends_cr && right.starts_lf;
+            left + right.first - if split_crlf { 1 } else { 0 }
+        };
+        let left_empty = !self.newline && self.first == 0;
+        let right_empty = !right.newline && right.first == 0;
+        let ends = LineWidths { starts_lf: if left_empty { right.starts_lf }
+                                           else { self.starts_lf }
+                              , ends_cr: if right_empty { self.ends_cr }
+                                         else { right.ends_cr }
+                              , ..*self
+                              };
+        match (self.newline, right.newline) {
+            (false, false) => LineWidths { first: self.first + right.first
+                                         , last: self.last + right.last
+                                         , ..ends }
+          , (true, false) => LineWidths { last: self.last + right.first
+                                        , ..ends }
+          , (false, true) => {
+                let first = joined(self.first);
+                LineWidths { first: first
+                           , last: right.last
+                           , max: right.max
+                           , newline: true
+                           , ..ends }
+            }
+          , (true, true) => {
+                let max = ::std::cmp::max(self.max, right.max);
+                LineWidths { last: right.last
+                           , max: ::std::cmp::max(max, joined(self.last))
+                           , ..ends }
+            }
+        }
+    }
+
+    /// Returns the width of the widest line.
+    #[inline]
+    pub fn max_width(&self) -> usize {
+        if self.newline {
+            ::std::cmp::max(::std::cmp::max(self.first, self.max), self.last)
+        } else {
+            self.first
         }
     }
 }
@@ -87,24 +242,90 @@ pub struct Node { len: Lazy<usize>
                 , weight: Lazy<usize>
                 , line_count: Lazy<Line>
                 , line_weight: Lazy<Line>
-                , grapheme_count: Lazy<Grapheme>
-                , grapheme_weight: Lazy<Grapheme>
+                , #[cfg(feature = "unicode")]
+                  grapheme_count: Lazy<Grapheme>
+                , #[cfg(feature = "unicode")]
+                  grapheme_weight: Lazy<Grapheme>
+                , char_count: Lazy<CharIndex>
+                , char_weight: Lazy<CharIndex>
+                , ascii: Lazy<bool>
+                , newlines: Lazy<usize>
+                , depth: Lazy<usize>
+                , leaf_count: Lazy<usize>
+                , line_widths: Lazy<LineWidths>
+                , #[cfg(feature = "wasm")]
+                  utf16_len: Lazy<usize>
                 , pub value: Value
                 }
 
 impl Node {
     pub fn new(value: Value) -> Self {
-        Node { value: value, ..Default::default() }
+        let mut node = Node::default();
+        node.value = value;
+        node
+    }
+
+    /// Returns true if every character in this `Node`'s subrope is ASCII.
+    ///
+    /// The result is cached in each node, so after the first call this is
+    /// O(1), and recomputing it after an edit only visits the new nodes.
+    #[inline]
+    pub fn is_ascii(&self) -> bool {
+        self.ascii.get_or_else(|| self.value.is_ascii())
+    }
+
+    /// Returns the number of `'\n'`s in this `Node`'s subrope.
+    ///
+    /// Unlike the `Line` metric, this counts every newline, not only those
+    /// at the ends of leaves. The result is cached in each node.
+    #[inline]
+    pub fn newline_count(&self) -> usize {
+        self.newlines.get_or_else(|| self.value.newline_count())
+    }
+
+    /// Returns the depth of this `Node`'s subtree.
+    ///
+    /// A leaf has depth 0, and a branch is one deeper than the deeper of its
+    /// children. The result is cached in each node.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth.get_or_else(|| self.value.depth())
+    }
+
+    /// Returns the number of leaves in this `Node`'s subtree, including
+    /// empty leaves. The result is cached in each node.
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count.get_or_else(|| self.value.leaf_count())
+    }
+
+    /// Returns the widths of the lines in this `Node`'s subrope. The result
+    /// is cached in each node.
+    #[inline]
+    pub fn line_widths(&self) -> LineWidths {
+        self.line_widths.get_or_else(|| self.value.line_widths())
+    }
+
+    /// Returns the length of this `Node`'s subrope in UTF-16 code units.
+    /// The result is cached in each node.
+    #[cfg(feature = "wasm")]
+    #[inline]
+    pub fn len_utf16(&self) -> usize {
+        self.utf16_len.get_or_else(|| if self.is_ascii() {
+            self.len()
+        } else {
+            self.value.len_utf16()
+        })
     }
 
     pub fn spanning(&self, i: usize, span_len: usize) -> (&Node, usize)
     where Node: Measured<usize> {
         assert!(self.len() >= span_len);
         match **self {
-            Branch { ref right, ref left } if < Node as Measured<usize>>::measure_weight(self) < i => {
-                // if this node is a branch, and the weight is less than the
-                // index, where the span begins, then the first index of the
-                // span is on the right side
+            Branch { ref right, ref left } if < Node as Measured<usize>>::measure_weight(self) <= i => {
+                // if this node is a branch, and the weight is less than or
+                // equal to the index where the span begins, then the first
+                // index of the span is on the right side
                 let span_i = or_zero!(i, left.len());
                 assert!(or_zero!(right.len(), span_i) >= span_len);
                 right.spanning(span_i, span_len)
@@ -135,14 +356,18 @@ impl fmt::Display for Node {
 
 impl fmt::Debug for Node {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(feature = "unicode")]
+        let graphemes = self.grapheme_count.get()
+                            .map(|w| format!("{} graphemes, ", w.0))
+                            .unwrap_or_else(|| { String::new() });
+        #[cfg(not(feature = "unicode"))]
+        let graphemes = String::new();
         write!( f, "Node {{{}{}{}{:#?} }}"
               , self.len.get().map(|l| format!("{} chars, ", l))
                     .unwrap_or_else(|| { String::new() })
             //   , self.weight.get().map(|w| format!("weight: {:?}, ", w))
             //         .unwrap_or_else(|| { String::new() })
-              , self.grapheme_count.get()
-                    .map(|w| format!("{} graphemes, ", w.0))
-                    .unwrap_or_else(|| { String::new() })
+              , graphemes
             //   , self.grapheme_weight.get().map(|w| format!("weight: {:?}, ", w))
             //         .unwrap_or_else(|| { String::new() })
               , self.line_count.get()
@@ -166,6 +391,31 @@ impl ops::Deref for Node {
     fn deref(&self) -> &Value { &self.value }
 }
 
+impl Drop for Node {
+    fn drop(&mut self) {
+        if let Leaf(_) = self.value { return }
+        // tear the tree down iteratively, since dropping a deep tree
+        // recursively could overflow the stack: each node which is no
+        // longer shared is emptied before it is dropped, and its children
+        // are dropped in turn by this loop
+        let mut values = vec![mem::replace(&mut self.value, Value::default())];
+        while let Some(value) = values.pop() {
+            if let Branch { left, right } = value {
+                values.extend(unlink(left));
+                values.extend(unlink(right));
+            }
+        }
+    }
+}
+
+/// Returns the `Value` of the `Node` that `link` points to, leaving it
+/// empty, if `link` was its only strong reference.
+#[inline]
+fn unlink(link: NodeLink) -> Option<Value> {
+    Link::try_unwrap(link.0).ok()
+        .map(|mut node| mem::replace(&mut node.value, Value::default()))
+}
+
 impl Measured<usize> for Node {
 
         #[inline] fn to_byte_index(&self, index: usize) -> Option<usize>  {
@@ -177,14 +427,28 @@ impl Measured<usize> for Node {
 
 }
 
+#[cfg(feature = "unicode")]
 impl Measured<Grapheme> for Node {
 
         #[inline] fn to_byte_index(&self, index: Grapheme) -> Option<usize>  {
             self.value.to_byte_index(index)
         }
 
-        lazy_field!(measure, grapheme_count, Grapheme);
-        lazy_field!(measure_weight, grapheme_weight, Grapheme);
+        #[inline] fn measure(&self) -> Grapheme {
+            self.grapheme_count.get_or_else(|| match self.value {
+                Leaf(ref s) if self.is_ascii() =>
+                    Grapheme(ascii_grapheme_count(s.as_ref()))
+              , _ => self.value.measure()
+            })
+        }
+
+        #[inline] fn measure_weight(&self) -> Grapheme {
+            self.grapheme_weight.get_or_else(|| match self.value {
+                Leaf(ref s) if self.is_ascii() =>
+                    Grapheme(ascii_grapheme_count(s.as_ref()))
+              , _ => self.value.measure_weight()
+            })
+        }
 
 }
 
@@ -199,6 +463,51 @@ impl Measured<Line> for Node {
 
 }
 
+impl Measured<CharIndex> for Node {
+
+        #[inline] fn to_byte_index(&self, index: CharIndex) -> Option<usize>  {
+            if self.is_ascii() {
+                // every char is one byte, so the index is already a byte index
+                if index.0 <= self.len() { Some(index.0) } else { None }
+            } else {
+                self.value.to_byte_index(index)
+            }
+        }
+
+        #[inline] fn measure(&self) -> CharIndex {
+            self.char_count.get_or_else(|| if self.is_ascii() {
+                CharIndex(Measured::<usize>::measure(self))
+            } else {
+                self.value.measure()
+            })
+        }
+
+        #[inline] fn measure_weight(&self) -> CharIndex {
+            self.char_weight.get_or_else(|| if self.is_ascii() {
+                CharIndex(Measured::<usize>::measure_weight(self))
+            } else {
+                self.value.measure_weight()
+            })
+        }
+
+}
+
+impl Measured<ByteIndex> for Node {
+
+        #[inline] fn to_byte_index(&self, index: ByteIndex) -> Option<usize>  {
+            Some(index.0)
+        }
+
+        #[inline] fn measure(&self) -> ByteIndex {
+            ByteIndex(Measured::<usize>::measure(self))
+        }
+
+        #[inline] fn measure_weight(&self) -> ByteIndex {
+            ByteIndex(Measured::<usize>::measure_weight(self))
+        }
+
+}
+
 
 impl<M> ops::Index<M> for Node
 where M: Metric
@@ -245,6 +554,67 @@ impl Value {
     pub fn new_branch(left: NodeLink, right: NodeLink) -> Self {
         Branch { left: left, right: right }
     }
+
+    /// Returns true if every character in this subrope is ASCII.
+    #[inline]
+    pub fn is_ascii(&self) -> bool {
+        match *self {
+            Leaf(ref s) => s.as_bytes().is_ascii()
+          , Branch { ref left, ref right } =>
+                left.is_ascii() && right.is_ascii()
+        }
+    }
+
+    /// Returns the number of `'\n'`s in this subrope.
+    #[inline]
+    pub fn newline_count(&self) -> usize {
+        match *self {
+            Leaf(ref s) => s.newline_count()
+          , Branch { ref left, ref right } =>
+                left.newline_count() + right.newline_count()
+        }
+    }
+
+    /// Returns the length of this subrope in UTF-16 code units.
+    #[cfg(feature = "wasm")]
+    #[inline]
+    pub fn len_utf16(&self) -> usize {
+        match *self {
+            Leaf(ref s) => s.chars().map(char::len_utf16).sum()
+          , Branch { ref left, ref right } =>
+                left.len_utf16() + right.len_utf16()
+        }
+    }
+
+    /// Returns the depth of this subtree.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        match *self {
+            Leaf(_) => 0
+          , Branch { ref left, ref right } =>
+                ::std::cmp::max(left.depth(), right.depth()) + 1
+        }
+    }
+
+    /// Returns the number of leaves in this subtree.
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        match *self {
+            Leaf(_) => 1
+          , Branch { ref left, ref right } =>
+                left.leaf_count() + right.leaf_count()
+        }
+    }
+
+    /// Returns the widths of the lines in this subrope.
+    #[inline]
+    pub fn line_widths(&self) -> LineWidths {
+        match *self {
+            Leaf(ref s) => LineWidths::of(s)
+          , Branch { ref left, ref right } =>
+                left.line_widths().join(&right.line_widths())
+        }
+    }
 }
 
 impl<M> Measured<M> for Value
@@ -255,10 +625,18 @@ where M: Metric
     fn to_byte_index(&self, index: M) -> Option<usize> {
         match *self {
             Leaf(ref r) => r.to_byte_index(index)
-          , Branch { ref left, ref right } =>
-                left.to_byte_index(index)
-                    .or_else(|| { right.to_byte_index(index)
-                                       .map(|i| i + left.len() ) })
+          , Branch { ref left, ref right } => {
+                // compare the index to this node's weight, as `split` does,
+                // so that only one subtree is searched at each level
+                let weight = left.measure();
+                if index < weight {
+                    left.to_byte_index(index)
+                } else {
+                    // indices in the right subtree are relative to its start
+                    right.to_byte_index(index - weight)
+                         .map(|i| i + left.len())
+                }
+            }
         }
     }
 
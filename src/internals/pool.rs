@@ -0,0 +1,97 @@
+//! A pooled allocator for `Node`s.
+//!
+//! Every edit to a `Rope` allocates a handful of new `Node`s, and dropping an
+//! old version of a `Rope` frees the nodes it no longer shares. When built
+//! with the `node-pool` feature, `Node`s are allocated with [`NodePool`],
+//! which keeps the memory of freed nodes on a thread-local free list and
+//! hands it back out for new nodes, rather than going through the global
+//! allocator every time.
+//!
+//! This relies on the unstable `allocator_api`, so it requires nightly Rust.
+//! `Allocator` is an `unsafe` trait, so this is one of the few modules with
+//! `unsafe` code; each use is justified where it occurs.
+//!
+//! [`NodePool`]: struct.NodePool.html
+use std::alloc::{AllocError, Allocator, Global, Layout};
+use std::cell::RefCell;
+use std::ptr::NonNull;
+
+/// The maximum number of freed blocks kept by each thread's pool.
+///
+/// Blocks freed while the pool is full are returned to the global allocator.
+const MAX_FREE: usize = 4096;
+
+/// A thread-local free list of blocks, all with the same `Layout`.
+struct FreeList { layout: Option<Layout>
+                , blocks: Vec<NonNull<u8>>
+                }
+
+impl Drop for FreeList {
+    fn drop(&mut self) {
+        if let Some(layout) = self.layout {
+            for block in self.blocks.drain(..) {
+                // every block on the list was allocated by `Global` with
+                // `layout`, and was freed by its `Node`, so nothing else can
+                // still be using it
+                unsafe { Global.deallocate(block, layout) }
+            }
+        }
+    }
+}
+
+thread_local! {
+    static FREE: RefCell<FreeList> =
+        RefCell::new(FreeList { layout: None, blocks: Vec::new() });
+}
+
+/// An allocator that recycles the memory of dropped `Node`s.
+///
+/// All `Node`s share the same layout, so a block freed by one node can be
+/// reused for the next without any bookkeeping. Blocks of any other layout
+/// are passed straight through to the global allocator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NodePool;
+
+impl NodePool {
+    /// Returns the number of freed blocks held by this thread's pool.
+    pub fn free_blocks() -> usize {
+        FREE.with(|free| free.borrow().blocks.len())
+    }
+}
+
+// a recycled block is only handed out for the layout it was allocated with,
+// and it stays valid until it is freed again, since it is removed from the
+// free list; `NodePool` is a unit struct, so its copies are interchangeable,
+// and a block allocated through one can be freed through any other
+unsafe impl Allocator for NodePool {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let recycled = FREE.with(|free| {
+            let mut free = free.borrow_mut();
+            if free.layout == Some(layout) { free.blocks.pop() } else { None }
+        });
+        match recycled {
+            Some(block) =>
+                Ok(NonNull::slice_from_raw_parts(block, layout.size()))
+          , None => Global.allocate(layout)
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let pooled = FREE.try_with(|free| {
+            let mut free = free.borrow_mut();
+            // the first layout this pool sees is the one it keeps
+            if free.layout.is_none() { free.layout = Some(layout) }
+            if free.layout == Some(layout) && free.blocks.len() < MAX_FREE {
+                free.blocks.push(ptr);
+                true
+            } else {
+                false
+            }
+        }).unwrap_or(false);
+        if !pooled {
+            // the caller guarantees that `ptr` was allocated by this pool
+            // with `layout`, so it came from `Global` with `layout`
+            Global.deallocate(ptr, layout)
+        }
+    }
+}
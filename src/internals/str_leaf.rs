@@ -0,0 +1,135 @@
+//! Leaves which may borrow static text.
+//!
+//! By default, each leaf owns a `String`. A leaf can instead borrow a
+//! `&'static str`, so that a `Rope` built from a string literal with
+//! `Rope::from_static` refers to the literal rather than copying it. Leaves
+//! are never modified in place, so the text is only copied when an edit
+//! builds a new leaf from part of a static one.
+use metric::{Measured, Metric};
+
+use std::borrow::Borrow;
+use std::convert;
+use std::fmt;
+use std::hash;
+use std::ops;
+
+/// The text of a leaf, either owned or borrowed for the `'static` lifetime.
+#[derive(Clone)]
+pub enum StrLeaf {
+    /// Text owned by the leaf.
+    Owned(String)
+  , /// Static text, such as a string literal, which is not copied.
+    Static(&'static str)
+}
+
+impl StrLeaf {
+    /// Returns a new, empty `StrLeaf`.
+    #[inline]
+    pub fn new() -> Self { StrLeaf::Static("") }
+
+    /// Returns a `StrLeaf` which borrows `text`, rather than copying it.
+    #[inline]
+    pub fn from_static(text: &'static str) -> Self { StrLeaf::Static(text) }
+
+    /// Appends `c` to the end of this leaf, copying its text first if it is
+    /// borrowed.
+    pub fn push(&mut self, c: char) {
+        let owned = match *self {
+            StrLeaf::Owned(ref mut string) => { string.push(c); return }
+          , StrLeaf::Static(text) => {
+                let mut string = String::with_capacity(text.len() + 4);
+                string.push_str(text);
+                string.push(c);
+                string
+            }
+        };
+        *self = StrLeaf::Owned(owned);
+    }
+}
+
+impl Default for StrLeaf {
+    #[inline] fn default() -> Self { StrLeaf::new() }
+}
+
+impl ops::Deref for StrLeaf {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        match *self {
+            StrLeaf::Owned(ref string) => string
+          , StrLeaf::Static(text) => text
+        }
+    }
+}
+
+impl AsRef<str> for StrLeaf {
+    #[inline] fn as_ref(&self) -> &str { self }
+}
+
+impl Borrow<str> for StrLeaf {
+    #[inline] fn borrow(&self) -> &str { self }
+}
+
+impl PartialEq for StrLeaf {
+    #[inline] fn eq(&self, other: &StrLeaf) -> bool { **self == **other }
+}
+
+impl Eq for StrLeaf {}
+
+impl hash::Hash for StrLeaf {
+    #[inline] fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl convert::From<String> for StrLeaf {
+    #[inline] fn from(string: String) -> Self { StrLeaf::Owned(string) }
+}
+
+impl<'a> convert::From<&'a str> for StrLeaf {
+    #[inline] fn from(string: &'a str) -> Self {
+        StrLeaf::Owned(string.to_owned())
+    }
+}
+
+impl fmt::Debug for StrLeaf {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for StrLeaf {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<M> Measured<M> for StrLeaf
+where M: Metric
+    , str: Measured<M>
+    {
+        #[inline] fn to_byte_index(&self, index: M) -> Option<usize> {
+            (**self).to_byte_index(index)
+        }
+        #[inline] fn measure(&self) -> M { (**self).measure() }
+        #[inline] fn measure_weight(&self) -> M {
+             (**self).measure_weight()
+         }
+    }
+
+#[cfg(test)]
+mod tests {
+    use super::StrLeaf;
+
+    #[test]
+    fn push_copies_static_text() {
+        let text = "abc";
+        let mut leaf = StrLeaf::from_static(text);
+        assert!(leaf.as_ptr() == text.as_ptr());
+        leaf.push('\n');
+        assert_eq!(&*leaf, "abc\n");
+        assert_eq!(text, "abc");
+        assert_eq!(leaf, StrLeaf::from("abc\n"));
+    }
+}
@@ -1,3 +1,4 @@
+use Rope;
 use internals::Node;
 use internals::Value::Leaf;
 use metric::Line;
@@ -126,3 +127,99 @@ fn line_split_test_10() {
         assert_eq!(&s[..], "yxcv\n");
     } else { assert!(false) }
 }
+
+#[cfg(feature = "bytes")]
+#[test]
+fn bytes_split_shares_buffer() {
+    use internals::{NodeLink, Utf8Bytes};
+    let buf = Utf8Bytes::from(String::from("asdf\nqwer"));
+    let base = buf.as_ptr() as usize;
+    let node = NodeLink::from(buf);
+    let (left, right) = node.split(2);
+    assert_eq!(left.strings().collect::<String>(), "as");
+    assert_eq!(right.strings().collect::<String>(), "df\nqwer");
+    let ptrs = left.strings().chain(right.strings())
+                   .map(|s| s.as_ptr() as usize - base)
+                   .collect::<Vec<_>>();
+    assert_eq!(ptrs, vec![0, 2, 5]);
+}
+
+#[cfg(feature = "node-pool")]
+#[test]
+fn node_pool_recycles_nodes() {
+    use internals::NodePool;
+    let l1 = Node::new_leaf("asdf");
+    let l2 = Node::new_leaf("qwer");
+    let b = Node::new_branch(l1, l2);
+    let addr = &*b as *const Node as usize;
+    drop(b);
+    assert!(NodePool::free_blocks() >= 3);
+    let b = Node::new_leaf("yxcv");
+    assert_eq!(&*b as *const Node as usize, addr);
+}
+
+#[cfg(feature = "smallstr")]
+#[test]
+fn small_leaves_are_inline() {
+    let node = Node::new_leaf("a");
+    if let Leaf(ref s) = **node {
+        assert!(!s.spilled());
+    } else { assert!(false) }
+    let node = Node::new_leaf("this leaf is longer than twenty-four bytes");
+    if let Leaf(ref s) = **node {
+        assert!(s.spilled());
+    } else { assert!(false) }
+}
+
+#[test]
+fn empty_nodes_are_shared() {
+    assert!(Node::empty().ptr_eq(&Node::empty()));
+    assert!(Rope::new().root.ptr_eq(&Rope::default().root));
+}
+
+#[test]
+fn ascii_fast_paths_match_slow_paths() {
+    use metric::{CharIndex, Measured};
+    for s in &["", "abc", "a\r\nb", "\r\n\r\n", "\r\r\n\n", "a\rb\n"] {
+        let node = Node::new_leaf(*s);
+        assert!(node.is_ascii());
+        #[cfg(feature = "unicode")] {
+            use metric::Grapheme;
+            assert_eq!( Measured::<Grapheme>::measure(&*node)
+                      , Measured::<Grapheme>::measure(*s), "{:?}", s);
+        }
+        assert_eq!( Measured::<CharIndex>::measure(&*node)
+                  , Measured::<CharIndex>::measure(*s), "{:?}", s);
+        for i in 0..s.len() + 2 {
+            let i = CharIndex(i);
+            assert_eq!( Measured::<CharIndex>::to_byte_index(&*node, i)
+                      , s.to_byte_index(i));
+        }
+    }
+}
+
+#[test]
+fn split_leaf_of_length_one() {
+    use metric::CharIndex;
+    // splitting at the start of a leaf of length 1 must put it all on the
+    // right; only splitting anywhere after its start puts it on the left
+    let leaf = Node::new_leaf("a");
+    let (left, right) = leaf.split(0);
+    assert_eq!(left.strings().collect::<String>(), "");
+    assert_eq!(right.strings().collect::<String>(), "a");
+    let (left, right) = leaf.split(1);
+    assert_eq!(left.strings().collect::<String>(), "a");
+    assert_eq!(right.strings().collect::<String>(), "");
+
+    // the same holds for other metrics, e.g. a leaf of one multibyte char
+    let leaf = Node::new_leaf("\u{3c0}");
+    let (left, right) = leaf.split(CharIndex(0));
+    assert_eq!(left.strings().collect::<String>(), "");
+    assert_eq!(right.strings().collect::<String>(), "\u{3c0}");
+
+    // so inserting before a one-byte leaf puts the text before it
+    let rope = Rope::from(Node::new_branch( Node::new_leaf("a")
+                                          , Node::new_leaf("b")));
+    assert_eq!(&rope.insert_str(0, "x"), "xab");
+    assert_eq!(&rope.insert_str(1, "x"), "axb");
+}
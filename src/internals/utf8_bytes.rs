@@ -0,0 +1,122 @@
+//! Leaves backed by `bytes::Bytes`.
+//!
+//! When built with the `bytes` feature, each leaf is a reference-counted view
+//! into a `Bytes` buffer. Building a `Rope` from a `Bytes` does not copy the
+//! buffer, and splitting a leaf produces two views into the same buffer,
+//! rather than two new allocations.
+use bytes::Bytes;
+
+use metric::{Measured, Metric};
+
+use std::borrow::Borrow;
+use std::convert;
+use std::fmt;
+use std::ops;
+use std::str;
+
+/// A `Bytes` buffer that is known to contain valid UTF-8.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Utf8Bytes(Bytes);
+
+impl Utf8Bytes {
+    /// Returns a new, empty `Utf8Bytes`.
+    #[inline]
+    pub fn new() -> Self { Utf8Bytes(Bytes::new()) }
+
+    /// Wrap a `Bytes` buffer, checking that it contains valid UTF-8.
+    ///
+    /// # Returns
+    /// - `Ok` with the wrapped buffer, if `bytes` is valid UTF-8
+    /// - `Err` if `bytes` is not valid UTF-8
+    #[inline]
+    pub fn from_bytes(bytes: Bytes) -> Result<Self, str::Utf8Error> {
+        str::from_utf8(&bytes)?;
+        Ok(Utf8Bytes(bytes))
+    }
+
+    /// Wrap a static string, without copying it.
+    #[inline]
+    pub fn from_static(s: &'static str) -> Self {
+        Utf8Bytes(Bytes::from_static(s.as_bytes()))
+    }
+
+    /// Returns a view into the given byte range of this buffer.
+    ///
+    /// The returned `Utf8Bytes` shares this buffer, rather than copying it.
+    ///
+    /// # Panics
+    /// If either end of `range` does not lie on a `char` boundary.
+    #[inline]
+    pub fn slice(&self, range: ops::Range<usize>) -> Self {
+        assert!( self.is_char_boundary(range.start)
+                 && self.is_char_boundary(range.end)
+               , "Utf8Bytes::slice: range {:?} is not on a char boundary"
+               , range);
+        Utf8Bytes(self.0.slice(range))
+    }
+
+    /// Returns the underlying `Bytes` buffer.
+    #[inline]
+    pub fn into_bytes(self) -> Bytes { self.0 }
+}
+
+impl ops::Deref for Utf8Bytes {
+    type Target = str;
+
+    /// # Time complexity
+    /// O(_n_) in the length of the buffer, which is validated again on each
+    /// call, rather than trusted, so that the `bytes` feature needs no
+    /// `unsafe` code.
+    #[inline]
+    fn deref(&self) -> &str {
+        // this can't panic, since the buffer is checked to be valid UTF-8
+        // when the `Utf8Bytes` is constructed, and `slice` only ever cuts it
+        // on `char` boundaries
+        str::from_utf8(&self.0).expect("Utf8Bytes: buffer is not UTF-8!")
+    }
+}
+
+impl AsRef<str> for Utf8Bytes {
+    #[inline] fn as_ref(&self) -> &str { self }
+}
+
+impl Borrow<str> for Utf8Bytes {
+    #[inline] fn borrow(&self) -> &str { self }
+}
+
+impl convert::From<String> for Utf8Bytes {
+    #[inline] fn from(string: String) -> Self {
+        Utf8Bytes(Bytes::from(string))
+    }
+}
+
+impl<'a> convert::From<&'a str> for Utf8Bytes {
+    #[inline] fn from(string: &'a str) -> Self {
+        Utf8Bytes(Bytes::copy_from_slice(string.as_bytes()))
+    }
+}
+
+impl fmt::Debug for Utf8Bytes {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for Utf8Bytes {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<M> Measured<M> for Utf8Bytes
+where M: Metric
+    , str: Measured<M>
+    {
+        #[inline] fn to_byte_index(&self, index: M) -> Option<usize> {
+            (**self).to_byte_index(index)
+        }
+        #[inline] fn measure(&self) -> M { (**self).measure() }
+        #[inline] fn measure_weight(&self) -> M {
+             (**self).measure_weight()
+         }
+    }
@@ -0,0 +1,464 @@
+//! Adapters between `Rope`s and `std::io`.
+//!
+//! A [`RopeWriter`] is an `io::Write` sink which builds a `Rope`, so code
+//! which is generic over writers, such as serializers and formatters, can
+//! emit text directly into a rope buffer.
+//!
+//! A [`RopeReader`] goes the other way, reading the bytes of a `Rope`
+//! through `io::Read`, `io::BufRead` and `io::Seek`, so that consumers which
+//! expect a file, such as archive readers and parsers which seek, can
+//! operate on text held in a rope.
+//!
+//! Code which only needs fixed-size windows of a `Rope`'s bytes, such as a
+//! hex view or a chunked upload, can instead copy them into its own buffer
+//! with [`Rope::read_at`], like `pread`, without a reader or an allocation.
+//!
+//! [`RopeWriter`]: struct.RopeWriter.html
+//! [`RopeReader`]: struct.RopeReader.html
+//! [`Rope::read_at`]: struct.Rope.html#method.read_at
+use std::cmp;
+use std::io;
+use std::ops;
+use std::str;
+
+use super::{Rope, RopeBuilder};
+
+/// An `io::Write` sink which builds a `Rope` from the bytes written to it.
+///
+/// The bytes must be UTF-8, but a `char` may be split across several
+/// writes; the incomplete end of a write is kept until the rest of it
+/// arrives. Text is collected into leaves by a [`RopeBuilder`].
+///
+/// # Examples
+/// ```
+/// use std::io::Write;
+/// use an_rope::RopeWriter;
+/// let mut writer = RopeWriter::new();
+/// write!(writer, "{} + {} = {}", 1, 2, 3).unwrap();
+/// writer.write_all(b"\xcf").unwrap();
+/// writer.write_all(b"\x80").unwrap();
+/// let rope = writer.into_rope().unwrap();
+/// assert_eq!(&rope, "1 + 2 = 3\u{3c0}");
+/// ```
+///
+/// [`RopeBuilder`]: struct.RopeBuilder.html
+#[derive(Debug, Default)]
+pub struct RopeWriter { builder: RopeBuilder
+                      , pending: Vec<u8>
+                      }
+
+/// Returns the length of the UTF-8 sequence beginning with `lead`.
+#[inline]
+fn utf8_width(lead: u8) -> usize {
+    if lead < 0xe0 { 2 } else if lead < 0xf0 { 3 } else { 4 }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new( io::ErrorKind::InvalidData
+                  , "stream did not contain valid UTF-8")
+}
+
+impl RopeWriter {
+    /// Returns a new `RopeWriter` which builds leaves of the default size.
+    #[inline]
+    pub fn new() -> Self { RopeWriter::from(RopeBuilder::new()) }
+
+    /// Finish writing, returning the `Rope`.
+    ///
+    /// # Errors
+    /// If the bytes written end part of the way through a `char`, an error
+    /// of kind `InvalidData` is returned.
+    pub fn into_rope(self) -> io::Result<Rope> {
+        if self.pending.is_empty() {
+            Ok(self.builder.finish())
+        } else {
+            Err(invalid_utf8())
+        }
+    }
+}
+
+impl From<RopeBuilder> for RopeWriter {
+    /// Returns a `RopeWriter` which pushes the bytes written to it onto the
+    /// end of `builder`.
+    #[inline] fn from(builder: RopeBuilder) -> Self {
+        RopeWriter { builder: builder, pending: Vec::with_capacity(4) }
+    }
+}
+
+impl io::Write for RopeWriter {
+    /// Push the UTF-8 text in `buf` onto the end of the `Rope` being built.
+    ///
+    /// # Errors
+    /// If `buf` contains invalid UTF-8, the valid text before it is
+    /// written and its length returned; writing from the invalid bytes then
+    /// fails with an error of kind `InvalidData`.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        // first, complete any `char` left over from the last write
+        if !self.pending.is_empty() {
+            let needed = utf8_width(self.pending[0]) - self.pending.len();
+            let taken = cmp::min(needed, rest.len());
+            self.pending.extend_from_slice(&rest[..taken]);
+            rest = &rest[taken..];
+            if taken < needed { return Ok(buf.len()) }
+            match str::from_utf8(&self.pending) {
+                Ok(s) => self.builder.push_str(s)
+              , Err(_) => { self.pending.clear(); return Err(invalid_utf8()) }
+            }
+            self.pending.clear();
+        }
+        match str::from_utf8(rest) {
+            Ok(s) => self.builder.push_str(s)
+          , Err(e) => {
+                let (valid, invalid) = rest.split_at(e.valid_up_to());
+                self.builder.push_str(str::from_utf8(valid)
+                                          .expect("valid_up_to is valid"));
+                match e.error_len() {
+                    // the end of `buf` may be the start of a `char`
+                    None => self.pending.extend_from_slice(invalid)
+                  , Some(_) if invalid.len() == buf.len() =>
+                        return Err(invalid_utf8())
+                  , Some(_) => return Ok(buf.len() - invalid.len())
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// An `io::Read` and `io::Seek` source which reads the bytes of a `Rope`.
+///
+/// As with `io::Cursor`, the reader keeps a byte position, which may be
+/// moved anywhere with `seek`, including into the middle of a `char` or past
+/// the end of the `Rope`. Each read copies from the leaf containing the
+/// position, which is found in O(log _n_) time, so seeking is cheap.
+///
+/// The leaves of the `Rope` serve as the reader's buffer: `fill_buf`
+/// returns the rest of the current leaf without copying it, and `read_line`
+/// finds the end of the line using the newline counts kept in the tree,
+/// rather than scanning the buffer for it.
+///
+/// The reader holds its own clone of the `Rope`, which shares the tree with
+/// the original, so later edits to the original do not affect it.
+///
+/// # Examples
+/// ```
+/// use std::io::{Read, Seek, SeekFrom};
+/// use an_rope::{Rope, RopeReader};
+/// let rope = Rope::from("hello ") + Rope::from("world");
+/// let mut reader = RopeReader::new(rope);
+/// reader.seek(SeekFrom::End(-5)).unwrap();
+/// let mut text = String::new();
+/// reader.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "world");
+/// ```
+#[derive(Clone, Debug)]
+pub struct RopeReader { rope: Rope
+                      , position: usize
+                      }
+
+impl RopeReader {
+    /// Returns a new `RopeReader` positioned at the beginning of `rope`.
+    #[inline]
+    pub fn new(rope: Rope) -> Self {
+        RopeReader { rope: rope, position: 0 }
+    }
+
+    /// Returns the byte position of this reader.
+    #[inline] pub fn position(&self) -> usize { self.position }
+
+    /// Returns a reference to the `Rope` being read.
+    #[inline] pub fn get_ref(&self) -> &Rope { &self.rope }
+
+    /// Consumes this reader, returning the `Rope` being read.
+    #[inline] pub fn into_inner(self) -> Rope { self.rope }
+
+    /// Returns the bytes from the position of this reader to the end of the
+    /// leaf containing it.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    fn remaining_chunk(&self) -> &[u8] {
+        if self.position >= self.rope.len() { return &[] }
+        let (leaf, offset) = self.rope.root.leaf_at(self.position);
+        &leaf.as_bytes()[self.position - offset..]
+    }
+}
+
+impl From<Rope> for RopeReader {
+    #[inline] fn from(rope: Rope) -> Self { RopeReader::new(rope) }
+}
+
+impl io::Read for RopeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let chunk = self.remaining_chunk();
+            let n = cmp::min(chunk.len(), buf.len());
+            buf[..n].copy_from_slice(&chunk[..n]);
+            n
+        };
+        self.position += n;
+        Ok(n)
+    }
+}
+
+impl io::BufRead for RopeReader {
+    /// Returns the bytes from the position of this reader to the end of the
+    /// leaf containing it.
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> { Ok(self.remaining_chunk()) }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) { self.position += amt; }
+
+    /// Read the rest of the current line, including its newline, onto the
+    /// end of `buf`.
+    ///
+    /// # Errors
+    /// If the position of this reader is in the middle of a `char`, an
+    /// error of kind `InvalidData` is returned, and nothing is read.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the line)
+    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        let len = self.rope.len();
+        if self.position >= len { return Ok(0) }
+        let start = self.position;
+        let line = self.rope.root.newlines_before(start);
+        let end = self.rope.root.after_newline(line).unwrap_or(len);
+        buf.reserve(end - start);
+        while self.position < end {
+            let (leaf, offset) = self.rope.root.leaf_at(self.position);
+            let from = self.position - offset;
+            if !leaf.is_char_boundary(from) { return Err(invalid_utf8()) }
+            let to = cmp::min(leaf.len(), end - offset);
+            buf.push_str(&leaf[from..to]);
+            self.position = offset + to;
+        }
+        Ok(end - start)
+    }
+}
+
+impl io::Seek for RopeReader {
+    /// Move the position of this reader.
+    ///
+    /// # Errors
+    /// Seeking to a negative position, or to one which does not fit in a
+    /// `usize`, fails with an error of kind `InvalidInput`.
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(n) => (0, n as i64)
+          , io::SeekFrom::End(n) => (self.rope.len() as i64, n)
+          , io::SeekFrom::Current(n) => (self.position as i64, n)
+        };
+        match base.checked_add(offset) {
+            Some(position) if position >= 0
+                           && position as u64 <= usize::max_value() as u64 => {
+                self.position = position as usize;
+                Ok(position as u64)
+            }
+          , _ => Err(io::Error::new( io::ErrorKind::InvalidInput
+                                   , "invalid seek to a negative or \
+                                      overflowing position"))
+        }
+    }
+}
+
+impl Rope {
+    /// Copy the bytes of this `Rope` starting at byte index `offset` into
+    /// `buf`, returning the number of bytes copied.
+    ///
+    /// As many bytes are copied as fit in `buf`, or as remain after
+    /// `offset`, whichever is fewer, so 0 bytes are copied if `offset` is at
+    /// or past the end of this `Rope`. `offset` may fall within a `char`;
+    /// the bytes are copied as they are.
+    ///
+    /// # Time complexity
+    /// O(log _n_) for each leaf which is copied from.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("hello ") + Rope::from("world");
+    /// let mut window = [0; 4];
+    /// assert_eq!(rope.read_at(4, &mut window), 4);
+    /// assert_eq!(&window, b"o wo");
+    /// assert_eq!(rope.read_at(9, &mut window), 2);
+    /// assert_eq!(&window[..2], b"ld");
+    /// assert_eq!(rope.read_at(20, &mut window), 0);
+    /// ```
+    pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        let end = cmp::min(offset.saturating_add(buf.len()), self.len());
+        let mut i = offset;
+        while i < end {
+            let (leaf, leaf_start) = self.root.leaf_at(i);
+            let bytes = &leaf.as_bytes()[i - leaf_start..];
+            let n = cmp::min(bytes.len(), end - i);
+            buf[i - offset..i - offset + n].copy_from_slice(&bytes[..n]);
+            i += n;
+        }
+        end.saturating_sub(offset)
+    }
+
+    /// Copy the bytes of this `Rope` in the byte range `range` into `buf`.
+    ///
+    /// # Panics
+    /// * If the start of `range` is greater than the end
+    /// * If the end of `range` is greater than the length of this `Rope`
+    /// * If the length of `buf` is not the length of `range`
+    ///
+    /// # Time complexity
+    /// O(log _n_) for each leaf which is copied from.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\u{3c0}b");
+    /// let mut pi = [0; 2];
+    /// rope.copy_range_to_slice(1..3, &mut pi);
+    /// assert_eq!(&pi, "\u{3c0}".as_bytes());
+    /// ```
+    pub fn copy_range_to_slice( &self, range: ops::Range<usize>
+                              , buf: &mut [u8]) {
+        let len = range_len!( "Rope::copy_range_to_slice"
+                            , range.start, range.end);
+        assert!( range.end <= self.len()
+               , "Rope::copy_range_to_slice: index {} was > length {}"
+               , range.end, self.len());
+        assert!( buf.len() == len
+               , "Rope::copy_range_to_slice: buffer length {} != range \
+                  length {}"
+               , buf.len(), len);
+        self.read_at(range.start, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp;
+    use std::io::{BufRead, ErrorKind, Read, Seek, SeekFrom, Write};
+    use ::Rope;
+    use ::test::leaves;
+    use super::{RopeReader, RopeWriter};
+
+    #[test]
+    fn chars_split_across_writes() {
+        let text = "a\u{3c0}\u{1f600}b\u{e9}";
+        let mut writer = RopeWriter::new();
+        for byte in text.bytes() {
+            assert_eq!(writer.write(&[byte]).unwrap(), 1);
+        }
+        assert_eq!(&writer.into_rope().unwrap(), text);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut writer = RopeWriter::new();
+        assert_eq!(writer.write(b"ab\xffcd").unwrap(), 2);
+        assert_eq!( writer.write(b"\xffcd").unwrap_err().kind()
+                  , ErrorKind::InvalidData);
+        let mut writer = RopeWriter::new();
+        writer.write_all(b"\xcf").unwrap();
+        assert!(writer.write_all(b"a").is_err());
+    }
+
+    #[test]
+    fn read_across_leaves() {
+        let rope = Rope::from("a\u{3c0}") + Rope::from("") + Rope::from("bc");
+        let mut reader = RopeReader::new(rope);
+        let mut bytes = Vec::new();
+        let mut buf = [0; 2];
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                0 => break
+              , n => bytes.extend_from_slice(&buf[..n])
+            }
+        }
+        assert_eq!(bytes, "a\u{3c0}bc".as_bytes());
+        assert_eq!(reader.position(), 5);
+    }
+
+    #[test]
+    fn seek() {
+        let rope = Rope::from("ab\u{3c0}") + Rope::from("cd");
+        let mut reader = RopeReader::new(rope);
+        let mut byte = [0; 1];
+        // into the middle of a `char`
+        assert_eq!(reader.seek(SeekFrom::Start(3)).unwrap(), 3);
+        reader.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], 0x80);
+        assert_eq!(reader.seek(SeekFrom::Current(-4)).unwrap(), 0);
+        reader.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"a");
+        assert_eq!(reader.seek(SeekFrom::End(-1)).unwrap(), 5);
+        reader.read_exact(&mut byte).unwrap();
+        assert_eq!(&byte, b"d");
+        // past the end
+        assert_eq!(reader.seek(SeekFrom::End(3)).unwrap(), 9);
+        assert_eq!(reader.read(&mut byte).unwrap(), 0);
+        assert_eq!( reader.seek(SeekFrom::Current(-10)).unwrap_err().kind()
+                  , ErrorKind::InvalidInput);
+        assert_eq!(reader.position(), 9);
+    }
+
+    #[test]
+    fn buffered_lines() {
+        let text = "one\ntw\u{3c0}o\n\nthree";
+        let rope = Rope::from("one\ntw\u{3c0}") + Rope::from("o\n")
+                 + Rope::from("\nthr") + Rope::from("ee");
+        let lines = RopeReader::new(rope.clone())
+                        .lines().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(lines, text.lines().collect::<Vec<_>>());
+
+        let mut reader = RopeReader::new(rope);
+        // the rest of the leaf is buffered, and leaves which index their
+        // lines aren't split after each newline
+        #[cfg(not(feature = "line-leaves"))]
+        let first = "one\n";
+        #[cfg(feature = "line-leaves")]
+        let first = "one\ntw\u{3c0}";
+        assert_eq!(reader.fill_buf().unwrap(), first.as_bytes());
+        reader.consume(2);
+        let mut line = String::new();
+        assert_eq!(reader.read_line(&mut line).unwrap(), 2);
+        assert_eq!(line, "e\n");
+        // the middle of the `\u{3c0}`
+        reader.seek(SeekFrom::Start(7)).unwrap();
+        assert_eq!( reader.read_line(&mut line).unwrap_err().kind()
+                  , ErrorKind::InvalidData);
+        assert_eq!(reader.position(), 7);
+    }
+
+    #[test]
+    fn read_at_across_leaves() {
+        let rope = leaves(&["ab\u{3c0}", "", "c", "def"]);
+        let bytes = rope.to_string().into_bytes();
+        for offset in 0..bytes.len() + 2 {
+            for len in 0..bytes.len() + 2 {
+                let mut buf = vec![0; len];
+                let n = rope.read_at(offset, &mut buf);
+                let expected = bytes.get(offset..).map_or(&[][..], |rest|
+                    &rest[..cmp::min(len, rest.len())]);
+                assert_eq!(&buf[..n], expected);
+            }
+        }
+        assert_eq!(rope.read_at(usize::max_value(), &mut [0; 4]), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer length 3 != range length 2")]
+    fn copy_range_to_short_slice() {
+        Rope::from("abc").copy_range_to_slice(0..2, &mut [0; 3]);
+    }
+
+    #[test]
+    fn truncated_char() {
+        let mut writer = RopeWriter::new();
+        writer.write_all(b"a\xf0\x9f").unwrap();
+        assert_eq!( writer.into_rope().unwrap_err().kind()
+                  , ErrorKind::InvalidData);
+    }
+}
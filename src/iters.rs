@@ -0,0 +1,786 @@
+//! Iterators over `Rope`s which can seek.
+//!
+//! The default `nth` for an iterator advances it one item at a time, so
+//! `rope.lines().nth(100_000)` would visit every line before the one that was
+//! asked for. The iterators in this module instead override `nth` to seek
+//! straight to the requested item, using the counts cached in each node of
+//! the `Rope`'s tree, so that skipping ahead is O(log _n_). Since `skip`
+//! is implemented in terms of `nth`, it benefits as well.
+
+use super::Rope;
+use super::slice::RopeSlice;
+use super::metric::{CharIndex, Line, Measured, Metric};
+use super::line_ending::is_line_break;
+
+use std::ops;
+use std::str;
+
+#[cfg(feature = "unicode")]
+use unicode_segmentation::{self, UnicodeSegmentation};
+
+/// An iterator over the lines of a `Rope`, without their line terminators.
+///
+/// This is the iterator returned by `Rope::lines`. The lines it yields
+/// borrow the `Rope`, rather than the iterator, so they may be collected
+/// and kept after it is dropped.
+#[derive(Clone, Debug)]
+pub struct Lines<'a> { rope: &'a Rope
+                     , offset: usize
+                     , line: usize
+                     }
+
+impl<'a> Lines<'a> {
+    #[inline]
+    pub fn new(rope: &'a Rope) -> Self {
+        Lines { rope: rope, offset: 0, line: 0 }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = RopeSlice<'a>;
+
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        let len = self.rope.len();
+        if self.offset >= len { return None }
+        // `self.line` newlines come before `self.offset`, so the next one
+        // ends the current line
+        let (end, next) = match self.rope.root.after_newline(self.line) {
+            Some(i) => (self.rope.line_content_end(i), i)
+          , None => (len, len)
+        };
+        let line = self.rope.slice(self.offset..end);
+        self.offset = next;
+        self.line += 1;
+        Some(line)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<RopeSlice<'a>> {
+        let line = self.line + n;
+        let start = if line == 0 { Some(0) }
+                    else { self.rope.root.after_newline(line - 1) };
+        match start {
+            Some(start) if start < self.rope.len() => {
+                self.offset = start;
+                self.line = line;
+                self.next()
+            }
+          , _ => {
+                self.offset = self.rope.len();
+                None
+            }
+        }
+    }
+}
+
+/// An iterator over the lines of a `Rope`, with their line numbers and
+/// whether each was ended by a line terminator.
+///
+/// This is the iterator returned by `Rope::lines_enumerated`.
+#[derive(Clone, Debug)]
+pub struct LinesEnumerated<'a>(Lines<'a>);
+
+impl<'a> LinesEnumerated<'a> {
+    #[inline]
+    pub fn new(rope: &'a Rope) -> Self { LinesEnumerated(Lines::new(rope)) }
+}
+
+impl<'a> Iterator for LinesEnumerated<'a> {
+    type Item = (usize, RopeSlice<'a>, bool);
+
+    fn next(&mut self) -> Option<(usize, RopeSlice<'a>, bool)> {
+        let (start, number) = (self.0.offset, self.0.line);
+        let line = self.0.next()?;
+        // the next line starts after the terminator, if there was one
+        let terminated = self.0.offset > start + line.len();
+        Some((number, line, terminated))
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<(usize, RopeSlice<'a>, bool)> {
+        if n > 0 { self.0.nth(n - 1)?; }
+        self.next()
+    }
+}
+
+/// An iterator over the `char`s of a `Rope`.
+///
+/// This is the iterator returned by `Rope::chars`.
+#[derive(Clone, Debug)]
+pub struct Chars<'a> { rope: &'a Rope
+                     , chars: str::Chars<'a>
+                     , next_leaf: usize
+                     , index: usize
+                     }
+
+impl<'a> Chars<'a> {
+    #[inline]
+    pub fn new(rope: &'a Rope) -> Self {
+        Chars { rope: rope, chars: "".chars(), next_leaf: 0, index: 0 }
+    }
+
+    /// Moves this iterator to byte index `i`, which must fall on a
+    /// character boundary.
+    fn seek(&mut self, i: usize) {
+        let (leaf, leaf_start) = self.rope.root.leaf_at(i);
+        self.chars = leaf[i - leaf_start..].chars();
+        self.next_leaf = leaf_start + leaf.len();
+    }
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            if let Some(c) = self.chars.next() {
+                self.index += 1;
+                return Some(c)
+            }
+            if self.next_leaf >= self.rope.len() { return None }
+            let i = self.next_leaf;
+            self.seek(i);
+        }
+    }
+
+    fn nth(&mut self, n: usize) -> Option<char> {
+        let index = self.index + n;
+        match self.rope.root.char_to_byte(index) {
+            Some(i) if i < self.rope.len() => {
+                self.seek(i);
+                self.index = index;
+                self.next()
+            }
+          , _ => {
+                self.chars = "".chars();
+                self.next_leaf = self.rope.len();
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.rope.len() - self.next_leaf;
+        let (lower, upper) = self.chars.size_hint();
+        ( lower + (remaining + 3) / 4
+        , upper.map(|upper| upper + remaining))
+    }
+}
+
+/// A double-ended iterator over the `char`s between two byte indices of a
+/// `Rope`.
+///
+/// This is the iterator returned by `Rope::chars_between`. Each end keeps
+/// the part of the leaf it is in, and seeks to the next leaf in O(log _n_)
+/// when that runs out, so iterating backwards never visits the text before
+/// the start of the range.
+#[derive(Clone, Debug)]
+pub struct CharsBetween<'a> { rope: &'a Rope
+                            , front: &'a str
+                            , back: &'a str
+                            , start: usize
+                            , end: usize
+                            }
+
+impl<'a> CharsBetween<'a> {
+    /// Returns an iterator over the `char`s between byte indices `start`
+    /// and `end` of `rope`, which must fall on character boundaries.
+    #[inline]
+    pub fn new(rope: &'a Rope, start: usize, end: usize) -> Self {
+        CharsBetween { rope: rope, front: "", back: "", start: start, end: end }
+    }
+}
+
+impl<'a> Iterator for CharsBetween<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.start >= self.end { return None }
+        if self.front.is_empty() {
+            let (leaf, leaf_start) = self.rope.root.leaf_at(self.start);
+            self.front = &leaf[self.start - leaf_start..];
+        }
+        // the text in `self.front` may run past `self.end`, if the back of
+        // the iterator has already yielded it, but the `char` at
+        // `self.start` has not been
+        let c = self.front.chars().next().unwrap();
+        self.front = &self.front[c.len_utf8()..];
+        self.start += c.len_utf8();
+        Some(c)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end.saturating_sub(self.start);
+        ((remaining + 3) / 4, Some(remaining))
+    }
+}
+
+impl<'a> DoubleEndedIterator for CharsBetween<'a> {
+    fn next_back(&mut self) -> Option<char> {
+        if self.start >= self.end { return None }
+        if self.back.is_empty() {
+            let (leaf, leaf_start) = self.rope.root.leaf_at(self.end - 1);
+            self.back = &leaf[..self.end - leaf_start];
+        }
+        let c = self.back.chars().next_back().unwrap();
+        self.back = &self.back[..self.back.len() - c.len_utf8()];
+        self.end -= c.len_utf8();
+        Some(c)
+    }
+}
+
+/// An iterator over the `char`s of a `Rope` from the start of a line, along
+/// with the line and column of each.
+///
+/// This is the iterator returned by `Rope::char_indices_at_line`.
+#[derive(Clone, Debug)]
+pub struct CharIndicesAtLine<'a> { chars: CharsBetween<'a>
+                                 , line: usize
+                                 , column: usize
+                                 }
+
+impl<'a> CharIndicesAtLine<'a> {
+    /// Returns an iterator over the `char`s of `rope` from the start of line
+    /// `line`, which is empty if `rope` has no such line.
+    pub fn new(rope: &'a Rope, line: usize) -> Self {
+        // line `line` starts just after the line ending of the line before
+        let start = if line == 0 { Some(0) }
+                    else { rope.to_byte_index(Line(line - 1)) };
+        let start = start.unwrap_or(rope.len());
+        CharIndicesAtLine { chars: CharsBetween::new(rope, start, rope.len())
+                          , line: line
+                          , column: 0
+                          }
+    }
+}
+
+impl<'a> Iterator for CharIndicesAtLine<'a> {
+    type Item = (usize, usize, char);
+
+    fn next(&mut self) -> Option<(usize, usize, char)> {
+        self.chars.next().map(|c| {
+            let item = (self.line, self.column, c);
+            if is_line_break(c) {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += 1;
+            }
+            item
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) { self.chars.size_hint() }
+}
+
+macro_rules! segment_leaves {
+    ($(#[$attr:meta])* pub struct $name: ident($seg: ident) = $split: expr;)
+    => {
+        $(#[$attr])*
+        #[cfg(feature = "unicode")]
+        #[derive(Clone)]
+        pub struct $name<'a> { rope: &'a Rope
+                             , segments: unicode_segmentation::$seg<'a>
+                             , next_leaf: usize
+                             }
+
+        #[cfg(feature = "unicode")]
+        impl<'a> $name<'a> {
+            #[inline]
+            pub fn new(rope: &'a Rope) -> Self {
+                $name { rope: rope, segments: $split(""), next_leaf: 0 }
+            }
+        }
+
+        #[cfg(feature = "unicode")]
+        impl<'a> Iterator for $name<'a> {
+            type Item = &'a str;
+
+            fn next(&mut self) -> Option<&'a str> {
+                loop {
+                    if let Some(s) = self.segments.next() { return Some(s) }
+                    if self.next_leaf >= self.rope.len() { return None }
+                    let (leaf, leaf_start) =
+                        self.rope.root.leaf_at(self.next_leaf);
+                    self.segments = $split(leaf);
+                    self.next_leaf = leaf_start + leaf.len();
+                }
+            }
+        }
+    }
+}
+
+segment_leaves! {
+    /// An iterator over the grapheme clusters of a `Rope`.
+    ///
+    /// This is the iterator returned by `Rope::graphemes`. It keeps its
+    /// position in the `Rope` itself, rather than in a chain of closures, so
+    /// it can be cloned, and stored alongside the `Rope` it borrows.
+    pub struct Graphemes(Graphemes) = |s: &'a str| s.graphemes(true);
+}
+
+segment_leaves! {
+    /// An iterator over the substrings of a `Rope` separated on UAX#29 word
+    /// boundaries.
+    ///
+    /// This is the iterator returned by `Rope::split_word_bounds`. Like
+    /// [`Graphemes`], it can be cloned and stored.
+    ///
+    /// [`Graphemes`]: struct.Graphemes.html
+    pub struct SplitWordBounds(UWordBounds) =
+        |s: &'a str| s.split_word_bounds();
+}
+
+/// An iterator over the words of a `Rope`.
+///
+/// This is the iterator returned by `Rope::unicode_words`. Like
+/// [`Graphemes`], it can be cloned and stored.
+///
+/// [`Graphemes`]: struct.Graphemes.html
+#[cfg(feature = "unicode")]
+#[derive(Clone)]
+pub struct UnicodeWords<'a>(SplitWordBounds<'a>);
+
+#[cfg(feature = "unicode")]
+impl<'a> UnicodeWords<'a> {
+    #[inline]
+    pub fn new(rope: &'a Rope) -> Self {
+        UnicodeWords(SplitWordBounds::new(rope))
+    }
+}
+
+#[cfg(feature = "unicode")]
+impl<'a> Iterator for UnicodeWords<'a> {
+    type Item = &'a str;
+
+    /// Words are the substrings between word boundaries which contain an
+    /// alphanumeric character, as with `str::unicode_words`.
+    #[inline]
+    fn next(&mut self) -> Option<&'a str> {
+        self.0.find(|s| s.chars().any(char::is_alphanumeric))
+    }
+}
+
+/// The runs of either whitespace or non-whitespace `char`s in a `Rope`,
+/// which may span leaves.
+#[derive(Clone, Debug)]
+struct Runs<'a> { rope: &'a Rope
+                , chars: str::CharIndices<'a>
+                , leaf_start: usize
+                , next_leaf: usize
+                , whitespace: bool
+                }
+
+impl<'a> Runs<'a> {
+    #[inline]
+    fn new(rope: &'a Rope, whitespace: bool) -> Self {
+        Runs { rope: rope, chars: "".char_indices(), leaf_start: 0
+             , next_leaf: 0, whitespace: whitespace }
+    }
+
+    /// Returns the next `char` of the `Rope`, with its byte index.
+    fn next_char(&mut self) -> Option<(usize, char)> {
+        loop {
+            if let Some((i, c)) = self.chars.next() {
+                return Some((self.leaf_start + i, c))
+            }
+            if self.next_leaf >= self.rope.len() { return None }
+            let (leaf, leaf_start) = self.rope.root.leaf_at(self.next_leaf);
+            self.chars = leaf.char_indices();
+            self.leaf_start = leaf_start;
+            self.next_leaf = leaf_start + leaf.len();
+        }
+    }
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = ops::Range<usize>;
+
+    fn next(&mut self) -> Option<ops::Range<usize>> {
+        let whitespace = self.whitespace;
+        let (start, c) = loop {
+            let (i, c) = self.next_char()?;
+            if c.is_whitespace() == whitespace { break (i, c) }
+        };
+        // the `char` which ends the run can't start the next one, so it is
+        // consumed along with the run
+        let mut end = start + c.len_utf8();
+        while let Some((i, c)) = self.next_char() {
+            if c.is_whitespace() != whitespace { break }
+            end = i + c.len_utf8();
+        }
+        Some(start..end)
+    }
+}
+
+/// An iterator over the words of a `Rope`, separated by whitespace, with
+/// their byte indices.
+///
+/// This is the iterator returned by `Rope::split_whitespace_indices`.
+/// Unlike `Rope::split_whitespace`, words which span leaves are yielded
+/// whole.
+#[derive(Clone, Debug)]
+pub struct SplitWhitespaceIndices<'a>(Runs<'a>);
+
+impl<'a> SplitWhitespaceIndices<'a> {
+    #[inline]
+    pub fn new(rope: &'a Rope) -> Self {
+        SplitWhitespaceIndices(Runs::new(rope, false))
+    }
+}
+
+impl<'a> Iterator for SplitWhitespaceIndices<'a> {
+    type Item = (usize, RopeSlice<'a>);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, RopeSlice<'a>)> {
+        let rope = self.0.rope;
+        self.0.next().map(|range| (range.start, rope.slice(range)))
+    }
+}
+
+/// An iterator over the byte ranges of the runs of whitespace in a `Rope`.
+///
+/// This is the iterator returned by `Rope::whitespace_runs`.
+#[derive(Clone, Debug)]
+pub struct WhitespaceRuns<'a>(Runs<'a>);
+
+impl<'a> WhitespaceRuns<'a> {
+    #[inline]
+    pub fn new(rope: &'a Rope) -> Self { WhitespaceRuns(Runs::new(rope, true)) }
+}
+
+impl<'a> Iterator for WhitespaceRuns<'a> {
+    type Item = ops::Range<usize>;
+
+    #[inline]
+    fn next(&mut self) -> Option<ops::Range<usize>> { self.0.next() }
+}
+
+/// An iterator over overlapping windows of a `Rope`, of a fixed width
+/// along the metric `M`.
+///
+/// This is the iterator returned by `Rope::window_iter`.
+#[derive(Clone, Debug)]
+pub struct Windows<'a, M> { rope: &'a Rope
+                          , start: M
+                          , size: usize
+                          , step: usize
+                          }
+
+impl<'a, M> Windows<'a, M>
+where M: Metric
+    , Rope: Measured<M> {
+    #[inline]
+    pub fn new(rope: &'a Rope, size: M, step: M) -> Self {
+        Windows { rope: rope, start: M::default()
+                , size: size.into(), step: step.into() }
+    }
+
+    /// Returns the byte index of `index`, which may be the end of the
+    /// `Rope`.
+    fn byte_index(&self, index: M) -> usize {
+        if index == self.rope.measure() { self.rope.len() }
+        else { self.rope.to_byte_index(index).expect("index within Rope") }
+    }
+}
+
+impl<'a, M> Iterator for Windows<'a, M>
+where M: Metric
+    , Rope: Measured<M> {
+    type Item = RopeSlice<'a>;
+
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        let end = self.start + self.size;
+        if end > self.rope.measure() { return None }
+        let range = self.byte_index(self.start)..self.byte_index(end);
+        self.start = self.start + self.step;
+        Some(self.rope.slice(range))
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<RopeSlice<'a>> {
+        let measure: usize = self.rope.measure().into();
+        let skip = n.saturating_mul(self.step);
+        if skip > measure { return None }
+        self.start = self.start + skip;
+        self.next()
+    }
+}
+
+/// An iterator over the text of a `Rope` in chunks of exactly `n` `char`s.
+///
+/// This is the iterator returned by `Rope::chunks_exact_chars`. As with
+/// `slice::chunks_exact`, the last `char`s of the `Rope` are not yielded if
+/// there are fewer than `n` of them; they are returned by `remainder`.
+#[derive(Clone, Debug)]
+pub struct ChunksExactChars<'a>(Windows<'a, CharIndex>);
+
+impl<'a> ChunksExactChars<'a> {
+    #[inline]
+    pub fn new(rope: &'a Rope, n: usize) -> Self {
+        ChunksExactChars(Windows::new(rope, CharIndex(n), CharIndex(n)))
+    }
+
+    /// Returns the `char`s at the end of the `Rope` which are too few to
+    /// make up a whole chunk.
+    pub fn remainder(&self) -> RopeSlice<'a> {
+        let CharIndex(len) = self.0.rope.measure();
+        let start = self.0.byte_index(CharIndex(len - len % self.0.size));
+        self.0.rope.slice(start..)
+    }
+}
+
+impl<'a> Iterator for ChunksExactChars<'a> {
+    type Item = RopeSlice<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<RopeSlice<'a>> { self.0.next() }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<RopeSlice<'a>> { self.0.nth(n) }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{Rope, RopeSlice};
+    use ::test::leaves;
+
+    #[test]
+    fn lines_enumerated_matches_lines() {
+        let rope = rope();
+        let lines = rope.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+        let enumerated = rope.lines_enumerated()
+                             .map(|(n, l, ended)| (n, l.to_string(), ended))
+                             .collect::<Vec<_>>();
+        assert_eq!(enumerated.len(), lines.len());
+        for (i, &(n, ref line, ended)) in enumerated.iter().enumerate() {
+            assert_eq!((n, line), (i, &lines[i]));
+            assert_eq!(ended, i + 1 < lines.len());
+        }
+        for n in 0..8 {
+            assert_eq!( rope.lines_enumerated().nth(n)
+                            .map(|(n, l, ended)| (n, l.to_string(), ended))
+                      , enumerated.get(n).cloned());
+        }
+        let ended = leaves(&["a\r", "\n"]).lines_enumerated()
+                                         .map(|(_, _, ended)| ended)
+                                         .collect::<Vec<_>>();
+        assert_eq!(ended, vec![true]);
+    }
+
+    #[test]
+    fn windows_by_bytes_and_chars() {
+        use ::metric::CharIndex;
+        let rope = rope();
+        let text = rope.to_string();
+        let chars = text.chars().collect::<Vec<_>>();
+        for size in 1..4 {
+            for step in 1..4 {
+                let windows = rope.window_iter(CharIndex(size)
+                                              , CharIndex(step))
+                                  .map(|w| w.to_string())
+                                  .collect::<Vec<_>>();
+                let expected = (0..chars.len() + 1 - size).step_by(step)
+                    .map(|i| chars[i..i + size].iter().collect::<String>())
+                    .collect::<Vec<_>>();
+                assert_eq!(windows, expected);
+                for n in 0..expected.len() + 2 {
+                    assert_eq!( rope.window_iter(CharIndex(size)
+                                                , CharIndex(step))
+                                    .nth(n).map(|w| w.to_string())
+                              , expected.get(n).cloned());
+                }
+            }
+        }
+        let ascii = leaves(&["abc", "de"]);
+        assert_eq!( ascii.window_iter(2, 2).map(|w| w.to_string())
+                         .collect::<Vec<_>>()
+                  , vec!["ab", "cd"]);
+        assert_eq!(ascii.window_iter(6, 1).count(), 0);
+    }
+
+    #[test]
+    fn chunks_exact_chars_across_leaves() {
+        let rope = leaves(&["ab\u{3c0}", "", "d\u{3c4}", "fg"]);
+        let text = rope.to_string();
+        let chars = text.chars().collect::<Vec<_>>();
+        for n in 1..chars.len() + 2 {
+            let mut chunks = rope.chunks_exact_chars(n);
+            let expected = chars.chunks(n)
+                                .filter(|c| c.len() == n)
+                                .map(|c| c.iter().collect::<String>())
+                                .collect::<Vec<_>>();
+            assert_eq!( chunks.by_ref().map(|c| c.to_string())
+                              .collect::<Vec<_>>()
+                      , expected);
+            let tail = chars[chars.len() - chars.len() % n..]
+                           .iter().collect::<String>();
+            assert_eq!(chunks.remainder().to_string(), tail);
+            assert_eq!( rope.chunks_exact_chars(n).nth(1)
+                            .map(|c| c.to_string())
+                      , expected.get(1).cloned());
+        }
+    }
+
+    fn rope() -> Rope {
+        Rope::from("one\n\u{3c0}\u{3c0}\n") + Rope::from("three\nfo")
+            + Rope::from("ur\n\nsix")
+    }
+
+    #[test]
+    fn lines_nth_matches_next() {
+        let rope = rope();
+        let lines = rope.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, vec!["one", "\u{3c0}\u{3c0}", "three", "four", ""
+                              , "six"]);
+        for n in 0..8 {
+            assert_eq!( rope.lines().nth(n).map(|l| l.to_string())
+                      , lines.get(n).cloned());
+            assert_eq!( rope.lines().skip(n).map(|l| l.to_string())
+                            .collect::<Vec<_>>()
+                      , lines.iter().skip(n).cloned().collect::<Vec<_>>());
+        }
+        let mut iter = rope.lines();
+        assert_eq!(&iter.nth(1).unwrap().to_string(), "\u{3c0}\u{3c0}");
+        assert_eq!(&iter.nth(1).unwrap().to_string(), "four");
+        assert_eq!(&iter.next().unwrap().to_string(), "");
+        assert!(iter.nth(1).is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn lines_outlive_iterator() {
+        fn viewport(rope: &Rope, first: usize, n: usize) -> Vec<RopeSlice> {
+            let mut lines = rope.lines();
+            let viewport = lines.by_ref().skip(first).take(n).collect();
+            drop(lines);
+            viewport
+        }
+        let rope = rope();
+        let lines = viewport(&rope, 1, 3);
+        assert_eq!( lines.iter().map(|l| l.to_string()).collect::<Vec<_>>()
+                  , vec!["\u{3c0}\u{3c0}", "three", "four"]);
+    }
+
+    #[test]
+    fn lines_strip_crlf_across_leaves() {
+        let rope = Rope::from("one\r") + Rope::from("\ntwo\r\r\n\r")
+                 + Rope::from("\nthree\r");
+        let text = rope.to_string();
+        assert_eq!( rope.lines().map(|l| l.to_string()).collect::<Vec<_>>()
+                  , text.lines().collect::<Vec<_>>());
+        assert_eq!(&rope.first_line(), "one");
+        assert_eq!(&rope.last_line(), "three\r");
+    }
+
+    #[test]
+    fn chars_nth_matches_next() {
+        let rope = rope();
+        let chars = rope.to_string().chars().collect::<Vec<_>>();
+        for n in 0..chars.len() + 2 {
+            assert_eq!(rope.chars().nth(n), chars.get(n).cloned());
+            assert_eq!( rope.chars().skip(n).collect::<Vec<_>>()
+                      , chars.iter().skip(n).cloned().collect::<Vec<_>>());
+        }
+        let mut iter = rope.chars();
+        assert_eq!(iter.nth(4), Some('\u{3c0}'));
+        assert_eq!(iter.nth(2), Some('t'));
+        assert_eq!(iter.next(), Some('h'));
+    }
+
+    #[test]
+    fn chars_between_from_both_ends() {
+        let rope = rope();
+        let s = rope.to_string();
+        let bounds = (0..s.len() + 1).filter(|&i| s.is_char_boundary(i))
+                                     .collect::<Vec<_>>();
+        for &a in &bounds {
+            for &b in bounds.iter().filter(|&&b| b >= a) {
+                assert_eq!( rope.chars_between(a, b).rev().collect::<String>()
+                          , s[a..b].chars().rev().collect::<String>());
+                // alternate ends, so that both hold the same leaf
+                let mut iter = rope.chars_between(a, b);
+                let mut expected = s[a..b].chars();
+                for i in 0.. {
+                    let (c, e) = if i % 2 == 0 {
+                        (iter.next(), expected.next())
+                    } else {
+                        (iter.next_back(), expected.next_back())
+                    };
+                    assert_eq!(c, e);
+                    if c.is_none() { break }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not a char boundary")]
+    fn chars_between_within_char() {
+        rope().chars_between(0, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn segmentation_iterators_clone_and_resume() {
+        let rope = Rope::from("one two, ") + Rope::from("")
+                 + Rope::from("thr\u{e9}e\u{301} 4");
+        let words = rope.unicode_words().collect::<Vec<_>>();
+        assert_eq!(words, vec!["one", "two", "thr\u{e9}e\u{301}", "4"]);
+        let bounds = rope.split_word_bounds().collect::<Vec<_>>();
+        assert_eq!(bounds.concat(), rope.to_string());
+        let graphemes = rope.graphemes().collect::<Vec<_>>();
+        assert_eq!(graphemes.concat(), rope.to_string());
+        for n in 0..graphemes.len() + 1 {
+            let mut iter = rope.graphemes();
+            for _ in 0..n { iter.next(); }
+            let resume = iter.clone();
+            assert_eq!(iter.collect::<Vec<_>>(), &graphemes[n..]);
+            assert_eq!(resume.collect::<Vec<_>>(), &graphemes[n..]);
+        }
+        let mut iter = rope.unicode_words().peekable();
+        assert_eq!(iter.peek(), Some(&"one"));
+        assert_eq!(iter.clone().last(), Some("4"));
+        assert!(Rope::new().graphemes().next().is_none());
+    }
+
+    #[test]
+    fn empty() {
+        assert!(Rope::new().lines().next().is_none());
+        assert!(Rope::new().lines().nth(0).is_none());
+        assert!((Rope::new() + Rope::new()).lines().next().is_none());
+        assert!(Rope::new().chars().nth(0).is_none());
+    }
+
+    #[test]
+    fn whitespace_runs_across_leaves() {
+        let rope = leaves(&["  one t", "wo \t", "\n\u{3c0}", "", " "]);
+        let text = rope.to_string();
+        let words = rope.split_whitespace_indices()
+                        .map(|(i, w)| (i, w.to_string()))
+                        .collect::<Vec<_>>();
+        assert_eq!(words, vec![ (2, String::from("one"))
+                              , (6, String::from("two"))
+                              , (12, String::from("\u{3c0}")) ]);
+        assert_eq!( words.iter().map(|&(_, ref w)| &w[..])
+                         .collect::<Vec<_>>()
+                  , text.split_whitespace().collect::<Vec<_>>());
+        assert_eq!( rope.whitespace_runs().collect::<Vec<_>>()
+                  , vec![0..2, 5..6, 9..12, 14..15]);
+        assert!(Rope::new().whitespace_runs().next().is_none());
+    }
+
+    #[test]
+    fn lines_of_newline_terminated_documents() {
+        for text in &["\n", "\n\n", "a\n", "a\r\n\r\n", "\na\n"] {
+            let rope = Rope::from(&text[..1]) + Rope::from(&text[1..]);
+            assert_eq!( rope.lines().map(|l| l.to_string())
+                            .collect::<Vec<_>>()
+                      , text.lines().collect::<Vec<_>>());
+            assert_eq!(rope.lines().count(), text.lines().count());
+        }
+    }
+}
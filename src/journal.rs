@@ -0,0 +1,308 @@
+//! Crash-recovery journals of the edits made to a `Rope`.
+//!
+//! A [`JournalWriter`] streams every [`Delta`] applied to a buffer to an
+//! `io::Write`, such as a file beside the document, as it is made. If the
+//! editor crashes, [`replay`] reads the journal back and reconstructs the
+//! buffer as it was after the last edit which reached the journal.
+//!
+//! Replaying a long editing session from its start would take time
+//! proportional to every edit ever made, so the writer also records a
+//! snapshot of the whole buffer every [`DEFAULT_SNAPSHOT_INTERVAL`] edits,
+//! or as often as it is told to. Replay starts from the last snapshot, and
+//! only applies the edits recorded after it.
+//!
+//! A journal is a short header followed by a sequence of records, each of
+//! which is a tag byte, `S` for a snapshot or `D` for a delta, followed by
+//! its fields. Numbers are written as 64-bit little-endian integers, and
+//! text as its length followed by its UTF-8 bytes. A record which was cut
+//! short, as by a crash while it was being written, ends the journal.
+//!
+//! [`JournalWriter`]: struct.JournalWriter.html
+//! [`Delta`]: ../struct.Delta.html
+//! [`replay`]: fn.replay.html
+//! [`DEFAULT_SNAPSHOT_INTERVAL`]: constant.DEFAULT_SNAPSHOT_INTERVAL.html
+use std::io::{self, Read};
+
+use super::{Delta, Rope};
+
+/// The bytes with which every journal starts, ending with a format version.
+const MAGIC: &[u8] = b"an-rope journal\x01";
+
+/// The number of deltas a `JournalWriter` records between snapshots, unless
+/// it is told otherwise.
+pub const DEFAULT_SNAPSHOT_INTERVAL: usize = 1024;
+
+#[inline]
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[inline]
+fn push_u64(buf: &mut Vec<u8>, n: usize) {
+    buf.extend_from_slice(&(n as u64).to_le_bytes());
+}
+
+/// A record in a journal.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Record { /// The whole text of the buffer.
+                  Snapshot(Rope)
+                , /// An edit to the buffer.
+                  Delta(Delta)
+                }
+
+/// Writes a journal of the edits made to a `Rope`.
+///
+/// # Examples
+/// ```
+/// use an_rope::{Delta, Rope};
+/// use an_rope::journal::{self, JournalWriter};
+///
+/// let rope = Rope::from("hello world");
+/// let mut writer = JournalWriter::new(Vec::new(), &rope).unwrap();
+/// let rope = writer.record(&Delta::new(6..11, "there"), &rope).unwrap();
+/// let rope = writer.record(&Delta::insert(11, "!"), &rope).unwrap();
+/// assert_eq!(&rope, "hello there!");
+///
+/// let journal = writer.into_inner();
+/// assert_eq!(journal::replay(&journal[..]).unwrap(), rope);
+/// ```
+#[derive(Debug)]
+pub struct JournalWriter<W: io::Write> { inner: W
+                                       , interval: Option<usize>
+                                       , since_snapshot: usize
+                                       }
+
+impl<W: io::Write> JournalWriter<W> {
+    /// Returns a new `JournalWriter` which writes to `inner`, starting the
+    /// journal with a snapshot of `rope`.
+    ///
+    /// # Errors
+    /// If writing to `inner` fails.
+    pub fn new(mut inner: W, rope: &Rope) -> io::Result<Self> {
+        inner.write_all(MAGIC)?;
+        let mut writer = JournalWriter { inner: inner
+                                       , interval:
+                                           Some(DEFAULT_SNAPSHOT_INTERVAL)
+                                       , since_snapshot: 0 };
+        writer.snapshot(rope)?;
+        Ok(writer)
+    }
+
+    /// Returns the number of deltas this writer records between snapshots,
+    /// or `None` if it only records them when `snapshot` is called.
+    #[inline]
+    pub fn snapshot_interval(&self) -> Option<usize> { self.interval }
+
+    /// Sets the number of deltas this writer records between snapshots.
+    /// `None` disables periodic snapshots.
+    ///
+    /// # Panics
+    /// If `interval` is `Some(0)`.
+    pub fn set_snapshot_interval(&mut self, interval: Option<usize>) {
+        assert!( interval != Some(0)
+               , "JournalWriter::set_snapshot_interval: interval must be > 0");
+        self.interval = interval;
+    }
+
+    /// Applies `delta` to `rope`, records it, and returns the edited
+    /// `Rope`.
+    ///
+    /// If this is the last delta before a snapshot is due, a snapshot of
+    /// the edited `Rope` is recorded after it.
+    ///
+    /// # Errors
+    /// - If `delta` can't be applied to `rope`, in which case nothing is
+    ///   recorded
+    /// - If writing to the underlying writer fails
+    pub fn record(&mut self, delta: &Delta, rope: &Rope) -> io::Result<Rope> {
+        let edited = delta.try_apply(rope)?;
+        let range = delta.range();
+        let mut header = vec![b'D'];
+        push_u64(&mut header, range.start);
+        push_u64(&mut header, range.end);
+        push_u64(&mut header, delta.text().len());
+        self.inner.write_all(&header)?;
+        self.inner.write_all(delta.text().as_bytes())?;
+        self.since_snapshot += 1;
+        if self.interval.map_or(false, |n| self.since_snapshot >= n) {
+            self.snapshot(&edited)?;
+        }
+        Ok(edited)
+    }
+
+    /// Records a snapshot of `rope`, which must be the result of the deltas
+    /// recorded so far.
+    ///
+    /// # Errors
+    /// If writing to the underlying writer fails.
+    pub fn snapshot(&mut self, rope: &Rope) -> io::Result<()> {
+        let mut header = vec![b'S'];
+        push_u64(&mut header, rope.len());
+        self.inner.write_all(&header)?;
+        for s in rope.strings() { self.inner.write_all(s.as_bytes())?; }
+        self.since_snapshot = 0;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    #[inline]
+    pub fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
+
+    /// Returns a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W { &self.inner }
+
+    /// Returns the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W { self.inner }
+}
+
+/// An iterator over the records of a journal.
+#[derive(Debug)]
+pub struct JournalReader<R: io::Read> { inner: R
+                                      , started: bool
+                                      , done: bool
+                                      }
+
+impl<R: io::Read> JournalReader<R> {
+    /// Returns a new `JournalReader` which reads a journal from `inner`.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        JournalReader { inner: inner, started: false, done: false }
+    }
+
+    fn read_u64(&mut self) -> io::Result<usize> {
+        let mut buf = [0; 8];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf) as usize)
+    }
+
+    fn read_text(&mut self) -> io::Result<String> {
+        let len = self.read_u64()?;
+        let mut buf = Vec::new();
+        (&mut self.inner).take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() < len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "text"))
+        }
+        String::from_utf8(buf).map_err(|_| invalid_data("invalid UTF-8"))
+    }
+
+    /// Reads the next record, failing with `UnexpectedEof` if the journal
+    /// ends before it does.
+    fn read_record(&mut self) -> io::Result<Record> {
+        if !self.started {
+            let mut magic = vec![0; MAGIC.len()];
+            self.inner.read_exact(&mut magic)?;
+            if &magic[..] != MAGIC { return Err(invalid_data("not a journal")) }
+            self.started = true;
+        }
+        let mut tag = [0];
+        self.inner.read_exact(&mut tag)?;
+        match tag[0] {
+            b'S' => Ok(Record::Snapshot(Rope::from(self.read_text()?)))
+          , b'D' => {
+                let (start, end) = (self.read_u64()?, self.read_u64()?);
+                let text = self.read_text()?;
+                if start > end { return Err(invalid_data("invalid range")) }
+                Ok(Record::Delta(Delta::new(start..end, text)))
+            }
+          , _ => Err(invalid_data("unknown record"))
+        }
+    }
+}
+
+impl<R: io::Read> Iterator for JournalReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<io::Result<Record>> {
+        if self.done { return None }
+        match self.read_record() {
+            Ok(record) => Some(Ok(record))
+          , Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                // the journal ended, either between records or part way
+                // through one which was never finished
+                self.done = true;
+                None
+            }
+          , Err(e) => { self.done = true; Some(Err(e)) }
+        }
+    }
+}
+
+/// Reconstructs a `Rope` from the journal read from `reader`.
+///
+/// Deltas recorded before the last snapshot are read, but not applied.
+///
+/// # Errors
+/// - If reading from `reader` fails
+/// - With kind `InvalidData`, if the journal is malformed, or one of its
+///   deltas can't be applied to the `Rope` built from the records before it
+pub fn replay<R: io::Read>(reader: R) -> io::Result<Rope> {
+    let mut rope = Rope::new();
+    let mut deltas = Vec::new();
+    for record in JournalReader::new(reader) {
+        match record? {
+            Record::Snapshot(snapshot) => { rope = snapshot; deltas.clear(); }
+          , Record::Delta(delta) => deltas.push(delta)
+        }
+    }
+    for delta in deltas {
+        rope = delta.try_apply(&rope)
+                    .map_err(|_| invalid_data("delta out of bounds"))?;
+    }
+    Ok(rope)
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{Delta, Rope};
+    use super::{replay, JournalReader, JournalWriter, Record};
+
+    fn edited(interval: Option<usize>) -> (Vec<u8>, Rope) {
+        let mut rope = Rope::from("one\ntwo\n");
+        let mut writer = JournalWriter::new(Vec::new(), &rope).unwrap();
+        writer.set_snapshot_interval(interval);
+        for i in 0..10 {
+            let delta = Delta::insert(rope.len(), format!("{}\u{3c0}\n", i));
+            rope = writer.record(&delta, &rope).unwrap();
+        }
+        rope = writer.record(&Delta::delete(0..4), &rope).unwrap();
+        (writer.into_inner(), rope)
+    }
+
+    #[test]
+    fn replay_from_last_snapshot() {
+        let (journal, rope) = edited(Some(4));
+        let records = JournalReader::new(&journal[..])
+                                    .collect::<Result<Vec<_>, _>>().unwrap();
+        let snapshots = records.iter().filter(|r| match **r {
+            Record::Snapshot(_) => true, _ => false
+        }).count();
+        assert_eq!(records.len(), 11 + snapshots);
+        assert_eq!(snapshots, 3);
+        assert_eq!(replay(&journal[..]).unwrap(), rope);
+        assert_eq!(replay(&edited(None).0[..]).unwrap(), rope);
+    }
+
+    #[test]
+    fn torn_record_ends_journal() {
+        let (journal, _) = edited(None);
+        // cut the journal part way through the last delta, which is 25
+        // bytes long
+        let before = replay(&journal[..journal.len() - 10]).unwrap();
+        assert_eq!(&before, "one\ntwo\n0\u{3c0}\n1\u{3c0}\n2\u{3c0}\n\
+                             3\u{3c0}\n4\u{3c0}\n5\u{3c0}\n6\u{3c0}\n\
+                             7\u{3c0}\n8\u{3c0}\n9\u{3c0}\n");
+        assert!(replay(&b"not a journal, but long enough"[..]).is_err());
+        assert_eq!(replay(&b""[..]).unwrap(), Rope::new());
+    }
+
+    #[test]
+    fn invalid_delta_is_not_recorded() {
+        let rope = Rope::from("abc");
+        let mut writer = JournalWriter::new(Vec::new(), &rope).unwrap();
+        assert!(writer.record(&Delta::delete(2..5), &rope).is_err());
+        let journal = writer.into_inner();
+        assert_eq!(JournalReader::new(&journal[..]).count(), 1);
+    }
+}
@@ -23,9 +23,19 @@
                     ))]
 #![cfg_attr( all( test, feature = "unstable")
            , feature( test, insert_str) )]
+#![cfg_attr( feature = "node-pool", feature(allocator_api) )]
 #![cfg_attr( feature = "clippy", feature(plugin) )]
 #![cfg_attr( feature = "clippy", plugin(clippy) )]
 #![cfg_attr( feature = "clippy", allow(unused_variables, dead_code))]
+// the only `unsafe` code is `Rope::from_utf8_unchecked`, the C interface,
+// and the node pool's `Allocator` impl, each behind its own feature; in any
+// other build, the compiler guarantees that there is none
+#![cfg_attr( not(any( feature = "unchecked-utf8", feature = "ffi"
+                    , feature = "node-pool"))
+           , forbid(unsafe_code) )]
+#![cfg_attr( any( feature = "unchecked-utf8", feature = "ffi"
+                , feature = "node-pool")
+           , deny(unsafe_code) )]
 
 #[macro_use] extern crate macro_attr;
 #[macro_use] extern crate newtype_derive;
@@ -33,34 +43,110 @@
 #[cfg(feature = "unstable")] extern crate collections;
 #[cfg(feature = "unstable")] use collections::range::RangeArgument;
 
-extern crate unicode_segmentation;
+#[cfg(feature = "unicode")] extern crate unicode_segmentation;
 
 use std::cmp;
 use std::ops;
 use std::convert;
 use std::fmt;
 use std::string;
+use std::str;
 use std::iter;
 
+/// Returns the visual column after the grapheme cluster `g`, which is
+/// displayed at visual column `col`.
+#[cfg(feature = "unicode")]
+#[inline]
+fn next_visual_col(col: usize, g: &str, tabstop: usize) -> usize {
+    if g == "\t" { col + tabstop - col % tabstop } else { col + 1 }
+}
+
+/// Converts `range` into a half-open `Range`, where an unbounded start is 0
+/// and an unbounded end is `len`.
+#[cfg(not(feature = "unstable"))]
+fn bounded_range<R>(range: &R, len: usize) -> ops::Range<usize>
+where R: ops::RangeBounds<usize> {
+    use std::ops::Bound::*;
+    let start = match range.start_bound() {
+        Included(&i) => i
+      , Excluded(&i) => i.checked_add(1).expect("range start overflowed")
+      , Unbounded => 0
+    };
+    let end = match range.end_bound() {
+        Included(&i) => i.checked_add(1).expect("range end overflowed")
+      , Excluded(&i) => i
+      , Unbounded => len
+    };
+    start..end
+}
+
 macro_rules! or_zero {
     ($a: expr, $b: expr) => { if $a > $b { $a - $b } else { 0 } }
 }
 
+/// Returns the length of the range from `$start` to `$end`, or panics with a
+/// message naming `$fn` if `$start` is greater than `$end`.
+macro_rules! range_len {
+    ($fn: expr, $start: expr, $end: expr) => {
+        $end.checked_sub($start)
+            .unwrap_or_else(|| panic!( "{}: start {:?} > end {:?}"
+                                     , $fn, $start, $end))
+    }
+}
+
 #[cfg(feature = "tendril")] extern crate tendril;
+#[cfg(feature = "bytes")] extern crate bytes;
+#[cfg(feature = "smallstr")] extern crate smallstr;
+#[cfg(feature = "tree-sitter")] extern crate tree_sitter;
+#[cfg(feature = "ropey")] extern crate ropey;
+#[cfg(feature = "wasm")] extern crate js_sys;
+#[cfg(feature = "serde")] extern crate serde;
+#[cfg(feature = "arbitrary")] extern crate arbitrary;
 
 #[cfg(test)] #[macro_use] extern crate quickcheck;
+#[cfg(all(test, feature = "serde"))] extern crate serde_json;
 #[cfg(test)] mod test;
 #[cfg(all( test, feature = "unstable"))] mod bench;
 
-mod unicode;
+#[cfg(feature = "unicode")] mod unicode;
 pub mod metric;
+pub mod raw;
+pub mod history;
+mod compaction;
+#[cfg(feature = "complexity")] pub mod instrument;
+#[cfg(feature = "journal")] pub mod journal;
+#[cfg(feature = "ffi")] #[allow(unsafe_code)] pub mod ffi;
+#[cfg(any(test, feature = "test-support"))] pub mod test_support;
 
 use metric::{Measured, Metric};
-use self::internals::{Node, NodeLink};
+#[cfg(feature = "unicode")] use metric::Grapheme;
+use self::internals::{Node, NodeLink, WeakNodeLink};
 
 pub use self::slice::{ RopeSlice
                     //, RopeSliceMut
                         };
+pub use self::builder::{RopeBuilder, DEFAULT_CHUNK_SIZE};
+#[cfg(feature = "unicode")] pub use self::cursor::GraphemeCursor;
+#[cfg(feature = "unicode")]
+pub use self::iters::{Graphemes, SplitWordBounds, UnicodeWords};
+pub use self::iters::{CharsBetween, SplitWhitespaceIndices, WhitespaceRuns};
+pub use self::iters::{CharIndicesAtLine, ChunksExactChars, Windows};
+pub use self::iters::{Lines, LinesEnumerated};
+pub use self::versioned::{Position, VersionedRope};
+pub use self::observed::{EditInfo, ObservedRope, SubscriptionId};
+pub use self::delta::Delta;
+pub use self::indexed::{IndexedRope, LineStarts, RopeIndex};
+pub use self::history::History;
+pub use self::search::SearchOptions;
+pub use self::stats::TextStats;
+pub use self::digest::ChunkDigest;
+pub use self::lsp::{LspChange, LspPosition};
+pub use self::line_ending::{LineEnding, is_line_break};
+pub use self::error::Error;
+pub use self::policy::{IndexPolicy, Lenient, PolicyRope, Strict};
+pub use self::shared::ArcRope;
+pub use self::io::{RopeReader, RopeWriter};
+pub use self::internals::InvariantError;
 
 impl<T> convert::From<T> for Rope
 where T: convert::Into<NodeLink> {
@@ -81,6 +167,26 @@ where T: convert::Into<NodeLink> {
 /// non-destructive, persistant API. The persistant API's methods have names
 /// prefixed with ``, such as `push()` and `append()`.
 ///
+/// # Cloning
+///
+/// Cloning a `Rope` is O(1): the clone shares every node of the original's
+/// tree, and only the reference count of the root node is incremented. Since
+/// nodes are never mutated once they are created, this is always safe, and
+/// keeping old versions of a `Rope` around (for undo, say) is cheap.
+///
+/// By default, nodes are linked with `Rc`, so `Rope`s are neither `Send` nor
+/// `Sync`. Building with the `atomic` feature links them with `Arc` instead,
+/// which makes `Rope`s thread-safe at the cost of atomic reference counting.
+///
+/// # Equivalence with `String`
+///
+/// After any sequence of insertions, deletions, splits, and concatenations,
+/// a `Rope` contains exactly the text which a `String` would contain after
+/// the same sequence of edits, regardless of how the `Rope`'s tree happens to
+/// be shaped. In particular, the `Rope`'s `to_string()` is equal to that
+/// `String`, and parsing that `String` with `str::parse` gives a `Rope` equal
+/// to the original. This is checked by property tests which apply random
+/// sequences of edits to a `Rope` and to a `String` side by side.
 #[derive(Clone, Default)]
 pub struct Rope {
     // can we get away with having these be of &str or will they need
@@ -124,6 +230,88 @@ impl fmt::Display for Rope {
         write!(f, "{}", self.root)
     }
 }
+
+impl std::str::FromStr for Rope {
+    type Err = convert::Infallible;
+
+    /// Parses a `Rope` from a string slice. This never fails.
+    #[inline]
+    fn from_str(s: &str) -> Result<Rope, Self::Err> { Ok(Rope::from(s)) }
+}
+
+impl<'a> convert::From<&'a Rope> for String {
+    /// Collects the text of a `Rope` into a new `String`, allocating exactly
+    /// enough capacity up front.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("hello ") + Rope::from("world");
+    /// let s = String::from(&rope);
+    /// assert_eq!(s, "hello world");
+    /// assert_eq!(s.capacity(), s.len());
+    /// ```
+    #[inline]
+    fn from(rope: &'a Rope) -> String {
+        let mut s = String::with_capacity(rope.len());
+        rope.extend_string(&mut s);
+        s
+    }
+}
+
+impl convert::From<Rope> for String {
+    #[inline] fn from(rope: Rope) -> String { String::from(&rope) }
+}
+
+/// A weak reference to a snapshot of a `Rope`.
+///
+/// A `RopeWeak` does not keep the snapshot's text alive, so caches keyed by
+/// buffer version can hold on to old snapshots without retaining them
+/// forever. It is created with [`Rope::downgrade`].
+///
+/// Empty `Rope`s all share one empty node per thread, so a `RopeWeak` to an
+/// empty `Rope` may stay alive after that `Rope` is dropped.
+///
+/// [`Rope::downgrade`]: struct.Rope.html#method.downgrade
+#[derive(Clone, Debug)]
+pub struct RopeWeak(WeakNodeLink);
+
+impl RopeWeak {
+    /// Returns the snapshot this `RopeWeak` refers to, if it is still alive.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("hello");
+    /// let weak = rope.downgrade();
+    /// assert_eq!(weak.upgrade(), Some(rope.clone()));
+    /// drop(rope);
+    /// assert_eq!(weak.upgrade(), None);
+    /// ```
+    #[inline]
+    pub fn upgrade(&self) -> Option<Rope> {
+        self.0.upgrade().map(|root| Rope { root: root })
+    }
+
+    /// Returns `true` if `rope` is the snapshot this `RopeWeak` refers to.
+    ///
+    /// This compares identity, not content, so it is O(1): an edited `Rope`
+    /// is never the same snapshot as the original, even if its text is
+    /// equal.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("hello");
+    /// let weak = rope.downgrade();
+    /// assert!(weak.is_snapshot_of(&rope.clone()));
+    /// assert!(!weak.is_snapshot_of(&rope.insert_str(5, "!")));
+    /// ```
+    #[inline]
+    pub fn is_snapshot_of(&self, rope: &Rope) -> bool {
+        self.0.ptr_eq(&rope.root)
+    }
+}
  #[cfg(feature = "unstable")]
 macro_rules! unstable_iters {
     ( $($(#[$attr:meta])*
@@ -224,28 +412,45 @@ macro_rules! unicode_seg_iters {
             }
         }
     )+ };
-    ( $($(#[$attr:meta])* impl $name: ident<$ty: ty> for Rope {})+ )=> { $(
-        unstable_iters! {
-            $(#[$attr])*
-            pub fn $name<'a>(&'a self) -> impl Iterator<Item=$ty> + 'a {
-                self.root.$name()
-            }
-        }
-    )+ }
-
 }
 
 mod internals;
 mod slice;
+mod builder;
+#[cfg(feature = "unicode")] mod cursor;
+mod iters;
+mod versioned;
+mod observed;
+mod delta;
+mod indexed;
+mod search;
+mod stats;
+mod digest;
+mod lsp;
+mod prefix;
+mod partition;
+mod policy;
+#[cfg(feature = "edit-distance")] mod distance;
+mod line_ending;
+mod error;
+mod shared;
+mod io;
+#[cfg(feature = "tree-sitter")] mod syntax;
+#[cfg(feature = "ropey")] mod ropey_interop;
+#[cfg(feature = "wasm")] mod wasm;
 
 impl Rope {
 
     /// Converts a vector of bytes to a `Rope`.
     ///
     /// If you are sure that the byte slice is valid UTF-8, and you don't want
-    /// to incur the overhead of the validity check, there is an unsafe version
-    /// of this function, `from_utf8_unchecked(),`` which has the same behavior
-    /// but skips the check.
+    /// to incur the overhead of the validity check, the `unchecked-utf8`
+    /// feature enables an unsafe version of this function,
+    /// [`from_utf8_unchecked()`], which has the same behavior but skips the
+    /// check. Without that feature, or the `ffi` or `node-pool` features,
+    /// the crate contains no `unsafe` code.
+    ///
+    /// [`from_utf8_unchecked()`]: struct.Rope.html#method.from_utf8_unchecked
     ///
     /// This method will take care to not copy the vector, for efficiency's
     /// sake.
@@ -254,6 +459,10 @@ impl Rope {
     ///
     /// Returns `Err` if the slice is not UTF-8 with a description as to why the
     /// provided bytes are not UTF-8. The vector you moved in is also included.
+    /// As with `String::from_utf8`, this is a `FromUtf8Error`, which converts
+    /// into an [`Error`] with `?`.
+    ///
+    /// [`Error`]: enum.Error.html
     ///
     /// # Examples
     ///
@@ -287,8 +496,91 @@ impl Rope {
         String::from_utf8(vec).map(Rope::from)
     }
 
+    /// Converts a slice of bytes to a `Rope`, replacing any invalid UTF-8
+    /// sequences with U+FFFD REPLACEMENT CHARACTER.
+    ///
+    /// This is the `Rope` counterpart of `String::from_utf8_lossy`, for
+    /// opening files which may be partly corrupt, such as logs. Each run of
+    /// valid text is pushed straight into the leaves of the new `Rope`, so
+    /// `bytes` is never copied into an intermediate `String`. Invalid
+    /// sequences are replaced as by `String::from_utf8_lossy`.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let log = b"ok\n\xF0\x9F\x92 truncated\n\xFF\xFEend\n";
+    /// assert_eq!( &Rope::from_utf8_lossy(log)
+    ///           , "ok\n\u{FFFD} truncated\n\u{FFFD}\u{FFFD}end\n");
+    /// ```
+    pub fn from_utf8_lossy(bytes: &[u8]) -> Rope {
+        let mut builder = RopeBuilder::with_capacity_hints( bytes.len()
+                                                          , DEFAULT_CHUNK_SIZE);
+        let mut rest = bytes;
+        loop {
+            match str::from_utf8(rest) {
+                Ok(s) => { builder.push_str(s); break }
+              , Err(e) => {
+                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                    builder.push_str(str::from_utf8(valid)
+                                         .expect("valid_up_to is valid"));
+                    builder.push('\u{FFFD}');
+                    // a sequence cut short by the end of `bytes` has no
+                    // error length, and is replaced as a whole
+                    rest = &invalid[e.error_len().unwrap_or(invalid.len())..];
+                }
+            }
+        }
+        builder.finish()
+    }
+
+    /// Converts a `Bytes` buffer to a `Rope`, without copying it.
+    ///
+    /// The `Rope`'s leaves are views into `bytes`, and share its reference
+    /// count. Splitting the returned `Rope` also does not copy the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `bytes` is not valid UTF-8. This is a `Utf8Error`,
+    /// as for `str::from_utf8`, which converts into an [`Error`] with `?`.
+    ///
+    /// [`Error`]: enum.Error.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate an_rope;
+    /// # extern crate bytes;
+    /// # fn main() {
+    /// use an_rope::Rope;
+    /// use bytes::Bytes;
+    ///
+    /// let buf = Bytes::from_static(b"hello\nworld");
+    /// let rope = Rope::from_utf8_bytes(buf).unwrap();
+    /// assert_eq!(&rope, "hello\nworld");
+    ///
+    /// let invalid = Bytes::from_static(&[0, 159, 146, 150]);
+    /// assert!(Rope::from_utf8_bytes(invalid).is_err());
+    /// # }
+    /// ```
+    #[cfg(feature = "bytes")]
+    #[inline]
+    pub fn from_utf8_bytes(bytes: bytes::Bytes)
+                           -> Result<Rope, std::str::Utf8Error> {
+        internals::Utf8Bytes::from_bytes(bytes).map(Rope::from)
+    }
+
     /// Decode a UTF-16 encoded vector `v` into a `Rope`,
     /// returning `Err` if `v` contains any invalid data.
+    ///
+    /// # Errors
+    ///
+    /// As with `String::from_utf16`, this is a `FromUtf16Error`, which
+    /// converts into an [`Error`] with `?`.
+    ///
+    /// [`Error`]: enum.Error.html
     #[inline]
     pub fn from_utf16(v: &[u16]) -> Result<Rope, string::FromUtf16Error> {
         String::from_utf16(v).map(Rope::from)
@@ -299,6 +591,12 @@ impl Rope {
     ///
     /// See the safe version, [`from_utf8()`], for more details.
     ///
+    /// This is only available with the `unchecked-utf8` feature, since it is
+    /// the only `unsafe` method of `Rope`. The crate is built with
+    /// `#![forbid(unsafe_code)]` unless this feature, `ffi` (whose C
+    /// functions are `unsafe`) or `node-pool` (which implements the unsafe
+    /// `Allocator` trait) is enabled.
+    ///
     /// [`from_utf8()`]: struct.Rope.html#method.from_utf8
     ///
     /// # Safety
@@ -325,12 +623,17 @@ impl Rope {
     /// assert_eq!(&sparkle_heart, "💖");
     /// ```
     #[inline]
+    #[cfg(feature = "unchecked-utf8")]
+    #[allow(unsafe_code)]
     pub unsafe fn from_utf8_unchecked(bytes: Vec<u8>) -> Rope {
         Rope::from(String::from_utf8_unchecked(bytes))
     }
 
     /// Returns a new empty Rope
     ///
+    /// This does not allocate: every empty `Rope` created on a thread shares
+    /// the same empty leaf node.
+    ///
     /// # Examples
     /// ```
     /// use an_rope::Rope;
@@ -339,6 +642,40 @@ impl Rope {
     /// ```
     #[inline] pub fn new() -> Rope { Rope::from(Node::empty()) }
 
+    /// Returns a new `Rope` containing the static text `s`, such as a
+    /// string literal, without copying it.
+    ///
+    /// The leaves of the returned `Rope` borrow `s`, so building it only
+    /// allocates its nodes. Text is copied only when an edit splits one of
+    /// those leaves, and then only the part of the leaf which the edit
+    /// keeps; the rest of the `Rope` goes on borrowing `s`. This makes
+    /// default documents, templates, and test fixtures cheap to create.
+    ///
+    /// With the `tendril`, `smallstr` or `line-leaves` features, whose leaf
+    /// types can't borrow text, `s` is copied as by `Rope::from`. With the
+    /// `bytes` feature, the leaves are views into `s`.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// static TEMPLATE: &str = "fn main() {\n}\n";
+    /// let rope = Rope::from_static(TEMPLATE);
+    /// assert_eq!(&rope, TEMPLATE);
+    /// assert_eq!(&rope.insert_str(12, "    todo!();\n")
+    ///           , "fn main() {\n    todo!();\n}\n");
+    /// ```
+    #[inline]
+    pub fn from_static(s: &'static str) -> Rope {
+        Rope { root: NodeLink::from_static(s) }
+    }
+
+    /// Returns a `RopeWeak` referring to this snapshot of the `Rope`.
+    ///
+    /// The `RopeWeak` does not keep the text of this `Rope` alive.
+    #[inline] pub fn downgrade(&self) -> RopeWeak {
+        RopeWeak(self.root.downgrade())
+    }
+
     /// Returns the length of this Rope
     ///
     /// # Examples
@@ -394,6 +731,58 @@ impl Rope {
     /// ```
     #[inline] pub fn is_empty(&self) -> bool { self.len() == 0 }
 
+    /// Returns `true` if every character in this `Rope` is ASCII.
+    ///
+    /// When this is `true`, byte, `char` and grapheme indices into the
+    /// `Rope` are all the same, so callers can choose cheaper code paths.
+    ///
+    /// # Time complexity
+    /// Each node caches whether its subrope is ASCII, so this is O(1) once
+    /// computed, and O(log _n_) after an edit.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// assert!(Rope::from("abc").is_ascii());
+    /// assert!(!(Rope::from("abc") + Rope::from("\u{3c0}")).is_ascii());
+    /// assert!(Rope::new().is_ascii());
+    /// ```
+    #[inline] pub fn is_ascii(&self) -> bool { self.root.is_ascii() }
+
+    /// Checks the internal invariants of this `Rope`'s tree.
+    ///
+    /// This is intended for fuzzers and tests, which can call it after every
+    /// operation. Each node's cached length, weight, `char`, line and
+    /// grapheme counts, and ASCII flag are recomputed from its children and
+    /// compared against the cached values.
+    ///
+    /// The shape of the tree is checked too: only the single leaf of an
+    /// empty `Rope` may be empty, no leaf may start or end within a `char`,
+    /// and the tree must be balanced. The UTF-8 validity of the rest of each
+    /// leaf is guaranteed by its type, so it is not checked here; text built
+    /// with `from_utf8_unchecked` can be checked with [`validate_utf8()`]
+    /// instead.
+    ///
+    /// # Returns
+    /// - `Ok` if every invariant holds
+    /// - `Err` describing the first broken invariant found, and where in the
+    ///   tree it was found
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("abc").insert_str(1, "\u{3c0}\n");
+    /// assert_eq!(rope.check_invariants(), Ok(()));
+    /// ```
+    ///
+    /// [`validate_utf8()`]: struct.Rope.html#method.validate_utf8
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        self.root.check_invariants("root")
+    }
+
     /// Insert `ch` into `index` in this `Rope`, returning a new `Rope`.
     ///
     ///
@@ -461,7 +850,7 @@ impl Rope {
     ///
     /// # Panics
     /// * If the start or end of `range` are indices outside of the `Rope`
-    /// * If the end index of `range` is greater than the start index
+    /// * If the start index of `range` is greater than the end index
     ///
     /// # Time Complexity
     /// O(log _n_)
@@ -491,10 +880,12 @@ impl Rope {
         let end = range.end().map(|e| *e)
                        .unwrap_or_else(|| { self.measure() });
 
-        assert!( start <= end
-               , "invalid index! start {:?} > end {:?}", end, start);
+        let len = range_len!("Rope::delete", start, end);
+        assert!( end <= self.measure()
+               , "Rope::delete: end {:?} was > length {:?}"
+               , end, self.measure());
         let (l, r) = self.root.split(start);
-        let (_, r) = r.split(end - start);
+        let (_, r) = r.split(len);
         Rope::from(Node::new_branch(l, r))
     }
 
@@ -505,11 +896,190 @@ impl Rope {
         , String: Measured<M>
         , str: Measured<M>
         {
+        let len = range_len!("Rope::delete", range.start, range.end);
+        assert!( range.end <= self.root.measure()
+               , "Rope::delete: end {:?} was > length {:?}"
+               , range.end, self.root.measure());
         let (l, r) = self.root.split(range.start);
-        let (_, r) = r.split(range.end - range.start);
+        let (_, r) = r.split(len);
         Rope::from(Node::new_branch(l, r))
     }
 
+    /// Replace the range `range` of this `Rope` with `replacement`, returning
+    /// a new `Rope`.
+    ///
+    /// This is equivalent to deleting `range` and then inserting
+    /// `replacement` at its start, but the tree is only split once at each
+    /// end of `range`, and the result is built in a single step.
+    ///
+    /// # Panics
+    /// * If the start or end of `range` are indices outside of the `Rope`
+    /// * If the start index of `range` is greater than the end index
+    ///
+    /// # Time Complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use an_rope::Rope;
+    /// let an_rope = Rope::from("this is not fine");
+    /// assert_eq!(&an_rope.edit(8..11, "very"), "this is very fine");
+    /// assert_eq!(&an_rope.edit(16..16, "!"), "this is not fine!");
+    /// assert_eq!(&an_rope.edit(4..8, ""), "thisnot fine");
+    /// ```
+    pub fn edit<M: Metric>(&self, range: ops::Range<M>, replacement: &str)
+                          -> Rope
+    where NodeLink: Measured<M>
+        , String: Measured<M>
+        , str: Measured<M>
+        {
+        let len = range_len!("Rope::edit", range.start, range.end);
+        assert!( range.end <= self.root.measure()
+               , "Rope::edit: end {:?} was > length {:?}"
+               , range.end, self.root.measure());
+        let (l, r) = self.root.split(range.start);
+        let (_, r) = r.split(len);
+        if replacement.is_empty() {
+            Rope::from(Node::new_branch(l, r))
+        } else {
+            let new = NodeLink::from(replacement);
+            Rope::from(Node::new_branch(l, Node::new_branch(new, r)))
+        }
+    }
+
+    /// Replace the range `range` of this `Rope` with the text yielded by
+    /// `replacement`, returning a new `Rope`.
+    ///
+    /// `replacement` may yield `char`s, `&str`s or `String`s. The text is fed
+    /// straight into a `RopeBuilder`, so generated text never needs to be
+    /// collected into a single `String` first.
+    ///
+    /// # Panics
+    /// * If the start or end of `range` are indices outside of the `Rope`
+    /// * If the start index of `range` is greater than the end index
+    ///
+    /// # Time Complexity
+    /// O(log _n_ + _m_), where _m_ is the length of the replacement
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use an_rope::Rope;
+    /// let an_rope = Rope::from("hello world");
+    /// let upper = "world".chars().flat_map(char::to_uppercase);
+    /// let shouted = an_rope.splice_iter(6..11, upper);
+    /// assert_eq!(&shouted, "hello WORLD");
+    /// let words = an_rope.splice_iter(5..6, vec![", ", "big", " "]);
+    /// assert_eq!(&words, "hello, big world");
+    /// ```
+    pub fn splice_iter<M, I>(&self, range: ops::Range<M>, replacement: I)
+                            -> Rope
+    where M: Metric
+        , I: IntoIterator
+        , RopeBuilder: iter::Extend<I::Item>
+        , NodeLink: Measured<M>
+        , String: Measured<M>
+        , str: Measured<M>
+        {
+        let len = range_len!("Rope::splice_iter", range.start, range.end);
+        assert!( range.end <= self.root.measure()
+               , "Rope::splice_iter: end {:?} was > length {:?}"
+               , range.end, self.root.measure());
+        let mut builder = RopeBuilder::new();
+        builder.extend(replacement);
+        let new = builder.finish().root;
+        let (l, r) = self.root.split(range.start);
+        let (_, r) = r.split(len);
+        Rope::from(Node::from_nodes(vec![l, new, r]))
+    }
+
+
+    /// Delete the grapheme cluster beginning at byte index `i`, returning a
+    /// new `Rope` along with the deleted text.
+    ///
+    /// This is the operation performed by the delete key in a text editor.
+    /// Grapheme clusters made up of several characters, such as a letter
+    /// followed by combining marks, or `"\r\n"`, are deleted in their entirety,
+    /// even if they span multiple leaf nodes.
+    ///
+    /// # Returns
+    /// * A new `Rope` with the grapheme cluster after `i` removed, and a
+    ///   `String` containing the removed cluster. If `i` is the end of this
+    ///   `Rope`, nothing is deleted and the returned `String` is empty.
+    ///
+    /// # Panics
+    /// * If `i` is greater than the length of this `Rope`
+    /// * If `i` does not fall on a character boundary
+    ///
+    /// # Time Complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("noe\u{308}l");
+    /// let (rope, deleted) = rope.delete_char(2);
+    /// assert_eq!(&rope, "nol");
+    /// assert_eq!(deleted, "e\u{308}");
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn delete_char(&self, i: usize) -> (Rope, String) {
+        match self.next_grapheme_boundary(i) {
+            Some(end) => self.remove_between(i, end)
+          , None => (self.clone(), String::new())
+        }
+    }
+
+    /// Delete the grapheme cluster ending at byte index `i`, returning a new
+    /// `Rope` along with the deleted text.
+    ///
+    /// This is the operation performed by the backspace key in a text editor.
+    /// Grapheme clusters made up of several characters, such as a letter
+    /// followed by combining marks, or `"\r\n"`, are deleted in their entirety,
+    /// even if they span multiple leaf nodes.
+    ///
+    /// # Returns
+    /// * A new `Rope` with the grapheme cluster before `i` removed, and a
+    ///   `String` containing the removed cluster. If `i` is 0, nothing is
+    ///   deleted and the returned `String` is empty.
+    ///
+    /// # Panics
+    /// * If `i` is greater than the length of this `Rope`
+    /// * If `i` does not fall on a character boundary
+    ///
+    /// # Time Complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("noe\u{308}l");
+    /// let (rope, deleted) = rope.backspace(5);
+    /// assert_eq!(&rope, "nol");
+    /// assert_eq!(deleted, "e\u{308}");
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn backspace(&self, i: usize) -> (Rope, String) {
+        match self.prev_grapheme_boundary(i) {
+            Some(start) => self.remove_between(start, i)
+          , None => (self.clone(), String::new())
+        }
+    }
+
+    /// Removes the text between byte indices `start` and `end`, returning the
+    /// new `Rope` and the removed text.
+    #[cfg(feature = "unicode")]
+    #[inline]
+    fn remove_between(&self, start: usize, end: usize) -> (Rope, String) {
+        let removed = self.root.text_between(start, end).into_owned();
+        let (left, right) = self.root.split(start);
+        let (_, right) = right.split(range_len!("Rope::remove_between"
+                                                , start, end));
+        (Rope::from(Node::new_branch(left, right)), removed)
+    }
 
     /// Insert `rope` into `index` in this `Rope`, returning a new `Rope`.
     ///
@@ -642,6 +1212,9 @@ impl Rope {
     ///
     /// Note that this is equivalent to using the `+` operator.
     ///
+    /// The leaves of both `Rope`s are shared with the result, rather than
+    /// copied. To merge short leaves where they meet, use `append_compact`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -695,51 +1268,507 @@ impl Rope {
         }
     }
 
+    /// Appends each of `lines` to the end of this `Rope`, separated by
+    /// newlines, returning a new `Rope`.
+    ///
+    /// If this `Rope` is not empty and does not already end with a newline,
+    /// one is added before the first of `lines`. No newline is added after
+    /// the last of `lines`.
+    ///
+    /// The lines are collected into a single balanced `Rope`, which is then
+    /// appended, so this is much faster than appending the lines and
+    /// newlines one at a time.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let log = Rope::from("started");
+    /// let log = log.append_newline_separated(vec!["one", "two"]);
+    /// assert_eq!(&log, "started\none\ntwo");
+    /// let log = log.append_newline_separated(vec!["three"]);
+    /// assert_eq!(&log, "started\none\ntwo\nthree");
+    /// assert_eq!( &Rope::new().append_newline_separated(vec!["a", "b"])
+    ///           , "a\nb");
+    /// ```
+    pub fn append_newline_separated<'a, I>(&self, lines: I) -> Rope
+    where I: IntoIterator<Item=&'a str> {
+        let mut builder = RopeBuilder::new();
+        let mut needs_newline = !self.is_empty() && !self.ends_with_newline();
+        for line in lines {
+            if needs_newline { builder.push('\n') }
+            builder.push_str(line);
+            needs_newline = true;
+        }
+        self.append(&builder.finish())
+    }
 
-
-    /// Splits the rope into two ropes at the given index.
+    /// Concatenates the items of `iter` into a single `Rope`, placing `sep`
+    /// between each pair of adjacent items.
+    ///
+    /// This is analogous to `[String]::join`. The result is built bottom-up
+    /// as a balanced tree, rather than by appending one item at a time, and
+    /// every separator shares the same nodes.
+    ///
+    /// # Time complexity
+    /// O(_n_) in the number of items
     ///
     /// # Examples
     /// ```
     /// use an_rope::Rope;
-    /// let an_rope = Rope::from(String::from("abcd"));
-    /// let (ab, cd) = an_rope.split(2);
-    /// assert_eq!(ab, Rope::from(String::from("ab")));
-    /// assert_eq!(cd, Rope::from(String::from("cd")));
+    /// let lines = vec!["one", "two", "three"];
+    /// assert_eq!(&Rope::join("\n", lines), "one\ntwo\nthree");
+    /// assert_eq!(&Rope::join(", ", Vec::<Rope>::new()), "");
     /// ```
-    pub fn split<M: Metric>(&self, index: M) -> (Rope, Rope)
-    where Self: Measured<M>
-        , NodeLink: Measured<M>
-        , String: Measured<M>
-        , str: Measured<M>
+    pub fn join<S, I>(sep: S, iter: I) -> Rope
+    where S: convert::Into<Rope>
+        , I: IntoIterator
+        , I::Item: convert::Into<Rope>
         {
-        assert!(index <= self.measure());
-        let (l, r) = self.root.split(index);
-        (Rope::from(l), Rope::from(r))
+        let sep = sep.into().root;
+        let mut first = true;
+        let nodes = iter.into_iter().flat_map(|item| {
+            let sep = if first { first = false; None }
+                      else { Some(sep.clone()) };
+            sep.into_iter().chain(Some(item.into().root))
+        });
+        Rope::from(Node::from_nodes(nodes))
     }
 
-    /// Rebalances this entire `Rope`, returning a balanced `Rope`.
-    #[inline]
-    #[cfg(any(test, feature = "rebalance"))]
-    fn rebalance(&mut self) {
-        if self.is_balanced() {
-            // the rope is already balanced, do nothing
-        } else {
-            // rebalance the rope
-            // self.root = self.root.rebalance();
+    /// Returns `true` if this `Rope` ends with a newline (`'\n'`).
+    ///
+    /// Only the last leaf of the `Rope` is examined.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// assert!(Rope::from("abc\n").ends_with_newline());
+    /// assert!(Rope::from("abc\r\n").ends_with_newline());
+    /// assert!(!Rope::from("abc").ends_with_newline());
+    /// assert!(!Rope::new().ends_with_newline());
+    /// ```
+    pub fn ends_with_newline(&self) -> bool {
+        let len = self.len();
+        if len == 0 { return false }
+        let (leaf, offset) = self.root.leaf_at(len - 1);
+        leaf.as_bytes()[len - 1 - offset] == b'\n'
+    }
+
+    /// Returns the byte index at which the text of the line ending just
+    /// before byte index `end` stops, which is before its `"\n"` or
+    /// `"\r\n"`.
+    ///
+    /// `end` must be just after a `'\n'`.
+    fn line_content_end(&self, end: usize) -> usize {
+        let before = end - 1;
+        if before == 0 { return before }
+        let (leaf, offset) = self.root.leaf_at(before - 1);
+        match leaf.as_bytes()[before - 1 - offset] {
+            b'\r' => before - 1
+          , _ => before
         }
     }
 
-    /// Returns true if this `Rope` is balanced.
+    /// Returns a `Rope` which is this `Rope` with a newline appended, unless
+    /// it already ends with one.
+    ///
+    /// Empty `Rope`s are returned unchanged.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let an_rope = Rope::from("abc");
+    /// assert_eq!(&an_rope.ensure_trailing_newline(), "abc\n");
+    /// assert_eq!( &an_rope.ensure_trailing_newline()
+    ///                     .ensure_trailing_newline()
+    ///           , "abc\n");
+    /// assert_eq!(&Rope::new().ensure_trailing_newline(), "");
+    /// ```
+    pub fn ensure_trailing_newline(&self) -> Rope {
+        if self.is_empty() || self.ends_with_newline() {
+            self.clone()
+        } else {
+            self.append(&Rope::from("\n"))
+        }
+    }
+
+    /// Returns the first line of this `Rope`, without its line terminator.
+    ///
+    /// This is the same line that `self.lines().next()` would return, but
+    /// is found without setting up an iterator. An empty `Rope` has an empty
+    /// first line.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("one\ntwo\n");
+    /// assert_eq!(&rope.first_line(), "one");
+    /// assert_eq!(&Rope::from("one").first_line(), "one");
+    /// ```
+    pub fn first_line(&self) -> RopeSlice {
+        let end = self.root.after_newline(0)
+                      .map_or(self.len(), |i| self.line_content_end(i));
+        self.slice(0..end)
+    }
+
+    /// Returns the last line of this `Rope`, without its line terminator.
+    ///
+    /// As with `lines()`, a newline at the very end of the `Rope` does not
+    /// begin a new, empty line, so this is the same line that
+    /// `self.lines().last()` would return. An empty `Rope` has an empty last
+    /// line.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// assert_eq!(&Rope::from("one\ntwo\nthree").last_line(), "three");
+    /// assert_eq!(&Rope::from("one\ntwo\n").last_line(), "two");
+    /// ```
+    pub fn last_line(&self) -> RopeSlice {
+        let mut end = self.len();
+        let mut newlines = self.root.newline_count();
+        if self.ends_with_newline() {
+            end = self.line_content_end(end);
+            newlines -= 1;
+        }
+        let start = if newlines == 0 { 0 }
+                    else { self.root.after_newline(newlines - 1).unwrap() };
+        self.slice(start..end)
+    }
+
+    /// Returns the zero-based line containing byte index `i`.
+    ///
+    /// Lines are separated by `'\n'`, and a newline belongs to the line it
+    /// ends, so this is the number of newlines before `i`.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("one\ntwo\n");
+    /// assert_eq!(rope.line_of_offset(0), 0);
+    /// assert_eq!(rope.line_of_offset(3), 0);
+    /// assert_eq!(rope.line_of_offset(4), 1);
+    /// assert_eq!(rope.line_of_offset(8), 2);
+    /// ```
+    pub fn line_of_offset(&self, i: usize) -> usize {
+        assert!( i <= self.len()
+               , "Rope::line_of_offset: index {} was > length {}"
+               , i, self.len());
+        self.root.newlines_before(i)
+    }
+
+    /// Returns the byte index of `char` `col` of line `line`, or `None` if
+    /// there is no such line, or `col` is past its end.
+    ///
+    /// Lines are separated by `'\n'`, and both `line` and `col` are
+    /// zero-based. `col` may be the length of the line, giving the index of
+    /// its end, before its line terminator, `"\n"` or `"\r\n"`. This is
+    /// the strict mode of resolving a position, for commands which should
+    /// fail on a position which does not exist, such as "go to line and
+    /// column"; [`byte_at_line_col_clamped()`] is the lenient mode.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + `col`)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ab\r\n\u{3c0}x\n");
+    /// assert_eq!(rope.byte_at_line_col(0, 2), Some(2));
+    /// assert_eq!(rope.byte_at_line_col(0, 3), None);
+    /// assert_eq!(rope.byte_at_line_col(1, 1), Some(6));
+    /// assert_eq!(rope.byte_at_line_col(2, 0), Some(8));
+    /// assert_eq!(rope.byte_at_line_col(3, 0), None);
+    /// ```
+    ///
+    /// [`byte_at_line_col_clamped()`]:
+    /// struct.Rope.html#method.byte_at_line_col_clamped
+    #[inline]
+    pub fn byte_at_line_col(&self, line: usize, col: usize) -> Option<usize> {
+        self.line_col(line, col, |_| 1).and_then(Result::ok)
+    }
+
+    /// Returns the byte index of `char` `col` of line `line`, or of the end
+    /// of the line if `col` is past it, or `None` if there is no such line.
+    ///
+    /// Positions are resolved as for [`byte_at_line_col()`], except that a
+    /// column past the end of the line is clamped to it, before its line
+    /// terminator. This is the lenient mode, for commands such as moving a
+    /// cursor onto a shorter line.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of line `line`)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ab\r\n\u{3c0}x\n");
+    /// assert_eq!(rope.byte_at_line_col_clamped(0, 3), Some(2));
+    /// assert_eq!(rope.byte_at_line_col_clamped(1, 9), Some(7));
+    /// assert_eq!(rope.byte_at_line_col_clamped(3, 0), None);
+    /// ```
+    ///
+    /// [`byte_at_line_col()`]: struct.Rope.html#method.byte_at_line_col
+    #[inline]
+    pub fn byte_at_line_col_clamped(&self, line: usize, col: usize)
+                                   -> Option<usize> {
+        self.line_col(line, col, |_| 1).map(|i| i.unwrap_or_else(|end| end))
+    }
+
+    /// Returns `None` if there is no line `line`; otherwise, `Ok` with the
+    /// byte index of column `col` of the line, or `Err` with the index of
+    /// the end of the line if `col` is past it.
+    ///
+    /// Each `char` `c` spans `unit_len(c)` columns; a column inside a `char`
+    /// resolves to the start of that `char`.
+    fn line_col<F>(&self, line: usize, col: usize, unit_len: F)
+                  -> Option<Result<usize, usize>>
+    where F: Fn(char) -> usize {
+        let start = if line == 0 { 0 } else {
+            match self.root.after_newline(line - 1) {
+                Some(start) => start
+              , None => return None
+            }
+        };
+        let end = match self.root.after_newline(line) {
+            Some(end) => self.line_content_end(end)
+          , None => self.len()
+        };
+        let (mut i, mut at) = (start, 0);
+        let mut chars = self.chars_between(start, end);
+        while at < col {
+            match chars.next() {
+                Some(c) if at + unit_len(c) > col => break
+              , Some(c) => { at += unit_len(c); i += c.len_utf8(); }
+              , None => return Some(Err(i))
+            }
+        }
+        Some(Ok(i))
+    }
+
+    /// Returns the width of the widest line in this `Rope`, in `char`s.
+    ///
+    /// A line's width does not include its line terminator. This is the
+    /// same as the `max_line_len` computed by [`analyze()`], but the widths
+    /// of the lines in each node of the tree are cached, so that after an
+    /// edit only the nodes the edit created are measured. A render layer
+    /// can call it after every edit to size a horizontal scrollbar.
+    ///
+    /// # Time complexity
+    /// O(1) if this `Rope` has been measured before; otherwise, O(_n_) in
+    /// the length of the text in the nodes which have not.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("fn main() {\r\n    \u{3c0}\r\n}\r\n");
+    /// assert_eq!(rope.max_line_width(), 11);
+    /// let rope = rope.insert_str(17, "println!();");
+    /// assert_eq!(rope.max_line_width(), 16);
+    /// ```
+    ///
+    /// [`analyze()`]: struct.Rope.html#method.analyze
+    #[inline]
+    pub fn max_line_width(&self) -> usize {
+        self.root.line_widths().max_width()
+    }
+
+    /// Returns the byte index of the text displayed at visual column `col`
+    /// of line `line`, with tab stops every `tabstop` columns.
+    ///
+    /// Lines are separated by `'\n'`, and both `line` and `col` are
+    /// zero-based. Each grapheme cluster occupies one column, except for
+    /// tabs, which extend to the next tab stop. If `col` falls within a tab,
+    /// the index of the tab is returned. If `col` is past the end of the
+    /// line, the index of the end of the line, before its line terminator,
+    /// is returned, as when moving a cursor onto a shorter line.
+    ///
+    /// # Returns
+    /// - `Some` with the byte index of column `col`
+    /// - `None` if this `Rope` has fewer than `line + 1` lines
+    ///
+    /// # Panics
+    /// If `tabstop` is 0.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the line)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ab\n\tx\u{301}y\r\n");
+    /// assert_eq!(rope.offset_at_visual_col(1, 2, 4), Some(3));
+    /// assert_eq!(rope.offset_at_visual_col(1, 4, 4), Some(4));
+    /// assert_eq!(rope.offset_at_visual_col(1, 5, 4), Some(7));
+    /// assert_eq!(rope.offset_at_visual_col(1, 9, 4), Some(8));
+    /// assert_eq!(rope.offset_at_visual_col(3, 0, 4), None);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn offset_at_visual_col(&self, line: usize, col: usize, tabstop: usize)
+                               -> Option<usize> {
+        use unicode_segmentation::UnicodeSegmentation;
+        assert!( tabstop > 0
+               , "Rope::offset_at_visual_col: tabstop must be > 0");
+        let start = if line == 0 { 0 } else {
+            match self.root.after_newline(line - 1) {
+                Some(start) => start
+              , None => return None
+            }
+        };
+        let end = self.root.after_newline(line).unwrap_or(self.len());
+        let text = self.root.text_between(start, end);
+        // leave out the line terminator, `"\n"` or `"\r\n"`
+        let content = match LineEnding::of_line(&text) {
+            Some(ending) => &text[..text.len() - ending.as_str().len()]
+          , None => &text[..]
+        };
+        let mut at = 0;
+        for (i, g) in content.grapheme_indices(true) {
+            at = next_visual_col(at, g, tabstop);
+            if at > col { return Some(start + i) }
+        }
+        Some(start + content.len())
+    }
+
+    /// Returns the visual column of byte index `i` on its line, with tab
+    /// stops every `tabstop` columns.
+    ///
+    /// Columns are counted as for `offset_at_visual_col`, of which this is
+    /// the inverse.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, if `i` does not
+    /// fall on a character boundary, or if `tabstop` is 0.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the line containing `i`)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ab\n\tx\u{301}y\r\n");
+    /// assert_eq!(rope.visual_col_at_offset(1, 4), 1);
+    /// assert_eq!(rope.visual_col_at_offset(4, 4), 4);
+    /// assert_eq!(rope.visual_col_at_offset(7, 4), 5);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn visual_col_at_offset(&self, i: usize, tabstop: usize) -> usize {
+        use unicode_segmentation::UnicodeSegmentation;
+        assert!( i <= self.len()
+               , "Rope::visual_col_at_offset: index {} was > length {}"
+               , i, self.len());
+        assert!( tabstop > 0
+               , "Rope::visual_col_at_offset: tabstop must be > 0");
+        let line = self.root.newlines_before(i);
+        let start = if line == 0 { 0 } else {
+            self.root.after_newline(line - 1).expect("line must exist")
+        };
+        self.root.text_between(start, i).graphemes(true)
+            .fold(0, |col, g| next_visual_col(col, g, tabstop))
+    }
+
+
+
+    /// Splits the rope into two ropes at the given index.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let an_rope = Rope::from(String::from("abcd"));
+    /// let (ab, cd) = an_rope.split(2);
+    /// assert_eq!(ab, Rope::from(String::from("ab")));
+    /// assert_eq!(cd, Rope::from(String::from("cd")));
+    /// ```
+    pub fn split<M: Metric>(&self, index: M) -> (Rope, Rope)
+    where Self: Measured<M>
+        , NodeLink: Measured<M>
+        , String: Measured<M>
+        , str: Measured<M>
+        {
+        assert!( index <= self.measure()
+               , "Rope::split: index {:?} was > length {:?}"
+               , index, self.measure());
+        let (l, r) = self.root.split(index);
+        (Rope::from(l), Rope::from(r))
+    }
+
+    /// Rebalances this entire `Rope`, if it is not already balanced.
+    ///
+    /// Every `Rope` is balanced when it is made, so this only has any effect
+    /// on one whose tree was put together by hand, as in tests.
+    #[inline]
+    #[cfg(test)]
+    fn rebalance(&mut self) {
+        if !self.is_balanced() {
+            self.root = self.root.clone().rebalance();
+        }
+    }
+
+    /// Returns true if this `Rope` is balanced.
     ///
     /// Balancing invariant:
-    /// the rope length needs to be less than _F_(rope_length) where F is fibonacci
+    /// a `Rope` whose tree has depth _n_ > 0 must be at least _F_(_n_ + 2)
+    /// bytes long, where _F_ is fibonacci, so its depth is O(log _n_)
     #[inline]
-    #[cfg(any(test, feature = "rebalance"))]
+    #[cfg(test)]
     fn is_balanced(&self) -> bool {
         self.root.is_balanced()
     }
 
+    /// Returns true if this `Rope` and `other` have not only the same text,
+    /// but the same tree: the same shape, with their text divided into
+    /// leaves at the same places.
+    ///
+    /// This is for tests, and for caches keyed on how a `Rope` is divided
+    /// into leaves, such as of the chunks yielded by `strings()`. `==`
+    /// should be used to compare text.
+    ///
+    /// Ropes are deterministic: the tree built by an operation depends only
+    /// on the trees of its operands and on its arguments, so applying the
+    /// same operations to structurally equal `Rope`s always produces
+    /// structurally equal `Rope`s. In particular, `clone()` always preserves
+    /// structure, and `strings()` and `leaf_ranges()` always yield the
+    /// leaves of structurally equal `Rope`s in the same order, left to
+    /// right. On the other hand, `Rope`s with the same text which were
+    /// built differently, such as by `Rope::from` and by a series of edits,
+    /// are usually not structurally equal, and which operations merge or
+    /// split leaves may change between versions of this crate.
+    ///
+    /// # Time complexity
+    /// O(_n_), or less where the two `Rope`s share subtrees
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let half = "x".repeat(1000);
+    /// let join = || Rope::from(half.as_str()) + Rope::from(half.as_str());
+    /// let whole = Rope::from(half.repeat(2));
+    /// assert_eq!(whole, join());
+    /// assert!(!whole.structural_eq(&join()));
+    /// assert!(join().structural_eq(&join()));
+    /// assert!(whole.structural_eq(&whole.clone()));
+    /// ```
+    pub fn structural_eq(&self, other: &Rope) -> bool {
+        self.root.structural_eq(&other.root)
+    }
+
     unstable_iters! {
         #[doc="Returns an iterator over all the strings in this `Rope`"]
         #[inline]
@@ -747,26 +1776,349 @@ impl Rope {
             self.root.strings()
         }
 
-        #[doc="Returns an iterator over all the lines of text in this `Rope`."]
-        pub fn lines<'a>(&'a self) -> impl Iterator<Item=RopeSlice<'a>> +'a  {
-            {   // create a new block here so the macro will bind the `use` stmt
-                use internals::IsLineEnding;
-                let last_idx = self.len() - 1;
-                Box::new(self.char_indices()
-                             .filter_map(move |(i, c)|
-                                if c.is_line_ending() { Some(i) }
-                                // special case: slice to the end of the rope
-                                // even if it doesn't end in a newline character
-                                else if i == last_idx { Some(i + 1) }
-                                else { None })
-                              .scan(0, move |mut l, i|  {
-                                    let last = *l;
-                                    *l = i + 1;
-                                    Some(self.slice(last..i))
-                                }))
+        #[doc="Returns an iterator over the leaves of this `Rope`, yielding \
+               the byte range each leaf covers along with its text.\n\
+               \nThis is intended for debugging and testing, e.g. to \
+               visualize how fragmented a `Rope` has become after a series \
+               of edits. Empty leaves are skipped.\n\
+               \n# Examples\n\
+               ```\n\
+               # use an_rope::Rope;\n\
+               let rope = Rope::from(\"abc\") + Rope::from(\"de\");\n\
+               let leaves = rope.leaf_ranges().collect::<Vec<_>>();\n\
+               assert_eq!(leaves, vec![(0..3, \"abc\"), (3..5, \"de\")]);\n\
+               ```"]
+        #[inline]
+        pub fn leaf_ranges<'a>(&'a self)
+                              -> impl Iterator<Item=(ops::Range<usize>, &'a str)> + 'a {
+            self.root.strings().scan(0, |offset, s| {
+                let start = *offset;
+                *offset += s.len();
+                Some((start..*offset, s))
+            })
+        }
+
+        #[doc="Returns an iterator over all the lines of text in this \
+               `Rope`, including their line terminators.\n\
+               \nThis is like `str::split_inclusive('\\n')`: each line \
+               ends with its `\"\\n\"` (or `\"\\r\\n\"`), except for \
+               the last line if the `Rope` does not end with a newline. \
+               Concatenating the lines always reproduces the `Rope` exactly.\n\
+               \n# Examples\n\
+               ```\n\
+               # use an_rope::Rope;\n\
+               let rope = Rope::from(\"one\\r\\ntwo\\n\\nthree\");\n\
+               let lines = rope.lines_inclusive()\n\
+                               .map(|l| l.to_string())\n\
+                               .collect::<Vec<_>>();\n\
+               assert_eq!(lines, vec![\"one\\r\\n\", \"two\\n\", \"\\n\", \"three\"]);\n\
+               ```"]
+        pub fn lines_inclusive<'a>(&'a self)
+                                  -> impl Iterator<Item=RopeSlice<'a>> + 'a {
+            self.leaf_ranges()
+                .flat_map(|(range, s)|
+                    s.match_indices(is_line_break)
+                     .map(move |(i, _)| range.start + i + 1))
+                .chain(Some(self.len()))
+                .scan(0, |start, end| {
+                    let line = *start..end;
+                    *start = end;
+                    Some(line)
+                })
+                .filter(|line| line.start < line.end)
+                .map(move |line| self.slice(line))
+        }
+    }
+
+    /// Returns an iterator over all the lines of text in this `Rope`,
+    /// without their line terminators, `"\n"` or `"\r\n"`.
+    ///
+    /// Lines are ended by the characters for which `is_line_break` returns
+    /// `true`, as with the `Line` metric.
+    ///
+    /// As with `str::lines`, a line terminator at the very end of the `Rope`
+    /// does not begin a new, empty line, so an empty `Rope` has no lines,
+    /// and `"a\n"` has the same lines as `"a"`. A `Rope` containing only
+    /// `"\n"` has one empty line.
+    ///
+    /// Skipping ahead with `nth` or `skip` seeks directly to the requested
+    /// line, in O(log _n_) time. The lines borrow only this `Rope`, not the
+    /// iterator, so they may be kept after it is dropped, such as to cache
+    /// the lines of a viewport.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, RopeSlice};
+    /// let lines = |s: &str| Rope::from(s).lines()
+    ///                                    .map(|l| l.to_string())
+    ///                                    .collect::<Vec<_>>();
+    /// assert!(lines("").is_empty());
+    /// assert_eq!(lines("\n"), vec![""]);
+    /// assert_eq!(lines("a\n\nb\n"), vec!["a", "", "b"]);
+    ///
+    /// let rope = Rope::from("one\ntwo\nthree\n");
+    /// let viewport: Vec<RopeSlice> = rope.lines().skip(1).collect();
+    /// assert_eq!(viewport.len(), 2);
+    /// assert_eq!(&viewport[1].to_string(), "three");
+    /// ```
+    #[inline]
+    pub fn lines(&self) -> Lines {
+        Lines::new(self)
+    }
+
+    /// Returns an iterator over the byte indices of the matches of `pat` in
+    /// this `Rope`, including matches which span leaves.
+    ///
+    /// If `overlapping` is true, the search for the next match resumes at
+    /// the second `char` of the last match, rather than after its end.
+    ///
+    /// `pat` must not be empty.
+    ///
+    /// Each leaf is searched in place; only the last `pat.len() - 1` bytes
+    /// before it are copied, to find the matches which straddle its start.
+    fn match_offsets<'a>(&'a self, pat: &'a str, overlapping: bool)
+                        -> Box<Iterator<Item=usize> + 'a> {
+        let step = if overlapping {
+            pat.chars().next().map_or(pat.len(), char::len_utf8)
+        } else {
+            pat.len()
+        };
+        // the text just before the current leaf which a match may start in,
+        // and the index at which the search resumes after the last match
+        let mut carry = String::new();
+        let mut resume = 0;
+        Box::new(self.leaf_ranges().flat_map(move |(range, leaf)| {
+            let mut found = Vec::new();
+            let carry_start = range.start - carry.len();
+            if !carry.is_empty() {
+                // matches starting in `carry` end within the first
+                // `pat.len() - 1` bytes of this leaf
+                let mut head = cmp::min(leaf.len(), pat.len() - 1);
+                while !leaf.is_char_boundary(head) { head += 1; }
+                let window = carry.clone() + &leaf[..head];
+                let mut from = resume.saturating_sub(carry_start);
+                while let Some(i) = window[from..].find(pat) {
+                    if from + i >= carry.len() { break }
+                    found.push(carry_start + from + i);
+                    resume = carry_start + from + i + step;
+                    from = resume - carry_start;
+                }
+            }
+            let mut from = resume.saturating_sub(range.start);
+            while let Some(i) = leaf[from..].find(pat) {
+                found.push(range.start + from + i);
+                resume = range.start + from + i + step;
+                from = resume - range.start;
             }
+            let keep = cmp::max( range.end.saturating_sub(pat.len() - 1)
+                               , resume);
+            carry = if keep >= range.start {
+                let mut keep = keep - range.start;
+                while !leaf.is_char_boundary(keep) { keep += 1; }
+                leaf[keep..].to_owned()
+            } else {
+                // this leaf is shorter than `pat`, so keep some of `carry`
+                let mut keep = keep.saturating_sub(carry_start);
+                while !carry.is_char_boundary(keep) { keep += 1; }
+                carry[keep..].to_owned() + leaf
+            };
+            found
+        }))
+    }
+
+    /// Returns an iterator over the slices of this `Rope` separated by
+    /// `pat`.
+    ///
+    /// As with `str::split_terminator`, if the `Rope` ends with `pat`, there
+    /// is no empty slice after it, so newline-terminated records can be
+    /// processed without special-casing the last one. Separators which span
+    /// leaves are found.
+    ///
+    /// # Panics
+    /// If `pat` is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\r\nb\r") + Rope::from("\n\r\n");
+    /// let records = rope.split_terminator("\r\n")
+    ///                   .map(|s| s.to_string())
+    ///                   .collect::<Vec<_>>();
+    /// assert_eq!(records, vec!["a", "b", ""]);
+    /// ```
+    pub fn split_terminator<'a>(&'a self, pat: &'a str)
+                               -> Box<Iterator<Item=RopeSlice<'a>> + 'a> {
+        assert!( !pat.is_empty()
+               , "Rope::split_terminator: pattern must not be empty");
+        Box::new(self.match_offsets(pat, false)
+                     .map(|i| (i, true))
+                     // the text after the last separator is only yielded if
+                     // it is not empty
+                     .chain(Some((self.len(), false)))
+                     .scan(0, move |start, (i, is_separator)| {
+                        let piece = *start..i;
+                        *start = i + pat.len();
+                        Some((piece, is_separator))
+                     })
+                     .filter(|&(ref piece, is_separator)|
+                        is_separator || piece.start < piece.end)
+                     .map(move |(piece, _)| self.slice(piece)))
+    }
+
+    /// Returns an iterator over the slices of this `Rope` separated by
+    /// `pat`, each including the `pat` which ends it.
+    ///
+    /// As with `str::split_inclusive`, if the `Rope` ends with `pat`, there
+    /// is no empty slice after it, and concatenating the slices reproduces
+    /// the `Rope` exactly. Separators which span leaves are found.
+    ///
+    /// # Panics
+    /// If `pat` is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\r\nb\r") + Rope::from("\n\r\nc");
+    /// let records = rope.split_inclusive("\r\n")
+    ///                   .map(|s| s.to_string())
+    ///                   .collect::<Vec<_>>();
+    /// assert_eq!(records, vec!["a\r\n", "b\r\n", "\r\n", "c"]);
+    /// ```
+    pub fn split_inclusive<'a>(&'a self, pat: &'a str)
+                              -> Box<Iterator<Item=RopeSlice<'a>> + 'a> {
+        assert!( !pat.is_empty()
+               , "Rope::split_inclusive: pattern must not be empty");
+        Box::new(self.match_offsets(pat, false)
+                     .map(move |i| i + pat.len())
+                     .chain(Some(self.len()))
+                     .scan(0, |start, end| {
+                        let piece = *start..end;
+                        *start = end;
+                        Some(piece)
+                     })
+                     // only the text after the last separator may be empty
+                     .filter(|piece| piece.start < piece.end)
+                     .map(move |piece| self.slice(piece)))
+    }
+
+    /// Returns the byte ranges of all the non-overlapping matches of `pat`
+    /// in this `Rope`, in order.
+    ///
+    /// As with `str::match_indices`, the search resumes after the end of
+    /// each match. Matches which span leaves are found. The ranges may be
+    /// passed directly to `slice` or `delete`.
+    ///
+    /// # Panics
+    /// If `pat` is empty.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("aaa ba") + Rope::from("a");
+    /// assert_eq!(rope.find_all("aa"), vec![0..2, 5..7]);
+    /// assert_eq!(&rope.slice(5..7), "aa");
+    /// ```
+    pub fn find_all(&self, pat: &str) -> Vec<ops::Range<usize>> {
+        assert!(!pat.is_empty(), "Rope::find_all: pattern must not be empty");
+        self.match_offsets(pat, false).map(|i| i..i + pat.len()).collect()
+    }
+
+    /// Returns the byte ranges of all the matches of `pat` in this `Rope`,
+    /// including matches which overlap, in order.
+    ///
+    /// After each match, the search resumes at the next `char` rather than
+    /// after the end of the match, so every position at which `pat` occurs
+    /// is reported.
+    ///
+    /// # Panics
+    /// If `pat` is empty.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("aaa ba") + Rope::from("a");
+    /// assert_eq!(rope.find_all_overlapping("aa"), vec![0..2, 1..3, 5..7]);
+    /// ```
+    pub fn find_all_overlapping(&self, pat: &str) -> Vec<ops::Range<usize>> {
+        assert!( !pat.is_empty()
+               , "Rope::find_all_overlapping: pattern must not be empty");
+        self.match_offsets(pat, true).map(|i| i..i + pat.len()).collect()
+    }
+
+    /// Split this `Rope` into lines, pushing a `Rope` for each line onto the
+    /// end of `lines`.
+    ///
+    /// As with `lines_inclusive`, each line includes its `"\n"`, except for
+    /// the last line if this `Rope` does not end with a newline, so
+    /// concatenating the lines reproduces this `Rope` exactly. An empty
+    /// `Rope` has no lines.
+    ///
+    /// The lines are all found in a single traversal of the tree. Subtrees
+    /// which contain no newlines are not searched, but are shared whole
+    /// with the line containing them, so splitting a document with long
+    /// lines is much cheaper than splitting it once per line.
+    ///
+    /// # Time complexity
+    /// O(_k_ log _n_) for _k_ lines
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("one\ntwo\n\nthree");
+    /// let mut lines = Vec::new();
+    /// rope.split_lines_into(&mut lines);
+    /// assert_eq!(lines, vec!["one\n", "two\n", "\n", "three"]);
+    /// ```
+    pub fn split_lines_into(&self, lines: &mut Vec<Rope>) {
+        self.split_inclusive_into('\n', &|node| node.newline_count() > 0, lines)
+    }
+
+    /// Split this `Rope` after each occurrence of `delimiter`, pushing a
+    /// `Rope` for each record onto the end of `records`.
+    ///
+    /// Each record includes its delimiter, except for the last record if
+    /// this `Rope` does not end with `delimiter`. As with `split_lines_into`,
+    /// the records are found in a single traversal of the tree, and share
+    /// the leaves which contain no delimiter with this `Rope`.
+    ///
+    /// # Time complexity
+    /// O(_n_) in the number of leaves. If `delimiter` is `'\n'`, this is the
+    /// same as `split_lines_into`.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a,b") + Rope::from(",,c,");
+    /// let mut records = Vec::new();
+    /// rope.split_records_into(',', &mut records);
+    /// assert_eq!(records, vec!["a,", "b,", ",", "c,"]);
+    /// ```
+    pub fn split_records_into(&self, delimiter: char, records: &mut Vec<Rope>) {
+        if delimiter == '\n' {
+            self.split_lines_into(records)
+        } else {
+            self.split_inclusive_into(delimiter, &|_| true, records)
         }
     }
+
+    fn split_inclusive_into<F>( &self, delimiter: char, may_contain: &F
+                              , pieces: &mut Vec<Rope>)
+    where F: Fn(&Node) -> bool {
+        let mut current = Vec::new();
+        let mut links = Vec::new();
+        self.root.split_inclusive_into( delimiter, may_contain
+                                      , &mut current, &mut links);
+        if !current.is_empty() { links.push(Node::from_nodes(current)); }
+        pieces.extend(links.into_iter()
+                           .filter(|link| !link.is_empty())
+                           .map(Rope::from));
+    }
     //
     //
     // /// Returns a move iterator over all the strings in this `Rope`
@@ -788,21 +2140,206 @@ impl Rope {
     // }
 
 
-    str_iters! {
-        #[doc="Returns an iterator over all the bytes in this `Rope`.\n\
-               \nAs a Rope consists of a sequence of bytes, we can iterate \
-               through a rope by byte. This method returns such an iterator."]
-        #[inline]
-        impl bytes<u8> for Rope {}
+    unstable_iters! {
         #[doc="Returns an iterator over all the characters in this `Rope`.\n\
                \nAs a `Rope` consists of valid UTF-8, we can iterate through a \
                `Rope` by `char`. This method returns such an iterator. \n\
                \nIt's important to remember that `char` represents a Unicode \
                Scalar Value, and may not match your idea of what a \
                'character' is. Iteration over grapheme clusters may be what \
-               you actually want."]
+               you actually want.\n\
+               \nSkipping ahead with `nth` or `skip` seeks directly to the \
+               requested `char`, in O(log _n_) time."]
         #[inline]
-        impl chars<char> for Rope {}
+        pub fn chars<'a>(&'a self) -> impl Iterator<Item=char> + 'a {
+            iters::Chars::new(self)
+        }
+    }
+
+    /// Returns a double-ended iterator over the characters between byte
+    /// indices `start` and `end` of this `Rope`.
+    ///
+    /// Both ends of the range are found in O(log _n_) time, so iterating
+    /// backwards from a byte index, as bracket matching and backwards
+    /// lexing do, costs only as much as the characters visited, rather
+    /// than a pass over the text before them.
+    ///
+    /// # Panics
+    /// * If `start` is greater than `end`
+    /// * If `end` is greater than the length of this `Rope`
+    /// * If either index does not fall on a character boundary
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("f(a, (b), \u{3c0})");
+    /// let before = rope.chars_between(0, 12).rev().collect::<String>();
+    /// assert_eq!(before, "\u{3c0} ,)b( ,a(f");
+    /// let unmatched = rope.chars_between(0, 10).rev()
+    ///                     .scan(0, |depth: &mut i32, c| {
+    ///                         match c { ')' => *depth += 1
+    ///                                 , '(' => *depth -= 1
+    ///                                 , _ => {} }
+    ///                         Some(*depth)
+    ///                     })
+    ///                     .position(|depth| depth < 0);
+    /// assert_eq!(unmatched, Some(8));
+    /// ```
+    pub fn chars_between(&self, start: usize, end: usize) -> CharsBetween {
+        range_len!("Rope::chars_between", start, end);
+        assert!( end <= self.len()
+               , "Rope::chars_between: end {} was > length {}"
+               , end, self.len());
+        for &i in &[start, end] {
+            assert!( i == 0 || i == self.len() || {
+                         let (leaf, offset) = self.root.leaf_at(i);
+                         leaf.is_char_boundary(i - offset)
+                     }
+                   , "Rope::chars_between: index {} is not a char boundary"
+                   , i);
+        }
+        iters::CharsBetween::new(self, start, end)
+    }
+
+    /// Returns an iterator over the lines of this `Rope`, as by `lines()`,
+    /// with the number of each line, counting from 0, and whether it was
+    /// ended by a line terminator.
+    ///
+    /// Only the last line may be unterminated. This saves tools such as
+    /// linters and diff viewers from counting lines as they iterate, and
+    /// from searching for a missing newline at the end of the `Rope`.
+    /// Skipping ahead with `nth` or `skip` seeks directly to the requested
+    /// line, in O(log _n_) time.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("one\r\n\nthree");
+    /// let lines = rope.lines_enumerated()
+    ///                 .map(|(n, line, ended)| (n, line.to_string(), ended))
+    ///                 .collect::<Vec<_>>();
+    /// assert_eq!(lines, vec![ (0, String::from("one"), true)
+    ///                       , (1, String::from(""), true)
+    ///                       , (2, String::from("three"), false) ]);
+    /// ```
+    #[inline]
+    pub fn lines_enumerated(&self) -> LinesEnumerated {
+        LinesEnumerated::new(self)
+    }
+
+    /// Returns an iterator over the words of this `Rope`, separated by
+    /// whitespace, with the byte index at which each one starts.
+    ///
+    /// Words are separated as with `str::split_whitespace`, and each one is
+    /// a `RopeSlice`, so words which span leaves are not split.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from(" one\t\u{3c0}  two ");
+    /// let words = rope.split_whitespace_indices()
+    ///                 .map(|(i, word)| (i, word.to_string()))
+    ///                 .collect::<Vec<_>>();
+    /// assert_eq!(words, vec![ (1, String::from("one"))
+    ///                       , (5, String::from("\u{3c0}"))
+    ///                       , (9, String::from("two")) ]);
+    /// ```
+    #[inline]
+    pub fn split_whitespace_indices(&self) -> SplitWhitespaceIndices {
+        SplitWhitespaceIndices::new(self)
+    }
+
+    /// Returns an iterator over the byte ranges of the runs of whitespace
+    /// in this `Rope`.
+    ///
+    /// Each run is as long as possible, so the text between two runs is
+    /// a word as yielded by `split_whitespace_indices`. Line terminators are
+    /// whitespace, so a run of trailing whitespace on a line continues onto
+    /// the next line; compare the end of a run with the end of its line to
+    /// find trailing whitespace.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a \t\nb\n  c");
+    /// assert_eq!( rope.whitespace_runs().collect::<Vec<_>>()
+    ///           , vec![1..4, 5..8]);
+    /// ```
+    #[inline]
+    pub fn whitespace_runs(&self) -> WhitespaceRuns {
+        WhitespaceRuns::new(self)
+    }
+
+    /// Returns an iterator over the windows of this `Rope` which are `size`
+    /// long along the metric `M`, starting every `step`.
+    ///
+    /// Each window is a `RopeSlice`, so no text is copied. Windows start at
+    /// 0, `step`, `2 * step`, and so on, for as long as a whole window
+    /// fits; a `Rope` shorter than `size` has no windows. Windows measured
+    /// in bytes may start or end part way through a `char`, so text which
+    /// may not be ASCII should be measured in `CharIndex`es instead.
+    ///
+    /// # Panics
+    /// If `size` or `step` is 0.
+    ///
+    /// # Time complexity
+    /// O(log _n_) for each window, and for skipping any number of them
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// use an_rope::metric::CharIndex;
+    /// let rope = Rope::from("\u{3c0}bcde");
+    /// let trigrams = rope.window_iter(CharIndex(3), CharIndex(1))
+    ///                    .map(|w| w.to_string())
+    ///                    .collect::<Vec<_>>();
+    /// assert_eq!(trigrams, vec!["\u{3c0}bc", "bcd", "cde"]);
+    /// assert_eq!( rope.window_iter(2, 2).map(|w| w.to_string())
+    ///                 .collect::<Vec<_>>()
+    ///           , vec!["\u{3c0}", "bc", "de"]);
+    /// ```
+    pub fn window_iter<M>(&self, size: M, step: M) -> Windows<M>
+    where M: Metric
+        , Rope: Measured<M> {
+        assert!( size != M::default() && step != M::default()
+               , "Rope::window_iter: size and step must be > 0");
+        Windows::new(self, size, step)
+    }
+
+    /// Returns an iterator over the text of this `Rope` in chunks of
+    /// exactly `n` `char`s, such as for wrapping output to a fixed width.
+    ///
+    /// As with `slice::chunks_exact`, if the number of `char`s in the
+    /// `Rope` is not a multiple of `n`, the last few are not yielded; they
+    /// can be had from the iterator's `remainder()`. Each chunk is a
+    /// `RopeSlice`, so no text is copied.
+    ///
+    /// # Panics
+    /// If `n` is 0.
+    ///
+    /// # Time complexity
+    /// O(log _n_) for each chunk, and for skipping any number of them
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("aGVsbG8gd29ybGQ=");
+    /// let mut lines = rope.chunks_exact_chars(6);
+    /// assert_eq!( lines.by_ref().map(|l| l.to_string()).collect::<Vec<_>>()
+    ///           , vec!["aGVsbG", "8gd29y"]);
+    /// assert_eq!(&lines.remainder().to_string(), "bGQ=");
+    /// ```
+    pub fn chunks_exact_chars(&self, n: usize) -> ChunksExactChars {
+        assert!(n > 0, "Rope::chunks_exact_chars: n must be > 0");
+        ChunksExactChars::new(self, n)
+    }
+
+    str_iters! {
+        #[doc="Returns an iterator over all the bytes in this `Rope`.\n\
+               \nAs a Rope consists of a sequence of bytes, we can iterate \
+               through a rope by byte. This method returns such an iterator."]
+        #[inline]
+        impl bytes<u8> for Rope {}
         #[inline]
         impl char_indices<(usize, char)> for Rope {}
         #[inline]
@@ -811,87 +2348,1256 @@ impl Rope {
         // impl lines<&'a str> for Rope {}
     }
 
-    unicode_seg_iters! {
-        #[doc=
-            "Returns an iterator over the [grapheme clusters][graphemes] of \
-             `self`.\n\
+    /// Returns an iterator over the [grapheme clusters][graphemes] of
+    /// `self`.
+    ///
+    /// The iterator is over the *extended grapheme clusters*; as
+    /// [UAX#29](http://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)
+    /// recommends extended grapheme cluster boundaries for general
+    /// processing.
+    ///
+    /// The returned [`Graphemes`] can be cloned, so a cursor can remember
+    /// its place in the iteration and resume from it later.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\u{310}e\u{301}o");
+    /// let mut graphemes = rope.graphemes();
+    /// assert_eq!(graphemes.next(), Some("a\u{310}"));
+    /// let resume = graphemes.clone();
+    /// assert_eq!(graphemes.collect::<Vec<_>>(), vec!["e\u{301}", "o"]);
+    /// assert_eq!(resume.collect::<Vec<_>>(), vec!["e\u{301}", "o"]);
+    /// ```
+    ///
+    /// [graphemes]:
+    /// http://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries
+    /// [`Graphemes`]: struct.Graphemes.html
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn graphemes(&self) -> iters::Graphemes {
+        iters::Graphemes::new(self)
+    }
+
+    /// Returns an iterator over the words of `self`, separated on
+    /// [UAX#29 word boundaries]
+    /// (http://www.unicode.org/reports/tr29/#Word_Boundaries).
+    ///
+    /// Here, "words" are just those substrings which, after splitting on
+    /// UAX#29 word boundaries, contain any alphanumeric characters. That
+    /// is, the substring must contain at least one character with the
+    /// [Alphabetic](http://unicode.org/reports/tr44/#Alphabetic)
+    /// property, or with [General_Category=Number]
+    /// (http://unicode.org/reports/tr44/#General_Category_Values).
+    ///
+    /// The returned [`UnicodeWords`] can be cloned.
+    ///
+    /// [`UnicodeWords`]: struct.UnicodeWords.html
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn unicode_words(&self) -> iters::UnicodeWords {
+        iters::UnicodeWords::new(self)
+    }
+
+    /// Returns an iterator over substrings of `self` separated on
+    /// [UAX#29 word boundaries]
+    /// (http://www.unicode.org/reports/tr29/#Word_Boundaries).
+    ///
+    /// The concatenation of the substrings returned by this function is
+    /// just the original string. The returned [`SplitWordBounds`] can be
+    /// cloned.
+    ///
+    /// [`SplitWordBounds`]: struct.SplitWordBounds.html
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn split_word_bounds(&self) -> iters::SplitWordBounds {
+        iters::SplitWordBounds::new(self)
+    }
+
+    /// Returns an iterator over the grapheme clusters of `self` and their
+    /// byte offsets. See `graphemes()` for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("a̐éö̲\r\n");
+    /// let gr_inds = rope.grapheme_indices()
+    ///                   .collect::<Vec<(usize, &str)>>();
+    /// let b: &[_] = &[(0, "a̐"), (3, "é"), (6, "ö̲"), (11, "\r\n")];
+    ///
+    /// assert_eq!(&gr_inds[..], b);
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn grapheme_indices(&self) -> internals::GraphemeIndices {
+        self.root.grapheme_indices()
+    }
+
+    /// Returns an iterator over substrings of `self`, split on UAX#29 word
+    /// boundaries, and their offsets. See `split_word_bounds()` for more
+    /// information.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("Brr, it's 29.3°F!");
+    /// let swi1 = rope.split_word_bound_indices()
+    ///                .collect::<Vec<(usize, &str)>>();
+    /// let b: &[_] = &[ (0, "Brr"), (3, ","), (4, " "), (5, "it's")
+    ///                , (9, " "), (10, "29.3"),  (14, "°"), (16, "F")
+    ///                , (17, "!")];
+    ///
+    /// assert_eq!(&swi1[..], b);
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn split_word_bound_indices(&self) -> internals::UWordBoundIndices {
+        self.root.split_word_bound_indices()
+    }
+
+    /// Returns an iterator over the elements of this `Rope` in `range`,
+    /// which is measured by the [`Metric`] `M`.
+    ///
+    /// The iterator yields the appropriate item for the metric: a
+    /// `RopeSlice` for each line of a [`Line`] range, excluding its line
+    /// terminator, a `char` for each of a [`CharIndex`] range, a `&str` for
+    /// each grapheme cluster of a [`Grapheme`] range, or a `u8` for each
+    /// byte of a [`ByteIndex`] or `usize` range. If `range` extends past the
+    /// end of this `Rope`, the iterator stops at the end.
+    ///
+    /// [`Metric`]: metric/trait.Metric.html
+    /// [`Line`]: metric/struct.Line.html
+    /// [`CharIndex`]: metric/struct.CharIndex.html
+    /// [`Grapheme`]: metric/struct.Grapheme.html
+    /// [`ByteIndex`]: metric/struct.ByteIndex.html
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than its end.
+    ///
+    /// # Time complexity
+    /// Finding the start of `range` is O(log _n_) for every metric except
+    /// `Grapheme`, for which it is O(_n_); each element after that is
+    /// yielded in O(1) on average.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// use an_rope::metric::{CharIndex, Line};
+    /// let rope = Rope::from("one\ntw\u{3c0}o\nthree\n");
+    /// let lines = rope.iter_range(Line(1)..Line(3))
+    ///                 .map(|l| l.to_string())
+    ///                 .collect::<Vec<_>>();
+    /// assert_eq!(lines, vec!["tw\u{3c0}o", "three"]);
+    /// let chars = rope.iter_range(CharIndex(5)..CharIndex(8))
+    ///                 .collect::<String>();
+    /// assert_eq!(chars, "w\u{3c0}o");
+    /// ```
+    pub fn iter_range<'a, M>(&'a self, range: ops::Range<M>)
+                            -> Box<Iterator<Item=M::Item> + 'a>
+    where M: metric::Iterable<'a> {
+        range_len!("Rope::iter_range", range.start, range.end);
+        M::iter_range(self, range)
+    }
+
+    /// Returns an iterator over the `char`s of this `Rope`, starting at the
+    /// beginning of line `line`, along with the line and column of each.
+    ///
+    /// The iterator yields `(line, column, char)` tuples, where lines are
+    /// separated by `'\n'` and columns are counted in `char`s from the start
+    /// of the line. Both are zero-based. If this `Rope` has fewer than
+    /// `line + 1` lines, the iterator is empty.
+    ///
+    /// # Time complexity
+    /// Finding the start of `line` is O(log _n_); each `char` after that is
+    /// yielded in O(1) on average.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ab\n") + Rope::from("c\nd");
+    /// let chars = rope.char_indices_at_line(1).collect::<Vec<_>>();
+    /// assert_eq!(chars, vec![(1, 0, 'c'), (1, 1, '\n'), (2, 0, 'd')]);
+    /// assert_eq!(rope.char_indices_at_line(3).next(), None);
+    /// ```
+    #[inline]
+    pub fn char_indices_at_line(&self, line: usize) -> CharIndicesAtLine {
+        CharIndicesAtLine::new(self, line)
+    }
+
+    /// Converts `index`, measured by metric `A`, into an index measured by
+    /// metric `B`.
+    ///
+    /// For example, this may be used to find the byte offset of a given
+    /// character index, or the line containing a given byte offset. If
+    /// `index` falls in the middle of an element of `B`, the index of the
+    /// element containing it is returned.
+    ///
+    /// # Returns
+    /// - `Some` with the converted index, if `index` is within this `Rope`
+    /// - `None` if `index` is greater than the length of this `Rope`
+    ///
+    /// # Time complexity
+    /// O(log _n_), plus the time to measure the part of the leaf before
+    /// `index`, once this `Rope` has been measured by both metrics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// use an_rope::metric::{ByteIndex, CharIndex, LineIndex};
+    /// let rope = Rope::from("\u{3c0} = 3.14\n") + Rope::from("\u{3c4} = 6.28");
+    ///
+    /// let byte: Option<ByteIndex> = rope.convert_index(CharIndex(2));
+    /// assert_eq!(byte, Some(ByteIndex(3)));
+    ///
+    /// let ch: Option<CharIndex> = rope.convert_index(ByteIndex(13));
+    /// assert_eq!(ch, Some(CharIndex(11)));
+    ///
+    /// let line: Option<LineIndex> = rope.convert_index(CharIndex(11));
+    /// assert_eq!(line, Some(LineIndex(1)));
+    ///
+    /// let past_end: Option<ByteIndex> = rope.convert_index(CharIndex(42));
+    /// assert_eq!(past_end, None);
+    /// ```
+    pub fn convert_index<A, B>(&self, index: A) -> Option<B>
+    where A: Metric
+        , B: Metric
+        , NodeLink: Measured<A> + Measured<B>
+        , Node: Measured<B>
+        , str: Measured<B>
+        {
+        let len: A = self.root.measure();
+        if index > len {
+            None
+        } else if index == len {
+            Some(self.root.measure())
+        } else {
+            // find the byte index of `index`, and measure the text before it
+            self.root.to_byte_index(index).map(|byte| {
+                let mut measure = [B::default()];
+                self.root.measure_prefixes( 0, B::default(), &[(0, byte)]
+                                          , &mut measure);
+                measure[0]
+            })
+        }
+    }
+
+    /// Converts each byte offset in `offsets` into an index measured by
+    /// metric `M`, in a single walk over the tree.
+    ///
+    /// This returns the same indices as calling `convert_index` with a
+    /// `ByteIndex` for each offset, but the offsets are sorted and resolved
+    /// together, so that the part of the tree above offsets which are close
+    /// together is only walked once. This is useful when many offsets must
+    /// be converted at once, such as the positions of all the diagnostics
+    /// in a file. The results are in the same order as `offsets`.
+    ///
+    /// # Panics
+    /// If any offset is greater than the length of this `Rope`, or does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(_k_ log _k_ + _k_ log _n_) for _k_ offsets, plus the time to
+    /// measure the part of the leaf before each offset.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// use an_rope::metric::CharIndex;
+    /// let rope = Rope::from("\u{3c0} = 3.14\n") + Rope::from("\u{3c4} = 6.28");
+    /// let chars: Vec<CharIndex> = rope.measure_many(&[13, 0, 2]);
+    /// assert_eq!(chars, vec![CharIndex(11), CharIndex(0), CharIndex(1)]);
+    /// ```
+    pub fn measure_many<M>(&self, offsets: &[usize]) -> Vec<M>
+    where M: Metric
+        , Node: Measured<M>
+        , str: Measured<M>
+        {
+        let mut sorted = offsets.iter().cloned().enumerate()
+                                .collect::<Vec<_>>();
+        sorted.sort_by_key(|&(_, offset)| offset);
+        if let Some(&(_, last)) = sorted.last() {
+            assert!( last <= self.len()
+                   , "Rope::measure_many: offset {} was > length {}"
+                   , last, self.len());
+        }
+        let mut measures = vec![M::default(); offsets.len()];
+        self.root.measure_prefixes(0, M::default(), &sorted, &mut measures);
+        measures
+    }
+
+    /// Returns the number of grapheme clusters in this `Rope`.
+    ///
+    /// # Time complexity
+    /// O(1) if the `Rope` has been measured by graphemes before, otherwise
+    /// O(_n_).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("a\u{310}e\u{301}o\u{308}\u{332}\r\n");
+    /// assert_eq!(rope.grapheme_len(), 4);
+    /// assert_eq!(rope.len(), 13);
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn grapheme_len(&self) -> usize {
+        let len: Grapheme = self.measure();
+        len.into()
+    }
+
+    /// Returns the byte index of the end of the first `n` grapheme
+    /// clusters of this `Rope`, or its length if it has fewer.
+    #[cfg(feature = "unicode")]
+    fn graphemes_end(&self, n: usize) -> usize {
+        let mut end = 0;
+        for _ in 0..n {
+            match self.root.next_grapheme_boundary(end) {
+                Some(next) => end = next
+              , None => break
+            }
+        }
+        end
+    }
+
+    /// Returns a slice of the first `n` grapheme clusters of this `Rope`,
+    /// or of the whole `Rope` if it has fewer.
+    ///
+    /// Unlike slicing at a byte or `char` index, this never cuts a grapheme
+    /// cluster in half, even one which spans leaves.
+    ///
+    /// # Time complexity
+    /// O(_n_ log _N_), for a `Rope` of length _N_
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("noe\u{308}l!");
+    /// assert_eq!(&rope.truncate_graphemes(3), "noe\u{308}");
+    /// assert_eq!(&rope.truncate_graphemes(9), "noe\u{308}l!");
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn truncate_graphemes(&self, n: usize) -> RopeSlice {
+        self.slice(..self.graphemes_end(n))
+    }
+
+    /// Returns a preview of this `Rope` at most `n` grapheme clusters long,
+    /// such as for a tooltip or a list entry.
+    ///
+    /// If this `Rope` is no more than `n` grapheme clusters long, it is
+    /// returned whole. Otherwise, it is truncated, as by
+    /// [`truncate_graphemes()`], and followed by `ellipsis`, if one is
+    /// given; the ellipsis counts towards the `n` clusters, unless it is
+    /// itself longer than that.
+    ///
+    /// # Time complexity
+    /// O(_n_ log _N_), for a `Rope` of length _N_
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("noe\u{308}l, noe\u{308}l");
+    /// assert_eq!(&rope.preview(5, Some("\u{2026}")), "noe\u{308}l\u{2026}");
+    /// assert_eq!(&rope.preview(5, None), "noe\u{308}l,");
+    /// assert_eq!(&rope.preview(20, Some("\u{2026}")), &rope);
+    /// ```
+    ///
+    /// [`truncate_graphemes()`]: struct.Rope.html#method.truncate_graphemes
+    #[cfg(feature = "unicode")]
+    pub fn preview(&self, n: usize, ellipsis: Option<&str>) -> Rope {
+        use unicode_segmentation::UnicodeSegmentation;
+        if self.graphemes_end(n) == self.len() { return self.clone() }
+        let ellipsis = ellipsis.unwrap_or("");
+        let kept = n.saturating_sub(ellipsis.graphemes(true).count());
+        let (prefix, _) = self.root.split(self.graphemes_end(kept));
+        Rope::from(prefix).append(&Rope::from(ellipsis))
+    }
+
+    /// Returns a new `Rope` containing the grapheme clusters of this `Rope`
+    /// in reverse order.
+    ///
+    /// Each grapheme cluster is kept intact, so combining marks stay with
+    /// the characters they modify, and `"\r\n"` is not turned into
+    /// `"\n\r"`. Clusters which span leaves are handled correctly. The
+    /// result is built with a `RopeBuilder`, so its tree is balanced.
+    ///
+    /// # Time complexity
+    /// O(_n_ log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ae\u{301}\r\nz");
+    /// assert_eq!(&rope.reversed(), "z\r\ne\u{301}a");
+    /// assert_eq!(rope.reversed().reversed(), rope);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn reversed(&self) -> Rope {
+        let mut builder = RopeBuilder::with_capacity_hints( self.len()
+                                                          , DEFAULT_CHUNK_SIZE);
+        let mut end = self.len();
+        while let Some(start) = self.root.prev_grapheme_boundary(end) {
+            builder.push_str(&self.root.text_between(start, end));
+            end = start;
+        }
+        builder.finish()
+    }
+
+    /// Returns the byte index of the next grapheme cluster boundary after
+    /// byte index `i`.
+    ///
+    /// This allows a cursor to be moved forward by one grapheme without
+    /// iterating over the graphemes from the beginning of the `Rope`.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the next grapheme boundary
+    /// - `None` if `i` is the end of the `Rope`
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme cluster)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("a\u{310}e\u{301}o\u{308}\u{332}\r\n");
+    /// assert_eq!(rope.next_grapheme_boundary(0), Some(3));
+    /// assert_eq!(rope.next_grapheme_boundary(3), Some(6));
+    /// assert_eq!(rope.next_grapheme_boundary(11), Some(13));
+    /// assert_eq!(rope.next_grapheme_boundary(13), None);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn next_grapheme_boundary(&self, i: usize) -> Option<usize> {
+        assert!( i <= self.len()
+               , "Rope::next_grapheme_boundary: index {} was > length {}"
+               , i, self.len());
+        self.root.next_grapheme_boundary(i)
+    }
+
+    /// Returns the byte index of the previous grapheme cluster boundary
+    /// before byte index `i`.
+    ///
+    /// This allows a cursor to be moved back by one grapheme without
+    /// iterating over the graphemes from the beginning of the `Rope`.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the previous grapheme boundary
+    /// - `None` if `i` is the beginning of the `Rope`
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme cluster)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("a\u{310}e\u{301}o\u{308}\u{332}\r\n");
+    /// assert_eq!(rope.prev_grapheme_boundary(13), Some(11));
+    /// assert_eq!(rope.prev_grapheme_boundary(6), Some(3));
+    /// assert_eq!(rope.prev_grapheme_boundary(3), Some(0));
+    /// assert_eq!(rope.prev_grapheme_boundary(0), None);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn prev_grapheme_boundary(&self, i: usize) -> Option<usize> {
+        assert!( i <= self.len()
+               , "Rope::prev_grapheme_boundary: index {} was > length {}"
+               , i, self.len());
+        self.root.prev_grapheme_boundary(i)
+    }
+
+    /// Returns `true` if byte index `i` falls on a grapheme cluster boundary.
+    ///
+    /// A byte index may fall on a `char` boundary without falling on a
+    /// grapheme boundary, such as between a base character and a combining
+    /// mark. Clusters which span multiple leaves are handled correctly.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("e\u{301}x");
+    /// assert!(rope.is_grapheme_boundary(0));
+    /// assert!(!rope.is_grapheme_boundary(1));
+    /// assert!(rope.is_grapheme_boundary(3));
+    /// assert!(rope.is_grapheme_boundary(4));
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn is_grapheme_boundary(&self, i: usize) -> bool {
+        assert!( i <= self.len()
+               , "Rope::is_grapheme_boundary: index {} was > length {}"
+               , i, self.len());
+        self.root.is_grapheme_boundary(i)
+    }
+
+    /// Returns `range`, with its ends moved outward to the nearest grapheme
+    /// cluster boundaries, so that it covers every cluster it overlaps.
+    ///
+    /// This is `align_to_char_boundary()` for editors which select whole
+    /// grapheme clusters, so that deleting a selection never leaves a
+    /// combining mark behind without its base character. Ends are clamped
+    /// as by `align_to_char_boundary()`.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme clusters at the ends)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ae\u{301}\r\nb");
+    /// assert_eq!(rope.align_to_grapheme_boundary(2..5), 1..6);
+    /// assert_eq!(rope.align_to_grapheme_boundary(0..1), 0..1);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn align_to_grapheme_boundary(&self, range: ops::Range<usize>)
+                                      -> ops::Range<usize> {
+        self.align_range( range, true
+                        , |i| self.floor_grapheme_boundary(i)
+                        , |i| self.ceil_grapheme_boundary(i))
+    }
+
+    /// Returns `range`, with its ends moved inward to the nearest grapheme
+    /// cluster boundaries, so that it covers only the clusters wholly
+    /// within it.
+    ///
+    /// Ends are clamped as by `align_to_char_boundary()`. A range within a
+    /// single grapheme cluster becomes empty.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the grapheme clusters at the ends)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("ae\u{301}\r\nb");
+    /// assert_eq!(rope.align_to_grapheme_boundary_inward(2..7), 4..7);
+    /// assert_eq!(rope.align_to_grapheme_boundary_inward(2..3), 1..1);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn align_to_grapheme_boundary_inward(&self, range: ops::Range<usize>)
+                                             -> ops::Range<usize> {
+        self.align_range( range, false
+                        , |i| self.floor_grapheme_boundary(i)
+                        , |i| self.ceil_grapheme_boundary(i))
+    }
+
+    /// Returns the greatest grapheme cluster boundary at or before byte
+    /// index `i`, which is clamped to the length of this `Rope`.
+    #[cfg(feature = "unicode")]
+    fn floor_grapheme_boundary(&self, i: usize) -> usize {
+        let i = self.floor_char_boundary(i);
+        if self.root.is_grapheme_boundary(i) { i }
+        else { self.root.prev_grapheme_boundary(i).unwrap_or(0) }
+    }
+
+    /// Returns the least grapheme cluster boundary at or after byte index
+    /// `i`, which is clamped to the length of this `Rope`.
+    #[cfg(feature = "unicode")]
+    fn ceil_grapheme_boundary(&self, i: usize) -> usize {
+        let i = self.ceil_char_boundary(i);
+        if self.root.is_grapheme_boundary(i) { i }
+        else { self.root.next_grapheme_boundary(i).unwrap_or(self.len()) }
+    }
+
+    /// Returns an immutable slice of this `Rope` between the given grapheme
+    /// indices.
+    ///
+    /// Unlike `slice`, which takes byte indices, the ends of the returned
+    /// slice always fall on grapheme cluster boundaries, so a cluster is
+    /// never split between a base character and its combining marks.
+    ///
+    /// # Panics
+    /// If the start of the range is greater than the end, or if the end of
+    /// the range is greater than the number of graphemes in this `Rope`.
+    ///
+    /// # Time complexity
+    /// O(_k_ log _n_), where _k_ is the end of the range
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("a\u{310}e\u{301}o\u{308}\u{332}");
+    /// assert_eq!(&rope.slice_graphemes(1..2), "e\u{301}");
+    /// assert_eq!(rope.slice_graphemes(0..3).len(), rope.len());
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn slice_graphemes(&self, range: ops::Range<usize>) -> RopeSlice {
+        range_len!("Rope::slice_graphemes", range.start, range.end);
+        let mut i = 0;
+        let mut boundaries = (0..range.end).map(|n| {
+            i = self.next_grapheme_boundary(i).unwrap_or_else(||
+                panic!( "Rope::slice_graphemes: end {} was > grapheme \
+                         length {}", range.end, n));
+            i
+        });
+        let start = if range.start == 0 { 0 } else {
+            boundaries.nth(range.start - 1).unwrap()
+        };
+        let end = if range.end == range.start { start } else {
+            boundaries.nth(range.end - range.start - 1).unwrap()
+        };
+        self.slice(start..end)
+    }
+
+    /// Returns a `GraphemeCursor` at byte index `offset` in this `Rope`.
+    ///
+    /// # Panics
+    /// If `offset` is greater than the length of this `Rope`, or if `offset`
+    /// does not fall on a character boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("e\u{301}x");
+    /// let mut cursor = rope.grapheme_cursor(rope.len());
+    /// assert_eq!(cursor.prev_boundary(), Some(3));
+    /// assert_eq!(cursor.prev_boundary(), Some(0));
+    /// assert_eq!(cursor.prev_boundary(), None);
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[inline]
+    pub fn grapheme_cursor(&self, offset: usize) -> GraphemeCursor {
+        GraphemeCursor::new(self, offset)
+    }
+
+    /// Returns the byte index of the next [UAX#29 word boundary]
+    /// (http://www.unicode.org/reports/tr29/#Word_Boundaries) after byte
+    /// index `i`.
+    ///
+    /// This is suitable for moving a cursor forward by one word, without
+    /// iterating over the words from the beginning of the `Rope`. Only the
+    /// words around `i` are examined, back to the last space or line break
+    /// before it and on to the first one after it.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the next word boundary
+    /// - `None` if `i` is the end of the `Rope`
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the words around `i`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("Brr, it's 29.3°F!");
+    /// assert_eq!(rope.next_word_boundary(0), Some(3));
+    /// assert_eq!(rope.next_word_boundary(6), Some(9));
+    /// assert_eq!(rope.next_word_boundary(10), Some(14));
+    /// assert_eq!(rope.next_word_boundary(rope.len()), None);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn next_word_boundary(&self, i: usize) -> Option<usize> {
+        assert!( i <= self.len()
+               , "Rope::next_word_boundary: index {} was > length {}"
+               , i, self.len());
+        self.root.next_word_boundary(i)
+    }
+
+    /// Returns the byte index of the previous [UAX#29 word boundary]
+    /// (http://www.unicode.org/reports/tr29/#Word_Boundaries) before byte
+    /// index `i`.
+    ///
+    /// This is suitable for moving a cursor back by one word, without
+    /// iterating over the words from the beginning of the `Rope`. Only the
+    /// words around `i` are examined, back to the last space or line break
+    /// before it and on to the first one after it.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the previous word boundary
+    /// - `None` if `i` is the beginning of the `Rope`
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the words around `i`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("Brr, it's\n29.3°F!");
+    /// assert_eq!(rope.prev_word_boundary(7), Some(5));
+    /// assert_eq!(rope.prev_word_boundary(10), Some(9));
+    /// assert_eq!(rope.prev_word_boundary(3), Some(0));
+    /// assert_eq!(rope.prev_word_boundary(0), None);
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn prev_word_boundary(&self, i: usize) -> Option<usize> {
+        assert!( i <= self.len()
+               , "Rope::prev_word_boundary: index {} was > length {}"
+               , i, self.len());
+        self.root.prev_word_boundary(i)
+    }
+
+    /// Returns true if byte index `i` is a [UAX#29 word boundary]
+    /// (http://www.unicode.org/reports/tr29/#Word_Boundaries) in this `Rope`.
+    ///
+    /// The beginning and end of the `Rope` are always word boundaries. Only
+    /// the words around `i` are examined, back to the last space or line
+    /// break before it and on to the first one after it.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of the words around `i`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("it's foo_bar");
+    /// assert!(rope.is_word_boundary(4));
+    /// assert!(!rope.is_word_boundary(2));
+    /// assert!(!rope.is_word_boundary(8));
+    /// assert!(rope.is_word_boundary(rope.len()));
+    /// ```
+    #[cfg(feature = "unicode")]
+    pub fn is_word_boundary(&self, i: usize) -> bool {
+        assert!( i <= self.len()
+               , "Rope::is_word_boundary: index {} was > length {}"
+               , i, self.len());
+        self.root.is_word_boundary(i)
+    }
+
+    /// Returns true if the text in the byte range `range` of this `Rope` is
+    /// equal to `s`.
+    ///
+    /// Unlike comparing a [`slice()`] of this `Rope`, this does not construct
+    /// a `RopeSlice` or iterate from the beginning of the `Rope`; only the
+    /// leaves spanned by `range` are examined.
+    ///
+    /// [`slice()`]: struct.Rope.html#method.slice
+    ///
+    /// # Returns
+    /// * `true` if the text in `range` is equal to `s`
+    /// * `false` if the text differs, or if `range` extends past the end of
+    ///   this `Rope`
+    ///
+    /// # Panics
+    /// * If the start of `range` is greater than the end
+    ///
+    /// # Time complexity
+    /// O(log _n_ + the length of `s`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("this is ") + Rope::from("an example");
+    /// assert!(rope.eq_str_slice(5..10, "is an"));
+    /// assert!(!rope.eq_str_slice(5..10, "is a "));
+    /// assert!(!rope.eq_str_slice(15..20, "mple!"));
+    /// ```
+    pub fn eq_str_slice(&self, range: ops::Range<usize>, s: &str) -> bool {
+        range_len!("Rope::eq_str_slice", range.start, range.end) == s.len()
+            && self.root.eq_str_at(range.start, s)
+    }
+
+    /// Returns the contiguous chunk of text starting at byte index `i` and
+    /// extending to the end of the leaf node containing `i`.
+    ///
+    /// This is useful for consumers which read text in pieces, such as
+    /// parsers, without needing to collect the whole `Rope` into a `String`.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("abc") + Rope::from("def");
+    /// assert_eq!(rope.chunk_at(1), "bc");
+    /// assert_eq!(rope.chunk_at(3), "def");
+    /// assert_eq!(rope.chunk_at(6), "");
+    /// ```
+    pub fn chunk_at(&self, i: usize) -> &str {
+        assert!( i <= self.len()
+               , "Rope::chunk_at: index {} was > length {}", i, self.len());
+        let (leaf, offset) = self.root.leaf_at(i);
+        &leaf[i - offset..]
+    }
+
+    /// Returns the byte index of the last occurrence of `ch` before byte
+    /// index `end` in this `Rope`.
+    ///
+    /// The search seeks to the leaf containing `end`, and walks the leaves
+    /// of the `Rope` backwards from there, so finding a nearby match does
+    /// not depend on the length of the `Rope`.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the start of the last match
+    /// - `None` if `ch` does not occur before `end`
+    ///
+    /// # Panics
+    /// If `end` is greater than the length of this `Rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_), plus O(_m_) in the distance from the match to `end`
+    ///
+    /// # Examples
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("(a (b)") + Rope::from(" c)");
+    /// assert_eq!(rope.rfind_char(rope.len(), '('), Some(3));
+    /// assert_eq!(rope.rfind_char(3, '('), Some(0));
+    /// assert_eq!(rope.rfind_char(0, '('), None);
+    /// ```
+    pub fn rfind_char(&self, end: usize, ch: char) -> Option<usize> {
+        let len = self.len();
+        assert!( end <= len
+               , "Rope::rfind_char: index {} was > length {}", end, len);
+        self.root.strings_before(end)
+            .filter_map(|(leaf_start, text)| text.rfind(ch)
+                                                 .map(|i| leaf_start + i))
+            .next()
+    }
+
+    /// Returns the byte index of the last occurrence of `pat` which ends at
+    /// or before byte index `end` in this `Rope`.
+    ///
+    /// Matches which span multiple leaves are found. As with `str::rfind`,
+    /// an empty pattern matches at `end`. As with `rfind_char`, the search
+    /// seeks to the leaf containing `end` and walks backwards from there.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the start of the last match
+    /// - `None` if `pat` does not occur before `end`
+    ///
+    /// # Panics
+    /// If `end` is greater than the length of this `Rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_), plus O(_m_) in the distance from the match to `end`
+    ///
+    /// # Examples
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("foo bar f") + Rope::from("oo baz");
+    /// assert_eq!(rope.rfind_str(rope.len(), "foo"), Some(8));
+    /// assert_eq!(rope.rfind_str(10, "foo"), Some(0));
+    /// assert_eq!(rope.rfind_str(rope.len(), "qux"), None);
+    /// ```
+    pub fn rfind_str(&self, end: usize, pat: &str) -> Option<usize> {
+        let len = self.len();
+        assert!( end <= len
+               , "Rope::rfind_str: index {} was > length {}", end, len);
+        if pat.is_empty() { return Some(end) }
+        // the start of the text following the current leaf, kept so that
+        // matches which straddle leaf boundaries can be found
+        let mut carry = String::new();
+        for (leaf_start, text) in self.root.strings_before(end) {
+            // any match straddling the boundary lies further right than a
+            // match within this leaf, so check the boundary first
+            if !carry.is_empty() {
+                let mut tail = text.len().saturating_sub(pat.len() - 1);
+                while !text.is_char_boundary(tail) { tail += 1; }
+                let window = String::from(&text[tail..]) + &carry;
+                if let Some(i) = window.rfind(pat) {
+                    return Some(leaf_start + tail + i)
+                }
+            }
+            if let Some(i) = text.rfind(pat) {
+                return Some(leaf_start + i)
+            }
+            carry.insert_str(0, text);
+            let mut keep = cmp::min(pat.len() - 1, carry.len());
+            while !carry.is_char_boundary(keep) { keep -= 1; }
+            carry.truncate(keep);
+        }
+        None
+    }
+
+    /// Returns the byte index of the delimiter matching the one at byte
+    /// index `i`, taking nesting into account.
+    ///
+    /// If the `char` at `i` is `open`, this scans forward for the `close`
+    /// that matches it; if it is `close`, this scans backward for the
+    /// matching `open`. The scan seeks to the leaf containing `i` and
+    /// counts delimiters a leaf at a time from there, rather than by
+    /// indexing into the `Rope` for every `char`. If `open` and `close` are
+    /// the same `char`, the scan is forward.
+    ///
+    /// # Returns
+    /// - `Some` with the index of the matching delimiter
+    /// - `None` if the `char` at `i` is neither `open` nor `close`, if `i` is
+    ///   the length of the `Rope`, or if the delimiter is unbalanced
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_) to find `i`, plus O(_m_) in the distance between the two
+    /// delimiters
+    ///
+    /// # Examples
+    /// ```
+    /// # use an_rope::Rope;
+    /// let rope = Rope::from("f(a, (b)") + Rope::from(", c)");
+    /// assert_eq!(rope.find_matching(1, '(', ')'), Some(11));
+    /// assert_eq!(rope.find_matching(11, '(', ')'), Some(1));
+    /// assert_eq!(rope.find_matching(5, '(', ')'), Some(7));
+    /// assert_eq!(rope.find_matching(0, '(', ')'), None);
+    /// ```
+    pub fn find_matching(&self, i: usize, open: char, close: char)
+                        -> Option<usize> {
+        let ch = match self.chunk_at(i).chars().next() {
+            Some(ch) => ch
+          , None => return None
+        };
+        let mut depth = 0usize;
+        if ch == open {
+            for (start, text) in self.root.strings_from(i) {
+                for (j, c) in text.char_indices() {
+                    let j = start + j;
+                    if j != i && c == close {
+                        depth -= 1;
+                        if depth == 0 { return Some(j) }
+                    } else if c == open {
+                        depth += 1;
+                    }
+                }
+            }
+        } else if ch == close {
+            let end = i + ch.len_utf8();
+            for (leaf_start, text) in self.root.strings_before(end) {
+                for (j, c) in text.char_indices().rev() {
+                    let j = leaf_start + j;
+                    if j != i && c == open {
+                        depth -= 1;
+                        if depth == 0 { return Some(j) }
+                    } else if c == close {
+                        depth += 1;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns a new `Rope` with each tab character replaced by enough
+    /// spaces to reach the next multiple of `tabstop` columns.
+    ///
+    /// Columns are counted in `char`s from the start of each line. Only the
+    /// leaves which contain tabs are rewritten; the rest of the tree is
+    /// shared with this `Rope`.
+    ///
+    /// # Panics
+    /// If `tabstop` is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\tbc\td\n\te");
+    /// assert_eq!(&rope.expand_tabs(4), "a   bc  d\n    e");
+    /// ```
+    pub fn expand_tabs(&self, tabstop: usize) -> Rope {
+        assert!(tabstop > 0, "Rope::expand_tabs: tabstop must be > 0");
+        let mut col = 0;
+        let root = self.root.map_leaves(&mut |leaf: &str| {
+            if !leaf.contains('\t') {
+                col = match leaf.rfind(is_line_break) {
+                    Some(i) => leaf[i + 1..].chars().count()
+                  , None => col + leaf.chars().count()
+                };
+                return None
+            }
+            let mut expanded = String::with_capacity(leaf.len());
+            for c in leaf.chars() {
+                match c {
+                    '\t' => {
+                        let spaces = tabstop - col % tabstop;
+                        expanded.extend(iter::repeat(' ').take(spaces));
+                        col += spaces;
+                    }
+                  , c if is_line_break(c) => { expanded.push(c); col = 0; }
+                  , _ => { expanded.push(c); col += 1; }
+                }
+            }
+            Some(expanded)
+        });
+        Rope::from(root)
+    }
+
+    /// Returns a new `Rope` with each run of whitespace replaced by a single
+    /// space.
+    ///
+    /// Runs which span multiple leaves are collapsed. Only the leaves which
+    /// contain whitespace that must be changed are rewritten; the rest of
+    /// the tree is shared with this `Rope`.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a  b\t\n c ") + Rope::from(" d");
+    /// assert_eq!(&rope.collapse_whitespace(), "a b c d");
+    /// ```
+    pub fn collapse_whitespace(&self) -> Rope {
+        let mut prev_ws = false;
+        let root = self.root.map_leaves(&mut |leaf: &str| {
+            // first, check whether this leaf needs rewriting at all
+            let mut ws = prev_ws;
+            let unchanged = leaf.chars().all(|c| {
+                let ok = !c.is_whitespace() || (c == ' ' && !ws);
+                ws = c.is_whitespace();
+                ok
+            });
+            if unchanged {
+                prev_ws = ws;
+                return None
+            }
+            let mut collapsed = String::with_capacity(leaf.len());
+            for c in leaf.chars() {
+                if !c.is_whitespace() {
+                    collapsed.push(c);
+                } else if !prev_ws {
+                    collapsed.push(' ');
+                }
+                prev_ws = c.is_whitespace();
+            }
+            Some(collapsed)
+        });
+        Rope::from(root)
+    }
+
+    /// Returns a new `Rope` with every non-overlapping match of `pat`
+    /// replaced by `replacement`, along with the byte range of each
+    /// replacement in the new `Rope`, in order.
+    ///
+    /// Matches are found as by [`find_all()`], including matches which span
+    /// leaves. Only the leaves which contain part of a match are rewritten,
+    /// each once however many matches it contains, and every subtree which
+    /// contains none is shared with this `Rope`. Replacing many matches one
+    /// at a time with `edit` would instead rebuild the path from the root
+    /// to each match.
+    ///
+    /// # Panics
+    /// If `pat` is empty.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("let x = x + 1;");
+    /// let (renamed, ranges) = rope.replace_all_shared("x", "count");
+    /// assert_eq!(&renamed, "let count = count + 1;");
+    /// assert_eq!(ranges, vec![4..9, 12..17]);
+    /// ```
+    ///
+    /// [`find_all()`]: struct.Rope.html#method.find_all
+    pub fn replace_all_shared(&self, pat: &str, replacement: &str)
+                             -> (Rope, Vec<ops::Range<usize>>) {
+        assert!( !pat.is_empty()
+               , "Rope::replace_all_shared: pattern must not be empty");
+        let matches = self.find_all(pat);
+        if matches.is_empty() { return (self.clone(), matches) }
+        let replaced = self.replace_ranges_shared(&matches, replacement);
+        let ranges = matches.iter().enumerate().map(|(n, m)| {
+            let start = m.start + n * replacement.len() - n * pat.len();
+            start..start + replacement.len()
+        }).collect();
+        (replaced, ranges)
+    }
 
-             [graphemes]: \
-             http://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries\
-             \n\
-             The iterator is over the  *extended grapheme clusters*; as \
-             [UAX#29]\
-             (http://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries)\
-             recommends extended grapheme cluster boundaries for general \
-             processing."]
-        #[inline]
-        impl graphemes<&'a str> for Rope {}
-        #[doc=
-            "Returns an iterator over the words of `self`, separated on \
-            [UAX#29 word boundaries]\
-            (http://www.unicode.org/reports/tr29/#Word_Boundaries).\n\n\
-
-            Here, \"words\" are just those substrings which, after splitting on\
-            UAX#29 word boundaries, contain any alphanumeric characters. That \
-            is, the substring must contain at least one character with the \
-            [Alphabetic](http://unicode.org/reports/tr44/#Alphabetic) \
-            property, or with [General_Category=Number]\
-            (http://unicode.org/reports/tr44/#General_Category_Values)."]
-        #[inline]
-        impl unicode_words<&'a str> for Rope {}
-        #[doc=
-            "Returns an iterator over substrings of `self` separated on \
-            [UAX#29 word boundaries]\
-            (http://www.unicode.org/reports/tr29/#Word_Boundaries). \n\n\
-            The concatenation of the substrings returned by this function is \
-            just the original string."]
-        #[inline]
-        impl split_word_bounds<&'a str> for Rope {}
-        // #[inline]
-        // impl grapheme_indices<(usize, &'a str)> for Rope {}
-        // #[inline]
-        // impl split_word_bound_indices<(usize, &'a str)> for Rope {}
+    /// Returns a new `Rope` with each of `ranges`, which must be sorted and
+    /// must not overlap, replaced by `replacement`, sharing every subtree
+    /// which contains none of them with this `Rope`.
+    fn replace_ranges_shared( &self, ranges: &[ops::Range<usize>]
+                            , replacement: &str)
+                            -> Rope {
+        let mut leaf_start = 0;
+        // the first range which does not end before the current leaf
+        let mut next = 0;
+        let root = self.root.map_leaves(&mut |leaf: &str| {
+            let leaf_end = leaf_start + leaf.len();
+            let overlapping = ranges[next..].iter()
+                .take_while(|r| r.start < leaf_end)
+                .collect::<Vec<_>>();
+            if overlapping.is_empty() || leaf.is_empty() {
+                leaf_start = leaf_end;
+                return None
+            }
+            let mut replaced = String::with_capacity(leaf.len());
+            // the byte index in `leaf` of the text not yet copied
+            let mut i = 0;
+            for r in overlapping {
+                // the replacement goes in the leaf where the range starts
+                if r.start >= leaf_start {
+                    replaced.push_str(&leaf[i..r.start - leaf_start]);
+                    replaced.push_str(replacement);
+                }
+                i = cmp::min(r.end, leaf_end) - leaf_start;
+                if r.end <= leaf_end { next += 1; }
+            }
+            replaced.push_str(&leaf[i..]);
+            leaf_start = leaf_end;
+            Some(replaced)
+        });
+        Rope::from(root)
     }
 
-    /// Returns an iterator over the grapheme clusters of `self` and their
-    /// byte offsets. See `graphemes()` for more information.
+    /// Returns a new `Rope` with the spaces and tabs at the end of each
+    /// line removed, along with the byte range in this `Rope` of each run
+    /// which was removed, in order.
+    ///
+    /// Lines are ended by `"\n"` or `"\r\n"`; whitespace before the
+    /// `'\r'` of a `"\r\n"` is removed, but the line terminator is kept.
+    /// Only the leaves which contain removed whitespace are rewritten, and
+    /// every subtree of lines which had none is shared with this `Rope`.
+    /// An editor can use the returned ranges to move its cursors, as a
+    /// cursor after a removed range moves back by its length.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("fn main() { \t\r\n    x  \n\n  }");
+    /// let (stripped, removed) = rope.strip_trailing_whitespace();
+    /// assert_eq!(&stripped, "fn main() {\r\n    x\n\n  }");
+    /// assert_eq!(removed, vec![11..13, 20..22]);
+    /// ```
+    pub fn strip_trailing_whitespace(&self)
+                                    -> (Rope, Vec<ops::Range<usize>>) {
+        let mut removed = Vec::new();
+        // the start of the run of spaces and tabs before the current `char`
+        let mut run = None;
+        // whether the last `char` was a `'\r'` which ended such a run
+        let mut cr = false;
+        for (range, leaf) in self.leaf_ranges() {
+            for (i, c) in leaf.char_indices() {
+                let i = range.start + i;
+                match c {
+                    ' ' | '\t' => if run.is_none() || cr { run = Some(i) }
+                  , '\r' if !cr => { cr = true; continue }
+                  , '\n' => if let Some(start) = run {
+                        removed.push(start..if cr { i - 1 } else { i });
+                    }
+                  , _ => {}
+                }
+                if c != ' ' && c != '\t' { run = None; }
+                cr = false;
+            }
+        }
+        if let (Some(start), false) = (run, cr) {
+            removed.push(start..self.len());
+        }
+        if removed.is_empty() { return (self.clone(), removed) }
+        (self.replace_ranges_shared(&removed, ""), removed)
+    }
+
+    /// Replace the contents of `buf` with the text of this `Rope`.
+    ///
+    /// `buf` is cleared, but its capacity is reused; it is only reallocated
+    /// if it is too small to hold this `Rope`. The text is copied leaf by
+    /// leaf, so this is cheaper than calling `to_string()` when a scratch
+    /// buffer is reused, e.g. in a render loop.
+    ///
+    /// # Time complexity
+    /// O(_n_)
     ///
     /// # Examples
     ///
     /// ```
     /// # use an_rope::Rope;
-    /// let rope = Rope::from("a̐éö̲\r\n");
-    /// let gr_inds = rope.grapheme_indices()
-    ///                   .collect::<Vec<(usize, &str)>>();
-    /// let b: &[_] = &[(0, "a̐"), (3, "é"), (6, "ö̲"), (11, "\r\n")];
+    /// let mut buf = String::with_capacity(64);
+    /// let rope = Rope::from("hello ") + Rope::from("world");
+    /// rope.write_into(&mut buf);
+    /// assert_eq!(buf, "hello world");
     ///
-    /// assert_eq!(&gr_inds[..], b);
+    /// Rope::from("goodbye").write_into(&mut buf);
+    /// assert_eq!(buf, "goodbye");
+    /// assert!(buf.capacity() >= 64);
     /// ```
     #[inline]
-    pub fn grapheme_indices(&self) -> internals::GraphemeIndices {
-        self.root.grapheme_indices()
+    pub fn write_into(&self, buf: &mut String) {
+        buf.clear();
+        self.extend_string(buf);
     }
 
-    /// Returns an iterator over substrings of `self`, split on UAX#29 word
-    /// boundaries, and their offsets. See `split_word_bounds()` for more
-    /// information.
+    /// Append the text of this `Rope` to the end of `buf`.
     ///
-    /// # Example
+    /// Enough capacity for the whole `Rope` is reserved up front, and the
+    /// text is then copied leaf by leaf.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
     ///
     /// ```
     /// # use an_rope::Rope;
-    /// let rope = Rope::from("Brr, it's 29.3°F!");
-    /// let swi1 = rope.split_word_bound_indices()
-    ///                .collect::<Vec<(usize, &str)>>();
-    /// let b: &[_] = &[ (0, "Brr"), (3, ","), (4, " "), (5, "it's")
-    ///                , (9, " "), (10, "29.3"),  (14, "°"), (16, "F")
-    ///                , (17, "!")];
+    /// let mut buf = String::from("> ");
+    /// let rope = Rope::from("hello ") + Rope::from("world");
+    /// rope.extend_string(&mut buf);
+    /// assert_eq!(buf, "> hello world");
+    /// ```
+    pub fn extend_string(&self, buf: &mut String) {
+        buf.reserve(self.len());
+        for s in self.root.strings() {
+            buf.push_str(s);
+        }
+    }
+
+    /// Append the UTF-8 bytes of this `Rope` to the end of `buf`.
+    ///
+    /// Enough capacity for the whole `Rope` is reserved up front, and the
+    /// text is then copied leaf by leaf.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
     ///
-    /// assert_eq!(&swi1[..], b);
     /// ```
-    #[inline]
-    pub fn split_word_bound_indices(&self) -> internals::UWordBoundIndices {
-        self.root.split_word_bound_indices()
+    /// # use an_rope::Rope;
+    /// let mut buf = b"> ".to_vec();
+    /// let rope = Rope::from("hello ") + Rope::from("world");
+    /// rope.extend_bytes(&mut buf);
+    /// assert_eq!(buf, b"> hello world");
+    /// ```
+    pub fn extend_bytes(&self, buf: &mut Vec<u8>) {
+        buf.reserve(self.len());
+        for s in self.root.strings() {
+            buf.extend_from_slice(s.as_bytes());
+        }
     }
 
     /// Returns true if the bytes in `self` equal the bytes in `other`
@@ -912,6 +3618,11 @@ impl Rope {
     /// If the start or end indices of the range to slice exceed the length of
     /// this `Rope`.
     ///
+    /// # Time complexity
+    /// O(log _n_). Slicing never copies text or allocates: the returned
+    /// `RopeSlice` borrows the lowest node in this `Rope`'s tree that spans
+    /// the whole range, however many leaves the range covers.
+    ///
     /// # Examples
     /// ```ignore
     //  this doctest fails to link on my macbook for Secret Reasons.
@@ -936,9 +3647,287 @@ impl Rope {
     where R: RangeArgument<usize> {
         RopeSlice::new(&self.root, range)
     }
+
+    /// Returns an immutable slice of this `Rope` between the given indices.
+    ///
+    /// # Arguments
+    /// + `range`: A [`RangeBounds`](https://doc.rust-lang.org/std/ops/trait.RangeBounds.html)
+    /// specifying the range to slice. This can be produced by range syntax
+    /// like `..`, `a..`, `..b`, `c..d` or `e..=f`.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than its end, or if the end of
+    /// `range` exceeds the length of this `Rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_). Slicing never copies text or allocates: the returned
+    /// `RopeSlice` borrows the lowest node in this `Rope`'s tree that spans
+    /// the whole range, however many leaves the range covers.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("this is ") + Rope::from("an example string");
+    /// assert_eq!(&rope.slice(5..10), "is an");
+    /// assert_eq!(&rope.slice(..4), "this");
+    /// assert_eq!(&rope.slice(19..), "string");
+    /// assert_eq!(&rope.slice(..), "this is an example string");
+    /// ```
+    #[inline]
     #[cfg(not(feature = "unstable"))]
-    pub fn slice(&self, range: ops::Range<usize>) -> RopeSlice {
-        RopeSlice::new(&self.root, range)
+    pub fn slice<R>(&self, range: R) -> RopeSlice
+    where R: ops::RangeBounds<usize> {
+        RopeSlice::new(&self.root, bounded_range(&range, self.len()))
+    }
+
+    /// Returns an immutable slice of this `Rope` between the given indices,
+    /// clamping them to the bounds of this `Rope` rather than panicking.
+    ///
+    /// Both ends of `range` are clamped to the length of this `Rope`, and the
+    /// start is then clamped to the end. This is useful when the range comes
+    /// from somewhere which may briefly be out of date, such as a viewport
+    /// computed before a deletion.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("this is an example string");
+    /// assert_eq!(&rope.slice_clamped(19..40), "string");
+    /// assert_eq!(&rope.slice_clamped(30..40), "");
+    /// assert_eq!(&rope.slice_clamped(8..5), "");
+    /// ```
+    pub fn slice_clamped(&self, range: ops::Range<usize>) -> RopeSlice {
+        let end = cmp::min(range.end, self.len());
+        let start = cmp::min(range.start, end);
+        RopeSlice::new(&self.root, start..end)
+    }
+
+    /// Returns the greatest `char` boundary at or before byte index `i`,
+    /// which is clamped to the length of this `Rope`.
+    fn floor_char_boundary(&self, i: usize) -> usize {
+        if i >= self.len() { return self.len() }
+        // a `char` never spans two leaves
+        let (leaf, offset) = self.root.leaf_at(i);
+        let mut j = i - offset;
+        while !leaf.is_char_boundary(j) { j -= 1; }
+        offset + j
+    }
+
+    /// Returns the least `char` boundary at or after byte index `i`, which
+    /// is clamped to the length of this `Rope`.
+    fn ceil_char_boundary(&self, i: usize) -> usize {
+        if i >= self.len() { return self.len() }
+        let (leaf, offset) = self.root.leaf_at(i);
+        let mut j = i - offset;
+        while !leaf.is_char_boundary(j) { j += 1; }
+        offset + j
+    }
+
+    /// Returns `range`, clamped to the bounds of this `Rope`, with its ends
+    /// moved to boundaries by `floor` and `ceil`: outward, so that the range
+    /// grows, if `outward` is true, and inward otherwise.
+    fn align_range<F, C>( &self, range: ops::Range<usize>, outward: bool
+                        , floor: F, ceil: C) -> ops::Range<usize>
+    where F: Fn(usize) -> usize
+        , C: Fn(usize) -> usize {
+        let end = cmp::min(range.end, self.len());
+        let start = cmp::min(range.start, end);
+        let (start, end) = if outward { (floor(start), ceil(end)) }
+                           else { (ceil(start), floor(end)) };
+        cmp::min(start, end)..end
+    }
+
+    /// Returns `range`, with its ends moved outward to the nearest `char`
+    /// boundaries, so that it covers every `char` it overlaps.
+    ///
+    /// Byte offsets which come from a front end, such as from hit-testing
+    /// a click, may fall within a `char`, or past the end of this `Rope`.
+    /// This returns a range which is safe to pass to `slice()`, `delete()`,
+    /// or any other method which requires `char` boundaries. As with
+    /// `slice_clamped()`, both ends of `range` are first clamped to the
+    /// length of this `Rope`, and its start to its end.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// // "\u{3c0}" is two bytes long
+    /// let rope = Rope::from("a\u{3c0}b\u{3c0}");
+    /// assert_eq!(rope.align_to_char_boundary(2..4), 1..4);
+    /// assert_eq!(rope.align_to_char_boundary(2..5), 1..6);
+    /// assert_eq!(rope.align_to_char_boundary(5..99), 4..6);
+    /// ```
+    pub fn align_to_char_boundary(&self, range: ops::Range<usize>)
+                                  -> ops::Range<usize> {
+        self.align_range( range, true
+                        , |i| self.floor_char_boundary(i)
+                        , |i| self.ceil_char_boundary(i))
+    }
+
+    /// Returns `range`, with its ends moved inward to the nearest `char`
+    /// boundaries, so that it covers only the `char`s wholly within it.
+    ///
+    /// Ends are clamped as by `align_to_char_boundary()`. A range within a
+    /// single `char` becomes empty.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\u{3c0}b\u{3c0}");
+    /// assert_eq!(rope.align_to_char_boundary_inward(2..5), 3..4);
+    /// assert_eq!(rope.align_to_char_boundary_inward(0..99), 0..6);
+    /// assert_eq!(rope.align_to_char_boundary_inward(2..2), 1..1);
+    /// ```
+    pub fn align_to_char_boundary_inward(&self, range: ops::Range<usize>)
+                                         -> ops::Range<usize> {
+        self.align_range( range, false
+                        , |i| self.floor_char_boundary(i)
+                        , |i| self.ceil_char_boundary(i))
+    }
+
+    /// Returns the byte index `n` bytes before the end of this `Rope`, or
+    /// `None` if `n` is greater than its length.
+    ///
+    /// # Time complexity
+    /// O(1)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("abcdef");
+    /// assert_eq!(rope.offset_from_end(2), Some(4));
+    /// assert_eq!(rope.offset_from_end(0), Some(6));
+    /// assert_eq!(rope.offset_from_end(7), None);
+    /// ```
+    #[inline]
+    pub fn offset_from_end(&self, n: usize) -> Option<usize> {
+        self.len().checked_sub(n)
+    }
+
+    /// Converts `range`, whose bounds are counted in bytes back from the end
+    /// of this `Rope`, into a range of byte indices, panicking with a
+    /// message naming `name` if a bound is greater than its length.
+    fn range_from_end<R>(&self, name: &str, range: &R) -> ops::Range<usize>
+    where R: ops::RangeBounds<usize> {
+        use std::ops::Bound::*;
+        let offset = |n: usize| {
+            self.offset_from_end(n)
+                .unwrap_or_else(|| panic!( "{}: {} from end was > length {}"
+                                         , name, n, self.len()))
+        };
+        let start = match range.start_bound() {
+            Included(&n) => offset(n)
+          , Excluded(&n) => offset(n) + 1
+          , Unbounded => 0
+        };
+        let end = match range.end_bound() {
+            Included(&n) => offset(n) + 1
+          , Excluded(&n) => offset(n)
+          , Unbounded => self.len()
+        };
+        start..end
+    }
+
+    /// Returns an immutable slice of this `Rope` between indices counted
+    /// back from its end.
+    ///
+    /// Each bound `n` of `range` stands for the byte index `n` bytes before
+    /// the end of this `Rope`, so `slice_from_end(100..)` is its last 100
+    /// bytes, and `slice_from_end(..1)` all but its last byte. Since the
+    /// bounds count backwards, the start of `range` is the larger of the
+    /// two. This saves computing `len() - n` at each call site, as when
+    /// showing the tail of a log which is only ever appended to.
+    ///
+    /// # Panics
+    /// If either bound of `range` exceeds the length of this `Rope`, or if
+    /// the start of `range` is less than its end.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let log = Rope::from("started\n") + Rope::from("ready\nstopped\n");
+    /// assert_eq!(&log.slice_from_end(8..), "stopped\n");
+    /// assert_eq!(&log.slice_from_end(14..8), "ready\n");
+    /// assert_eq!(&log.slice_from_end(..14), "started\n");
+    /// ```
+    pub fn slice_from_end<R>(&self, range: R) -> RopeSlice
+    where R: ops::RangeBounds<usize> {
+        let range = self.range_from_end("Rope::slice_from_end", &range);
+        range_len!("Rope::slice_from_end", range.start, range.end);
+        self.slice(range)
+    }
+
+    /// Deletes the text of this `Rope` between indices counted back from
+    /// its end, returning a new `Rope`.
+    ///
+    /// The bounds of `range` are interpreted as by [`slice_from_end()`].
+    ///
+    /// [`slice_from_end()`]: struct.Rope.html#method.slice_from_end
+    ///
+    /// # Panics
+    /// If either bound of `range` exceeds the length of this `Rope`, or if
+    /// the start of `range` is less than its end.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("keep these, drop these");
+    /// assert_eq!(&rope.delete_from_end(12..), "keep these");
+    /// assert_eq!(&rope.delete_from_end(12..6), "keep these these");
+    /// ```
+    pub fn delete_from_end<R>(&self, range: R) -> Rope
+    where R: ops::RangeBounds<usize> {
+        let range = self.range_from_end("Rope::delete_from_end", &range);
+        self.delete(range)
+    }
+
+    /// Returns a new `Rope` containing the text of this `Rope` between the
+    /// given indices.
+    ///
+    /// This is the owned counterpart to [`slice()`], for when the text must
+    /// outlive the borrow of this `Rope`, as when it is put on a clipboard or
+    /// handed to another thread. Subtrees which lie wholly within `range`
+    /// are shared with this `Rope`, rather than copied; only the leaves at
+    /// either end of `range`, which it covers in part, are copied.
+    ///
+    /// [`slice()`]: struct.Rope.html#method.slice
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than its end, if the end of
+    /// `range` exceeds the length of this `Rope`, or if either end of `range`
+    /// does not fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("this is ") + Rope::from("an example string");
+    /// let sub = rope.subrope(5..18);
+    /// drop(rope);
+    /// assert_eq!(&sub, "is an example");
+    /// ```
+    pub fn subrope(&self, range: ops::Range<usize>) -> Rope {
+        range_len!("Rope::subrope", range.start, range.end);
+        assert!( range.end <= self.len()
+               , "Rope::subrope: index {} was > length {}"
+               , range.end, self.len());
+        Rope::from(self.root.subrope(range.start, range.end))
     }
 
 }
@@ -1032,6 +4021,67 @@ impl cmp::PartialEq<String> for Rope {
 }
 
 
+impl<'a> cmp::PartialEq<&'a str> for Rope {
+    /// A rope equals a string if all the bytes in the string equal the rope's.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// assert_eq!(Rope::from("abcd"), "abcd");
+    /// assert!(Rope::from("abcd") != "ab");
+    /// ```
+    #[inline]
+    fn eq(&self, other: &&'a str) -> bool {
+        self == *other
+    }
+}
+
+impl cmp::PartialEq<Rope> for str {
+    /// A string equals a rope if all the bytes in the rope equal the string's.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// assert!("abcd" == &Rope::from("abcd"));
+    /// assert!("ab" != &Rope::from("abcd"));
+    /// ```
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        other == self
+    }
+}
+
+impl<'a> cmp::PartialEq<Rope> for &'a str {
+    /// A string equals a rope if all the bytes in the rope equal the string's.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// assert_eq!("abcd", Rope::from("abcd"));
+    /// assert!("dcab" != Rope::from("abcd"));
+    /// ```
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        other == *self
+    }
+}
+
+impl cmp::PartialEq<Rope> for String {
+    /// A string equals a rope if all the bytes in the rope equal the string's.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// assert_eq!(String::from("abcd"), Rope::from("abcd"));
+    /// assert!(String::from("ab") != Rope::from("abcd"));
+    /// ```
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        other == self
+    }
+}
+
+
 //-- concatenation --------------------------------------------------
 impl<'a> ops::Add for &'a Rope {
     type Output = Rope;
@@ -1193,17 +4243,35 @@ impl ops::IndexMut<ops::RangeFrom<usize>> for Rope {
 //     }
 // }
 
-// impl<A> iter::Extend<A> for Rope
-// where Rope: iter::FromIterator<A>{
-//
-//     fn extend<B>(&mut self, iter: B)
-//     where B: IntoIterator<Item=A> {
-//
-//         self.append(&(iter.into_iter().collect()));
-//
-//     }
-//
-// }
+impl iter::Extend<char> for Rope {
+    /// Append the characters yielded by an iterator to this `Rope`.
+    ///
+    /// The characters are collected into a single `String`, which is appended
+    /// to the `Rope` once, rather than creating a new node per character.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let mut an_rope = Rope::from("abc");
+    /// an_rope.extend("def".chars());
+    /// assert_eq!(&an_rope, "abcdef");
+    /// ```
+    fn extend<I>(&mut self, iter: I)
+    where I: IntoIterator<Item=char> {
+        let s: String = iter.into_iter().collect();
+        if !s.is_empty() {
+            *self = self.append(&Rope::from(s));
+        }
+    }
+}
+
+impl<'a> iter::Extend<&'a char> for Rope {
+    #[inline]
+    fn extend<I>(&mut self, iter: I)
+    where I: IntoIterator<Item=&'a char> {
+        self.extend(iter.into_iter().cloned())
+    }
+}
 
 impl iter::FromIterator<char> for Rope {
 
@@ -1220,7 +4288,7 @@ impl iter::FromIterator<String> for Rope {
 
     fn from_iter<I>(iter: I) -> Rope
     where I: IntoIterator<Item=String> {
-        iter.into_iter().fold(Rope::new(), |acc, x| acc + x)
+        Rope::from(Node::from_nodes(iter.into_iter().map(NodeLink::from)))
     }
 
 }
@@ -1229,7 +4297,7 @@ impl iter::FromIterator<Rope> for Rope {
 
     fn from_iter<I>(iter: I) -> Rope
     where I: IntoIterator<Item=Rope> {
-        iter.into_iter().fold(Rope::new(), |acc, x| acc + x)
+        Rope::from(Node::from_nodes(iter.into_iter().map(|rope| rope.root)))
     }
 
 }
@@ -1248,7 +4316,7 @@ impl<'a> iter::FromIterator<&'a str> for Rope {
 
     fn from_iter<I>(iter: I) -> Rope
     where I: IntoIterator<Item=&'a str> {
-        iter.into_iter().fold(Rope::new(), |acc, x| acc + x)
+        Rope::from(Node::from_nodes(iter.into_iter().map(NodeLink::from)))
     }
 
 }
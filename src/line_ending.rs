@@ -0,0 +1,80 @@
+//! Line endings.
+//!
+//! A line of a `Rope` is ended by a line break, `'\n'`, which may be
+//! preceded by a carriage return, `'\r'`. This is the definition used by
+//! `Rope::lines`, the [`Line`] metric, and the other line-oriented methods
+//! of `Rope`, so code which splits text into lines itself can use
+//! [`is_line_break`] and [`LineEnding`] to agree with them exactly.
+//!
+//! [`Line`]: ../metric/struct.Line.html
+//! [`is_line_break`]: fn.is_line_break.html
+//! [`LineEnding`]: enum.LineEnding.html
+use std::fmt;
+
+/// Returns `true` if `c` ends a line.
+///
+/// Only `'\n'` is a line break; a `'\r'` on its own does not end a line.
+///
+/// # Examples
+/// ```
+/// use an_rope::is_line_break;
+/// assert!(is_line_break('\n'));
+/// assert!(!is_line_break('\r'));
+/// ```
+#[inline]
+pub fn is_line_break(c: char) -> bool { c == '\n' }
+
+/// The sequence of characters which ends a line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// A line feed, `"\n"`.
+    Lf
+  , /// A carriage return followed by a line feed, `"\r\n"`.
+    CrLf
+}
+
+impl LineEnding {
+    /// Returns the text of this line ending.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::LineEnding;
+    /// assert_eq!(LineEnding::CrLf.as_str(), "\r\n");
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match *self { LineEnding::Lf => "\n"
+                    , LineEnding::CrLf => "\r\n"
+                    }
+    }
+
+    /// Returns the line ending at the end of `line`.
+    ///
+    /// # Returns
+    /// - `Some` with the line ending `line` ends with
+    /// - `None` if `line` does not end with a line break
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::LineEnding;
+    /// assert_eq!(LineEnding::of_line("abc\r\n"), Some(LineEnding::CrLf));
+    /// assert_eq!(LineEnding::of_line("abc\n"), Some(LineEnding::Lf));
+    /// assert_eq!(LineEnding::of_line("abc\r"), None);
+    /// ```
+    pub fn of_line(line: &str) -> Option<LineEnding> {
+        if !line.chars().next_back().map_or(false, is_line_break) {
+            None
+        } else if line[..line.len() - 1].ends_with('\r') {
+            Some(LineEnding::CrLf)
+        } else {
+            Some(LineEnding::Lf)
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
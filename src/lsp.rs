@@ -0,0 +1,158 @@
+//! Applying changes from the Language Server Protocol.
+//!
+//! LSP clients report edits to a document as a list of
+//! `TextDocumentContentChangeEvent`s, each of which replaces a range given
+//! as zero-based line numbers and character offsets, counted in UTF-16 code
+//! units, with some new text. Converting those positions into byte offsets
+//! is easy to get subtly wrong, so [`Rope::apply_lsp_changes`] does it, and
+//! applies the changes, returning the byte offset edits it made.
+//!
+//! [`LspChange`] and [`LspPosition`] mirror the protocol's types, without
+//! depending on any particular LSP crate; converting from them is a matter
+//! of copying fields.
+//!
+//! [`Rope::apply_lsp_changes`]: ../struct.Rope.html#method.apply_lsp_changes
+//! [`LspChange`]: struct.LspChange.html
+//! [`LspPosition`]: struct.LspPosition.html
+use std::char;
+use std::cmp;
+use std::ops;
+
+use super::{Delta, Rope};
+
+/// A position in a document, as in the Language Server Protocol.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LspPosition { /// The zero-based line number.
+                         pub line: usize
+                       , /// The zero-based offset on the line, in UTF-16
+                         /// code units.
+                         pub character: usize
+                       }
+
+impl LspPosition {
+    /// Returns a new `LspPosition` at `character` on line `line`.
+    #[inline]
+    pub fn new(line: usize, character: usize) -> Self {
+        LspPosition { line: line, character: character }
+    }
+}
+
+/// A change to a document, as in the Language Server Protocol's
+/// `TextDocumentContentChangeEvent`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LspChange { /// The range which is replaced, or `None` if the
+                       /// whole document is.
+                       pub range: Option<ops::Range<LspPosition>>
+                     , /// The text which replaces it.
+                       pub text: String
+                     }
+
+impl Rope {
+    /// Returns the byte index of `position`.
+    ///
+    /// As the protocol specifies, a `character` past the end of its line
+    /// resolves to the end of the line, before its line terminator. A
+    /// `line` past the end of the document resolves to its end, and a
+    /// `character` between the two halves of a surrogate pair resolves to
+    /// the start of the `char`.
+    fn lsp_to_byte_index(&self, position: LspPosition) -> usize {
+        let LspPosition { line, character } = position;
+        self.line_col(line, character, char::len_utf16)
+            .map_or(self.len(), |i| i.unwrap_or_else(|end| end))
+    }
+
+    /// Applies `changes`, in order, to this `Rope`, returning the new
+    /// `Rope` and the edits which were made, as byte offsets.
+    ///
+    /// As in the protocol, the range of each change refers to the document
+    /// as it is after the changes before it, and a change without a range
+    /// replaces the whole document. Positions are resolved leniently, since
+    /// a server should not fail on a client's slightly wrong position: a
+    /// `character` past the end of its line resolves to the end of the
+    /// line, and a `line` past the end of the document to its end. A range
+    /// whose end resolves to before its start is treated as empty.
+    ///
+    /// # Time complexity
+    /// O(_m_ (log _n_ + _l_)), for _m_ changes, where _l_ is the length of
+    /// the lines on which they start and end.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Delta, LspChange, LspPosition, Rope};
+    /// let rope = Rope::from("let \u{1F600} = 1;\nlet b = 2;\n");
+    /// let changes = [ LspChange { range: Some( LspPosition::new(0, 4)
+    ///                                        ..LspPosition::new(0, 6))
+    ///                           , text: String::from("a") }
+    ///               , LspChange { range: Some( LspPosition::new(1, 8)
+    ///                                        ..LspPosition::new(1, 99))
+    ///                           , text: String::from("a + 1;") } ];
+    /// let (rope, edits) = rope.apply_lsp_changes(&changes);
+    /// assert_eq!(&rope, "let a = 1;\nlet b = a + 1;\n");
+    /// assert_eq!(edits, vec![ Delta::new(4..8, "a")
+    ///                       , Delta::new(19..21, "a + 1;") ]);
+    /// ```
+    pub fn apply_lsp_changes(&self, changes: &[LspChange])
+                            -> (Rope, Vec<Delta>) {
+        let mut rope = self.clone();
+        let deltas = changes.iter().map(|change| {
+            let range = match change.range {
+                Some(ref range) => {
+                    let start = rope.lsp_to_byte_index(range.start);
+                    let end = rope.lsp_to_byte_index(range.end);
+                    start..cmp::max(start, end)
+                }
+              , None => 0..rope.len()
+            };
+            let delta = Delta::new(range, change.text.as_str());
+            rope = delta.apply(&rope);
+            delta
+        }).collect();
+        (rope, deltas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{Delta, Rope};
+    use ::test::leaves;
+    use super::{LspChange, LspPosition};
+
+    fn change(start: (usize, usize), end: (usize, usize), text: &str)
+             -> LspChange {
+        LspChange { range: Some( LspPosition::new(start.0, start.1)
+                               ..LspPosition::new(end.0, end.1))
+                  , text: String::from(text) }
+    }
+
+    #[test]
+    fn utf16_positions_across_leaves() {
+        // U+1F600 is two UTF-16 code units, and four bytes
+        let rope = leaves(&["a\u{1F600}", "b\r\n\u{3c0}", "c\n"]);
+        let (edited, deltas) = rope.apply_lsp_changes(&[
+            change((0, 3), (0, 4), "B")
+          , change((1, 1), (1, 1), "!")
+          , change((0, 9), (1, 0), " ")
+          , change((5, 0), (5, 0), "end")
+          , change((0, 2), (0, 0), "?")
+          , change((0, 2), (0, 2), "?")
+        ]);
+        assert_eq!(&edited, "a??\u{1F600}B \u{3c0}!c\nend");
+        assert_eq!(deltas, vec![ Delta::new(5..6, "B")
+                               , Delta::new(10..10, "!")
+                               , Delta::new(6..8, " ")
+                               , Delta::new(12..12, "end")
+                               , Delta::new(1..1, "?")
+                               , Delta::new(2..2, "?") ]);
+    }
+
+    #[test]
+    fn change_without_range_replaces_document() {
+        let rope = Rope::from("old\ntext");
+        let (edited, deltas) = rope.apply_lsp_changes(&[
+            LspChange { range: None, text: String::from("new") }
+          , change((0, 3), (0, 3), "er")
+        ]);
+        assert_eq!(&edited, "newer");
+        assert_eq!(deltas[0], Delta::new(0..8, "new"));
+    }
+}
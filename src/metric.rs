@@ -25,12 +25,18 @@
 //!
 //! ```
 //! # use an_rope::Rope;
+//! # #[cfg(feature = "unicode")] {
 //! use an_rope::metric::Grapheme;
 //! let r = Rope::from("this is a 🆒🆕 rope, 🆗!");
 //! let r = r.delete(Grapheme(10)..Grapheme(13));
 //! assert_eq!(&r, "this is a rope, 🆗!");
+//! # }
 //! ```
 //!
+//! The `Grapheme` metric, like the other methods which segment text into
+//! grapheme clusters or words, requires the `unicode` feature, which is
+//! enabled by default.
+//!
 //! Or, suppose my `Rope` spanned multiple lines:
 //!
 //  FIXME: this test is ignored until
@@ -55,14 +61,18 @@
 //! [`insert`]: ../struct.Rope.html#method.insert
 //! [`delete`]: ../struct.Rope.html#method.delete
 
+use std::cmp;
 use std::convert;
-use std::ops::{Add, Sub};
+use std::iter;
+use std::ops::{Add, Range, Sub};
 use std::default::Default;
 use std::fmt;
 
 
-use internals::IsLineEnding;
-use unicode_segmentation::UnicodeSegmentation;
+use line_ending::is_line_break;
+#[cfg(feature = "unicode")] use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Rope, RopeSlice};
 
 
 /// The class of monoids
@@ -154,8 +164,26 @@ pub trait Metric: Monoid + Eq + Add<usize, Output=Self>
 
     /// Returns true if index `i` in `node` is a boundary along this `Metric`
     fn is_boundary<M: Measured<Self>>(node: &M, i: usize) -> bool;
+
+    /// Subtract `other` from this measurement, without overflowing.
+    ///
+    /// # Returns
+    /// - `Some` with the difference, if `other` is not greater than `self`
+    /// - `None` if `other` is greater than `self`
+    #[inline]
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        if other <= self { Some(self - other) } else { None }
+    }
+
+    /// Subtract `other` from this measurement, returning zero rather than
+    /// overflowing if `other` is greater than `self`.
+    #[inline]
+    fn saturating_sub(self, other: Self) -> Self {
+        self.checked_sub(other).unwrap_or_else(Self::default)
+    }
 }
 
+#[cfg(feature = "unicode")]
 macro_attr! {
     /// A metric for calculating indices in `Rope`s based on Unicode graphemes.
     #[derive( Clone, Copy, PartialOrd, Ord, PartialEq, Eq
@@ -166,12 +194,15 @@ macro_attr! {
     pub struct Grapheme(pub usize);
 }
 
+#[cfg(feature = "unicode")]
 impl Default for Grapheme {
     #[inline] fn default() -> Self { Grapheme(0) }
 }
 
+#[cfg(feature = "unicode")]
 impl Monoid for Grapheme { }
 
+#[cfg(feature = "unicode")]
 impl fmt::Debug for Grapheme {
    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
        write!(f, "grapheme {}", self.0)
@@ -201,6 +232,7 @@ impl fmt::Debug for Line {
    }
 }
 
+#[cfg(feature = "unicode")]
 impl Metric for Grapheme {
 
     #[inline] fn is_splittable() -> bool { false }
@@ -211,6 +243,7 @@ impl Metric for Grapheme {
     }
 }
 
+#[cfg(feature = "unicode")]
 impl Measured<Grapheme> for str {
     /// Convert the `Metric` into a byte index into the given `Node`
     ///
@@ -235,6 +268,7 @@ impl Measured<Grapheme> for str {
     }
 }
 
+#[cfg(feature = "unicode")]
 impl Measured<Grapheme> for String {
     fn to_byte_index(&self, index: Grapheme) -> Option<usize>  {
         self.grapheme_indices(true)
@@ -266,36 +300,44 @@ impl Metric for Line {
 }
 
 impl Measured<Line> for str {
-    // This can only handle line endings at the end of a string.
+    // As for a `LineLeaf`, `Line(n)` is just after the `n`th line ending, or
+    // at the end of the text if it has exactly `n` line endings.
     fn to_byte_index(&self, index: Line) -> Option<usize>  {
-        match index.into() {
-            0 => Some(self.len())
-          , _ => None
-        }
+        let Line(n) = index;
+        self.match_indices(is_line_break).nth(n).map(|(i, _)| i + 1)
+            .or_else(|| {
+                let count: Line = self.measure();
+                if n == count.0 { Some(self.len()) } else { None }
+            })
     }
 
+    // Every line ending is counted, so that the part of a leaf before an
+    // index can be measured, even if the leaf holds more than one line.
     #[inline]
     fn measure(&self) -> Line {
-        let len = self.len();
-        Line(if self[or_zero!(len, 1)..len].is_line_ending() { 1 } else { 0 })
+        Line(self.matches(is_line_break).count())
     }
 
     #[inline] fn measure_weight(&self) -> Line { self.measure() }
 }
 
 impl Measured<Line> for String {
-    // This can only handle line endings at the end of a string.
+    // As for a `LineLeaf`, `Line(n)` is just after the `n`th line ending, or
+    // at the end of the text if it has exactly `n` line endings.
     fn to_byte_index(&self, index: Line) -> Option<usize>  {
-        match index.into() {
-            0 => Some(self.len())
-          , _ => None
-        }
+        let Line(n) = index;
+        self.match_indices(is_line_break).nth(n).map(|(i, _)| i + 1)
+            .or_else(|| {
+                let count: Line = self.measure();
+                if n == count.0 { Some(self.len()) } else { None }
+            })
     }
 
+    // Every line ending is counted, so that the part of a leaf before an
+    // index can be measured, even if the leaf holds more than one line.
     #[inline]
     fn measure(&self) -> Line {
-        let len = self.len();
-        Line(if self[or_zero!(len, 1)..len].is_line_ending() { 1 } else { 0 })
+        Line(self.matches(is_line_break).count())
     }
 
     #[inline] fn measure_weight(&self) -> Line { self.measure() }
@@ -328,6 +370,235 @@ impl Measured<usize> for String {
     #[inline] fn measure_weight(&self) -> usize { self.len() }
 }
 
+macro_attr! {
+    /// A metric for calculating indices in `Rope`s based on byte offsets.
+    ///
+    /// This is equivalent to indexing a `Rope` with a bare `usize`, but it
+    /// makes it explicit that an index is a byte offset.
+    #[derive( Clone, Copy, PartialOrd, Ord, PartialEq, Eq
+            , NewtypeFrom!
+            , NewtypeAdd!(*), NewtypeAdd!(&self, usize), NewtypeAdd!(usize)
+            , NewtypeSub!(*), NewtypeSub!(&self, usize), NewtypeSub!(usize)
+            , NewtypeMul!(*), NewtypeMul!(&self, usize), NewtypeMul!(usize) )]
+    pub struct ByteIndex(pub usize);
+}
+
+impl Default for ByteIndex {
+    #[inline] fn default() -> Self { ByteIndex(0) }
+}
+
+impl Monoid for ByteIndex { }
+
+impl fmt::Debug for ByteIndex {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+       write!(f, "byte {}", self.0)
+   }
+}
+
+impl Metric for ByteIndex {
+    #[inline] fn is_splittable() -> bool { true }
+
+    /// Returns true if index `i` in `node` is a boundary along this `Metric`
+    #[inline] fn is_boundary<M: Measured<Self>>(_node: &M, _i: usize) -> bool {
+        true
+    }
+}
+
+impl Measured<ByteIndex> for str {
+    #[inline] fn to_byte_index(&self, index: ByteIndex) -> Option<usize>  {
+        Some(index.0)
+    }
+    #[inline] fn measure(&self) -> ByteIndex { ByteIndex(self.len()) }
+    #[inline] fn measure_weight(&self) -> ByteIndex { ByteIndex(self.len()) }
+}
+
+impl Measured<ByteIndex> for String {
+    #[inline] fn to_byte_index(&self, index: ByteIndex) -> Option<usize>  {
+        Some(index.0)
+    }
+    #[inline] fn measure(&self) -> ByteIndex { ByteIndex(self.len()) }
+    #[inline] fn measure_weight(&self) -> ByteIndex { ByteIndex(self.len()) }
+}
+
+macro_attr! {
+    /// A metric for calculating indices in `Rope`s based on Unicode scalar
+    /// values (`char`s).
+    #[derive( Clone, Copy, PartialOrd, Ord, PartialEq, Eq
+            , NewtypeFrom!
+            , NewtypeAdd!(*), NewtypeAdd!(&self, usize), NewtypeAdd!(usize)
+            , NewtypeSub!(*), NewtypeSub!(&self, usize), NewtypeSub!(usize)
+            , NewtypeMul!(*), NewtypeMul!(&self, usize), NewtypeMul!(usize) )]
+    pub struct CharIndex(pub usize);
+}
+
+impl Default for CharIndex {
+    #[inline] fn default() -> Self { CharIndex(0) }
+}
+
+impl Monoid for CharIndex { }
+
+impl fmt::Debug for CharIndex {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+       write!(f, "char {}", self.0)
+   }
+}
+
+impl Metric for CharIndex {
+    #[inline] fn is_splittable() -> bool { true }
+
+    /// Returns true if index `i` in `node` is a boundary along this `Metric`
+    ///
+    /// Every `char` boundary is a boundary, including the start and end of
+    /// `node`. This binary searches the byte indices of `node`'s `char`s,
+    /// so it makes O(log _n_) calls to `to_byte_index`.
+    fn is_boundary<M: Measured<Self>>(node: &M, i: usize) -> bool {
+        let (mut lo, mut hi) = (0, node.measure().0);
+        // find the first `char` which doesn't start before `i`
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match node.to_byte_index(CharIndex(mid)) {
+                Some(start) if start < i => lo = mid + 1
+              , _ => hi = mid
+            }
+        }
+        node.to_byte_index(CharIndex(lo)) == Some(i)
+    }
+}
+
+impl Measured<CharIndex> for str {
+    /// Convert the `Metric` into a byte index into the given `Node`
+    ///
+    /// # Returns
+    /// - `Some` with the byte index of the beginning of the `n`th  element
+    ///    in `node` measured by this `Metric`, or the length of `node` if `n`
+    ///    is the number of elements in `node`
+    /// - `None` if `n` is greater than the number of elements in `node`
+    fn to_byte_index(&self, index: CharIndex) -> Option<usize>  {
+        self.char_indices()
+            .map(|(offset, _)| offset)
+            .chain(Some(self.len()))
+            .nth(index.into())
+    }
+
+    #[inline]
+    fn measure(&self) -> CharIndex { CharIndex(self.chars().count()) }
+
+    #[inline]
+    fn measure_weight(&self) -> CharIndex { self.measure() }
+}
+
+impl Measured<CharIndex> for String {
+    #[inline] fn to_byte_index(&self, index: CharIndex) -> Option<usize>  {
+        self.as_str().to_byte_index(index)
+    }
+    #[inline] fn measure(&self) -> CharIndex { self.as_str().measure() }
+    #[inline] fn measure_weight(&self) -> CharIndex {
+        self.as_str().measure_weight()
+    }
+}
+
+/// A [`Metric`] whose elements can be iterated over.
+///
+/// This is the trait behind [`Rope::iter_range`], which iterates over the
+/// elements of a `Rope` in a range measured by any such metric, yielding
+/// the appropriate item for the metric: lines for [`Line`], `char`s for
+/// [`CharIndex`], grapheme clusters for [`Grapheme`] and bytes for
+/// [`ByteIndex`] and `usize`.
+///
+/// [`Metric`]: trait.Metric.html
+/// [`Rope::iter_range`]: ../struct.Rope.html#method.iter_range
+/// [`Line`]: struct.Line.html
+/// [`CharIndex`]: struct.CharIndex.html
+/// [`Grapheme`]: struct.Grapheme.html
+/// [`ByteIndex`]: struct.ByteIndex.html
+pub trait Iterable<'a>: Metric {
+    /// The type of the elements measured by this metric.
+    type Item;
+
+    /// Returns an iterator over the elements of `rope` in `range`.
+    ///
+    /// The iterator stops at the end of `rope`, if `range` extends past it.
+    fn iter_range(rope: &'a Rope, range: Range<Self>)
+                 -> Box<Iterator<Item=Self::Item> + 'a>;
+}
+
+impl<'a> Iterable<'a> for Line {
+    type Item = RopeSlice<'a>;
+
+    /// Skipping to the start of `range` takes O(log _n_) time.
+    fn iter_range(rope: &'a Rope, range: Range<Line>)
+                 -> Box<Iterator<Item=RopeSlice<'a>> + 'a> {
+        Box::new(rope.lines().skip(range.start.0)
+                             .take(range.end.0 - range.start.0))
+    }
+}
+
+impl<'a> Iterable<'a> for CharIndex {
+    type Item = char;
+
+    /// Skipping to the start of `range` takes O(log _n_) time.
+    fn iter_range(rope: &'a Rope, range: Range<CharIndex>)
+                 -> Box<Iterator<Item=char> + 'a> {
+        Box::new(rope.chars().skip(range.start.0)
+                             .take(range.end.0 - range.start.0))
+    }
+}
+
+#[cfg(feature = "unicode")]
+impl<'a> Iterable<'a> for Grapheme {
+    type Item = &'a str;
+
+    /// Skipping to the start of `range` takes O(_n_) time in the number of
+    /// grapheme clusters before it.
+    fn iter_range(rope: &'a Rope, range: Range<Grapheme>)
+                 -> Box<Iterator<Item=&'a str> + 'a> {
+        Box::new(rope.graphemes().skip(range.start.0)
+                                 .take(range.end.0 - range.start.0))
+    }
+}
+
+/// Returns an iterator over the bytes of `rope` between byte indices
+/// `start` and `end`, finding each leaf in O(log _n_) time.
+fn byte_range<'a>(rope: &'a Rope, start: usize, end: usize)
+                 -> Box<Iterator<Item=u8> + 'a> {
+    let end = cmp::min(end, rope.len());
+    Box::new(iter::repeat(()).scan(start, move |position, ()| {
+        if *position >= end { return None }
+        let (leaf, offset) = rope.root.leaf_at(*position);
+        let chunk = &leaf.as_bytes()[ *position - offset
+                                    ..cmp::min(leaf.len(), end - offset)];
+        *position = offset + leaf.len();
+        Some(chunk)
+    }).flat_map(|chunk| chunk.iter().cloned()))
+}
+
+impl<'a> Iterable<'a> for ByteIndex {
+    type Item = u8;
+
+    /// Skipping to the start of `range` takes O(log _n_) time.
+    #[inline]
+    fn iter_range(rope: &'a Rope, range: Range<ByteIndex>)
+                 -> Box<Iterator<Item=u8> + 'a> {
+        byte_range(rope, range.start.0, range.end.0)
+    }
+}
+
+impl<'a> Iterable<'a> for usize {
+    type Item = u8;
+
+    /// Skipping to the start of `range` takes O(log _n_) time.
+    #[inline]
+    fn iter_range(rope: &'a Rope, range: Range<usize>)
+                 -> Box<Iterator<Item=u8> + 'a> {
+        byte_range(rope, range.start, range.end)
+    }
+}
+
+/// Another name for the [`Line`] metric.
+///
+/// [`Line`]: struct.Line.html
+pub use self::Line as LineIndex;
+
 #[cfg(feature = "tendril")] use tendril::fmt::UTF8;
 #[cfg(feature = "tendril")] use tendril::Atomicity;
 #[cfg(feature = "tendril")] use tendril::Tendril;
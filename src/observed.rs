@@ -0,0 +1,207 @@
+//! `Rope`s which notify observers of edits.
+//!
+//! An [`ObservedRope`] owns a `Rope` and edits it in place. Every edit is
+//! described by an [`EditInfo`], which is passed to each callback registered
+//! with [`ObservedRope::subscribe`], so that structures derived from the text
+//! (search indexes, folds, diagnostics) can be updated incrementally rather
+//! than recomputed from scratch.
+//!
+//! [`ObservedRope`]: struct.ObservedRope.html
+//! [`EditInfo`]: struct.EditInfo.html
+//! [`ObservedRope::subscribe`]: struct.ObservedRope.html#method.subscribe
+use std::fmt;
+use std::ops;
+
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use super::Rope;
+
+/// A description of a single edit to a `Rope`.
+///
+/// Every edit replaces a byte range of the old text with some new text;
+/// insertions replace an empty range, and deletions insert no text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EditInfo { range: ops::Range<usize>
+                    , inserted_len: usize
+                    }
+
+impl EditInfo {
+    /// Returns a new `EditInfo` describing the replacement of the byte range
+    /// `range` of the old text with `inserted_len` bytes of new text.
+    #[inline]
+    pub fn new(range: ops::Range<usize>, inserted_len: usize) -> Self {
+        EditInfo { range: range, inserted_len: inserted_len }
+    }
+
+    /// Returns the byte range of the old text which was replaced.
+    #[inline]
+    pub fn old_range(&self) -> ops::Range<usize> { self.range.clone() }
+
+    /// Returns the byte range of the new text which replaced it.
+    #[inline]
+    pub fn new_range(&self) -> ops::Range<usize> {
+        self.range.start..self.range.start + self.inserted_len
+    }
+
+    /// Returns the change in the length of the text, in bytes.
+    #[inline]
+    pub fn delta(&self) -> isize {
+        let removed_len = self.range.end - self.range.start;
+        self.inserted_len as isize - removed_len as isize
+    }
+}
+
+/// A callback registered with `ObservedRope::subscribe`.
+type Observer = Box<FnMut(&EditInfo)>;
+
+/// Identifies a callback registered with `ObservedRope::subscribe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(usize);
+
+/// A `Rope` which is edited in place, and notifies subscribers of each edit.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use an_rope::{Rope, ObservedRope};
+///
+/// let edits = Rc::new(RefCell::new(Vec::new()));
+/// let mut buffer = ObservedRope::new(Rope::from("hello world"));
+/// let log = edits.clone();
+/// buffer.subscribe(move |edit| log.borrow_mut().push(edit.clone()));
+///
+/// buffer.edit(6..11, "there");
+/// buffer.delete(0..6);
+/// assert_eq!(buffer.rope(), &Rope::from("there"));
+/// assert_eq!(edits.borrow()[0].old_range(), 6..11);
+/// assert_eq!(edits.borrow()[1].delta(), -6);
+/// ```
+pub struct ObservedRope { rope: Rope
+                        , observers: Vec<(SubscriptionId, Observer)>
+                        , next_id: usize
+                        }
+
+impl ObservedRope {
+    /// Returns a new `ObservedRope` holding `rope`, with no subscribers.
+    #[inline]
+    pub fn new(rope: Rope) -> Self {
+        ObservedRope { rope: rope, observers: Vec::new(), next_id: 0 }
+    }
+
+    /// Returns the current `Rope`.
+    ///
+    /// Since `Rope`s are persistent, cloning the result is a cheap way to
+    /// take a snapshot which later edits will not affect.
+    #[inline] pub fn rope(&self) -> &Rope { &self.rope }
+
+    /// Returns the current `Rope`, consuming this `ObservedRope`.
+    #[inline] pub fn into_rope(self) -> Rope { self.rope }
+
+    /// Register `f` to be called after every edit to this `ObservedRope`.
+    ///
+    /// Subscribers are called in the order in which they were registered.
+    ///
+    /// # Returns
+    /// A `SubscriptionId` which may be passed to `unsubscribe`.
+    pub fn subscribe<F>(&mut self, f: F) -> SubscriptionId
+    where F: FnMut(&EditInfo) + 'static {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.observers.push((id, Box::new(f)));
+        id
+    }
+
+    /// Remove the subscriber registered with the given `SubscriptionId`.
+    ///
+    /// # Returns
+    /// `true` if a subscriber was removed, `false` if there was none with
+    /// that `SubscriptionId`.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let len = self.observers.len();
+        self.observers.retain(|&(ref sub, _)| *sub != id);
+        self.observers.len() != len
+    }
+
+    /// Replace the byte range `range` with `replacement`.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than the end, or if the end of
+    /// `range` is greater than the length of the `Rope`.
+    pub fn edit(&mut self, range: ops::Range<usize>, replacement: &str) {
+        self.rope = self.rope.edit(range.clone(), replacement);
+        self.notify(EditInfo::new(range, replacement.len()));
+    }
+
+    /// Insert `s` at byte index `index`.
+    ///
+    /// # Panics
+    /// If `index` is greater than the length of the `Rope`.
+    #[inline]
+    pub fn insert_str(&mut self, index: usize, s: &str) {
+        self.edit(index..index, s)
+    }
+
+    /// Delete the byte range `range`.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than the end, or if the end of
+    /// `range` is greater than the length of the `Rope`.
+    #[inline]
+    pub fn delete(&mut self, range: ops::Range<usize>) {
+        self.edit(range, "")
+    }
+
+    /// Append `s` to the end of the `Rope`.
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        let len = self.rope.len();
+        self.edit(len..len, s)
+    }
+
+    fn notify(&mut self, edit: EditInfo) {
+        for &mut (_, ref mut observer) in &mut self.observers {
+            observer(&edit)
+        }
+    }
+}
+
+impl fmt::Debug for ObservedRope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!( f, "ObservedRope {{ rope: {:?}, subscribers: {} }}"
+              , self.rope, self.observers.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use ::Rope;
+    use super::{EditInfo, ObservedRope};
+
+    #[test]
+    fn edit_info_ranges() {
+        let edit = EditInfo::new(2..5, 1);
+        assert_eq!(edit.old_range(), 2..5);
+        assert_eq!(edit.new_range(), 2..3);
+        assert_eq!(edit.delta(), -2);
+    }
+
+    #[test]
+    fn unsubscribe() {
+        let calls = Rc::new(Cell::new(0));
+        let mut buffer = ObservedRope::new(Rope::from("abc"));
+        let counter = calls.clone();
+        let id = buffer.subscribe(move |_| counter.set(counter.get() + 1));
+        buffer.push_str("d");
+        assert!(buffer.unsubscribe(id));
+        assert!(!buffer.unsubscribe(id));
+        buffer.insert_str(0, "z");
+        assert_eq!(calls.get(), 1);
+        assert_eq!(buffer.rope(), &Rope::from("zabcd"));
+    }
+}
@@ -0,0 +1,123 @@
+//! Binary searches over the prefixes of a `Rope`.
+//!
+//! Many questions about a buffer have monotone answers as its prefix grows,
+//! such as "where does the text first become wider than the viewport?" or
+//! "where does the fifth paragraph begin?". [`Rope::partition_point`]
+//! answers them with a binary search guided by the tree: it descends from
+//! the root, testing the prefix which ends at each branch's midpoint, and
+//! finishes with a binary search within a single leaf. The predicate is
+//! therefore called once per level of the tree, and O(log _m_) times within
+//! a leaf of _m_ bytes, rather than at every offset; since `Rope`s are always
+//! kept balanced, that is O(log _n_) times in all.
+//!
+//! [`Rope::partition_point`]: ../struct.Rope.html#method.partition_point
+use super::Rope;
+use super::slice::RopeSlice;
+use super::internals::Value;
+
+impl Rope {
+    /// Returns the first byte index `i`, on a `char` boundary, for which
+    /// `pred(self.slice(..i))` is false, or the length of this `Rope` if
+    /// there is none.
+    ///
+    /// `pred` must be monotone: once it returns false for a prefix, it must
+    /// return false for every longer prefix. If it is not, the result is
+    /// some index at which it returns false, or the length of this `Rope`,
+    /// but which one is unspecified.
+    ///
+    /// # Time complexity
+    /// One call to `pred` for each level of the tree, plus O(log _m_) calls
+    /// within the leaf of _m_ bytes holding the answer. This is O(log _n_)
+    /// calls in all only because a `Rope` is always kept balanced, so its
+    /// depth is O(log _n_). Finding the `char` boundaries within the leaf
+    /// takes O(_m_) time, and the cost of each call is that of `pred`
+    /// itself, which is not counted here: a predicate which copies its
+    /// prefix into a `String`, for example, costs O(_n_) per call.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("one\n") + Rope::from("two\nthree\n");
+    /// // the offset just after the second newline
+    /// let i = rope.partition_point(|prefix| {
+    ///     prefix.to_string().matches('\n').count() < 2
+    /// });
+    /// assert_eq!(i, 8);
+    /// assert_eq!(rope.partition_point(|prefix| prefix.len() < 100), 14);
+    /// ```
+    pub fn partition_point<F>(&self, mut pred: F) -> usize
+    where F: FnMut(RopeSlice) -> bool {
+        if !pred(self.slice(..0)) { return 0 }
+        // `pred` holds for the prefix ending at `start`, and the answer is
+        // in `start + 1..end + 1`; it fails at `end`, unless `end` is the
+        // length of the `Rope`
+        let (mut node, mut start) = (&self.root, 0);
+        loop {
+            match node.value {
+                Value::Branch { ref left, ref right } => {
+                    let mid = start + left.len();
+                    if pred(self.slice(..mid)) {
+                        start = mid;
+                        node = right;
+                    } else {
+                        node = left;
+                    }
+                }
+              , Value::Leaf(ref leaf) => {
+                    let leaf: &str = leaf.as_ref();
+                    let ends = leaf.char_indices().skip(1).map(|(i, _)| i)
+                                   .chain(Some(leaf.len()))
+                                   .collect::<Vec<_>>();
+                    // ends[..lo] hold, and ends[hi..] fail, except perhaps
+                    // the last end
+                    let (mut lo, mut hi) = (0, ends.len());
+                    while lo < hi {
+                        let mid = lo + (hi - lo) / 2;
+                        if pred(self.slice(..start + ends[mid])) {
+                            lo = mid + 1;
+                        } else {
+                            hi = mid;
+                        }
+                    }
+                    return start + ends.get(lo).cloned()
+                                       .unwrap_or_else(|| leaf.len())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use ::test::leaves;
+
+    #[test]
+    fn matches_linear_scan() {
+        let rope = leaves(&["ab\n", "", "c\u{3c0}\n", "d\n\n", "e"]);
+        let text = rope.to_string();
+        let boundaries = (0..text.len() + 1)
+            .filter(|&i| text.is_char_boundary(i))
+            .collect::<Vec<_>>();
+        for &answer in &boundaries {
+            let mut calls = 0;
+            let found = rope.partition_point(|prefix| {
+                calls += 1;
+                prefix.len() < answer
+            });
+            assert_eq!(found, answer);
+            assert!(calls < boundaries.len());
+        }
+        for newlines in 0..6 {
+            let linear = boundaries.iter().cloned().find(|&i| {
+                text[..i].matches('\n').count() >= newlines
+            }).unwrap_or(text.len());
+            assert_eq!( rope.partition_point(|prefix| {
+                            prefix.to_string().matches('\n').count()
+                                < newlines
+                        })
+                      , linear);
+        }
+        assert_eq!(Rope::new().partition_point(|_| true), 0);
+    }
+}
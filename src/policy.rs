@@ -0,0 +1,487 @@
+//! Policies for handling invalid indices when editing a `Rope`.
+//!
+//! `Rope`'s editing methods panic when given an index past the end of the
+//! text or within a `char`, as slicing a `str` does. That is the right
+//! behaviour for most programs, where a bad offset is a bug, but a server or
+//! an embedded editor must not abort because one client sent a stale offset.
+//! A [`PolicyRope`] chooses between these at the type level. Each of its
+//! methods which takes an index checks it as the `try_` variant of the
+//! `Rope` method would, and only if that returns an [`Error`] does it
+//! consult its policy: with the [`Strict`] policy, it panics with that
+//! error, and with the [`Lenient`] policy, it clamps the index to the length
+//! of the text, moves it to a `char` boundary and makes the edit there, so
+//! that none of its methods can panic. Its own `try_` methods bypass the
+//! policy, and return the error instead, for callers which want to handle
+//! some invalid indices themselves.
+//!
+//! A `PolicyRope` does not dereference to its `Rope`, since most of `Rope`'s
+//! methods take indices which are not checked by the policy. Its `Rope` can
+//! be had with `into_rope()`, for code which checks its own indices.
+//!
+//! [`PolicyRope`]: struct.PolicyRope.html
+//! [`Error`]: ../enum.Error.html
+//! [`Strict`]: enum.Strict.html
+//! [`Lenient`]: enum.Lenient.html
+use std::convert;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops;
+
+use super::{Delta, LspChange, Rope};
+use super::error::Error;
+use super::slice::RopeSlice;
+
+/// A policy for handling the invalid indices passed to a `PolicyRope`'s
+/// methods.
+pub trait IndexPolicy {
+    /// Returns the byte index to insert or split at in place of `index`,
+    /// which `error` says is not a valid index into `rope`.
+    fn index(rope: &Rope, index: usize, error: Error) -> usize;
+
+    /// Returns the byte range to slice, delete or replace in place of
+    /// `range`, which `error` says is not a valid range of `rope`.
+    fn range(rope: &Rope, range: ops::Range<usize>, error: Error)
+             -> ops::Range<usize>;
+}
+
+/// Invalid indices are a bug: `PolicyRope`'s methods panic with the `Error`
+/// describing them, as `Rope`'s methods do.
+#[derive(Clone, Copy, Debug)]
+pub enum Strict {}
+
+impl IndexPolicy for Strict {
+    #[inline]
+    fn index(_: &Rope, _: usize, error: Error) -> usize {
+        panic!("PolicyRope: {}", error)
+    }
+
+    #[inline]
+    fn range(_: &Rope, _: ops::Range<usize>, error: Error)
+             -> ops::Range<usize> {
+        panic!("PolicyRope: {}", error)
+    }
+}
+
+/// Invalid indices are clamped to the length of the `Rope` and moved to
+/// `char` boundaries, so that no method panics.
+///
+/// An index within a `char` is moved back to its start. A range is moved
+/// outward, as by [`Rope::align_to_char_boundary()`], so that a deletion
+/// which splits a `char` removes the whole of it; a range whose start is
+/// after its end is emptied.
+///
+/// [`Rope::align_to_char_boundary()`]:
+///     ../struct.Rope.html#method.align_to_char_boundary
+#[derive(Clone, Copy, Debug)]
+pub enum Lenient {}
+
+impl IndexPolicy for Lenient {
+    #[inline]
+    fn index(rope: &Rope, index: usize, _: Error) -> usize {
+        rope.floor_char_boundary(index)
+    }
+
+    #[inline]
+    fn range(rope: &Rope, range: ops::Range<usize>, _: Error)
+             -> ops::Range<usize> {
+        rope.align_to_char_boundary(range)
+    }
+}
+
+/// A `Rope` whose methods handle invalid indices according to the policy
+/// `P`.
+///
+/// # Examples
+///
+/// ```
+/// use an_rope::{Rope, PolicyRope, Lenient};
+/// let buffer = PolicyRope::<Lenient>::new(Rope::from("caf\u{e9}"));
+/// // 4 is within the 'é', so the insertion is made before it
+/// let buffer = buffer.insert_str(4, "!");
+/// assert_eq!(buffer.to_string(), "caf!\u{e9}");
+/// // and a range past the end is clamped
+/// assert_eq!(buffer.delete(3..99).to_string(), "caf");
+/// ```
+#[derive(Clone, Debug)]
+pub struct PolicyRope<P = Strict> { rope: Rope
+                                  , policy: PhantomData<P>
+                                  }
+
+impl<P: IndexPolicy> PolicyRope<P> {
+    /// Returns a new `PolicyRope` holding `rope`.
+    #[inline]
+    pub fn new(rope: Rope) -> Self {
+        PolicyRope { rope: rope, policy: PhantomData }
+    }
+
+    /// Returns the `Rope` held by this `PolicyRope`, consuming it.
+    #[inline] pub fn into_rope(self) -> Rope { self.rope }
+
+    /// Returns the length in bytes of the text of this `PolicyRope`.
+    #[inline] pub fn len(&self) -> usize { self.rope.len() }
+
+    /// Returns true if the text of this `PolicyRope` is empty.
+    #[inline] pub fn is_empty(&self) -> bool { self.rope.is_empty() }
+
+    /// Returns `index`, if it is a valid index to insert or split at, or the
+    /// index the policy gives in its place.
+    fn checked_index(&self, index: usize) -> usize {
+        // an empty range at `index` is valid exactly when `index` is
+        match self.rope.try_slice(index..index) {
+            Ok(_) => index
+          , Err(e) => P::index(&self.rope, index, e)
+        }
+    }
+
+    /// Insert `s` at byte index `index`, returning a new `PolicyRope`.
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `index` is greater than the length of
+    /// the `Rope`, or falls within a `char`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn insert_str(&self, index: usize, s: &str) -> Self {
+        let rope = self.rope.try_insert_str(index, s).unwrap_or_else(|e| {
+            self.rope.insert_str(P::index(&self.rope, index, e), s)
+        });
+        PolicyRope::new(rope)
+    }
+
+    /// Insert `ch` at byte index `index`, returning a new `PolicyRope`.
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `index` is greater than the length of
+    /// the `Rope`, or falls within a `char`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn insert(&self, index: usize, ch: char) -> Self {
+        self.insert_str(index, ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Insert `rope` at byte index `index`, returning a new `PolicyRope`.
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `index` is greater than the length of
+    /// the `Rope`, or falls within a `char`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn insert_rope(&self, index: usize, rope: &Rope) -> Self {
+        PolicyRope::new(self.rope.insert_rope(self.checked_index(index), rope))
+    }
+
+    /// Append `other` to the end of the `Rope`, returning a new
+    /// `PolicyRope`.
+    ///
+    /// This takes no index, so it never consults the policy.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn append(&self, other: &Rope) -> Self {
+        PolicyRope::new(self.rope.append(other))
+    }
+
+    /// Prepend `other` to the beginning of the `Rope`, returning a new
+    /// `PolicyRope`.
+    ///
+    /// This takes no index, so it never consults the policy.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn prepend(&self, other: &Rope) -> Self {
+        PolicyRope::new(self.rope.prepend(other))
+    }
+
+    /// Delete the grapheme cluster beginning at byte index `i`, returning a
+    /// new `PolicyRope` along with the deleted text, as
+    /// [`Rope::delete_char`] does.
+    ///
+    /// [`Rope::delete_char`]: ../struct.Rope.html#method.delete_char
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `i` is greater than the length of the
+    /// `Rope`, or falls within a `char`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[cfg(feature = "unicode")]
+    pub fn delete_char(&self, i: usize) -> (Self, String) {
+        let (rope, deleted) = self.rope.delete_char(self.checked_index(i));
+        (PolicyRope::new(rope), deleted)
+    }
+
+    /// Delete the grapheme cluster ending at byte index `i`, returning a new
+    /// `PolicyRope` along with the deleted text, as [`Rope::backspace`]
+    /// does.
+    ///
+    /// [`Rope::backspace`]: ../struct.Rope.html#method.backspace
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `i` is greater than the length of the
+    /// `Rope`, or falls within a `char`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[cfg(feature = "unicode")]
+    pub fn backspace(&self, i: usize) -> (Self, String) {
+        let (rope, deleted) = self.rope.backspace(self.checked_index(i));
+        (PolicyRope::new(rope), deleted)
+    }
+
+    /// Applies `changes` from the Language Server Protocol, returning a new
+    /// `PolicyRope` and the edits which were made, as
+    /// [`Rope::apply_lsp_changes`] does.
+    ///
+    /// The protocol says how to resolve positions past the end of a line or
+    /// of the document, so they are always resolved leniently, under either
+    /// policy, and this never panics.
+    ///
+    /// [`Rope::apply_lsp_changes`]:
+    ///     ../struct.Rope.html#method.apply_lsp_changes
+    ///
+    /// # Time complexity
+    /// O(_m_ (log _n_ + _l_)), for _m_ changes, where _l_ is the length of
+    /// the lines on which they start and end.
+    pub fn apply_lsp_changes(&self, changes: &[LspChange])
+                            -> (Self, Vec<Delta>) {
+        let (rope, deltas) = self.rope.apply_lsp_changes(changes);
+        (PolicyRope::new(rope), deltas)
+    }
+
+    /// Delete the text in `range`, returning a new `PolicyRope`.
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `range` is not a valid range of the
+    /// `Rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn delete(&self, range: ops::Range<usize>) -> Self {
+        let rope = self.rope.try_delete(range.clone()).unwrap_or_else(|e| {
+            self.rope.delete(P::range(&self.rope, range, e))
+        });
+        PolicyRope::new(rope)
+    }
+
+    /// Replace the text in `range` with `replacement`, returning a new
+    /// `PolicyRope`.
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `range` is not a valid range of the
+    /// `Rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn edit(&self, range: ops::Range<usize>, replacement: &str) -> Self {
+        let rope = self.rope.try_edit(range.clone(), replacement)
+            .unwrap_or_else(|e| {
+                self.rope.edit(P::range(&self.rope, range, e), replacement)
+            });
+        PolicyRope::new(rope)
+    }
+
+    /// Split the `Rope` at byte index `index`, returning the text before
+    /// and after it.
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `index` is greater than the length of
+    /// the `Rope`, or falls within a `char`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn split(&self, index: usize) -> (Self, Self) {
+        let (left, right) = self.rope.split(self.checked_index(index));
+        (PolicyRope::new(left), PolicyRope::new(right))
+    }
+
+    /// Returns the text in `range`.
+    ///
+    /// # Panics
+    /// Under the `Strict` policy, if `range` is not a valid range of the
+    /// `Rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    pub fn slice(&self, range: ops::Range<usize>) -> RopeSlice {
+        match self.rope.try_slice(range.clone()) {
+            Ok(slice) => slice
+          , Err(e) => self.rope.slice(P::range(&self.rope, range, e))
+        }
+    }
+
+    /// Insert `s` at byte index `index`, returning a new `PolicyRope`, or
+    /// the `Error` describing why `index` is invalid, whatever the policy.
+    ///
+    /// # Errors
+    /// As for [`Rope::try_insert_str()`], since the policy is not consulted.
+    ///
+    /// [`Rope::try_insert_str()`]: ../struct.Rope.html#method.try_insert_str
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn try_insert_str(&self, index: usize, s: &str)
+                          -> Result<Self, Error> {
+        self.rope.try_insert_str(index, s).map(PolicyRope::new)
+    }
+
+    /// Delete the text in `range`, returning a new `PolicyRope`, or the
+    /// `Error` describing why `range` is invalid, whatever the policy.
+    ///
+    /// # Errors
+    /// As for [`Rope::try_delete()`], since the policy is not consulted.
+    ///
+    /// [`Rope::try_delete()`]: ../struct.Rope.html#method.try_delete
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn try_delete(&self, range: ops::Range<usize>) -> Result<Self, Error> {
+        self.rope.try_delete(range).map(PolicyRope::new)
+    }
+
+    /// Replace the text in `range` with `replacement`, returning a new
+    /// `PolicyRope`, or the `Error` describing why `range` is invalid,
+    /// whatever the policy.
+    ///
+    /// # Errors
+    /// As for [`Rope::try_edit()`], since the policy is not consulted.
+    ///
+    /// [`Rope::try_edit()`]: ../struct.Rope.html#method.try_edit
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn try_edit(&self, range: ops::Range<usize>, replacement: &str)
+                    -> Result<Self, Error> {
+        self.rope.try_edit(range, replacement).map(PolicyRope::new)
+    }
+
+    /// Returns the text in `range`, or the `Error` describing why `range` is
+    /// invalid, whatever the policy.
+    ///
+    /// # Errors
+    /// As for [`Rope::try_slice()`], since the policy is not consulted.
+    ///
+    /// [`Rope::try_slice()`]: ../struct.Rope.html#method.try_slice
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    #[inline]
+    pub fn try_slice(&self, range: ops::Range<usize>)
+                     -> Result<RopeSlice, Error> {
+        self.rope.try_slice(range)
+    }
+}
+
+impl<P: IndexPolicy> Default for PolicyRope<P> {
+    #[inline] fn default() -> Self { PolicyRope::new(Rope::new()) }
+}
+
+impl<P: IndexPolicy> convert::From<Rope> for PolicyRope<P> {
+    #[inline] fn from(rope: Rope) -> Self { PolicyRope::new(rope) }
+}
+
+impl<P> fmt::Display for PolicyRope<P> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.rope, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::{Error, LspChange, LspPosition, Rope};
+    use ::test::leaves;
+    use super::{PolicyRope, Strict, Lenient};
+
+    #[test]
+    fn lenient_never_panics() {
+        let rope = leaves(&["a\u{3c0}", "", "\u{3c4}b"]);
+        let text = rope.to_string();
+        let buffer = PolicyRope::<Lenient>::new(rope.clone());
+        for i in 0..text.len() + 3 {
+            let at = (0..i + 1).rev()
+                               .find(|&j| text.is_char_boundary(j))
+                               .unwrap();
+            let expected = format!("{}x{}", &text[..at], &text[at..]);
+            assert_eq!(buffer.insert_str(i, "x").to_string(), expected);
+            assert_eq!(buffer.insert(i, 'x').to_string(), expected);
+            let (left, right) = buffer.split(i);
+            assert_eq!(left.to_string(), &text[..at]);
+            assert_eq!(right.to_string(), &text[at..]);
+            assert_eq!( buffer.insert_rope(i, &Rope::from("x")).to_string()
+                      , expected);
+            #[cfg(feature = "unicode")]
+            {
+                let (deleted, _) = rope.delete_char(at);
+                assert_eq!(buffer.delete_char(i).0.to_string(), deleted);
+                let (backspaced, _) = rope.backspace(at);
+                assert_eq!(buffer.backspace(i).0.to_string(), backspaced);
+            }
+            for j in 0..text.len() + 3 {
+                let range = rope.align_to_char_boundary(i..j);
+                let mut expected = text.clone();
+                expected.drain(range.clone());
+                assert_eq!(buffer.delete(i..j).to_string(), expected);
+                assert_eq!( buffer.slice(i..j).to_string()
+                          , &text[range.clone()]);
+                expected.insert_str(range.start, "y");
+                assert_eq!(buffer.edit(i..j, "y").to_string(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn strict_accepts_valid_indices() {
+        let buffer = PolicyRope::<Strict>::from(Rope::from("a\u{3c0}b"));
+        assert_eq!(buffer.insert_str(3, "c").to_string(), "a\u{3c0}cb");
+        assert_eq!(buffer.delete(1..3).to_string(), "ab");
+        assert_eq!(buffer.slice(1..4).to_string(), "\u{3c0}b");
+    }
+
+    #[test]
+    fn try_methods_bypass_the_policy() {
+        let buffer = PolicyRope::<Lenient>::from(Rope::from("a\u{3c0}b"));
+        assert_eq!( buffer.try_insert_str(2, "x").unwrap_err()
+                  , Error::NotCharBoundary { index: 2 });
+        assert_eq!( buffer.try_delete(0..9).unwrap_err()
+                  , Error::IndexOutOfBounds { index: 9, len: 4 });
+        assert!(buffer.try_edit(1..2, "x").is_err());
+        assert!(buffer.try_slice(3..1).is_err());
+        assert_eq!( buffer.try_edit(1..3, "p").unwrap().to_string(), "apb");
+        assert_eq!(&buffer.try_slice(0..1).unwrap(), "a");
+    }
+
+    #[test]
+    fn unindexed_edits() {
+        let buffer = PolicyRope::<Strict>::from(Rope::from("b"));
+        let buffer = buffer.append(&Rope::from("c"))
+                           .prepend(&Rope::from("a"));
+        assert_eq!(buffer.to_string(), "abc");
+        let change = LspChange { range: Some( LspPosition::new(0, 1)
+                                            ..LspPosition::new(9, 9))
+                               , text: String::from("!") };
+        let (buffer, _) = buffer.apply_lsp_changes(&[change]);
+        assert_eq!(buffer.to_string(), "a!");
+    }
+
+    #[test]
+    #[should_panic(expected = "PolicyRope: index 3 was > length 2")]
+    fn strict_panics_past_end() {
+        let buffer = PolicyRope::<Strict>::from(Rope::from("ab"));
+        buffer.insert_str(3, "c");
+    }
+
+    #[test]
+    #[should_panic(expected = "PolicyRope: index 2 is not a char boundary")]
+    fn strict_panics_within_char() {
+        let buffer = PolicyRope::<Strict>::from(Rope::from("a\u{3c0}b"));
+        buffer.split(2);
+    }
+}
@@ -0,0 +1,177 @@
+//! Prefix and suffix checks.
+//!
+//! Besides strings, a `Rope` can be checked for a prefix or suffix which is
+//! itself a `Rope`. Text is compared a run of bytes at a time, wherever the
+//! leaves of the two `Rope`s overlap, rather than a byte at a time. Before
+//! any text is compared, the tree is checked for the other `Rope`'s root:
+//! a `Rope` made by appending to another usually keeps the other's root on
+//! the left spine of its tree, unless their short leaves were merged or the
+//! tree was rebalanced, so checking whether a buffer was only appended to
+//! is usually O(log _n_).
+use std::cmp;
+
+use super::Rope;
+use super::internals::{NodeLink, Value};
+
+impl Rope {
+    /// Returns true if the bytes of this `Rope` from byte index `i` on
+    /// begin with those of `s`, which must fit before its end.
+    fn eq_at(&self, mut i: usize, s: &str) -> bool {
+        let mut s = s.as_bytes();
+        while !s.is_empty() {
+            let (leaf, offset) = self.root.leaf_at(i);
+            let text = &leaf.as_bytes()[i - offset..];
+            let n = cmp::min(text.len(), s.len());
+            if text[..n] != s[..n] { return false }
+            s = &s[n..];
+            i += n;
+        }
+        true
+    }
+
+    /// Returns true if the bytes of this `Rope` from byte index `i` on
+    /// begin with those of `other`, which must fit before its end.
+    fn eq_rope_at(&self, i: usize, other: &Rope) -> bool {
+        other.leaf_ranges()
+             .all(|(range, leaf)| self.eq_at(i + range.start, leaf))
+    }
+
+    /// Returns true if `target` is the root of this `Rope`, or of one of the
+    /// subtrees along its left spine, if `leftward` is true, or its right
+    /// spine otherwise.
+    fn spine_contains(&self, target: &NodeLink, leftward: bool) -> bool {
+        let mut node = &self.root;
+        loop {
+            if node.ptr_eq(target) { return true }
+            node = match node.value {
+                Value::Branch { ref left, ref right } => {
+                    let next = if leftward { left } else { right };
+                    if next.len() < target.len() { return false }
+                    next
+                }
+              , _ => return false
+            };
+        }
+    }
+
+    /// Returns true if this `Rope` begins with `prefix`.
+    ///
+    /// # Time complexity
+    /// O(_m_ log _n_), for a prefix of length _m_
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("hello world");
+    /// assert!(rope.starts_with("hello"));
+    /// assert!(!rope.starts_with("world"));
+    /// ```
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        prefix.len() <= self.len() && self.eq_at(0, prefix)
+    }
+
+    /// Returns true if this `Rope` ends with `suffix`.
+    ///
+    /// # Time complexity
+    /// O(_m_ log _n_), for a suffix of length _m_
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("hello world");
+    /// assert!(rope.ends_with("world"));
+    /// assert!(!rope.ends_with("hello"));
+    /// ```
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        suffix.len() <= self.len()
+            && self.eq_at(self.len() - suffix.len(), suffix)
+    }
+
+    /// Returns true if this `Rope` begins with the text of `prefix`.
+    ///
+    /// If `prefix` shares its root with this `Rope`, or with a subtree on
+    /// the left spine of this `Rope`'s tree, as when this `Rope` was made
+    /// by appending to `prefix`, no text is compared.
+    ///
+    /// # Time complexity
+    /// O(log _n_) if `prefix` is shared; otherwise, O(_m_ log _n_), for a
+    /// prefix of length _m_
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let saved = Rope::from("fn main() {\n");
+    /// let typed = saved.append(&Rope::from("}\n"));
+    /// assert!(typed.starts_with_rope(&saved));
+    /// assert!(typed.starts_with_rope(&Rope::from("fn")));
+    /// assert!(!saved.starts_with_rope(&typed));
+    /// ```
+    pub fn starts_with_rope(&self, prefix: &Rope) -> bool {
+        if prefix.len() > self.len() { return false }
+        self.spine_contains(&prefix.root, true) || self.eq_rope_at(0, prefix)
+    }
+
+    /// Returns true if this `Rope` ends with the text of `suffix`.
+    ///
+    /// If `suffix` shares its root with this `Rope`, or with a subtree on
+    /// the right spine of this `Rope`'s tree, as when this `Rope` was made
+    /// by prepending to `suffix`, no text is compared.
+    ///
+    /// # Time complexity
+    /// O(log _n_) if `suffix` is shared; otherwise, O(_m_ log _n_), for a
+    /// suffix of length _m_
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let body = Rope::from("text\n");
+    /// let headed = body.prepend(&Rope::from("# title\n"));
+    /// assert!(headed.ends_with_rope(&body));
+    /// assert!(!headed.ends_with_rope(&Rope::from("title\n")));
+    /// ```
+    pub fn ends_with_rope(&self, suffix: &Rope) -> bool {
+        if suffix.len() > self.len() { return false }
+        self.spine_contains(&suffix.root, false)
+            || self.eq_rope_at(self.len() - suffix.len(), suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use ::test::leaves;
+
+    #[test]
+    fn compare_across_leaves() {
+        let rope = leaves(&["ab", "c\u{3c0}d", "", "ef"]);
+        let text = rope.to_string();
+        for i in 0..text.len() + 1 {
+            if !text.is_char_boundary(i) { continue }
+            let (prefix, suffix) = text.split_at(i);
+            assert!(rope.starts_with(prefix));
+            assert!(rope.ends_with(suffix));
+            let mid = (0..i / 2 + 1).rev()
+                                    .find(|&j| prefix.is_char_boundary(j))
+                                    .unwrap();
+            let prefix = leaves(&[&prefix[..mid], &prefix[mid..]]);
+            assert!(rope.starts_with_rope(&prefix));
+            assert!(rope.ends_with_rope(&Rope::from(suffix)));
+        }
+        assert!(!rope.starts_with("abd"));
+        assert!(!rope.ends_with_rope(&Rope::from("e")));
+        assert!(!rope.starts_with_rope(&Rope::from(text.clone() + "g")));
+    }
+
+    #[test]
+    fn shared_roots_skip_comparison() {
+        let base = Rope::from("x".repeat(5000));
+        let tail = Rope::from("y".repeat(5000));
+        let appended = base.append(&tail);
+        assert!(appended.spine_contains(&base.root, true));
+        assert!(appended.starts_with_rope(&base));
+        assert!(!appended.spine_contains(&base.root, false));
+        assert!(appended.spine_contains(&tail.root, false));
+        assert!(!appended.ends_with_rope(&Rope::from("xy")));
+        assert!(appended.ends_with_rope(&tail.delete(0..1)));
+    }
+}
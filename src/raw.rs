@@ -0,0 +1,392 @@
+//! Raw access to the trees underlying `Rope`s.
+//!
+//! A `Rope` is a persistent tree of nodes, and edits share every node they
+//! don't change with the original `Rope`. This module exposes handles to
+//! those subtrees, for embedders which want to build their own structures on
+//! top of a `Rope` (such as a piece table whose pieces are subtrees) without
+//! reaching into the crate's internals.
+//!
+//! A [`Subtree`] is an immutable, reference-counted handle to a node. It can
+//! be found by the range of text it covers, compared by identity with other
+//! handles, turned back into a `Rope`, or grafted into another `Rope`
+//! without copying its text.
+//!
+//! [`Subtree`]: struct.Subtree.html
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops;
+
+use super::Rope;
+use super::internals::{NodeLink, Value};
+
+/// A handle to a subtree of a `Rope`.
+///
+/// Cloning a `Subtree` is cheap, as it only increments a reference count.
+#[derive(Clone, Debug)]
+pub struct Subtree(NodeLink);
+
+impl Subtree {
+    /// Returns the whole tree of `rope`.
+    #[inline]
+    pub fn root(rope: &Rope) -> Subtree { Subtree(rope.root.clone()) }
+
+    /// Returns the smallest subtree of `rope` containing the byte range
+    /// `range`, along with the byte offset in `rope` at which that subtree
+    /// begins.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than the end, or if the end of
+    /// `range` is greater than the length of `rope`.
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// use an_rope::raw::Subtree;
+    /// let rope = Rope::from("abc") + Rope::from("def");
+    /// let (subtree, offset) = Subtree::covering(&rope, 4..6);
+    /// assert_eq!(offset, 3);
+    /// assert_eq!(&subtree.to_rope(), "def");
+    /// ```
+    pub fn covering(rope: &Rope, range: ops::Range<usize>) -> (Subtree, usize) {
+        range_len!("Subtree::covering", range.start, range.end);
+        assert!( range.end <= rope.len()
+               , "Subtree::covering: end {} was > length {}"
+               , range.end, rope.len());
+        let mut node = &rope.root;
+        let mut offset = 0;
+        while let Value::Branch { ref left, ref right } = node.value {
+            let weight = left.len();
+            if range.end - offset <= weight {
+                node = left;
+            } else if range.start - offset >= weight {
+                offset += weight;
+                node = right;
+            } else {
+                break
+            }
+        }
+        (Subtree(node.clone()), offset)
+    }
+
+    /// Returns the length of the text in this subtree, in bytes.
+    #[inline] pub fn len(&self) -> usize { self.0.len() }
+
+    /// Returns `true` if this subtree contains no text.
+    #[inline] pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Returns `true` if this subtree is a single leaf.
+    #[inline]
+    pub fn is_leaf(&self) -> bool {
+        match self.0.value {
+            Value::Leaf(_) => true
+          , Value::Branch { .. } => false
+        }
+    }
+
+    /// Returns the left and right children of this subtree, or `None` if it
+    /// is a leaf.
+    #[inline]
+    pub fn children(&self) -> Option<(Subtree, Subtree)> {
+        match self.0.value {
+            Value::Branch { ref left, ref right } =>
+                Some((Subtree(left.clone()), Subtree(right.clone())))
+          , Value::Leaf(_) => None
+        }
+    }
+
+    /// Returns `true` if `self` and `other` are handles to the same node.
+    ///
+    /// Two subtrees with equal text are not necessarily the same node; this
+    /// compares identity, not content, and so is O(1).
+    #[inline]
+    pub fn ptr_eq(&self, other: &Subtree) -> bool { self.0.ptr_eq(&other.0) }
+
+    /// Returns a `Rope` whose tree is this subtree.
+    ///
+    /// The `Rope` shares this subtree's nodes, rather than copying them.
+    #[inline]
+    pub fn to_rope(&self) -> Rope { Rope { root: self.0.clone() } }
+}
+
+impl fmt::Display for Subtree {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Returns a new `Rope` with `subtree` inserted at byte index `index` in
+/// `rope`.
+///
+/// The nodes of `subtree` are shared with the new `Rope`, rather than copied,
+/// so `subtree` can still be found in it by identity.
+///
+/// # Panics
+/// If `index` is greater than the length of `rope`, or if `index` does not
+/// fall on a character boundary.
+///
+/// # Time complexity
+/// O(log _n_)
+///
+/// # Examples
+/// ```
+/// use an_rope::Rope;
+/// use an_rope::raw::{self, Subtree};
+/// let piece = Subtree::root(&Rope::from("bcd"));
+/// let rope = raw::graft(&Rope::from("ae"), 1, &piece);
+/// assert_eq!(&rope, "abcde");
+/// assert!(Subtree::covering(&rope, 1..4).0.ptr_eq(&piece));
+/// ```
+pub fn graft(rope: &Rope, index: usize, subtree: &Subtree) -> Rope {
+    rope.insert_rope(index, &subtree.to_rope())
+}
+
+/// Statistics describing the shape of a `Rope`'s tree.
+///
+/// These are useful when measuring how the chunk size of a `RopeBuilder`,
+/// or a series of edits, affects the tree underlying a `Rope`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeStats { /// The number of leaves in the tree.
+                       pub leaves: usize
+                     , /// The number of branches on the longest path from
+                       /// the root to a leaf.
+                       pub depth: usize
+                     , /// The length of the shortest leaf, in bytes.
+                       pub min_leaf: usize
+                     , /// The length of the longest leaf, in bytes.
+                       pub max_leaf: usize
+                     , /// The length of the whole tree, in bytes.
+                       pub len: usize
+                     }
+
+impl TreeStats {
+    /// Returns the statistics of `rope`'s tree.
+    ///
+    /// # Time complexity
+    /// O(_n_) in the number of nodes in the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// use an_rope::raw::TreeStats;
+    /// let rope = Rope::from("abc") + Rope::from("de");
+    /// let stats = TreeStats::of(&rope);
+    /// assert_eq!(stats.leaves, 2);
+    /// assert_eq!(stats.depth, 1);
+    /// assert_eq!((stats.min_leaf, stats.max_leaf), (2, 3));
+    /// ```
+    pub fn of(rope: &Rope) -> TreeStats {
+        let mut stats = TreeStats { leaves: 0
+                                  , depth: 0
+                                  , min_leaf: usize::max_value()
+                                  , max_leaf: 0
+                                  , len: rope.len()
+                                  };
+        let mut stack = vec![(&rope.root, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            match node.value {
+                Value::Branch { ref left, ref right } => {
+                    stack.push((right, depth + 1));
+                    stack.push((left, depth + 1));
+                }
+              , Value::Leaf(_) => {
+                    let len = node.len();
+                    stats.leaves += 1;
+                    stats.depth = cmp::max(stats.depth, depth);
+                    stats.min_leaf = cmp::min(stats.min_leaf, len);
+                    stats.max_leaf = cmp::max(stats.max_leaf, len);
+                }
+            }
+        }
+        stats
+    }
+
+    /// Returns the mean length of a leaf, in bytes.
+    #[inline]
+    pub fn mean_leaf(&self) -> usize { self.len / self.leaves }
+}
+
+impl fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!( f, "{} bytes in {} leaves ({}..{} bytes, mean {}), depth {}"
+              , self.len, self.leaves, self.min_leaf, self.max_leaf
+              , self.mean_leaf(), self.depth)
+    }
+}
+
+/// How [`Rope::estimate_memory_usage`] counts nodes which are shared with
+/// other `Rope`s.
+///
+/// [`Rope::estimate_memory_usage`]:
+///     ../struct.Rope.html#method.estimate_memory_usage
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SharedNodes {
+    /// Count every node reachable from the `Rope` in full, once.
+    ///
+    /// This is the memory which the `Rope` keeps alive, so the estimates
+    /// for two `Rope`s which share nodes add up to more than they use.
+    Full
+  , /// Count each node in proportion to the `Rope`'s share of the links to
+    /// it.
+    ///
+    /// A node linked from two places, only one of which belongs to the
+    /// `Rope`, counts for half its size, and so on down the tree. The
+    /// estimates for a set of `Rope`s which share nodes add up to roughly
+    /// the memory they use between them.
+    Proportional
+}
+
+/// An estimate of the heap memory used by a `Rope`, as returned by
+/// [`Rope::estimate_memory_usage`].
+///
+/// [`Rope::estimate_memory_usage`]:
+///     ../struct.Rope.html#method.estimate_memory_usage
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage { /// The bytes used by the nodes of the tree,
+                         /// including their reference counts.
+                         pub nodes: usize
+                       , /// The bytes of text held by the leaves.
+                         pub text: usize
+                       }
+
+impl MemoryUsage {
+    /// Returns the total bytes used.
+    #[inline] pub fn total(&self) -> usize { self.nodes + self.text }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!( f, "{} bytes ({} in nodes, {} of text)"
+              , self.total(), self.nodes, self.text)
+    }
+}
+
+impl Rope {
+    /// Returns an estimate of the heap memory used by this `Rope`.
+    ///
+    /// A node which appears more than once in this `Rope`'s tree is only
+    /// counted once. `shared` controls how nodes which are also linked from
+    /// other `Rope`s, such as earlier versions of this one, are counted.
+    /// Allocator overhead and unused string capacity are not counted.
+    ///
+    /// # Time complexity
+    /// O(_n_) in the number of distinct nodes.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// use an_rope::raw::SharedNodes;
+    /// let rope = Rope::from("a".repeat(4096));
+    /// let full = rope.estimate_memory_usage(SharedNodes::Full);
+    /// assert!(full.text == 4096 && full.nodes > 0);
+    ///
+    /// let copy = rope.clone();
+    /// let half = copy.estimate_memory_usage(SharedNodes::Proportional);
+    /// assert_eq!(half.text, 2048);
+    /// assert_eq!(rope.estimate_memory_usage(SharedNodes::Full), full);
+    /// ```
+    pub fn estimate_memory_usage(&self, shared: SharedNodes) -> MemoryUsage {
+        // the distinct nodes of the tree, with every node before its
+        // children
+        let mut order = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![(&self.root, false)];
+        while let Some((node, children_done)) = stack.pop() {
+            if children_done {
+                order.push(node);
+                continue
+            }
+            if !seen.insert(node.as_ptr()) { continue }
+            stack.push((node, true));
+            if let Value::Branch { ref left, ref right } = node.value {
+                stack.push((left, false));
+                stack.push((right, false));
+            }
+        }
+        order.reverse();
+
+        // the share of each node which belongs to this `Rope`, handed down
+        // from the links to it
+        let mut shares = HashMap::new();
+        shares.insert( self.root.as_ptr()
+                     , 1.0 / self.root.strong_count() as f64);
+        let mut usage = (0.0, 0.0);
+        for node in order {
+            let share = match shared {
+                SharedNodes::Full => 1.0
+              , SharedNodes::Proportional => shares[&node.as_ptr()]
+            };
+            let (nodes, text) = node.allocated_bytes();
+            usage.0 += nodes as f64 * share;
+            usage.1 += text as f64 * share;
+            if let Value::Branch { ref left, ref right } = node.value {
+                for child in [left, right].iter() {
+                    *shares.entry(child.as_ptr()).or_insert(0.0) +=
+                        share / child.strong_count() as f64;
+                }
+            }
+        }
+        MemoryUsage { nodes: usage.0.round() as usize
+                    , text: usage.1.round() as usize
+                    }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::{graft, SharedNodes, Subtree};
+
+    #[test]
+    fn covering_finds_shared_nodes() {
+        let rope = Rope::from("abc") + Rope::from("def") + Rope::from("ghi");
+        let edited = rope.insert_str(1, "xyz");
+        let (before, _) = Subtree::covering(&rope, 6..9);
+        let (after, offset) = Subtree::covering(&edited, 9..12);
+        assert_eq!(offset, 9);
+        assert!(before.ptr_eq(&after));
+        assert!(!Subtree::root(&rope).ptr_eq(&Subtree::root(&edited)));
+    }
+
+    #[test]
+    fn covering_spanning_children() {
+        let rope = Rope::from("abc") + Rope::from("def");
+        let (subtree, offset) = Subtree::covering(&rope, 2..4);
+        assert_eq!(offset, 0);
+        assert!(subtree.ptr_eq(&Subtree::root(&rope)));
+        assert!(subtree.children().is_some());
+    }
+
+    #[test]
+    fn proportional_usage_adds_up() {
+        let rope = Rope::from("abc") + Rope::from("def") + Rope::from("ghi");
+        let edited = rope.insert_str(1, "xyz");
+        let full = |r: &Rope| r.estimate_memory_usage(SharedNodes::Full);
+        let part = |r: &Rope|
+            r.estimate_memory_usage(SharedNodes::Proportional);
+        let retained = ::history::retained_bytes(&[ rope.clone()
+                                                  , edited.clone()]);
+        assert!(full(&rope).total() + full(&edited).total() > retained);
+        let sum = part(&rope).total() + part(&edited).total();
+        assert!(sum <= retained + 1 && sum + 1 >= retained);
+        assert_eq!(full(&rope).text, 9);
+    }
+
+    #[test]
+    fn graft_at_ends() {
+        let piece = Subtree::root(&Rope::from("xy"));
+        let rope = graft(&Rope::from("ab"), 0, &piece);
+        assert_eq!(&rope, "xyab");
+        // the grafted subtree is shared, not merged with the text beside it
+        assert!(Subtree::covering(&rope, 0..2).0.ptr_eq(&piece));
+        let rope = graft(&Rope::from("ab"), 2, &piece);
+        assert_eq!(&rope, "abxy");
+        assert!(Subtree::covering(&rope, 2..4).0.ptr_eq(&piece));
+        let rope = graft(&Rope::new(), 0, &piece);
+        assert_eq!(&rope, "xy");
+        assert!(Subtree::root(&rope).ptr_eq(&piece));
+    }
+}
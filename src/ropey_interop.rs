@@ -0,0 +1,63 @@
+//! Conversions to and from [ropey]'s `Rope`.
+//!
+//! When built with the `ropey` feature, an `an_rope::Rope` may be converted
+//! to and from a `ropey::Rope` with `From` and `Into`. Both conversions copy
+//! the text a chunk at a time, so that neither rope is ever collected into a
+//! single `String`.
+//!
+//! [ropey]: https://docs.rs/crate/ropey
+
+use ropey;
+
+use std::convert;
+
+use super::Rope;
+use super::internals::{Node, NodeLink};
+
+// `From<&ropey::Rope>` can't be implemented alongside the blanket
+// `From<&S> for NodeLink`, but cloning a `ropey::Rope` is cheap, so callers
+// holding a reference may convert a clone instead.
+impl convert::From<ropey::Rope> for NodeLink {
+    #[inline] fn from(that: ropey::Rope) -> Self {
+        Node::from_nodes(that.chunks().map(NodeLink::from))
+    }
+}
+
+impl<'a> convert::From<&'a Rope> for ropey::Rope {
+    fn from(that: &'a Rope) -> Self {
+        let mut builder = ropey::RopeBuilder::new();
+        for string in that.strings() {
+            builder.append(string);
+        }
+        builder.finish()
+    }
+}
+
+impl convert::From<Rope> for ropey::Rope {
+    #[inline] fn from(that: Rope) -> Self { ropey::Rope::from(&that) }
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey;
+    use ::Rope;
+
+    #[test]
+    fn round_trip() {
+        let text = "hello\n\u{3c0} world\r\n".repeat(200);
+        let theirs = ropey::Rope::from_str(&text);
+        let ours = Rope::from(theirs.clone());
+        assert_eq!(&ours, text.as_str());
+        assert!(ours.check_invariants().is_ok());
+        let back = ropey::Rope::from(&ours);
+        assert_eq!(back, theirs);
+        assert_eq!(back.len_bytes(), ours.len());
+    }
+
+    #[test]
+    fn empty() {
+        let ours: Rope = ropey::Rope::new().into();
+        assert!(ours.is_empty());
+        assert_eq!(ropey::Rope::from(ours).len_bytes(), 0);
+    }
+}
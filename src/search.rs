@@ -0,0 +1,295 @@
+//! Searching a `Rope` with options.
+//!
+//! [`Rope::find_with`] and [`Rope::find_all_with`] take a [`SearchOptions`],
+//! so that an interactive search can toggle case sensitivity and whole word
+//! matching without the caller making lowercased copies of the pattern and
+//! the buffer, or checking word boundaries around each match itself.
+//!
+//! [`Rope::find_with`]: ../struct.Rope.html#method.find_with
+//! [`Rope::find_all_with`]: ../struct.Rope.html#method.find_all_with
+//! [`SearchOptions`]: struct.SearchOptions.html
+use std::collections::VecDeque;
+use std::ops;
+
+use super::Rope;
+
+/// Options controlling how [`Rope::find_with`] and [`Rope::find_all_with`]
+/// match a pattern.
+///
+/// The default options match exactly the same text as `Rope::find_all`.
+///
+/// # Examples
+/// ```
+/// use an_rope::{Rope, SearchOptions};
+/// let options = SearchOptions { case_insensitive: true
+///                             , ..SearchOptions::default() };
+/// let rope = Rope::from("Foo fOO");
+/// assert_eq!(rope.find_all_with("foo", &options), vec![0..3, 4..7]);
+/// ```
+///
+/// [`Rope::find_with`]: ../struct.Rope.html#method.find_with
+/// [`Rope::find_all_with`]: ../struct.Rope.html#method.find_all_with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SearchOptions {
+    /// Whether letters match regardless of case.
+    ///
+    /// Each `char` of the pattern and the `Rope` is compared by its
+    /// lowercase mapping, so, for example, `"É"` matches `"é"`. Matches
+    /// always start and end on `char` boundaries of the `Rope`.
+    pub case_insensitive: bool
+  , /// Whether matches may overlap.
+    ///
+    /// If this is `false`, the search resumes after the end of each match,
+    /// as with `str::match_indices`; if it is `true`, every position at
+    /// which the pattern occurs is reported.
+    pub overlapping: bool
+  , /// Whether matches must be whole words.
+    ///
+    /// If this is `true`, only matches which begin and end on [UAX#29 word
+    /// boundaries](http://www.unicode.org/reports/tr29/#Word_Boundaries)
+    /// are reported, so `"foo"` does not match in `"food"` or `"foo_bar"`.
+    ///
+    /// This option requires the `unicode` feature.
+    #[cfg(feature = "unicode")]
+    pub whole_word: bool
+}
+
+impl SearchOptions {
+    #[cfg(feature = "unicode")]
+    #[inline] fn whole_word(&self) -> bool { self.whole_word }
+
+    #[cfg(not(feature = "unicode"))]
+    #[inline] fn whole_word(&self) -> bool { false }
+}
+
+/// The word boundaries of the most recently segmented stretch of a `Rope`,
+/// so that checking many matches in the same words only segments them once.
+#[cfg(feature = "unicode")]
+struct WordBounds<'a> { rope: &'a Rope
+                      , bounds: Vec<usize>
+                      }
+
+#[cfg(feature = "unicode")]
+impl<'a> WordBounds<'a> {
+    fn contains(&mut self, i: usize) -> bool {
+        if i == 0 || i == self.rope.len() { return true }
+        let seen = self.bounds.first().map_or(false, |&start| start <= i)
+                && self.bounds.last().map_or(false, |&end| i < end);
+        if !seen { self.bounds = self.rope.root.word_bounds_around(i); }
+        self.bounds.binary_search(&i).is_ok()
+    }
+}
+
+impl Rope {
+    /// Returns the byte range of the first match of `pat` in this `Rope`,
+    /// using `options`.
+    ///
+    /// # Returns
+    /// - `Some` with the range of the first match
+    /// - `None` if `pat` does not occur in this `Rope`
+    ///
+    /// # Panics
+    /// If `pat` is empty.
+    ///
+    /// # Time complexity
+    /// O(_n_) in the distance to the first match
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, SearchOptions};
+    /// let options = SearchOptions { case_insensitive: true
+    ///                             , ..SearchOptions::default() };
+    /// let rope = Rope::from("say Hello");
+    /// assert_eq!(rope.find_with("hello", &options), Some(4..9));
+    /// assert_eq!(rope.find_with("hello", &SearchOptions::default()), None);
+    /// ```
+    pub fn find_with(&self, pat: &str, options: &SearchOptions)
+                    -> Option<ops::Range<usize>> {
+        assert!(!pat.is_empty(), "Rope::find_with: pattern must not be empty");
+        self.matches_with(pat, options).next()
+    }
+
+    /// Returns the byte ranges of all the matches of `pat` in this `Rope`,
+    /// using `options`, in order.
+    ///
+    /// # Panics
+    /// If `pat` is empty.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, SearchOptions};
+    /// let options = SearchOptions { case_insensitive: true
+    ///                             , overlapping: true
+    ///                             , ..SearchOptions::default() };
+    /// let rope = Rope::from("AaA");
+    /// assert_eq!(rope.find_all_with("aa", &options), vec![0..2, 1..3]);
+    /// ```
+    pub fn find_all_with(&self, pat: &str, options: &SearchOptions)
+                        -> Vec<ops::Range<usize>> {
+        assert!( !pat.is_empty()
+               , "Rope::find_all_with: pattern must not be empty");
+        self.matches_with(pat, options).collect()
+    }
+
+    fn matches_with<'a>(&'a self, pat: &'a str, options: &SearchOptions)
+                       -> Box<Iterator<Item=ops::Range<usize>> + 'a> {
+        // a match which is rejected for not being a whole word may overlap
+        // one which is not, so whole word searches consider every match
+        // before discarding overlapping ones
+        let matches = if options.case_insensitive {
+            self.caseless_matches(pat)
+        } else {
+            let len = pat.len();
+            let overlapping = options.overlapping || options.whole_word();
+            Box::new(self.match_offsets(pat, overlapping)
+                         .map(move |i| i..i + len))
+        };
+        #[cfg(feature = "unicode")]
+        let matches = if options.whole_word {
+            let mut bounds = WordBounds { rope: self, bounds: Vec::new() };
+            Box::new(matches.filter(move |m|
+                bounds.contains(m.start) && bounds.contains(m.end)))
+        } else {
+            matches
+        };
+        if options.overlapping { return matches }
+        let mut last_end = 0;
+        Box::new(matches.filter(move |m| {
+            if m.start < last_end { return false }
+            last_end = m.end;
+            true
+        }))
+    }
+
+    /// Returns an iterator over the ranges of all the matches of `pat` in
+    /// this `Rope`, including overlapping matches, ignoring case.
+    ///
+    /// The lowercased `Rope` is searched for the lowercased pattern with the
+    /// Knuth-Morris-Pratt algorithm, so each `char` of the `Rope` is
+    /// lowercased once, and the search takes amortized constant time per
+    /// `char`.
+    fn caseless_matches<'a>(&'a self, pat: &str)
+                           -> Box<Iterator<Item=ops::Range<usize>> + 'a> {
+        let pat = pat.chars().flat_map(char::to_lowercase)
+                     .collect::<Vec<_>>();
+        let fail = failure_function(&pat);
+        // the byte index of the `char` each of the last `pat.len()`
+        // lowercased `char`s of the `Rope` came from, and whether it is the
+        // first `char` of that `char`'s lowercase mapping
+        let mut starts = VecDeque::with_capacity(pat.len());
+        // the length of the longest prefix of `pat` which the lowercased
+        // `Rope` ends with so far
+        let mut matched = 0;
+        let lowered = self.leaf_ranges().flat_map(|(range, leaf)| {
+            leaf.char_indices().flat_map(move |(i, c)| {
+                let start = range.start + i;
+                let end = start + c.len_utf8();
+                let lower = c.to_lowercase();
+                let n = lower.clone().count();
+                lower.enumerate()
+                     .map(move |(j, l)| (l, start..end, j == 0, j + 1 == n))
+            })
+        });
+        Box::new(lowered.filter_map(move |(l, range, first, last)| {
+            if starts.len() == pat.len() { starts.pop_front(); }
+            starts.push_back((range.start, first));
+            while matched > 0 && pat[matched] != l {
+                matched = fail[matched - 1];
+            }
+            if pat[matched] == l { matched += 1; }
+            if matched < pat.len() { return None }
+            matched = fail[matched - 1];
+            match starts[0] {
+                (start, true) if last => Some(start..range.end)
+              , _ => None
+            }
+        }))
+    }
+}
+
+/// Returns the Knuth-Morris-Pratt failure function of `pat`: the length of
+/// the longest proper prefix of each prefix of `pat` which is also a suffix
+/// of it.
+fn failure_function(pat: &[char]) -> Vec<usize> {
+    let mut fail = vec![0; pat.len()];
+    let mut k = 0;
+    for q in 1..pat.len() {
+        while k > 0 && pat[k] != pat[q] { k = fail[k - 1]; }
+        if pat[k] == pat[q] { k += 1; }
+        fail[q] = k;
+    }
+    fail
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::SearchOptions;
+
+    #[test]
+    fn case_insensitive_across_leaves() {
+        let options = SearchOptions { case_insensitive: true
+                                    , ..SearchOptions::default() };
+        let rope = Rope::from("xA") + Rope::from("b\u{c9}") + Rope::from("a")
+                 + Rope::from("B\u{e9}");
+        assert_eq!(rope.find_all_with("ab\u{e9}", &options), vec![1..5, 5..9]);
+        assert_eq!(rope.find_with("\u{c9}A", &options), Some(3..6));
+        assert_eq!(rope.find_with("q", &options), None);
+    }
+
+    #[test]
+    fn case_insensitive_mapping_changes_length() {
+        // 'İ' lowercases to two `char`s, "i\u{307}"
+        let options = SearchOptions { case_insensitive: true
+                                    , ..SearchOptions::default() };
+        let rope = Rope::from("\u{130}x i\u{307}X");
+        assert_eq!( rope.find_all_with("i\u{307}x", &options)
+                  , vec![0..3, 4..8]);
+        // a match must not start or end inside a `char`'s mapping
+        assert_eq!(rope.find_all_with("\u{307}x", &options), vec![5..8]);
+    }
+
+    #[test]
+    fn case_insensitive_repeated_prefixes() {
+        let overlapping = SearchOptions { case_insensitive: true
+                                        , overlapping: true
+                                        , ..SearchOptions::default() };
+        let rope = Rope::from("aAaA") + Rope::from("ab") + Rope::from("AAB");
+        assert_eq!( rope.find_all_with("aab", &overlapping)
+                  , vec![3..6, 6..9]);
+        assert_eq!( rope.find_all_with("aa", &overlapping)
+                  , vec![0..2, 1..3, 2..4, 3..5, 6..8]);
+        let options = SearchOptions { overlapping: false, ..overlapping };
+        assert_eq!( rope.find_all_with("aa", &options)
+                  , vec![0..2, 2..4, 6..8]);
+        assert_eq!(rope.find_with("abaa", &options), Some(4..8));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn whole_word_across_leaves() {
+        let options = SearchOptions { whole_word: true
+                                    , ..SearchOptions::default() };
+        let rope = Rope::from("foo food fo") + Rope::from("o foo_bar\nfoo");
+        assert_eq!( rope.find_all_with("foo", &options)
+                  , vec![0..3, 9..12, 21..24]);
+        let rope = Rope::from("ba a a");
+        assert_eq!(rope.find_all_with("a a", &options), vec![3..6]);
+        let caseless = SearchOptions { case_insensitive: true, ..options };
+        assert_eq!( Rope::from("Foo FOOD").find_with("food", &caseless)
+                  , Some(4..8));
+    }
+
+    #[test]
+    fn default_options_match_find_all() {
+        let rope = Rope::from("aaa ba") + Rope::from("a");
+        let options = SearchOptions::default();
+        assert_eq!(rope.find_all_with("aa", &options), rope.find_all("aa"));
+        let overlapping = SearchOptions { overlapping: true, ..options };
+        assert_eq!( rope.find_all_with("aa", &overlapping)
+                  , rope.find_all_overlapping("aa"));
+    }
+}
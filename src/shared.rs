@@ -0,0 +1,177 @@
+//! Sharing the current `Rope` between threads.
+//!
+//! Since a `Rope` is persistent, a reader never needs to hold a lock while
+//! it reads: it can take its own snapshot of the buffer, which edits made
+//! afterwards will not affect. The usual way for an editor to share its
+//! buffer with background threads, such as a syntax highlighter or a
+//! language server client, is therefore to keep the current version in an
+//! [`ArcRope`] behind a `RwLock` (or an `ArcSwap`, from the `arc-swap`
+//! crate), and:
+//!
+//! - to read, lock only long enough to clone the `Arc`, then read from the
+//!   clone, which stays consistent however long the read takes;
+//! - to edit, compute the new `Rope` from a snapshot, and store it only if
+//!   the current version is still the snapshot it was computed from, which
+//!   [`Rope::try_commit`] does. If another writer got there first, the
+//!   caller gets the newer version back, to rebase its edit onto or to
+//!   discard it.
+//!
+//! There is no read guard type, as there is nothing which needs guarding
+//! once the snapshot is taken. An `ArcRope` is only `Send` and `Sync` when
+//! the `atomic` feature is enabled, since otherwise the nodes of the tree
+//! are reference counted with `Rc`.
+//!
+//! [`ArcRope`]: type.ArcRope.html
+//! [`Rope::try_commit`]: struct.Rope.html#method.try_commit
+use std::ops;
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(all(feature = "atomic", not(feature = "tendril")))]
+use std::thread;
+
+use super::Rope;
+
+/// A `Rope` shared behind an `Arc`.
+///
+/// Two `ArcRope`s are the same version of a buffer if they point to the same
+/// `Rope`, which can be checked with `Arc::ptr_eq` without comparing any
+/// text.
+pub type ArcRope = Arc<Rope>;
+
+impl Rope {
+    /// Replace the range `range` of the `Rope` in `this` with
+    /// `replacement`, returning a new `ArcRope`.
+    ///
+    /// This is `Rope::edit` for a `Rope` shared in an `Arc`: `this` is left
+    /// unchanged, so other holders of it keep their snapshot.
+    ///
+    /// # Panics
+    /// * If the start or end of `range` are indices outside of the `Rope`
+    /// * If the start index of `range` is greater than the end index
+    ///
+    /// # Time Complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use an_rope::{ArcRope, Rope};
+    /// let buffer: ArcRope = Arc::new(Rope::from("hello world"));
+    /// let edited = Rope::edit_arc(&buffer, 0..5, "goodbye");
+    /// assert_eq!(&*edited, "goodbye world");
+    /// assert_eq!(&*buffer, "hello world");
+    /// ```
+    #[inline]
+    pub fn edit_arc(this: &ArcRope, range: ops::Range<usize>, replacement: &str)
+                   -> ArcRope {
+        Arc::new(this.edit(range, replacement))
+    }
+
+    /// Replace the `Rope` in `current` with the result of applying `edit` to
+    /// `base`, if `current` still holds `base`.
+    ///
+    /// `base` is the snapshot of `current` that the edit was computed
+    /// against. Comparing it with the version in `current` is a pointer
+    /// comparison, and the write lock is only held while `edit` is applied,
+    /// so an edit should be cheap, such as a single call to `Rope::edit`;
+    /// expensive work should be done before calling this.
+    ///
+    /// A poisoned lock is not treated as an error, since the `ArcRope` it
+    /// holds is always a complete version of the buffer.
+    ///
+    /// # Returns
+    /// - `Ok` with the new `ArcRope`, which `current` now holds
+    /// - `Err` with the newer `ArcRope` held by `current`, if another
+    ///   writer replaced `base` first; `edit` is not called
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::{Arc, RwLock};
+    /// use an_rope::Rope;
+    /// let current = RwLock::new(Arc::new(Rope::from("abc")));
+    /// let base = current.read().unwrap().clone();
+    ///
+    /// let ours = Rope::try_commit(&current, &base, |r| r.edit(0..1, "A"));
+    /// assert_eq!(&**ours.as_ref().unwrap(), "Abc");
+    ///
+    /// // a second edit computed against the old `base` is rejected
+    /// let theirs = Rope::try_commit(&current, &base, |r| r.edit(2..3, "C"));
+    /// assert_eq!(&*theirs.unwrap_err(), "Abc");
+    /// ```
+    pub fn try_commit<F>(current: &RwLock<ArcRope>, base: &ArcRope, edit: F)
+                        -> Result<ArcRope, ArcRope>
+    where F: FnOnce(&Rope) -> Rope {
+        let mut guard = current.write()
+                               .unwrap_or_else(PoisonError::into_inner);
+        if !Arc::ptr_eq(&*guard, base) { return Err(guard.clone()) }
+        let edited = Arc::new(edit(base));
+        *guard = edited.clone();
+        Ok(edited)
+    }
+
+    /// Drop this `Rope` on a new thread, returning a handle to it.
+    ///
+    /// Dropping the last reference to a large `Rope` frees each of its
+    /// nodes, which may take long enough to stall a UI thread. This hands
+    /// the `Rope` to another thread to be freed there instead. Nodes which
+    /// are still shared with other `Rope`s are not freed, whichever thread
+    /// drops it.
+    ///
+    /// This is only available with the `atomic` feature, and without the
+    /// `tendril` feature, since otherwise a `Rope` cannot be sent to another
+    /// thread.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let big = Rope::from("x".repeat(1 << 20));
+    /// let kept = big.delete(3..big.len());
+    /// big.drop_in_background().join().unwrap();
+    /// assert_eq!(&kept, "xxx");
+    /// ```
+    #[cfg(all(feature = "atomic", not(feature = "tendril")))]
+    pub fn drop_in_background(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || drop(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::ArcRope;
+    use std::sync::{Arc, RwLock};
+
+    #[test]
+    fn try_commit_rebases_after_conflict() {
+        let current = RwLock::new(Arc::new(Rope::from("one two")));
+        let a: ArcRope = current.read().unwrap().clone();
+        let b = a.clone();
+        Rope::try_commit(&current, &a, |r| r.edit(0..3, "1")).unwrap();
+        let newer = Rope::try_commit(&current, &b, |_| unreachable!())
+                        .unwrap_err();
+        assert!(Arc::ptr_eq(&newer, &current.read().unwrap()));
+        let end = newer.len();
+        let rebased = Rope::try_commit(&current, &newer, |r|
+            r.edit(end - 3..end, "2")).unwrap();
+        assert_eq!(&*rebased, "1 2");
+        assert_eq!(&*a, "one two");
+    }
+
+    #[test]
+    #[cfg(feature = "atomic")]
+    fn concurrent_writers_each_commit_once() {
+        use std::thread;
+        let current = Arc::new(RwLock::new(Arc::new(Rope::new())));
+        let writers = (0..4).map(|_| {
+            let current = current.clone();
+            thread::spawn(move || loop {
+                let base = current.read().unwrap().clone();
+                let end = base.len();
+                let committed = Rope::try_commit(&current, &base, |r|
+                    r.edit(end..end, "x"));
+                if committed.is_ok() { return }
+            })
+        }).collect::<Vec<_>>();
+        for writer in writers { writer.join().unwrap(); }
+        assert_eq!(&**current.read().unwrap(), "xxxx");
+    }
+}
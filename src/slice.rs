@@ -21,6 +21,11 @@ use std::ops::Range;
 use super::Rope;
 use super::internals::Node;
 
+#[cfg(feature = "tree-sitter")]
+use tree_sitter::{Node as SyntaxNode, TextProvider};
+#[cfg(feature = "tree-sitter")]
+use super::syntax::ByteChunks;
+
 /// An immutable borrowed slice of a `Rope`.
 ///
 /// A `RopeSlice` represents an immutable borrowed slice of some or all the
@@ -31,9 +36,23 @@ pub struct RopeSlice<'a> { node: &'a Node
                          }
 
 impl<'a> fmt::Display for RopeSlice<'a> {
+    /// Writes the text of this `RopeSlice` to the formatter one leaf at a
+    /// time, without collecting it into an intermediate `String`.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("this is ") + Rope::from("an example");
+    /// assert_eq!(format!("{}", rope.slice(5..13)), "is an ex");
+    /// ```
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // TODO: don't create an intermediate string?
-        write!(f, "{}", self.chars().collect::<String>())
+        let mut i = 0;
+        while i < self.len {
+            let chunk = self.chunk_at(i);
+            f.write_str(chunk)?;
+            i += chunk.len();
+        }
+        Ok(())
     }
 }
 
@@ -439,7 +458,9 @@ impl<'a> RopeSlice<'a> {
         // is the last index in the Rope.
         let end = *range.end().unwrap_or(&node.len());
 
-        let slice_len = end - start;
+        let slice_len = range_len!("RopeSlice::new", start, end);
+        assert!( end <= len
+               , "RopeSlice::new: end {} was > length {}", end, len);
 
         // find the lowest node that contains both the slice start index and
         // the end index
@@ -459,7 +480,9 @@ impl<'a> RopeSlice<'a> {
     #[cfg(not(feature = "unstable"))]
     pub fn new(node: &'a Node, range: Range<usize>) -> Self {
         let len = node.len();
-        let slice_len = range.end - range.start;
+        let slice_len = range_len!("RopeSlice::new", range.start, range.end);
+        assert!( range.end <= len
+               , "RopeSlice::new: end {} was > length {}", range.end, len);
 
         // find the lowest node that contains both the slice start index and
         // the end index
@@ -597,6 +620,75 @@ impl<'a> RopeSlice<'a> {
     /// assert!(!an_rope.slice(0..5).is_empty());
     /// ```
     #[inline] pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns the contiguous chunk of text starting at byte index `i` in this
+    /// `RopeSlice`, and extending to the end of the leaf node containing `i`
+    /// or the end of the slice, whichever comes first.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `RopeSlice`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("abc") + Rope::from("def");
+    /// let slice = rope.slice(1..5);
+    /// assert_eq!(slice.chunk_at(0), "bc");
+    /// assert_eq!(slice.chunk_at(2), "de");
+    /// assert_eq!(slice.chunk_at(4), "");
+    /// ```
+    pub fn chunk_at(&self, i: usize) -> &'a str {
+        assert!( i <= self.len
+               , "RopeSlice::chunk_at: index {} was > length {}", i, self.len);
+        let (leaf, leaf_offset) = self.node.leaf_at(self.offset + i);
+        let start = self.offset + i - leaf_offset;
+        let end = cmp::min(leaf.len(), self.offset + self.len - leaf_offset);
+        &leaf[start..end]
+    }
+
+    /// Returns the text of this `RopeSlice` as a `&str`, if it lies within
+    /// a single leaf of the `Rope`.
+    ///
+    /// A short selection usually does, so callers can use this to pass it to
+    /// functions which take a `&str` without copying it, and fall back to
+    /// iterating over the slice's chunks, or collecting it into a `String`,
+    /// otherwise.
+    ///
+    /// # Returns
+    /// - `Some` with the text of this `RopeSlice`, if it is contiguous
+    /// - `None` if the text spans more than one leaf
+    ///
+    /// # Time complexity
+    /// O(log _n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let chunks = vec![Rope::from("abc"), Rope::from("def")];
+    /// let rope = chunks.into_iter().collect::<Rope>();
+    /// assert_eq!(rope.slice(3..5).as_str(), Some("de"));
+    /// assert_eq!(rope.slice(1..5).as_str(), None);
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> Option<&'a str> {
+        let chunk = self.chunk_at(0);
+        if chunk.len() == self.len { Some(chunk) } else { None }
+    }
+}
+
+#[cfg(feature = "tree-sitter")]
+impl<'a> TextProvider<&'a [u8]> for RopeSlice<'a> {
+    type I = ByteChunks<'a>;
+
+    #[inline]
+    fn text(&mut self, node: SyntaxNode) -> Self::I {
+        // tree-sitter's byte offsets are relative to the start of the slice
+        let end = cmp::min(node.end_byte(), self.len);
+        ByteChunks::new( self.node
+                       , self.offset + node.start_byte()
+                       , self.offset + end)
+    }
 }
 
 //-- comparisons ----------------------------------------------------
@@ -637,6 +729,58 @@ impl<'a> cmp::PartialEq<&'a str> for RopeSlice<'a>  {
         }
     }
 }
+impl<'a> cmp::PartialEq<String> for RopeSlice<'a> {
+    /// A rope slice equals a string if all the bytes in both are equal.
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl<'a> cmp::PartialEq<RopeSlice<'a>> for str {
+    /// A string equals a rope slice if all the bytes in both are equal.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("abcd");
+    /// assert!("abc" == &rope.slice(0..3));
+    /// ```
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        other == self
+    }
+}
+
+impl<'a, 'b> cmp::PartialEq<RopeSlice<'a>> for &'b str {
+    /// A string equals a rope slice if all the bytes in both are equal.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("abcd");
+    /// assert_eq!("abc", rope.slice(0..3));
+    /// ```
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        other == *self
+    }
+}
+
+impl<'a> cmp::PartialEq<RopeSlice<'a>> for String {
+    /// A string equals a rope slice if all the bytes in both are equal.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("abcd");
+    /// assert_eq!(String::from("abc"), rope.slice(0..3));
+    /// ```
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        other == self.as_str()
+    }
+}
 //
 // impl<'a> cmp::Eq for RopeSliceMut<'a> {}
 // impl<'a> cmp::PartialEq for RopeSliceMut<'a> {
@@ -680,16 +824,35 @@ impl<'a> cmp::PartialEq<&'a str> for RopeSlice<'a>  {
 impl<'a> convert::Into<Rope> for RopeSlice<'a> {
     /// Converts this `RopeSlice` into a new `Rope`
     fn into(self) -> Rope {
-        Rope::from(self.chars().collect::<String>())
+        Rope::from(String::from(&self))
     }
 }
 
-impl<'a> convert::Into<String> for RopeSlice<'a> {
-    /// Converts this `RopeSlice` into a new `String`
-    fn into(self) -> String {
-        self.chars().collect::<String>()
+impl<'a, 'b> convert::From<&'b RopeSlice<'a>> for String {
+    /// Collects the text of a `RopeSlice` into a new `String`, allocating
+    /// exactly enough capacity up front.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("this is ") + Rope::from("an example");
+    /// assert_eq!(String::from(&rope.slice(5..13)), "is an ex");
+    /// ```
+    fn from(slice: &'b RopeSlice<'a>) -> String {
+        let mut s = String::with_capacity(slice.len);
+        let mut i = 0;
+        while i < slice.len {
+            let chunk = slice.chunk_at(i);
+            s.push_str(chunk);
+            i += chunk.len();
+        }
+        s
     }
 }
+
+impl<'a> convert::From<RopeSlice<'a>> for String {
+    #[inline] fn from(slice: RopeSlice<'a>) -> String { String::from(&slice) }
+}
 //
 // impl<'a> convert::Into<Rope> for RopeSliceMut<'a> {
 //     /// Converts this `RopeSliceMut` into a new `Rope`
@@ -709,6 +872,7 @@ impl<'a> convert::Into<String> for RopeSlice<'a> {
 mod tests {
     use super::*;
     use ::Rope;
+    use ::test::leaves;
 
     #[test]
     fn char_indices() {
@@ -732,6 +896,48 @@ mod tests {
         assert_eq!(&rope_slice, string_slice)
     }
 
+    #[test]
+    fn slice_borrows_lowest_spanning_node() {
+        use internals::Value::Leaf;
+        let rope = (0..64).map(|i| format!("{:02}", i)).collect::<Rope>();
+        assert_eq!(rope.root.depth(), 6);
+        // within a single leaf
+        let slice = rope.slice(11..12);
+        assert_eq!(&slice, "5");
+        assert!(match slice.node.value { Leaf(_) => true, _ => false });
+        // exactly one leaf, starting at a leaf boundary
+        let slice = rope.slice(12..14);
+        assert_eq!(&slice, "06");
+        assert!(match slice.node.value { Leaf(_) => true, _ => false });
+        // spanning two sibling leaves
+        let slice = rope.slice(9..11);
+        assert_eq!(&slice, "40");
+        assert_eq!(slice.node.depth(), 1);
+        // spanning most of the rope
+        let slice = rope.slice(1..127);
+        assert_eq!(slice.len(), 126);
+        assert_eq!(slice.node.depth(), 6);
+    }
+
+    #[test]
+    fn display_non_ascii_across_leaves() {
+        let rope = Rope::from("a\u{3c0}b") + Rope::from("\u{1f192}c");
+        let slice = rope.slice(1..8);
+        assert_eq!(slice.to_string(), "\u{3c0}b\u{1f192}");
+        assert_eq!(String::from(slice), "\u{3c0}b\u{1f192}");
+    }
+
+    #[test]
+    fn as_str_within_one_leaf() {
+        let rope = leaves(&["a\u{3c0}b", "", "\u{1f192}c"]);
+        assert_eq!(rope.slice(1..4).as_str(), Some("\u{3c0}b"));
+        assert_eq!(rope.slice(4..9).as_str(), Some("\u{1f192}c"));
+        assert_eq!(rope.slice(4..4).as_str(), Some(""));
+        assert_eq!(rope.slice(9..9).as_str(), Some(""));
+        assert_eq!(rope.slice(3..5).as_str(), None);
+        assert_eq!(Rope::new().slice(0..0).as_str(), Some(""));
+    }
+
     // #[test]
     // fn between() {
     //     let string = "aaaaabbbbbbccccccccccccdefgdefgaabababab";
@@ -0,0 +1,114 @@
+//! Whole-document statistics.
+//!
+//! Status bars and "file info" dialogs typically show several counts for a
+//! buffer at once. Computing each with its own method would make a pass
+//! over the text for every count, so [`Rope::analyze`] gathers them all in
+//! a single pass, returning a [`TextStats`].
+//!
+//! [`Rope::analyze`]: ../struct.Rope.html#method.analyze
+//! [`TextStats`]: struct.TextStats.html
+use std::cmp;
+
+use super::Rope;
+use super::line_ending::is_line_break;
+
+/// Statistics describing the text of a `Rope`.
+///
+/// This is returned by `Rope::analyze`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextStats { /// The number of lines, as counted by `Rope::lines`.
+                       pub lines: usize
+                     , /// The number of `char`s.
+                       pub chars: usize
+                     , /// The number of words, separated by whitespace, as
+                       /// with `str::split_whitespace`.
+                       pub words: usize
+                     , /// The length of the longest line, in `char`s, not
+                       /// counting its line terminator.
+                       pub max_line_len: usize
+                     , /// Whether any line ends with `"\r\n"`.
+                       pub has_crlf: bool
+                     , /// Whether any `char` is not ASCII.
+                       pub has_non_ascii: bool
+                     }
+
+impl Rope {
+    /// Returns statistics describing the text of this `Rope`, computed in a
+    /// single pass.
+    ///
+    /// # Time complexity
+    /// O(_n_)
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, TextStats};
+    /// let rope = Rope::from("one two\r\n\u{3c0}\n\nthree");
+    /// assert_eq!(rope.analyze(), TextStats { lines: 4
+    ///                                      , chars: 17
+    ///                                      , words: 4
+    ///                                      , max_line_len: 7
+    ///                                      , has_crlf: true
+    ///                                      , has_non_ascii: true
+    ///                                      });
+    /// ```
+    pub fn analyze(&self) -> TextStats {
+        let mut stats = TextStats::default();
+        // the length of the current line, in `char`s, including any `'\r'`
+        // at its end
+        let mut line_len = 0;
+        let mut prev = None;
+        for s in self.strings() {
+            stats.has_non_ascii |= !s.is_ascii();
+            for c in s.chars() {
+                stats.chars += 1;
+                let in_word = prev.map_or(false, |p: char| !p.is_whitespace());
+                if !c.is_whitespace() && !in_word { stats.words += 1; }
+                if is_line_break(c) {
+                    let crlf = prev == Some('\r');
+                    stats.has_crlf |= crlf;
+                    let len = if crlf { line_len - 1 } else { line_len };
+                    stats.max_line_len = cmp::max(stats.max_line_len, len);
+                    stats.lines += 1;
+                    line_len = 0;
+                } else {
+                    line_len += 1;
+                }
+                prev = Some(c);
+            }
+        }
+        // a last line without a terminator is still a line; its `'\r'`, if
+        // it has one, is part of its text
+        if line_len > 0 {
+            stats.max_line_len = cmp::max(stats.max_line_len, line_len);
+            stats.lines += 1;
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use ::test::leaves;
+
+    #[test]
+    fn matches_separate_passes() {
+        let texts = [ leaves(&["ab c\r", "\n\u{3c0} ", " d\n", "\n"])
+                    , leaves(&["", " x\r"])
+                    , Rope::from("\n\n")
+                    , Rope::new()
+                    ];
+        for rope in texts.iter() {
+            let s = rope.to_string();
+            let stats = rope.analyze();
+            assert_eq!(stats.lines, rope.lines().count(), "{:?}", s);
+            assert_eq!(stats.chars, s.chars().count());
+            assert_eq!(stats.words, s.split_whitespace().count());
+            assert_eq!( stats.max_line_len
+                      , s.lines().map(|l| l.chars().count()).max()
+                         .unwrap_or(0));
+            assert_eq!(stats.has_crlf, s.contains("\r\n"));
+            assert_eq!(stats.has_non_ascii, !rope.is_ascii());
+        }
+    }
+}
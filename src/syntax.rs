@@ -0,0 +1,152 @@
+//! [tree-sitter] integration.
+//!
+//! When built with the `tree-sitter` feature, `Rope`s and `RopeSlice`s
+//! implement tree-sitter's [`TextProvider`] trait, so that they may be passed
+//! directly to a `QueryCursor`, and may be parsed with a tree-sitter `Parser`
+//! without first collecting their contents into a `String`.
+//!
+//! [tree-sitter]: https://tree-sitter.github.io/tree-sitter/
+//! [`TextProvider`]: https://docs.rs/tree-sitter/*/tree_sitter/trait.TextProvider.html
+
+use tree_sitter::{Node as SyntaxNode, Parser, Point, TextProvider, Tree};
+
+use std::cmp;
+
+use super::Rope;
+use super::internals::{Node, StringsFrom};
+use super::slice::RopeSlice;
+
+/// An iterator over the chunks of bytes in a range of a `Node`'s subrope.
+///
+/// This is the iterator returned by the `TextProvider` implementations for
+/// `Rope` and `RopeSlice`.
+pub struct ByteChunks<'a> { strings: StringsFrom<'a>
+                          , end: usize
+                          }
+
+impl<'a> ByteChunks<'a> {
+    /// Returns an iterator over the bytes in `node` between byte indices
+    /// `start` and `end`.
+    ///
+    /// # Time complexity
+    /// O(log _n_) to find the leaf containing `start`; the leaves before it
+    /// are never visited.
+    pub fn new(node: &'a Node, start: usize, end: usize) -> Self {
+        ByteChunks { strings: node.strings_from(start)
+                   , end: end
+                   }
+    }
+}
+
+impl<'a> Iterator for ByteChunks<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.strings.next() {
+            // yield the part of this leaf that falls before the end of the
+            // requested range
+            Some((offset, s)) if offset < self.end => {
+                let to = cmp::min(s.len(), self.end - offset);
+                Some(&s.as_bytes()[..to])
+            }
+          , _ => None
+        }
+    }
+}
+
+impl<'a> TextProvider<&'a [u8]> for &'a Rope {
+    type I = ByteChunks<'a>;
+
+    #[inline]
+    fn text(&mut self, node: SyntaxNode) -> Self::I {
+        ByteChunks::new(&self.root, node.start_byte(), node.end_byte())
+    }
+}
+
+impl Rope {
+    /// Parse this `Rope` using the given tree-sitter `Parser`.
+    ///
+    /// The `Rope` is read leaf by leaf, rather than being collected into a
+    /// single contiguous string. If `old_tree` is provided, it is used for
+    /// incremental re-parsing, as in `Parser::parse_with`.
+    ///
+    /// # Returns
+    /// - `Some` with the syntax tree if parsing succeeded
+    /// - `None` if the parser has no language set, or parsing was cancelled
+    pub fn parse_with(&self, parser: &mut Parser, old_tree: Option<&Tree>)
+                      -> Option<Tree> {
+        parser.parse_with( &mut |i: usize, _: Point| {
+                                let i = cmp::min(i, self.len());
+                                self.chunk_at(i).as_bytes()
+                            }
+                         , old_tree)
+    }
+}
+
+impl<'a> RopeSlice<'a> {
+    /// Parse this `RopeSlice` using the given tree-sitter `Parser`.
+    ///
+    /// Byte offsets in the returned tree are relative to the beginning of
+    /// this slice, rather than to the beginning of the sliced `Rope`.
+    ///
+    /// # Returns
+    /// - `Some` with the syntax tree if parsing succeeded
+    /// - `None` if the parser has no language set, or parsing was cancelled
+    pub fn parse_with(&self, parser: &mut Parser, old_tree: Option<&Tree>)
+                      -> Option<Tree> {
+        parser.parse_with(&mut |i: usize, _: Point| self.chunk_at(i)
+                                                        .as_bytes()
+                         , old_tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteChunks;
+    use internals::Node;
+
+    fn chunks(node: &Node, start: usize, end: usize) -> Vec<&str> {
+        use std::str;
+        ByteChunks::new(node, start, end)
+            .map(|b| str::from_utf8(b).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn byte_chunks_whole() {
+        let node = Node::new_branch( Node::new_leaf("abc")
+                                   , Node::new_leaf("def"));
+        assert_eq!(chunks(&node, 0, 6), vec!["abc", "def"]);
+    }
+
+    #[test]
+    fn byte_chunks_within_leaf() {
+        let node = Node::new_branch( Node::new_leaf("abc")
+                                   , Node::new_leaf("def"));
+        assert_eq!(chunks(&node, 4, 5), vec!["e"]);
+    }
+
+    #[test]
+    fn byte_chunks_spanning_leaves() {
+        let node = Node::new_branch(
+            Node::new_branch(Node::new_leaf("ab"), Node::new_leaf("cd"))
+          , Node::new_leaf("ef"));
+        assert_eq!(chunks(&node, 1, 5), vec!["b", "cd", "e"]);
+    }
+
+    #[test]
+    fn byte_chunks_from_deep_leaf() {
+        let node = Node::new_branch(
+            Node::new_branch(Node::new_leaf("ab"), Node::new_leaf("cd"))
+          , Node::new_branch(Node::new_leaf("ef"), Node::new_leaf("gh")));
+        assert_eq!(chunks(&node, 5, 8), vec!["f", "gh"]);
+        assert_eq!(chunks(&node, 6, 7), vec!["g"]);
+        assert!(chunks(&node, 8, 8).is_empty());
+    }
+
+    #[test]
+    fn byte_chunks_empty_range() {
+        let node = Node::new_leaf("abc");
+        assert!(chunks(&node, 2, 2).is_empty());
+    }
+}
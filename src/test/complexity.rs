@@ -0,0 +1,118 @@
+//! Checks of the time complexities stated in `Rope`'s documentation.
+//!
+//! Each test applies an operation to `Rope`s of increasing length `n`, and
+//! checks that the trees are O(log _n_) deep, that the nodes the operation
+//! allocates are bounded by a constant times log2(_n_), that the leaves it
+//! allocates and the text it copies into them are bounded by a constant,
+//! and that the nodes it allocates grow sublinearly with `n`.
+use ::Rope;
+use ::instrument::count_allocations;
+
+/// The lengths, in lines, of the `Rope`s the operations are applied to.
+const LINES: [usize; 4] = [1 << 4, 1 << 8, 1 << 12, 1 << 16];
+
+/// The bound on the depth of a balanced tree, per level of a perfectly
+/// balanced one: a Fibonacci-balanced tree is at most log_φ(_n_) deep.
+const DEPTH_PER_LOG: f64 = 1.45;
+
+/// The leaves any of the operations tested may allocate: the two halves of
+/// each leaf split at either end of the range edited, and the new text.
+const LEAVES: usize = 6;
+
+/// Returns a balanced `Rope` of `n` lines of 64 bytes each.
+fn rope_of_lines(n: usize) -> Rope {
+    (0..n).map(|i| format!("{:063x}\n", i)).collect()
+}
+
+/// Asserts that `rope`, of `n` lines, is at most `DEPTH_PER_LOG` times
+/// log2(_n_) deep, plus a constant.
+fn assert_shallow(rope: &Rope, n: usize) {
+    let depth = rope.root.depth();
+    assert!( depth as f64 <= DEPTH_PER_LOG * (n as f64).log2() + 2.0
+           , "a Rope of {} lines is {} deep", n, depth);
+}
+
+/// Asserts that `op` allocates at most `per_level` nodes for each of the
+/// log2(_n_) levels of a balanced tree, plus a small constant, and at most
+/// `LEAVES` leaves holding at most that many leaves' worth of text, when
+/// applied at the start, middle and end of `Rope`s of `n` lines, and that
+/// its allocations grow sublinearly with `n`.
+fn assert_logarithmic<F, T>(name: &str, per_level: usize, op: F)
+where F: Fn(&Rope, usize) -> T {
+    // allocate this thread's shared empty leaf before counting
+    drop(Rope::new());
+    let mut most_nodes = Vec::new();
+    for &n in &LINES {
+        let rope = rope_of_lines(n);
+        assert_shallow(&rope, n);
+        let log_n = (n as f64).log2();
+        let longest_leaf = rope.root.strings().map(str::len).max().unwrap();
+        let node_bound =
+            (per_level as f64 * (DEPTH_PER_LOG * log_n + 2.0)) as usize + 8;
+        let mut most = 0;
+        for &i in &[0, rope.len() / 2 + 3, rope.len() - 100] {
+            let (_, allocated) = count_allocations(|| op(&rope, i));
+            assert!( allocated.nodes <= node_bound
+                   , "{} allocated {} nodes at {} in a Rope of {} lines"
+                   , name, allocated.nodes, i, n);
+            assert!( allocated.leaves <= LEAVES
+                   , "{} allocated {} leaves at {} in a Rope of {} lines"
+                   , name, allocated.leaves, i, n);
+            assert!( allocated.leaf_bytes <= LEAVES * longest_leaf
+                   , "{} copied {} bytes at {} in a Rope of {} lines"
+                   , name, allocated.leaf_bytes, i, n);
+            most = ::std::cmp::max(most, allocated.nodes);
+        }
+        most_nodes.push(most);
+    }
+    // the longest `Rope` has 4096 times the lines of the shortest, and its
+    // tree 4 times the levels; an operation which is O(n) would allocate
+    // about 4096 times the nodes
+    let (first, last) = (most_nodes[0], most_nodes[LINES.len() - 1]);
+    assert!( last <= 2 * 4 * first + 8
+           , "{} allocated {} nodes in the shortest Rope, but {} in the \
+              longest", name, first, last);
+}
+
+#[test]
+fn insert_str_is_logarithmic() {
+    assert_logarithmic("insert_str", 1, |rope, i| rope.insert_str(i, "hi"));
+}
+
+#[test]
+fn delete_is_logarithmic() {
+    assert_logarithmic("delete", 2, |rope, i| rope.delete(i..i + 100));
+}
+
+#[test]
+fn edit_is_logarithmic() {
+    assert_logarithmic("edit", 2, |rope, i| rope.edit(i..i + 100, "x"));
+}
+
+#[test]
+fn split_is_logarithmic() {
+    assert_logarithmic("split", 1, |rope, i| rope.split(i));
+}
+
+#[test]
+fn append_is_logarithmic() {
+    let tail = Rope::from("tail");
+    assert_logarithmic("append", 1, |rope, _| rope.append(&tail));
+}
+
+#[test]
+fn clone_and_slice_allocate_nothing() {
+    assert_logarithmic("clone", 0, |rope, _| rope.clone());
+    assert_logarithmic("slice", 0, |rope, i| rope.slice(i..i + 100).len());
+}
+
+/// A tree which is O(_n_) deep fails the depth check, however few nodes an
+/// operation on it allocates per level.
+#[test]
+#[should_panic(expected = "a Rope of 256 lines is")]
+fn degenerate_trees_are_not_shallow() {
+    use ::internals::{Node, NodeLink};
+    let root = (1..256).map(|_| NodeLink::from("a\n"))
+                       .fold(NodeLink::from("a\n"), Node::new_branch);
+    assert_shallow(&Rope { root: root }, 256);
+}
@@ -1,9 +1,11 @@
 use super::Rope;
 use std::iter;
-use internals::Node;
+use internals::{Node, NodeLink, Value};
 use metric::Line;
 use metric::Measured;
 
+#[cfg(feature = "complexity")] mod complexity;
+
 #[cfg(feature = "atomic")]
 use quickcheck::{Arbitrary, Gen};
 
@@ -21,6 +23,13 @@ impl Arbitrary for Rope {
 
 }
 
+/// Returns a `Rope` with a leaf for each of `chunks`, in order.
+pub fn leaves(chunks: &[&str]) -> Rope {
+    let mut nodes = chunks.iter().map(|&chunk| NodeLink::from(chunk));
+    let first = nodes.next().unwrap_or_else(Node::empty);
+    Rope::from(nodes.fold(first, |left, right| Node::new_branch(left, right)))
+}
+
 
 #[ignore]
 fn line_delete_test_1() {
@@ -100,23 +109,935 @@ fn delete_test_5() {
 // this range syntax only works on nightly rust
 #[cfg(feature = "unstable")]
 #[test]
-#[should_panic(expected = "byte index 42 is out of bounds")]
+#[should_panic(expected = "Rope::delete: end 42 was > length 16")]
 fn delete_test_6() {
     let mut r = Rope::from("this is not fine");
     r.delete((..42));
 }
 
 #[test]
-// TODO: panic messages differ on nightly/stable, should fix this...
-#[cfg_attr(feature = "unstable", should_panic(expected = "invalid index!"))]
-#[cfg_attr( not(feature = "unstable")
-          , should_panic(expected = "attempt to subtract with overflow"))]
-#[should_panic]
+#[should_panic(expected = "Rope::delete: start 12 > end 8")]
 fn delete_test_7() {
     let mut r = Rope::from("this is not fine");
     r.delete((12..8)); // lol, fuck you
 }
 
+#[test]
+#[should_panic(expected = "Rope::delete: end 42 was > length 16")]
+fn delete_test_8() {
+    let r = Rope::from("this is not fine");
+    r.delete((8..42));
+}
+
+#[test]
+#[should_panic(expected = "RopeSlice::new: start 5 > end 2")]
+fn slice_reversed_range() {
+    let r = Rope::from("this is not fine");
+    r.slice(5..2);
+}
+
+#[test]
+#[should_panic(expected = "Rope::eq_str_slice: start 5 > end 2")]
+fn eq_str_slice_reversed_range() {
+    let r = Rope::from("this is not fine");
+    r.eq_str_slice(5..2, "");
+}
+
+#[test]
+fn metric_checked_sub() {
+    use metric::{CharIndex, Line, Metric};
+    assert_eq!(CharIndex(5).checked_sub(CharIndex(2)), Some(CharIndex(3)));
+    #[cfg(feature = "unicode")] {
+        use metric::Grapheme;
+        assert_eq!(Grapheme(5).checked_sub(Grapheme(2)), Some(Grapheme(3)));
+    }
+    assert_eq!(Line(2).checked_sub(Line(5)), None);
+    assert_eq!(Line(2).saturating_sub(Line(5)), Line(0));
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn delete_char_combining_across_leaves() {
+    let r = Rope::from("noe") + Rope::from("\u{308}l");
+    let (r2, deleted) = r.delete_char(2);
+    assert_eq!(&r2, "nol");
+    assert_eq!(deleted, "e\u{308}");
+    assert_eq!(&r, "noe\u{308}l");
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn backspace_crlf_across_leaves() {
+    let r = Rope::from("line a\r") + Rope::from("\nline b");
+    let (r2, deleted) = r.backspace(8);
+    assert_eq!(&r2, "line aline b");
+    assert_eq!(deleted, "\r\n");
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn delete_char_at_end() {
+    let r = Rope::from("abc");
+    let (r2, deleted) = r.delete_char(3);
+    assert_eq!(&r2, "abc");
+    assert_eq!(deleted, "");
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn backspace_at_start() {
+    let r = Rope::from("abc");
+    let (r2, deleted) = r.backspace(0);
+    assert_eq!(&r2, "abc");
+    assert_eq!(deleted, "");
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn backspace_single_byte_leaves() {
+    let r = Rope::from("a") + Rope::from("b") + Rope::from("c");
+    let (r2, deleted) = r.backspace(1);
+    assert_eq!(&r2, "bc");
+    assert_eq!(deleted, "a");
+    let (r3, deleted) = r.delete_char(2);
+    assert_eq!(&r3, "ab");
+    assert_eq!(deleted, "c");
+}
+
+#[test]
+fn eq_str_slice_spanning_leaves() {
+    let r = Rope::from("ab") + Rope::from("cd") + Rope::from("ef");
+    assert!(r.eq_str_slice(1..5, "bcde"));
+    assert!(r.eq_str_slice(0..6, "abcdef"));
+    assert!(r.eq_str_slice(3..3, ""));
+    assert!(!r.eq_str_slice(1..5, "bcdf"));
+    assert!(!r.eq_str_slice(1..5, "bcd"));
+    assert!(!r.eq_str_slice(4..8, "efgh"));
+}
+
+#[test]
+fn drop_deep_tree() {
+    // dropping this recursively would overflow the test thread's stack;
+    // it is built by hand, since `Rope::from` would rebalance it
+    let root = (0..200_000).map(|_| NodeLink::from("a"))
+                           .fold(Node::empty(), Node::new_branch);
+    drop(Rope { root: root });
+}
+
+#[test]
+fn from_iter_strings_balanced() {
+    let r = (0..1024).map(|i| format!("{:x}", i % 16)).collect::<Rope>();
+    assert_eq!(r.len(), 1024);
+    assert_eq!(r.root.depth(), 10);
+}
+
+#[test]
+fn from_iter_strs_balanced() {
+    let strs = vec!["a", "", "b", "c", "", "d", "e"];
+    let r = strs.into_iter().collect::<Rope>();
+    assert_eq!(&r, "abcde");
+    assert_eq!(r.root.depth(), 3);
+}
+
+#[test]
+fn from_iter_ropes_balanced() {
+    let r = (0..1000).map(|_| Rope::from("ab")).collect::<Rope>();
+    assert_eq!(r.len(), 2000);
+    assert!(r.root.depth() <= 10);
+    assert!(r.strings().all(|s| s == "ab"));
+}
+
+#[test]
+fn from_string_of_many_lines_balanced() {
+    // a leaf per line is far too many to fold into a chain and rebalance
+    let r = Rope::from("a\n".repeat(200_000));
+    assert_eq!(r.len(), 400_000);
+    assert!(r.root.depth() <= 18);
+    assert!(r.check_invariants().is_ok());
+}
+
+#[test]
+fn from_iter_empty() {
+    let r = Vec::<String>::new().into_iter().collect::<Rope>();
+    assert!(r.is_empty());
+}
+
+#[test]
+fn extend_chars_batched() {
+    let mut r = Rope::from("abc");
+    r.extend((0..1_000_000).map(|_| 'x'));
+    assert_eq!(r.len(), 1_000_003);
+    assert_eq!(r.strings().count(), 2);
+    r.extend(vec!['y', 'z'].iter());
+    assert_eq!(r.strings().count(), 3);
+    r.extend("".chars());
+    assert_eq!(r.strings().count(), 3);
+}
+
+#[test]
+fn split_char_index_across_leaves() {
+    use metric::CharIndex;
+    let r = Rope::from("\u{3c0}\u{3c0}") + Rope::from("\u{3c4}\u{3c4}");
+    let (left, right) = r.split(CharIndex(3));
+    assert_eq!(&left, "\u{3c0}\u{3c0}\u{3c4}");
+    assert_eq!(&right, "\u{3c4}");
+    assert_eq!(&r.delete(CharIndex(1)..CharIndex(3)), "\u{3c0}\u{3c4}");
+}
+
+#[test]
+fn convert_index_round_trip() {
+    use metric::{ByteIndex, CharIndex};
+    let r = Rope::from("a\u{3c0}b\n") + Rope::from("\u{1f192}c");
+    let s = r.to_string();
+    for (n, (i, _)) in s.char_indices().enumerate() {
+        let ch: CharIndex = r.convert_index(ByteIndex(i)).unwrap();
+        assert_eq!(ch, CharIndex(n));
+        let byte: ByteIndex = r.convert_index(ch).unwrap();
+        assert_eq!(byte, ByteIndex(i));
+    }
+    let end: Option<CharIndex> = r.convert_index(ByteIndex(r.len()));
+    assert_eq!(end, Some(CharIndex(6)));
+}
+
+#[test]
+fn convert_index_to_line_within_leaf() {
+    use metric::ByteIndex;
+    let r = Rope { root: Node::new_leaf("a\nbc\n\nd") };
+    let s = r.to_string();
+    for i in 0..s.len() + 1 {
+        let line: Line = r.convert_index(ByteIndex(i)).unwrap();
+        assert_eq!(line, Line(s[..i].matches('\n').count()));
+    }
+}
+
+#[test]
+fn char_index_boundaries() {
+    use metric::{CharIndex, Metric};
+    let r = leaves(&["a\u{3c0}b\n", "\u{1f192}c"]);
+    let s = r.to_string();
+    for i in 0..s.len() + 2 {
+        assert_eq!(CharIndex::is_boundary(&r, i), s.is_char_boundary(i));
+    }
+}
+
+#[test]
+fn cheap_clone() {
+    let r = Rope::from("this is ") + Rope::from("a rope");
+    let c = r.clone();
+    assert!(r.root.ptr_eq(&c.root));
+    // edits to the clone share the untouched nodes of the original
+    let c = c.append(&Rope::from("!"));
+    assert_eq!(&r, "this is a rope");
+    assert_eq!(&c, "this is a rope!");
+}
+
+#[cfg(feature = "atomic")]
+#[test]
+fn atomic_rope_is_send_sync() {
+    use std::thread;
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Rope>();
+    let r = Rope::from("this is ") + Rope::from("a rope");
+    let c = r.clone();
+    let len = thread::spawn(move || c.len()).join().unwrap();
+    assert_eq!(len, r.len());
+}
+
+#[test]
+fn leaf_ranges_after_edits() {
+    let r = Rope::from("this is not fine");
+    let r = r.delete(8..12).insert_str(8, "very ");
+    assert_eq!(&r, "this is very fine");
+    let leaves = r.leaf_ranges().collect::<Vec<_>>();
+    assert_eq!(leaves, vec![ (0..8, "this is "), (8..13, "very ")
+                           , (13..17, "fine")]);
+}
+
+#[test]
+fn lines_inclusive_round_trip() {
+    let strings = [ "", "\n", "a", "a\n", "\n\na", "a\nb\n"
+                  , "\u{3c0}\r\n\u{3c4}\n\u{1f192}"];
+    for s in strings.iter() {
+        let r = Rope::from("") + Rope::from(*s) + Rope::from("");
+        let lines = r.lines_inclusive()
+                     .map(|l| l.to_string())
+                     .collect::<Vec<_>>();
+        let expected = s.split_terminator('\n')
+                        .map(|l| l.to_owned())
+                        .collect::<Vec<_>>();
+        assert_eq!(lines.len(), expected.len(), "{:?}", s);
+        assert_eq!(lines.concat(), *s);
+    }
+}
+
+#[test]
+fn lines_inclusive_across_leaves() {
+    let r = Rope::from("ab") + Rope::from("c\nd") + Rope::from("e\n");
+    let lines = r.lines_inclusive().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert_eq!(lines, vec!["abc\n", "de\n"]);
+}
+
+#[test]
+fn ends_with_newline_across_leaves() {
+    let r = Rope::from("ab\n") + Rope::from("") + Rope::from("");
+    assert!(r.ends_with_newline());
+    let r = Rope::from("ab\n") + Rope::from("\u{3c0}");
+    assert!(!r.ends_with_newline());
+    let r = r.ensure_trailing_newline();
+    assert_eq!(&r, "ab\n\u{3c0}\n");
+    assert!(r.ends_with_newline());
+}
+
+#[test]
+fn rfind_str_spanning_leaves() {
+    let r = Rope::from("xa") + Rope::from("b") + Rope::from("")
+          + Rope::from("c\u{3c0}") + Rope::from("yabc");
+    assert_eq!(r.rfind_str(r.len(), "abc\u{3c0}"), Some(1));
+    assert_eq!(r.rfind_str(r.len(), "abc"), Some(7));
+    assert_eq!(r.rfind_str(9, "abc"), Some(1));
+    assert_eq!(r.rfind_str(4, "abc"), Some(1));
+    assert_eq!(r.rfind_str(3, "abc"), None);
+    assert_eq!(r.rfind_str(r.len(), ""), Some(r.len()));
+}
+
+#[test]
+fn find_all_spanning_leaves() {
+    let r = Rope::from("xa") + Rope::from("b") + Rope::from("")
+          + Rope::from("a\u{3c0}\u{3c0}") + Rope::from("\u{3c0}bab");
+    let s = r.to_string();
+    for pat in ["ab", "aba", "\u{3c0}\u{3c0}", "b", "bab", "q"].iter() {
+        let expected = s.match_indices(pat).map(|(i, _)| i..i + pat.len())
+                        .collect::<Vec<_>>();
+        assert_eq!(r.find_all(pat), expected);
+        let overlapping = s.char_indices()
+                           .filter(|&(i, _)| s[i..].starts_with(pat))
+                           .map(|(i, _)| i..i + pat.len())
+                           .collect::<Vec<_>>();
+        assert_eq!(r.find_all_overlapping(pat), overlapping);
+    }
+    assert_eq!(r.find_all_overlapping("\u{3c0}\u{3c0}").len(), 2);
+
+    // patterns longer than several leaves, starting part way into a leaf
+    let r = leaves(&["aab", "a", "b", "\u{3c0}", "ab", "aaba", "b\u{3c0}ab"]);
+    let s = r.to_string();
+    for pat in ["ab\u{3c0}a", "aabab", "ba", "aba", "b\u{3c0}ab"].iter() {
+        let expected = s.match_indices(pat).map(|(i, _)| i..i + pat.len())
+                        .collect::<Vec<_>>();
+        assert_eq!(r.find_all(pat), expected, "{:?}", pat);
+        let overlapping = s.char_indices()
+                           .filter(|&(i, _)| s[i..].starts_with(pat))
+                           .map(|(i, _)| i..i + pat.len())
+                           .collect::<Vec<_>>();
+        assert_eq!(r.find_all_overlapping(pat), overlapping, "{:?}", pat);
+    }
+}
+
+#[test]
+fn rfind_char_non_ascii() {
+    let r = Rope::from("\u{3c0}a") + Rope::from("\u{3c0}b");
+    assert_eq!(r.rfind_char(r.len(), '\u{3c0}'), Some(3));
+    assert_eq!(r.rfind_char(3, '\u{3c0}'), Some(0));
+    assert_eq!(r.rfind_char(r.len(), 'z'), None);
+}
+
+#[test]
+fn rfind_from_every_end() {
+    let r = leaves(&["ab\u{3c0}", "a", "b", "xab", "\u{3c0}ab", "ba"]);
+    let s = r.to_string();
+    for end in (0..s.len() + 1).filter(|&i| s.is_char_boundary(i)) {
+        assert_eq!(r.rfind_char(end, 'a'), s[..end].rfind('a'));
+        assert_eq!(r.rfind_char(end, '\u{3c0}'), s[..end].rfind('\u{3c0}'));
+        for pat in &["ab", "ba", "\u{3c0}a", "bxa", "abab"] {
+            assert_eq!(r.rfind_str(end, pat), s[..end].rfind(pat));
+        }
+    }
+}
+
+#[test]
+fn find_matching_across_leaves() {
+    let r = Rope::from("{\u{3c0}{") + Rope::from("}") + Rope::from("")
+          + Rope::from("{}}{");
+    assert_eq!(r.find_matching(0, '{', '}'), Some(7));
+    assert_eq!(r.find_matching(7, '{', '}'), Some(0));
+    assert_eq!(r.find_matching(3, '{', '}'), Some(4));
+    assert_eq!(r.find_matching(4, '{', '}'), Some(3));
+    assert_eq!(r.find_matching(8, '{', '}'), None);
+    assert_eq!(r.find_matching(1, '{', '}'), None);
+    assert_eq!(r.find_matching(r.len(), '{', '}'), None);
+    // each scan starts part way through a leaf, several leaves in
+    let r = leaves(&["(a", "(", "b)(", "\u{3c0})", ")"]);
+    for &(open, close) in &[(0, 9), (2, 4), (5, 8)] {
+        assert_eq!(r.find_matching(open, '(', ')'), Some(close));
+        assert_eq!(r.find_matching(close, '(', ')'), Some(open));
+    }
+}
+
+#[test]
+fn find_matching_same_delimiter() {
+    let r = Rope::from("a\"b") + Rope::from("c\"d");
+    assert_eq!(r.find_matching(1, '"', '"'), Some(4));
+}
+
+#[test]
+fn join_balanced() {
+    let rope = Rope::join(Rope::from("\n"), (0..1024).map(|i| i.to_string()));
+    let expected = (0..1024).map(|i| i.to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n");
+    assert_eq!(rope, expected);
+    assert!(rope.root.depth() <= 12);
+}
+
+#[test]
+fn expand_tabs_shares_unchanged_leaves() {
+    let r = Rope::from("ab") + Rope::from("\u{3c0}\t|") + Rope::from("xyz");
+    let expanded = r.expand_tabs(4);
+    assert_eq!(&expanded, "ab\u{3c0} |xyz");
+    let leaves = |r: &Rope| match r.root.value {
+        Value::Branch { ref left, ref right } =>
+            (left.clone(), right.clone())
+      , _ => unreachable!()
+    };
+    assert!(leaves(&r).1.ptr_eq(&leaves(&expanded).1));
+}
+
+#[test]
+fn split_lines_into_shares_subtrees() {
+    let middle = Rope::from("bb") + Rope::from("\u{3c0}") + Rope::from("cc");
+    let r = Rope::from("one\na") + middle.clone() + Rope::from("d\n\ne");
+    let mut lines = vec![Rope::from("kept")];
+    r.split_lines_into(&mut lines);
+    assert_eq!(lines, vec!["kept", "one\n", "abb\u{3c0}ccd\n", "\n", "e"]);
+    fn contains(node: &Node, target: &Node) -> bool {
+        ::std::ptr::eq(node, target) || match node.value {
+            Value::Branch { ref left, ref right } =>
+                contains(left, target) || contains(right, target)
+          , _ => false
+        }
+    }
+    assert!(contains(&lines[2].root, &middle.root));
+
+    let mut records = Vec::new();
+    r.split_records_into('\u{3c0}', &mut records);
+    assert_eq!(records, vec!["one\nabb\u{3c0}", "ccd\n\ne"]);
+    let mut none = Vec::new();
+    Rope::new().split_lines_into(&mut none);
+    assert!(none.is_empty());
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn visual_cols_round_trip() {
+    let r = Rope::from("a\tb\u{3c0}") + Rope::from("e\u{301}\t\t")
+          + Rope::from("z\n\n") + Rope::from("\tq");
+    for &tabstop in [1, 3, 8].iter() {
+        for line in 0..3 {
+            let mut prev = None;
+            for col in 0..30 {
+                let i = r.offset_at_visual_col(line, col, tabstop).unwrap();
+                let found = r.visual_col_at_offset(i, tabstop);
+                assert!(found <= col);
+                if prev != Some(i) { assert_eq!(found, col); }
+                prev = Some(i);
+            }
+        }
+        assert_eq!(r.offset_at_visual_col(3, 0, tabstop), None);
+    }
+    assert_eq!(r.offset_at_visual_col(0, 9, 4), Some(9));
+    assert_eq!(r.offset_at_visual_col(0, 7, 4), Some(8));
+    assert_eq!(r.offset_at_visual_col(1, 3, 4), Some(12));
+    assert_eq!(r.offset_at_visual_col(2, 3, 4), Some(13));
+}
+
+#[test]
+fn subrope_shares_covered_subtrees() {
+    let middle = Rope::from("cd") + Rope::from("\u{3c0}f");
+    let r = Rope::from("ab") + middle.clone() + Rope::from("gh");
+    let s = r.to_string();
+    for start in 0..r.len() + 1 {
+        for end in start..r.len() + 1 {
+            if !s.is_char_boundary(start) || !s.is_char_boundary(end) {
+                continue
+            }
+            let sub = r.subrope(start..end);
+            assert_eq!(&sub, &s[start..end]);
+            sub.check_invariants().unwrap();
+        }
+    }
+    let sub = r.subrope(1..8);
+    assert_eq!(&sub, "bcd\u{3c0}fg");
+    let shared = match sub.root.value {
+        Value::Branch { ref left, .. } => match left.value {
+            Value::Branch { ref right, .. } => right.ptr_eq(&middle.root)
+          , _ => false
+        }
+      , _ => false
+    };
+    assert!(shared);
+}
+
+#[test]
+fn iter_range_matches_full_iterators() {
+    use metric::{ByteIndex, CharIndex};
+    let r = Rope::from("a\u{3c0}\n") + Rope::from("e\u{301}b\n")
+          + Rope::from("\ncd");
+    let s = r.to_string();
+    let lines = r.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    let chars = s.chars().collect::<Vec<_>>();
+    #[cfg(feature = "unicode")]
+    let graphemes = r.graphemes().collect::<Vec<_>>();
+    for a in 0..s.len() + 2 {
+        for b in a..s.len() + 2 {
+            let got = r.iter_range(Line(a)..Line(b)).map(|l| l.to_string())
+                       .collect::<Vec<_>>();
+            assert_eq!(got, lines.iter().skip(a).take(b - a).cloned()
+                                 .collect::<Vec<_>>());
+            let got = r.iter_range(CharIndex(a)..CharIndex(b))
+                       .collect::<Vec<_>>();
+            assert_eq!(got, chars.iter().skip(a).take(b - a).cloned()
+                                 .collect::<Vec<_>>());
+            #[cfg(feature = "unicode")] {
+                use metric::Grapheme;
+                let got = r.iter_range(Grapheme(a)..Grapheme(b))
+                           .collect::<Vec<_>>();
+                assert_eq!(got, graphemes.iter().skip(a).take(b - a).cloned()
+                                         .collect::<Vec<_>>());
+            }
+            let bytes = s.bytes().skip(a).take(b - a).collect::<Vec<_>>();
+            assert_eq!(r.iter_range(a..b).collect::<Vec<_>>(), bytes);
+            assert_eq!( r.iter_range(ByteIndex(a)..ByteIndex(b))
+                         .collect::<Vec<_>>()
+                      , bytes);
+        }
+    }
+}
+
+#[test]
+fn slice_open_ended_ranges() {
+    let r = Rope::from("ab") + Rope::from("cd") + Rope::from("ef");
+    assert_eq!(&r.slice(..), "abcdef");
+    assert_eq!(&r.slice(3..), "def");
+    assert_eq!(&r.slice(..3), "abc");
+    assert_eq!(&r.slice(1..=3), "bcd");
+    assert_eq!(&r.slice(..=5), "abcdef");
+    assert_eq!(&r.slice(6..), "");
+    assert_eq!(&Rope::new().slice(..), "");
+}
+
+#[test]
+#[should_panic(expected = "end 7 was > length 6")]
+fn slice_inclusive_past_end() {
+    let r = Rope::from("abc") + Rope::from("def");
+    r.slice(..=6);
+}
+
+#[test]
+fn measure_many_matches_convert_index() {
+    use metric::{ByteIndex, CharIndex};
+    let r = Rope::from("a\u{3c0}\n") + (Rope::from("") + Rope::from("bc"))
+          + (Rope::from("\u{3c4}\n") + Rope::from("\nd"));
+    let s = r.to_string();
+    let offsets = (0..s.len() + 1).rev().filter(|&i| s.is_char_boundary(i))
+                                  .collect::<Vec<_>>();
+    let chars: Vec<CharIndex> = r.measure_many(&offsets);
+    let bytes: Vec<ByteIndex> = r.measure_many(&offsets);
+    for (k, &i) in offsets.iter().enumerate() {
+        assert_eq!(Some(chars[k]), r.convert_index(ByteIndex(i)));
+        assert_eq!(bytes[k], ByteIndex(i));
+    }
+    let empty: Vec<CharIndex> = r.measure_many(&[]);
+    assert!(empty.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "offset 4 was > length 3")]
+fn measure_many_past_end() {
+    let r = Rope::from("abc");
+    let _: Vec<usize> = r.measure_many(&[1, 4, 0]);
+}
+
+#[test]
+fn replace_all_shared_across_leaves() {
+    let r = leaves(&["one ", "a", "bc two ab", "c", "x", "abc"]);
+    let (replaced, ranges) = r.replace_all_shared("abc", "XY");
+    assert_eq!(&replaced, "one XY two XYxXY");
+    assert_eq!(ranges, vec![4..6, 11..13, 14..16]);
+    for range in ranges { assert_eq!(&replaced.slice(range), "XY"); }
+    // the leaf between the last two matches is shared
+    let x = r.root.leaf_at(15).0;
+    assert_eq!(x, "x");
+    assert!(replaced.root.leaf_at(13).0.as_ptr() == x.as_ptr());
+
+    let (same, ranges) = r.replace_all_shared("q", "z");
+    assert!(same.root.ptr_eq(&r.root));
+    assert!(ranges.is_empty());
+}
+
+#[test]
+fn from_static_borrows_until_edited() {
+    static TEXT: &str = "one\ntwo\nthree";
+    let rope = Rope::from_static(TEXT);
+    assert_eq!(&rope, TEXT);
+    assert_eq!(rope.lines().count(), 3);
+    let edited = rope.insert_str(5, "w");
+    assert_eq!(&edited, "one\ntwwo\nthree");
+    assert_eq!(&rope, TEXT);
+    assert!(Rope::from_static("").is_empty());
+    // these leaf types copy static text rather than borrowing it
+    #[cfg(not(any( feature = "tendril", feature = "smallstr"
+                 , feature = "line-leaves")))]
+    {
+        assert!(rope.root.leaf_at(0).0.as_ptr() == TEXT.as_ptr());
+        // the leaves the edit didn't touch still borrow `TEXT`
+        assert!(edited.root.leaf_at(0).0.as_ptr() == TEXT.as_ptr());
+        assert!(edited.root.leaf_at(11).0.as_ptr() == TEXT[8..].as_ptr());
+        assert!(edited.root.leaf_at(5).0.as_ptr() != TEXT[4..].as_ptr());
+    }
+}
+
+#[test]
+fn byte_at_line_col_across_leaves() {
+    let r = leaves(&["a\u{3c0}", "b\r", "\n\r", "x\n", "", "\n"]);
+    let lines = [vec![0, 1, 3, 4], vec![6, 7, 8], vec![9], vec![10]];
+    for (line, offsets) in lines.iter().enumerate() {
+        for (col, &i) in offsets.iter().enumerate() {
+            assert_eq!(r.byte_at_line_col(line, col), Some(i));
+            assert_eq!(r.byte_at_line_col_clamped(line, col), Some(i));
+        }
+        let end = offsets[offsets.len() - 1];
+        assert_eq!(r.byte_at_line_col(line, offsets.len()), None);
+        assert_eq!(r.byte_at_line_col_clamped(line, offsets.len()), Some(end));
+    }
+    assert_eq!(r.byte_at_line_col_clamped(4, 0), None);
+    assert_eq!(Rope::new().byte_at_line_col(0, 0), Some(0));
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn truncate_graphemes_across_leaves() {
+    let r = leaves(&["ae\u{301}", "\u{302}\r", "\nb"]);
+    let prefixes = ["", "a", "ae\u{301}\u{302}", "ae\u{301}\u{302}\r\n"];
+    for (n, prefix) in prefixes.iter().enumerate() {
+        assert_eq!(&r.truncate_graphemes(n), prefix);
+    }
+    assert_eq!(&r.truncate_graphemes(4), "ae\u{301}\u{302}\r\nb");
+    assert_eq!(&r.preview(3, Some("...")), "...");
+    assert_eq!(&r.preview(3, Some("..")), "a..");
+    assert_eq!(&r.preview(2, Some("...")), "...");
+    assert_eq!(&r.preview(4, Some("...")), &r);
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn reversed_keeps_graphemes_across_leaves() {
+    let r = leaves(&["ae\u{301}", "\u{302}\r", "\nb"]);
+    assert_eq!(&r.reversed(), "b\r\ne\u{301}\u{302}a");
+    assert_eq!(r.reversed().reversed(), r);
+    assert!(r.reversed().is_balanced());
+    assert_eq!(&Rope::new().reversed(), "");
+}
+
+#[test]
+fn strip_trailing_whitespace_across_leaves() {
+    let r = leaves(&["a \t", " \r", "\nkeep\n", "b\r\r\n  ", "\n \r"]);
+    let (stripped, removed) = r.strip_trailing_whitespace();
+    assert_eq!(&stripped, "a\r\nkeep\nb\r\r\n\n \r");
+    assert_eq!(removed, vec![1..4, 15..17]);
+    // the leaf whose line had no trailing whitespace is shared
+    let keep = r.root.leaf_at(6).0;
+    assert!(stripped.root.leaf_at(3).0.as_ptr() == keep.as_ptr());
+
+    let (same, removed) = Rope::from("a\n b").strip_trailing_whitespace();
+    assert_eq!(&same, "a\n b");
+    assert!(removed.is_empty());
+}
+
+#[test]
+fn collapse_whitespace_across_leaves() {
+    let r = Rope::from("a ") + Rope::from(" \n") + Rope::from("b c");
+    assert_eq!(&r.collapse_whitespace(), "a b c");
+    let r = Rope::from("a b") + Rope::from(" c");
+    assert_eq!(&r.collapse_whitespace(), "a b c");
+}
+
+#[test]
+fn char_indices_at_line_non_ascii() {
+    let r = Rope::from("\u{3c0}\n\u{3c4}") + Rope::from("x\n") + Rope::from("");
+    assert_eq!( r.char_indices_at_line(0).collect::<Vec<_>>()
+              , vec![ (0, 0, '\u{3c0}'), (0, 1, '\n'), (1, 0, '\u{3c4}')
+                    , (1, 1, 'x'), (1, 2, '\n')]);
+    assert_eq!( r.char_indices_at_line(1).collect::<Vec<_>>()
+              , vec![(1, 0, '\u{3c4}'), (1, 1, 'x'), (1, 2, '\n')]);
+    assert_eq!(r.char_indices_at_line(2).next(), None);
+    assert_eq!(Rope::new().char_indices_at_line(0).next(), None);
+}
+
+#[test]
+fn char_indices_at_line_seeks_across_leaves() {
+    use internals::{Node, NodeLink};
+    let text = "ab\nc\n\nd\u{3c0}\ne";
+    let mut expected = Vec::new();
+    let (mut line, mut col) = (0, 0);
+    for c in text.chars() {
+        expected.push((line, col, c));
+        if c == '\n' { line += 1; col = 0; } else { col += 1; }
+    }
+    // a leaf may hold several lines, if it was built by hand or merged
+    let merged = Rope { root: Node::new_branch( NodeLink::from("ab")
+                                              , Node::new_leaf(&text[2..])) };
+    let ropes = [ Rope::from(text)
+                , leaves(&["ab\nc", "\n", "\nd\u{3c0}", "\ne"])
+                , merged ];
+    for r in &ropes {
+        for line in 0..7 {
+            let from_line = expected.iter().cloned()
+                                    .filter(|&(l, _, _)| l >= line)
+                                    .collect::<Vec<_>>();
+            assert_eq!( r.char_indices_at_line(line).collect::<Vec<_>>()
+                      , from_line);
+        }
+    }
+}
+
+#[test]
+fn is_ascii_after_edits() {
+    let r = Rope::from("abc") + Rope::from("def");
+    assert!(r.is_ascii());
+    let r2 = r.insert_str(3, "\u{1f192}");
+    assert!(!r2.is_ascii());
+    assert!(r2.delete(3..7).is_ascii());
+    assert!(r.is_ascii());
+}
+
+#[test]
+#[should_panic(expected = "is not a char boundary")]
+fn split_inside_char_across_leaves() {
+    let r = Rope::from("ab") + Rope::from("\u{3c0}c");
+    r.split(3);
+}
+
+#[test]
+#[should_panic(expected = "is not a char boundary")]
+fn insert_inside_char() {
+    let r = Rope::from("a\u{1f192}b");
+    r.insert_str(2, "x");
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn slice_graphemes_across_leaves() {
+    let r = Rope::from("e") + Rope::from("\u{301}x\r") + Rope::from("\ny");
+    assert!(!r.is_grapheme_boundary(1));
+    assert!(!r.is_grapheme_boundary(5));
+    assert_eq!(&r.slice_graphemes(0..1), "e\u{301}");
+    assert_eq!(&r.slice_graphemes(2..3), "\r\n");
+    assert_eq!(&r.slice_graphemes(1..4), "x\r\ny");
+    assert_eq!(&r.slice_graphemes(4..4), "");
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+#[should_panic(expected = "end 5 was > grapheme length 4")]
+fn slice_graphemes_past_end() {
+    let r = Rope::from("e\u{301}x\r\ny");
+    r.slice_graphemes(1..5);
+}
+
+#[test]
+fn split_terminator_is_str_split_terminator() {
+    let cases = [ ("", "\n"), ("\n", "\n"), ("a\n\nb", "\n"), ("a\nb\n", "\n")
+                , ("aaaa", "aa"), ("aaa", "aa"), ("x\u{3c0}y\u{3c0}", "\u{3c0}")
+                , ("a--b---c", "--")];
+    for &(s, pat) in cases.iter() {
+        // split the text into single-character leaves, so that every
+        // separator longer than one character spans leaves
+        let rope = s.chars().map(|c| Rope::from(c.to_string()))
+                    .fold(Rope::new(), |rope, leaf| rope + leaf);
+        let pieces = rope.split_terminator(pat)
+                         .map(|piece| piece.to_string())
+                         .collect::<Vec<_>>();
+        assert_eq!(pieces, s.split_terminator(pat).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn split_inclusive_is_str_split_inclusive() {
+    let cases = [ ("", "\n"), ("\n", "\n"), ("a\n\nb", "\n"), ("a\nb\n", "\n")
+                , ("aaa", "aa"), ("x\u{3c0}y\u{3c0}", "\u{3c0}")
+                , ("a--b---c", "--")];
+    for &(s, pat) in cases.iter() {
+        let chars = s.char_indices().map(|(i, c)| &s[i..i + c.len_utf8()])
+                     .collect::<Vec<_>>();
+        let rope = leaves(&chars);
+        let pieces = rope.split_inclusive(pat)
+                         .map(|piece| piece.to_string())
+                         .collect::<Vec<_>>();
+        assert_eq!(pieces, s.split_inclusive(pat).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn edit_is_delete_then_insert() {
+    let r = Rope::from("abc") + Rope::from("\u{3c0}def") + Rope::from("ghi");
+    for &(start, end) in [(0, 0), (0, 3), (2, 7), (5, 8), (11, 11)].iter() {
+        let edited = r.edit(start..end, "\u{1f192}x");
+        let expected = r.delete(start..end).insert_str(start, "\u{1f192}x");
+        assert_eq!(edited, expected);
+        assert!(edited.check_invariants().is_ok());
+    }
+}
+
+#[test]
+#[should_panic(expected = "Rope::edit: end 12 was > length 11")]
+fn edit_past_end() {
+    Rope::from("hello world").edit(6..12, "there");
+}
+
+#[test]
+fn splice_iter_long_replacement() {
+    let r = Rope::from("<>");
+    let spliced = r.splice_iter(1..1, (0..1000).map(|i| i.to_string()));
+    let expected = (0..1000).map(|i| i.to_string()).collect::<String>();
+    assert_eq!(spliced, format!("<{}>", expected));
+    assert!(spliced.check_invariants().is_ok());
+    assert_eq!(&r.splice_iter(0..2, "".chars()), "");
+}
+
+#[test]
+fn weak_snapshot_outlived_by_edit() {
+    let rope = Rope::from("abc") + Rope::from("def");
+    let weak = rope.downgrade();
+    let edited = rope.insert_str(3, "xyz");
+    drop(rope);
+    // the edit shares the old leaves, but not the old root
+    assert!(weak.upgrade().is_none());
+    assert!(!weak.is_snapshot_of(&edited));
+    assert_eq!(&edited, "abcxyzdef");
+}
+
+#[test]
+fn slice_clamped_in_bounds_is_slice() {
+    let rope = Rope::from("abc") + Rope::from("def");
+    for start in 0..7 {
+        for end in start..7 {
+            assert_eq!(rope.slice_clamped(start..end), rope.slice(start..end));
+        }
+    }
+    assert_eq!(&rope.slice_clamped(2..usize::max_value()), "cdef");
+    assert_eq!(&Rope::new().slice_clamped(1..2), "");
+}
+
+#[test]
+fn from_utf8_lossy_matches_string() {
+    let inputs: &[&[u8]] = &[ b"", b"plain\nascii", b"\xFF", b"a\xC3"
+                            , b"\xE2\x82 \xE2\x82\xAC \xF0\x9F\x92"
+                            , b"\xED\xA0\x80 surrogate", b"\xC0\x80\x80" ];
+    for bytes in inputs {
+        assert_eq!( &Rope::from_utf8_lossy(bytes)
+                  , &*String::from_utf8_lossy(bytes));
+    }
+    // replacements which fall across chunk boundaries
+    let mut long = vec![b'x'; ::DEFAULT_CHUNK_SIZE - 1];
+    long.extend_from_slice(b"\xE2\x82\xAC\xFF");
+    long.extend(vec![b'y'; ::DEFAULT_CHUNK_SIZE]);
+    assert_eq!( Rope::from_utf8_lossy(&long).to_string()
+              , String::from_utf8_lossy(&long));
+}
+
+#[test]
+fn structural_eq_distinguishes_leaves() {
+    let rope = leaves(&["ab", "c", "d"]);
+    assert!(rope.structural_eq(&leaves(&["ab", "c", "d"])));
+    assert!(!rope.structural_eq(&leaves(&["ab", "cd"])));
+    assert!(!rope.structural_eq(&leaves(&["a", "bc", "d"])));
+    assert!(!rope.structural_eq(&leaves(&["ab", "c", "e"])));
+    // the same edits to structurally equal ropes build the same trees
+    let edit = |r: &Rope| r.insert_str(3, "xyz").delete(1..2).append(r);
+    assert!(edit(&rope).structural_eq(&edit(&leaves(&["ab", "c", "d"]))));
+}
+
+#[test]
+fn align_to_boundaries_across_leaves() {
+    // "\u{1F600}" is four bytes long
+    let rope = leaves(&["a\u{1F600}", "", "\u{3c0}b"]);
+    for start in 0..rope.len() + 2 {
+        for end in start..rope.len() + 2 {
+            let outward = rope.align_to_char_boundary(start..end);
+            let inward = rope.align_to_char_boundary_inward(start..end);
+            assert!(outward.start <= inward.start && inward.end <= outward.end);
+            for &i in &[outward.start, outward.end, inward.start, inward.end] {
+                // slicing at anything but a char boundary would panic here
+                assert_eq!(rope.slice(..i).to_string().len(), i);
+            }
+        }
+    }
+    assert_eq!(rope.align_to_char_boundary(2..6), 1..7);
+    assert_eq!(rope.align_to_char_boundary_inward(2..6), 5..5);
+}
+
+#[test]
+#[cfg(feature = "unicode")]
+fn align_to_grapheme_boundaries_across_leaves() {
+    let rope = leaves(&["ae\u{301}", "\u{302}\r", "\nb"]);
+    // the cluster "e\u{301}\u{302}" spans 1..6, and "\r\n" 6..8
+    assert_eq!(rope.align_to_grapheme_boundary(2..7), 1..8);
+    assert_eq!(rope.align_to_grapheme_boundary_inward(0..7), 0..6);
+    assert_eq!(rope.align_to_grapheme_boundary_inward(2..5), 1..1);
+    assert_eq!(rope.align_to_grapheme_boundary(8..20), 8..9);
+}
+
+#[test]
+fn from_end_matches_from_start() {
+    let rope = leaves(&["abc", "", "d\u{3c0}f"]);
+    let (text, len) = (rope.to_string(), rope.len());
+    for start in 0..len + 1 {
+        for end in 0..start + 1 {
+            let from_start = len - start..len - end;
+            assert_eq!( rope.slice_from_end(start..end)
+                      , rope.slice(from_start.clone()));
+            if text.is_char_boundary(from_start.start)
+                && text.is_char_boundary(from_start.end) {
+                assert_eq!( rope.delete_from_end(start..end)
+                          , rope.delete(from_start));
+            }
+        }
+    }
+    assert_eq!(&rope.slice_from_end(3..=2), "\u{3c0}");
+    assert_eq!(rope.slice_from_end(..), rope.slice(..));
+}
+
+#[test]
+#[should_panic(expected = "Rope::slice_from_end: 8 from end was > length 7")]
+fn slice_from_end_past_start() {
+    leaves(&["abc", "d\u{3c0}f"]).slice_from_end(8..);
+}
+
+#[test]
+fn line_accessors_match_lines() {
+    let rope = Rope::from("one\n\u{3c0}") + Rope::from("\ntwo\n\n")
+             + Rope::from("three\n");
+    let lines = rope.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert_eq!(rope.first_line().to_string(), lines[0]);
+    assert_eq!(&rope.last_line().to_string(), lines.last().unwrap());
+    let string = rope.to_string();
+    for i in (0..string.len() + 1).filter(|&i| string.is_char_boundary(i)) {
+        assert_eq!( rope.line_of_offset(i)
+                  , string[..i].matches('\n').count());
+    }
+    assert_eq!(&Rope::new().last_line(), "");
+    assert_eq!(&Rope::from("\n").last_line(), "");
+}
+
+#[test]
+fn append_newline_separated_is_join() {
+    let lines = (0..500).map(|i| format!("line {}", i)).collect::<Vec<_>>();
+    let rope = Rope::from("header\n")
+        .append_newline_separated(lines.iter().map(String::as_str));
+    assert_eq!(rope, format!("header\n{}", lines.join("\n")));
+    assert!(rope.check_invariants().is_ok());
+    assert_eq!(rope.append_newline_separated(None), rope);
+}
+
 #[cfg(not(feature = "tendril"))]
 mod fmt {
     use Rope;
@@ -226,6 +1147,30 @@ fn repeated_concat_right_rebalance() {
     assert!(r.is_balanced());
 }
 
+#[test]
+fn prepend_short_leaves_rebalance() {
+    use std::collections::HashSet;
+    // a long leaf lets a balanced rope be deep, so a chain of short leaves
+    // prepended to it must be split up rather than kept whole, or it could
+    // only be rebalanced by rebuilding the whole tree
+    let mut r = Rope::from("a".repeat(10_000));
+    let mut seen = HashSet::new();
+    for i in 0..200 {
+        r = Rope::from(Node::new_branch(NodeLink::from(i.to_string()), r.root));
+        assert!(r.is_balanced());
+        let mut stack = vec![&r.root];
+        while let Some(node) = stack.pop() {
+            if !seen.insert(node.as_ptr()) { continue }
+            if let Value::Branch { ref left, ref right } = node.value {
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+    }
+    // each version shares all but a few nodes with the one before it
+    assert!(seen.len() < 200 * 3);
+}
+
 
 
 #[test]
@@ -408,9 +1353,149 @@ fn rope_char_indices() {
     }
 }
 
+/// `to_byte_index` once searched each left subtree before the right one,
+/// which visited every leaf before the index, and found `Line`s in the
+/// wrong leaf when a left subtree didn't end with a newline.
+#[test]
+fn to_byte_index_descends_by_weight() {
+    use metric::CharIndex;
+    let rope = leaves(&["a\u{3c0}", "b", "\u{3c4}\nc", "", "d\ne\n"]);
+    let text = rope.to_string();
+    let starts = text.char_indices().map(|(i, _)| i).chain(Some(text.len()));
+    for (n, i) in starts.enumerate() {
+        assert_eq!(rope.to_byte_index(CharIndex(n)), Some(i));
+    }
+    let chars = text.chars().count();
+    assert_eq!(rope.to_byte_index(CharIndex(chars + 1)), None);
+    for (n, (i, _)) in text.match_indices('\n').enumerate() {
+        assert_eq!(rope.to_byte_index(Line(n)), Some(i + 1));
+    }
+}
+
 mod properties {
-    use ::Rope;
-    use quickcheck::{TestResult, quickcheck};
+    use ::{Delta, Rope};
+    use super::leaves;
+    use test_support::{EditSequence, Op, ReferenceRope};
+    use quickcheck::{Arbitrary, Gen, TestResult, quickcheck};
+
+    use std::cmp;
+
+    /// Text made mostly of multi-byte characters, in several pieces, so that
+    /// ropes built from it have multi-byte characters at leaf boundaries.
+    #[derive(Clone, Debug)]
+    struct Multibyte(Vec<String>);
+
+    impl Multibyte {
+        fn rope(&self) -> Rope {
+            self.0.iter().fold(Rope::new(), |rope, s| rope + s.as_str())
+        }
+
+        fn string(&self) -> String { self.0.concat() }
+    }
+
+    impl Arbitrary for Multibyte {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            const CHARS: &'static [char] =
+                &['a', '\n', '\u{e9}', '\u{3c0}', '\u{800}', '\u{1f192}'];
+            let pieces = g.gen_range(1, 6);
+            Multibyte((0..pieces).map(|_| {
+                let len = g.gen_range(0, 8);
+                (0..len).map(|_| *g.choose(CHARS).unwrap()).collect()
+            }).collect())
+        }
+    }
+
+    /// Round `i` down to a char boundary in `s`.
+    fn char_boundary(s: &str, i: usize) -> usize {
+        let mut i = i % (s.len() + 1);
+        while !s.is_char_boundary(i) { i -= 1; }
+        i
+    }
+
+    #[test]
+    fn multibyte_split_is_string_split() {
+        fn prop(text: Multibyte, i: usize) -> bool {
+            let (rope, string) = (text.rope(), text.string());
+            let i = char_boundary(&string, i);
+            let (left, right) = rope.split(i);
+            left == string[..i] && right == string[i..]
+                && left.check_invariants().is_ok()
+                && right.check_invariants().is_ok()
+        }
+        quickcheck(prop as fn(Multibyte, usize) -> bool);
+    }
+
+    #[test]
+    fn multibyte_insert_delete_is_string_insert_delete() {
+        fn prop(text: Multibyte, insert: Multibyte, i: usize, j: usize)
+                -> bool {
+            let (rope, mut string) = (text.rope(), text.string());
+            let (i, j) = (char_boundary(&string, i), char_boundary(&string, j));
+            let (start, end) = if i <= j { (i, j) } else { (j, i) };
+            let deleted = rope.delete(start..end);
+            let inserted = rope.insert_str(i, &insert.string());
+            let deleted_ok = deleted == format!( "{}{}"
+                                              , &string[..start]
+                                              , &string[end..]);
+            string.insert_str(i, &insert.string());
+            deleted_ok && inserted == string
+                && deleted.check_invariants().is_ok()
+                && inserted.check_invariants().is_ok()
+        }
+        quickcheck(prop as fn(Multibyte, Multibyte, usize, usize) -> bool);
+    }
+
+    /// A sequence of `Op`s inserting multi-byte text, with indices which
+    /// are fitted to the text they are applied to by `EditSequence::new`.
+    #[derive(Clone, Debug)]
+    struct MultibyteOps(Vec<Op>);
+
+    impl Arbitrary for MultibyteOps {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let n = g.gen_range(0, 16);
+            MultibyteOps((0..n).map(|_| {
+                let (i, j) = (usize::arbitrary(g), usize::arbitrary(g));
+                let (i, j) = (cmp::min(i, j), cmp::max(i, j));
+                let text = Multibyte::arbitrary(g).string();
+                match g.gen_range(0, 7) {
+                    0 => Op::Edit(Delta::insert(i, text))
+                  , 1 => Op::Edit(Delta::delete(i..j))
+                  , 2 => Op::Edit(Delta::new(i..j, text))
+                  , 3 => Op::SplitLeft(i)
+                  , 4 => Op::SplitRight(i)
+                  , 5 => Op::Append(text)
+                  , _ => Op::Prepend(text)
+                }
+            }).collect())
+        }
+    }
+
+    #[test]
+    fn edit_sequences_match_string_model() {
+        fn prop(text: Multibyte, ops: MultibyteOps) -> TestResult {
+            let sequence = EditSequence::new(&text.string(), ops.0);
+            let mut rope = text.rope();
+            let mut reference = ReferenceRope::from(text.string());
+            for op in sequence.ops() {
+                rope = op.apply(&rope);
+                reference = op.apply_reference(&reference);
+                if rope != reference || rope.len() != reference.len() {
+                    return TestResult::error(format!( "after {:?}: {:?} != {:?}"
+                                                    , op, rope, reference))
+                }
+                if let Err(e) = rope.check_invariants() {
+                    return TestResult::error(
+                        format!("after {:?}: {}", op, e))
+                }
+            }
+            let string = reference.as_str();
+            let parsed: Rope = string.parse().unwrap();
+            TestResult::from_bool( rope.to_string() == string
+                                && format!("{}", rope) == string
+                                && parsed == rope)
+        }
+        quickcheck(prop as fn(Multibyte, MultibyteOps) -> TestResult);
+    }
 
     #[cfg(feature = "atomic")]
     quickcheck! {
@@ -442,7 +1527,77 @@ mod properties {
 
     }
 
+    #[test]
+    fn edits_preserve_invariants() {
+        fn prop(a: String, b: String, i: usize) -> TestResult {
+            let mut i = i % (a.len() + 1);
+            while !a.is_char_boundary(i) { i -= 1; }
+            let rope = Rope::from(a.clone()) + Rope::from(b.clone());
+            let ropes = [ rope.insert_str(i, &b)
+                        , rope.delete(i..a.len())
+                        , rope.split(i).0
+                        , rope.split(i).1
+                        , rope.append(&rope.slice(0..i).into())
+                        ];
+            for rope in ropes.iter() {
+                if let Err(e) = rope.check_invariants() {
+                    return TestResult::error(e.to_string())
+                }
+            }
+            TestResult::passed()
+        }
+        quickcheck(prop as fn(String, String, usize) -> TestResult);
+    }
+
+    #[test]
+    fn edits_keep_ropes_balanced() {
+        use ::RopeBuilder;
+        use test_support::{random_ops, sample_text, typing_ops};
+        fn prop(seed: u64) -> TestResult {
+            let text = sample_text(2048, seed);
+            let mut builder = RopeBuilder::with_capacity_hints(0, 16);
+            builder.push_str(&text);
+            let ops = random_ops(&text, 100, seed).into_iter()
+                .chain(typing_ops(0, &sample_text(100, seed)));
+            let mut rope = builder.finish();
+            for op in ops {
+                rope = op.apply(&rope);
+                // balanced ropes are at most log_φ(len) + 2 deep
+                let bound = (rope.len() as f64).log(1.618) + 2.0;
+                if !rope.is_balanced() || rope.root.depth() as f64 > bound {
+                    return TestResult::error(format!( "{:?} left depth {}"
+                                                    , op, rope.root.depth()))
+                }
+            }
+            TestResult::passed()
+        }
+        quickcheck(prop as fn(u64) -> TestResult);
+    }
+
+    #[test]
+    fn max_line_width_is_widest_line() {
+        fn prop(pieces: Vec<Vec<u8>>) -> bool {
+            // a "\r\n" is often split between two leaves
+            const CHARS: &'static [char] = &['a', '\r', '\n', '\u{3c0}'];
+            let pieces = pieces.iter().map(|piece| {
+                piece.iter().map(|&b| CHARS[b as usize % CHARS.len()])
+                     .collect::<String>()
+            }).collect::<Vec<_>>();
+            let rope = leaves(&pieces.iter().map(String::as_str)
+                                     .collect::<Vec<_>>());
+            let s = rope.to_string();
+            let mid = (0..s.len() / 2 + 1).rev()
+                                          .find(|&i| s.is_char_boundary(i))
+                                          .unwrap();
+            let edited = rope.insert_str(mid, "\n");
+            rope.max_line_width() == rope.analyze().max_line_len
+                && edited.max_line_width() == edited.analyze().max_line_len
+        }
+        quickcheck(prop as fn(Vec<Vec<u8>>) -> bool);
+    }
+
     #[ignore]
+    #[cfg(feature = "unicode")]
     fn rope_indexing_is_string_indexing() {
         fn prop(string: String, i: usize) -> TestResult {
             use ::unicode::Unicode;
@@ -459,6 +1614,7 @@ mod properties {
     }
 
     #[ignore]
+    #[cfg(feature = "unicode")]
     fn rope_insert_char_is_string_insert_char() {
         fn prop(a: String, ch: char, i: usize) -> TestResult {
             use unicode::Unicode;
@@ -485,7 +1641,7 @@ mod properties {
     }
 
     // #[cfg(all(feature = "unstable", not(feature = "tendril")))]
-    #[cfg(all(feature = "unstable"))]
+    #[cfg(all(feature = "unstable", feature = "unicode"))]
     #[test]
     fn rope_insert_str_is_string_insert_str() {
         fn prop(a: String, b: String, i: usize) -> TestResult {
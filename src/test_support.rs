@@ -0,0 +1,728 @@
+//! Support for testing code built on `Rope`s against a reference model.
+//!
+//! When built with the `test-support` feature, this module provides a
+//! [`ReferenceRope`], a deliberately naïve implementation of part of
+//! `Rope`'s persistent API which simply copies a `String` on every edit.
+//! Since it is too simple to be wrong, it can be used as a model to check
+//! that a `Rope`, or an operation built on top of one, behaves as expected.
+//!
+//! [`check_ops`] applies a sequence of [`Op`]s to a `Rope` and to a
+//! `ReferenceRope` side by side, and [`compare`] does the same for a single
+//! custom operation. Both report the first point at which the two disagree,
+//! or at which the `Rope`'s internal invariants were broken.
+//!
+//! The module also generates realistic, deterministic workloads for tests
+//! and benchmarks: [`sample_text`] produces a document, and [`typing_ops`]
+//! and [`random_ops`] produce sequences of edits to apply to it.
+//!
+//! When a sequence of edits breaks something, an [`EditSequence`] can be
+//! shrunk to a minimal sequence which still does, with
+//! [`EditSequence::minimize`]. With the `arbitrary` feature, it also
+//! implements `arbitrary`'s `Arbitrary`, so that sequences of edits can be
+//! generated by fuzzers and property tests.
+//!
+//! [`ReferenceRope`]: struct.ReferenceRope.html
+//! [`check_ops`]: fn.check_ops.html
+//! [`Op`]: enum.Op.html
+//! [`compare`]: fn.compare.html
+//! [`sample_text`]: fn.sample_text.html
+//! [`typing_ops`]: fn.typing_ops.html
+//! [`random_ops`]: fn.random_ops.html
+//! [`EditSequence`]: struct.EditSequence.html
+//! [`EditSequence::minimize`]: struct.EditSequence.html#method.minimize
+use std::cmp;
+use std::convert;
+use std::error;
+use std::fmt;
+use std::ops;
+
+use super::{Delta, Rope};
+
+#[cfg(test)] use quickcheck::{Arbitrary, Gen};
+#[cfg(feature = "arbitrary")] use arbitrary::{self, Unstructured};
+
+/// A naïve, `String`-backed implementation of `Rope`'s persistent editing
+/// API.
+///
+/// Every edit copies the whole text, so a `ReferenceRope` is only suitable
+/// for testing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ReferenceRope(String);
+
+impl ReferenceRope {
+    /// Returns a new, empty `ReferenceRope`.
+    #[inline] pub fn new() -> Self { ReferenceRope(String::new()) }
+
+    /// Returns the text of this `ReferenceRope`.
+    #[inline] pub fn as_str(&self) -> &str { &self.0 }
+
+    /// Returns the length of this `ReferenceRope`, in bytes.
+    #[inline] pub fn len(&self) -> usize { self.0.len() }
+
+    /// Returns `true` if this `ReferenceRope` is empty.
+    #[inline] pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Returns a new `ReferenceRope` with `s` inserted at byte index `index`.
+    ///
+    /// # Panics
+    /// Under the same conditions as `Rope::insert_str`.
+    pub fn insert_str(&self, index: usize, s: &str) -> Self {
+        assert!( index <= self.len()
+               , "ReferenceRope::insert_str: index {} was > length {}"
+               , index, self.len());
+        let mut string = self.0.clone();
+        string.insert_str(index, s);
+        ReferenceRope(string)
+    }
+
+    /// Returns a new `ReferenceRope` with the byte range `range` deleted.
+    ///
+    /// # Panics
+    /// Under the same conditions as `Rope::delete`.
+    #[inline]
+    pub fn delete(&self, range: ops::Range<usize>) -> Self {
+        self.edit(range, "")
+    }
+
+    /// Returns a new `ReferenceRope` with the byte range `range` replaced
+    /// by `replacement`.
+    ///
+    /// # Panics
+    /// Under the same conditions as `Rope::edit`.
+    pub fn edit(&self, range: ops::Range<usize>, replacement: &str) -> Self {
+        range_len!("ReferenceRope::edit", range.start, range.end);
+        assert!( range.end <= self.len()
+               , "ReferenceRope::edit: end {} was > length {}"
+               , range.end, self.len());
+        ReferenceRope(format!( "{}{}{}"
+                             , &self.0[..range.start]
+                             , replacement
+                             , &self.0[range.end..]))
+    }
+
+    /// Splits this `ReferenceRope` into two at byte index `index`.
+    ///
+    /// # Panics
+    /// Under the same conditions as `Rope::split`.
+    pub fn split(&self, index: usize) -> (Self, Self) {
+        assert!( index <= self.len()
+               , "ReferenceRope::split: index {} was > length {}"
+               , index, self.len());
+        let (left, right) = self.0.split_at(index);
+        (ReferenceRope::from(left), ReferenceRope::from(right))
+    }
+
+    /// Returns a new `ReferenceRope` with `other` appended to the end.
+    #[inline]
+    pub fn append(&self, other: &ReferenceRope) -> Self {
+        ReferenceRope(self.0.clone() + &other.0)
+    }
+
+    /// Returns a new `ReferenceRope` with `other` prepended to the start.
+    #[inline]
+    pub fn prepend(&self, other: &ReferenceRope) -> Self {
+        other.append(self)
+    }
+
+    /// Returns the byte range `range` of this `ReferenceRope`.
+    ///
+    /// # Panics
+    /// Under the same conditions as `Rope::slice`.
+    #[inline]
+    pub fn slice(&self, range: ops::Range<usize>) -> Self {
+        ReferenceRope::from(&self.0[range])
+    }
+}
+
+impl<'a> convert::From<&'a str> for ReferenceRope {
+    #[inline] fn from(s: &'a str) -> Self { ReferenceRope(s.to_owned()) }
+}
+
+impl convert::From<String> for ReferenceRope {
+    #[inline] fn from(s: String) -> Self { ReferenceRope(s) }
+}
+
+impl fmt::Display for ReferenceRope {
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl cmp::PartialEq<Rope> for ReferenceRope {
+    #[inline] fn eq(&self, other: &Rope) -> bool { other == self.as_str() }
+}
+
+impl cmp::PartialEq<ReferenceRope> for Rope {
+    #[inline] fn eq(&self, other: &ReferenceRope) -> bool {
+        self == other.as_str()
+    }
+}
+
+/// An edit which can be applied both to a `Rope` and to a `ReferenceRope`.
+///
+/// Insertions, deletions and replacements are all `Delta`s; the other
+/// variants exercise `Rope`'s splitting and concatenation directly.
+///
+/// All indices are byte indices, and are used as given, so an `Op` whose
+/// indices are out of range, or not on character boundaries, will panic
+/// when it is applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op { /// Replace a range with a string, as by `Delta::apply`.
+              Edit(Delta)
+            , /// Keep only the text before an index.
+              SplitLeft(usize)
+            , /// Keep only the text after an index.
+              SplitRight(usize)
+            , /// Append a string.
+              Append(String)
+            , /// Prepend a string.
+              Prepend(String)
+            }
+
+impl Op {
+    /// Returns the result of applying this `Op` to `rope`.
+    pub fn apply(&self, rope: &Rope) -> Rope {
+        match *self {
+            Op::Edit(ref delta) => delta.apply(rope)
+          , Op::SplitLeft(i) => rope.split(i).0
+          , Op::SplitRight(i) => rope.split(i).1
+          , Op::Append(ref s) => rope.append(&Rope::from(s.as_str()))
+          , Op::Prepend(ref s) => rope.prepend(&Rope::from(s.as_str()))
+        }
+    }
+
+    /// Returns the result of applying this `Op` to `reference`.
+    pub fn apply_reference(&self, reference: &ReferenceRope)
+                          -> ReferenceRope {
+        match *self {
+            Op::Edit(ref delta) =>
+                reference.edit(delta.range(), delta.text())
+          , Op::SplitLeft(i) => reference.split(i).0
+          , Op::SplitRight(i) => reference.split(i).1
+          , Op::Append(ref s) => reference.append(&ReferenceRope::from(&s[..]))
+          , Op::Prepend(ref s) =>
+                reference.prepend(&ReferenceRope::from(&s[..]))
+        }
+    }
+
+    /// Returns this `Op`, with its indices moved so that it can be applied
+    /// to `text`.
+    ///
+    /// Each index is clamped to the length of `text`, and rounded down to a
+    /// character boundary.
+    pub fn fitted(&self, text: &str) -> Op {
+        let fit = |i: usize| {
+            let mut i = cmp::min(i, text.len());
+            while !text.is_char_boundary(i) { i -= 1; }
+            i
+        };
+        match *self {
+            Op::Edit(ref delta) => {
+                let end = fit(delta.range().end);
+                let start = cmp::min(fit(delta.range().start), end);
+                Op::Edit(Delta::new(start..end, delta.text()))
+            }
+          , Op::SplitLeft(i) => Op::SplitLeft(fit(i))
+          , Op::SplitRight(i) => Op::SplitRight(fit(i))
+          , Op::Append(_) | Op::Prepend(_) => self.clone()
+        }
+    }
+
+    /// Returns the text this `Op` inserts, if any.
+    fn text(&self) -> Option<&str> {
+        match *self {
+            Op::Edit(ref delta) => Some(delta.text())
+          , Op::Append(ref s) | Op::Prepend(ref s) => Some(s)
+          , _ => None
+        }
+    }
+
+    /// Returns this `Op`, inserting `s` instead, if it inserts any text.
+    fn with_text(&self, s: &str) -> Op {
+        match *self {
+            Op::Edit(ref delta) => Op::Edit(Delta::new(delta.range(), s))
+          , Op::Append(_) => Op::Append(s.to_owned())
+          , Op::Prepend(_) => Op::Prepend(s.to_owned())
+          , _ => self.clone()
+        }
+    }
+}
+
+/// A sequence of `Op`s, and the text to which they are applied.
+///
+/// Every `Op` in an `EditSequence` can be applied to the text as it is
+/// after the `Op`s before it. When a sequence breaks an invariant, or
+/// exposes a bug in code built on `Rope`s, [`minimize`] shrinks it to a
+/// minimal sequence which still does, so that the bug can be reproduced
+/// with as few edits, and as little text, as possible.
+///
+/// With the `arbitrary` feature, `EditSequence` implements `arbitrary`'s
+/// `Arbitrary`, generating realistic edits; a failing sequence can then be
+/// shrunk with `minimize`.
+///
+/// [`minimize`]: #method.minimize
+///
+/// # Examples
+/// ```
+/// use an_rope::test_support::{random_ops, sample_text, EditSequence};
+/// let text = sample_text(200, 1);
+/// let sequence = EditSequence::new(&text, random_ops(&text, 50, 2));
+/// assert!(sequence.check().is_ok());
+///
+/// // a "bug" which only shows up when the text contains a tab
+/// let has_tab = |s: &EditSequence| s.text().as_str().contains('\t');
+/// assert!(has_tab(&sequence));
+/// let minimal = sequence.minimize(has_tab);
+/// assert_eq!(minimal.text().as_str(), "\t");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditSequence { initial: String
+                        , ops: Vec<Op>
+                        }
+
+impl EditSequence {
+    /// Returns a new `EditSequence` which applies `ops` to `initial`, with
+    /// each `Op` fitted to the text it is applied to, as by `Op::fitted`.
+    pub fn new<I>(initial: &str, ops: I) -> Self
+    where I: IntoIterator<Item=Op> {
+        let mut text = ReferenceRope::from(initial);
+        let ops = ops.into_iter().map(|op| {
+            let op = op.fitted(text.as_str());
+            text = op.apply_reference(&text);
+            op
+        }).collect();
+        EditSequence { initial: initial.to_owned(), ops: ops }
+    }
+
+    /// Returns the text to which the first `Op` is applied.
+    #[inline] pub fn initial(&self) -> &str { &self.initial }
+
+    /// Returns the `Op`s of this sequence, in order.
+    #[inline] pub fn ops(&self) -> &[Op] { &self.ops }
+
+    /// Returns the text after every `Op` has been applied, as computed by
+    /// a `ReferenceRope`.
+    pub fn text(&self) -> ReferenceRope {
+        self.ops.iter().fold( ReferenceRope::from(self.initial.as_str())
+                            , |text, op| op.apply_reference(&text))
+    }
+
+    /// Applies this sequence to a `Rope` and to a `ReferenceRope`, as by
+    /// `check_ops`.
+    #[inline]
+    pub fn check(&self) -> Result<Rope, Divergence> {
+        check_ops(&self.initial, &self.ops)
+    }
+
+    /// Returns the sequences one step smaller than this one: those with
+    /// fewer `Op`s, a shorter initial text, or `Op`s which insert less
+    /// text, with their `Op`s fitted to their new texts.
+    fn shrunk(&self) -> Vec<EditSequence> {
+        let n = self.ops.len();
+        let mut ops = Vec::new();
+        // drop halves first, so that long sequences shrink quickly
+        if n > 1 {
+            ops.push(self.ops[n / 2..].to_vec());
+            ops.push(self.ops[..n / 2].to_vec());
+        }
+        for i in 0..n {
+            let mut fewer = self.ops.clone();
+            fewer.remove(i);
+            ops.push(fewer);
+        }
+        let mut shrunk = ops.into_iter()
+                            .map(|ops| EditSequence::new(&self.initial, ops))
+                            .collect::<Vec<_>>();
+        // each of these is strictly shorter than `s`, so shrinking ends
+        let halve = |s: &str| -> Vec<String> {
+            let chars = s.chars().collect::<Vec<_>>();
+            if chars.is_empty() { return vec![] }
+            let mut shorter = vec![ chars[..chars.len() / 2].iter().collect()
+                                  , chars[(chars.len() + 1) / 2..].iter()
+                                                                  .collect() ];
+            if chars.len() > 2 { shorter.push(chars[1..].iter().collect()) }
+            shorter.dedup();
+            shorter
+        };
+        for initial in halve(&self.initial) {
+            shrunk.push(EditSequence::new(&initial, self.ops.clone()));
+        }
+        for (i, op) in self.ops.iter().enumerate() {
+            for s in op.text().map(&halve).unwrap_or_default() {
+                let mut ops = self.ops.clone();
+                ops[i] = op.with_text(&s);
+                shrunk.push(EditSequence::new(&self.initial, ops));
+            }
+        }
+        shrunk
+    }
+
+    /// Returns a minimal sequence, made by removing `Op`s and text from
+    /// this one, for which `fails` still returns true.
+    ///
+    /// The sequence is shrunk greedily, one step at a time, for as long as
+    /// some smaller sequence still fails, so the result is minimal in that
+    /// no single step makes it smaller, though a smaller failing sequence
+    /// may exist. If `fails` returns false for this sequence, it is
+    /// returned unchanged.
+    pub fn minimize<F>(&self, mut fails: F) -> EditSequence
+    where F: FnMut(&EditSequence) -> bool {
+        let mut current = self.clone();
+        if !fails(&current) { return current }
+        while let Some(smaller) = current.shrunk().into_iter()
+                                         .find(|s| fails(s)) {
+            current = smaller;
+        }
+        current
+    }
+}
+
+/// The number of kinds of `Op` which `generated_op` can return.
+#[cfg(any(test, feature = "arbitrary"))]
+const OP_KINDS: usize = 7;
+
+/// Returns an `Op` of the kind numbered `kind`, at byte index `i`, which
+/// deletes `j` bytes or inserts `word` if it needs to.
+///
+/// The `Op` is not fitted to any text; `EditSequence::new` does that.
+#[cfg(any(test, feature = "arbitrary"))]
+fn generated_op(kind: usize, i: usize, j: usize, word: &str) -> Op {
+    match kind {
+        0 => Op::Edit(Delta::insert(i, word))
+      , 1 => Op::Edit(Delta::delete(i..i + j))
+      , 2 => Op::Edit(Delta::new(i..i + j, word))
+      , 3 => Op::SplitLeft(i)
+      , 4 => Op::SplitRight(i)
+      , 5 => Op::Append(word.to_owned())
+      , _ => Op::Prepend(word.to_owned())
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for EditSequence {
+    /// Generates a sample text, as by `sample_text`, of up to four times the
+    /// generator's size in bytes, and up to that size of `Op`s of every
+    /// kind, inserting words from the same vocabulary.
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let size = g.size();
+        let initial = sample_text(g.gen_range(0, 4 * size + 1), g.gen());
+        let ops = (0..g.gen_range(0, size + 1)).map(|_| {
+            let (i, j) = (g.gen_range(0, 4 * size + 1), g.gen_range(0, 16));
+            let word = *g.choose(WORDS).unwrap();
+            generated_op(g.gen_range(0, OP_KINDS), i, j, word)
+        }).collect::<Vec<_>>();
+        EditSequence::new(&initial, ops)
+    }
+
+    fn shrink(&self) -> Box<Iterator<Item=EditSequence>> {
+        Box::new(self.shrunk().into_iter())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for EditSequence {
+    /// Generates a sample text, as by `sample_text`, of up to 1024 bytes,
+    /// and up to 64 `Op`s of every kind, inserting words from the same
+    /// vocabulary.
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let initial = sample_text(u.int_in_range(0..=1024)?, u.arbitrary()?);
+        let mut ops = Vec::new();
+        for _ in 0..u.int_in_range(0..=64)? {
+            let (i, j) = (u.int_in_range(0..=1024)?, u.int_in_range(0..=15)?);
+            let word = *u.choose(WORDS)?;
+            ops.push(generated_op( u.int_in_range(0..=OP_KINDS - 1)?
+                                 , i, j, word));
+        }
+        Ok(EditSequence::new(&initial, ops))
+    }
+}
+
+/// A disagreement between a `Rope` and a `ReferenceRope`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence { step: usize
+                      , message: String
+                      }
+
+impl Divergence {
+    /// Returns the index of the operation after which the `Rope` and the
+    /// `ReferenceRope` disagreed.
+    #[inline] pub fn step(&self) -> usize { self.step }
+
+    /// Returns a description of the disagreement.
+    #[inline] pub fn message(&self) -> &str { &self.message }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "diverged after step {}: {}", self.step, self.message)
+    }
+}
+
+impl error::Error for Divergence {
+    fn description(&self) -> &str { &self.message }
+}
+
+/// Check that `rope` and `reference` agree, and that `rope`'s invariants
+/// hold.
+fn check_step(step: usize, rope: &Rope, reference: &ReferenceRope)
+              -> Result<(), Divergence> {
+    if rope != reference {
+        return Err(Divergence {
+            step: step
+          , message: format!( "rope {:?} != reference {:?}"
+                            , rope.to_string(), reference.as_str())
+        })
+    }
+    rope.check_invariants().map_err(|e| Divergence {
+        step: step
+      , message: e.to_string()
+    })
+}
+
+/// Applies a custom operation to both a `Rope` and a `ReferenceRope`, and
+/// checks that the results agree.
+///
+/// `on_rope` and `on_reference` should implement the same operation for
+/// each type. The disagreement, if there is one, is reported as step 0.
+///
+/// # Examples
+/// ```
+/// use an_rope::Rope;
+/// use an_rope::test_support::{compare, ReferenceRope};
+/// let text = "hello\tworld";
+/// let (rope, _) = compare( &Rope::from(text), &ReferenceRope::from(text)
+///                        , |rope| rope.expand_tabs(4)
+///                        , |reference| ReferenceRope::from(
+///                              reference.as_str().replace('\t', "   ")))
+///                 .unwrap();
+/// assert_eq!(&rope, "hello   world");
+/// ```
+pub fn compare<F, G>( rope: &Rope, reference: &ReferenceRope
+                    , on_rope: F, on_reference: G)
+                    -> Result<(Rope, ReferenceRope), Divergence>
+where F: FnOnce(&Rope) -> Rope
+    , G: FnOnce(&ReferenceRope) -> ReferenceRope {
+    let (rope, reference) = (on_rope(rope), on_reference(reference));
+    check_step(0, &rope, &reference)?;
+    Ok((rope, reference))
+}
+
+/// Applies each of `ops` in turn to a `Rope` and to a `ReferenceRope`, both
+/// starting with the text `initial`, and checks that they agree after every
+/// step.
+///
+/// # Returns
+/// - `Ok` with the final `Rope`, if the two agreed after every step
+/// - `Err` with the first `Divergence` otherwise
+///
+/// # Panics
+/// If any `Op` has indices which are invalid for the text it is applied to.
+///
+/// # Examples
+/// ```
+/// use an_rope::Delta;
+/// use an_rope::test_support::{check_ops, Op};
+/// let ops = [ Op::Edit(Delta::insert(5, ","))
+///           , Op::Edit(Delta::new(7..12, "there"))
+///           , Op::SplitLeft(6)
+///           ];
+/// let rope = check_ops("hello world", &ops).unwrap();
+/// assert_eq!(&rope, "hello,");
+/// ```
+pub fn check_ops<'a, I>(initial: &str, ops: I) -> Result<Rope, Divergence>
+where I: IntoIterator<Item=&'a Op> {
+    let (mut rope, mut reference) =
+        (Rope::from(initial), ReferenceRope::from(initial));
+    for (step, op) in ops.into_iter().enumerate() {
+        rope = op.apply(&rope);
+        reference = op.apply_reference(&reference);
+        check_step(step, &rope, &reference).map_err(|e| Divergence {
+            message: format!("{:?}: {}", op, e.message)
+          , ..e
+        })?;
+    }
+    Ok(rope)
+}
+
+/// A small xorshift pseudo-random number generator, so that generated
+/// workloads are the same on every run and on every platform.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck at 0, so mix in a nonzero constant
+        XorShift(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: usize) -> usize { (self.next() % n as u64) as usize }
+}
+
+/// Words from which `sample_text` builds its lines.
+const WORDS: &'static [&'static str] =
+    &[ "the", "rope", "of", "a", "text", "editor", "is", "na\u{ef}ve"
+     , "\u{3c0}", "fn", "main()", "{", "}", "let", "x", "=", "42;", "caf\u{e9}"
+     , "\u{1f980}", "and", "or", "buffer", "\t"
+     ];
+
+/// Returns `len` bytes of text resembling a source file or document, built
+/// from lines of words including some multi-byte characters.
+///
+/// The same `seed` always produces the same text. If the last character
+/// would not fit in `len` bytes, the text is slightly shorter than `len`.
+///
+/// # Examples
+/// ```
+/// use an_rope::test_support::sample_text;
+/// let text = sample_text(1000, 7);
+/// assert!(text.len() <= 1000 && text.len() > 990);
+/// assert_eq!(text, sample_text(1000, 7));
+/// ```
+pub fn sample_text(len: usize, seed: u64) -> String {
+    let mut rng = XorShift::new(seed);
+    let mut text = String::with_capacity(len + 16);
+    while text.len() < len {
+        let words = 1 + rng.below(12);
+        for i in 0..words {
+            if i > 0 { text.push(' ') }
+            text.push_str(WORDS[rng.below(WORDS.len())]);
+        }
+        text.push('\n');
+    }
+    let mut end = len;
+    while !text.is_char_boundary(end) { end -= 1; }
+    text.truncate(end);
+    text
+}
+
+/// Returns the `Op`s which type `text` one character at a time, starting at
+/// byte index `at`.
+pub fn typing_ops(at: usize, text: &str) -> Vec<Op> {
+    text.char_indices()
+        .map(|(i, c)| Op::Edit(Delta::insert(at + i, c.to_string())))
+        .collect()
+}
+
+/// Returns `count` random `Op`s which may be applied in order to `initial`.
+///
+/// The edits are small insertions, deletions and replacements scattered
+/// throughout the text, as when editing many parts of a document. Every
+/// index falls on a character boundary of the text at the point where its
+/// `Op` is applied. The same `seed` always produces the same `Op`s.
+pub fn random_ops(initial: &str, count: usize, seed: u64) -> Vec<Op> {
+    let mut rng = XorShift::new(seed);
+    let mut text = ReferenceRope::from(initial);
+    (0..count).map(|_| {
+        let boundary = |rng: &mut XorShift, text: &ReferenceRope| {
+            let mut i = rng.below(text.len() + 1);
+            while !text.as_str().is_char_boundary(i) { i -= 1; }
+            i
+        };
+        let start = boundary(&mut rng, &text);
+        let word = WORDS[rng.below(WORDS.len())].to_owned();
+        let delta = match rng.below(3) {
+            0 => Delta::insert(start, word)
+          , n => {
+                let mut end = cmp::min(start + rng.below(8), text.len());
+                while !text.as_str().is_char_boundary(end) { end -= 1; }
+                if n == 1 { Delta::delete(start..end) }
+                else { Delta::new(start..end, word) }
+            }
+        };
+        let op = Op::Edit(delta);
+        text = op.apply_reference(&text);
+        op
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::{check_ops, compare, random_ops, sample_text, typing_ops};
+    use super::{EditSequence, Op, ReferenceRope};
+    use ::Delta;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn generated_ops_apply_cleanly() {
+        let text = sample_text(4096, 1);
+        let mut ops = random_ops(&text, 500, 2);
+        ops.extend(typing_ops(0, "hello, \u{3c0} world"));
+        assert!(check_ops(&text, &ops).is_ok());
+    }
+
+    #[test]
+    fn compare_reports_divergence() {
+        let text = "abc";
+        let err = compare( &Rope::from(text), &ReferenceRope::from(text)
+                         , |rope| rope.insert_str(1, "x")
+                         , |reference| reference.insert_str(2, "x"))
+                  .unwrap_err();
+        assert_eq!(err.step(), 0);
+        assert_eq!(err.message(), "rope \"axbc\" != reference \"abxc\"");
+    }
+
+    #[test]
+    fn arbitrary_sequences_check() {
+        fn prop(sequence: EditSequence) -> bool { sequence.check().is_ok() }
+        quickcheck(prop as fn(EditSequence) -> bool);
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn unstructured_sequences_check() {
+        use arbitrary::{Arbitrary, Unstructured};
+        let data = (0..4096u32).map(|i| (i * 7919 % 251) as u8)
+                               .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&data);
+        let mut checked = 0;
+        while let Ok(sequence) = EditSequence::arbitrary(&mut u) {
+            assert!(sequence.check().is_ok());
+            checked += 1;
+            if u.is_empty() { break }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn fitted_ops_apply() {
+        let text = "a\u{3c0}b";
+        assert_eq!( Op::Edit(Delta::insert(2, "x")).fitted(text)
+                  , Op::Edit(Delta::insert(1, "x")));
+        assert_eq!( Op::Edit(Delta::delete(2..9)).fitted(text)
+                  , Op::Edit(Delta::delete(1..4)));
+        assert_eq!(Op::SplitRight(9).fitted(text), Op::SplitRight(4));
+    }
+
+    #[test]
+    fn minimize_finds_minimal_sequence() {
+        let text = sample_text(300, 3);
+        let sequence = EditSequence::new(&text, random_ops(&text, 80, 4));
+        // a "bug" which only shows up when the final text contains "x"
+        let fails = |s: &EditSequence| s.text().as_str().contains('x');
+        assert!(fails(&sequence));
+        let minimal = sequence.minimize(&fails);
+        assert!(fails(&minimal));
+        assert_eq!(minimal.text().as_str(), "x");
+        assert!(minimal.ops().len() <= 1);
+        assert!(minimal.check().is_ok());
+        // no single step shrinks it further
+        assert!(minimal.shrunk().iter().all(|s| !fails(s)));
+    }
+
+    #[test]
+    fn reference_matches_string() {
+        let reference = ReferenceRope::from("hello world");
+        assert_eq!(reference.edit(0..5, "goodbye").as_str(), "goodbye world");
+        assert_eq!(reference.split(5).1.as_str(), " world");
+        assert_eq!(reference.slice(6..11).as_str(), "world");
+        assert!(reference == Rope::from("hello world"));
+    }
+}
@@ -11,6 +11,8 @@
 // Tests based on unicode-segmentation's test suite.
 mod testdata;
 use Rope;
+use test::leaves;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[test]
 fn graphemes_iter() {
@@ -160,3 +162,100 @@ fn test_graphemes() {
 //                  "Reverse word indices");
 //     }
 // }
+
+#[test]
+fn next_grapheme_boundary_across_leaves() {
+    // a combining acute accent in a separate leaf from the letter it modifies
+    let r = Rope::from("e") + Rope::from("\u{301}x");
+    assert_eq!(r.next_grapheme_boundary(0), Some(3));
+    assert_eq!(r.next_grapheme_boundary(3), Some(4));
+    assert_eq!(r.next_grapheme_boundary(4), None);
+}
+
+#[test]
+fn prev_grapheme_boundary_across_leaves() {
+    let r = Rope::from("x\r") + Rope::from("\n");
+    assert_eq!(r.prev_grapheme_boundary(3), Some(1));
+    assert_eq!(r.prev_grapheme_boundary(1), Some(0));
+    assert_eq!(r.prev_grapheme_boundary(0), None);
+}
+
+#[test]
+fn prev_grapheme_boundary_spanning_many_leaves() {
+    // a cluster of a letter and many combining marks, each in its own leaf
+    let marks = vec!["\u{301}"; 50];
+    let mut chunks = vec!["x", "e"];
+    chunks.extend(marks);
+    let r = leaves(&chunks);
+    assert_eq!(r.prev_grapheme_boundary(r.len()), Some(1));
+    assert_eq!(r.prev_grapheme_boundary(1), Some(0));
+}
+
+#[test]
+fn grapheme_boundaries_roundtrip() {
+    let s = "a̐éö̲\r\nπ🆒🆕 rope";
+    let r = Rope::from("a̐é") + Rope::from("ö̲\r") + Rope::from("\nπ🆒🆕 rope");
+    let mut forward = vec![0];
+    while let Some(i) = r.next_grapheme_boundary(*forward.last().unwrap()) {
+        forward.push(i);
+    }
+    let mut backward = vec![s.len()];
+    while let Some(i) = r.prev_grapheme_boundary(*backward.last().unwrap()) {
+        backward.push(i);
+    }
+    backward.reverse();
+    assert_eq!(forward, backward);
+    assert_eq!(forward.len() - 1, s.graphemes(true).count());
+}
+
+#[test]
+fn word_boundaries_across_leaves() {
+    // "can't" spans three leaves, but is still a single word
+    let r = Rope::from("I ca") + Rope::from("n'") + Rope::from("t go\nhome");
+    assert_eq!(r.next_word_boundary(2), Some(7));
+    assert_eq!(r.prev_word_boundary(7), Some(2));
+    assert_eq!(r.prev_word_boundary(5), Some(2));
+    assert_eq!(r.next_word_boundary(10), Some(11));
+    assert_eq!(r.prev_word_boundary(11), Some(10));
+}
+
+#[test]
+fn word_boundaries_match_split_word_bound_indices() {
+    let s = "The quick (\"brown\") fox\ncan't jump 32.3 feet, right?";
+    let r = Rope::from(s);
+    let expected = s.split_word_bound_indices()
+                    .map(|(i, _)| i)
+                    .skip(1)
+                    .chain(Some(s.len()))
+                    .collect::<Vec<_>>();
+    let mut forward = vec![];
+    let mut i = 0;
+    while let Some(b) = r.next_word_boundary(i) {
+        forward.push(b);
+        i = b;
+    }
+    assert_eq!(forward, expected);
+}
+
+#[test]
+fn word_boundaries_on_a_long_line_of_short_leaves() {
+    let s = "It's  3.14 o'clock \u{1f1e6}\u{1f1e7}\u{1f1e8} and\r\n  done. "
+            .repeat(20);
+    let chunks = s.char_indices()
+                  .map(|(i, c)| &s[i..i + c.len_utf8()])
+                  .collect::<Vec<_>>();
+    let r = leaves(&chunks);
+    let expected = s.split_word_bound_indices()
+                    .map(|(i, _)| i)
+                    .chain(Some(s.len()))
+                    .collect::<Vec<_>>();
+    for (i, _) in s.char_indices().chain(Some((s.len(), ' '))) {
+        assert_eq!( r.is_word_boundary(i)
+                  , expected.binary_search(&i).is_ok(), "at {}", i);
+        assert_eq!( r.next_word_boundary(i)
+                  , expected.iter().cloned().find(|&b| b > i), "at {}", i);
+        assert_eq!( r.prev_word_boundary(i)
+                  , expected.iter().cloned().rev().find(|&b| b < i)
+                  , "at {}", i);
+    }
+}
@@ -0,0 +1,426 @@
+//! `Rope`s tagged with a revision number.
+//!
+//! A [`VersionedRope`] pairs a `Rope` with a revision number, which changes
+//! on every edit made through it. Consumers which compute something from a
+//! buffer asynchronously, such as a linter, can remember the revision they
+//! computed it from and later check whether the buffer has changed since,
+//! without comparing any text.
+//!
+//! Each `VersionedRope` also remembers the edits which produced it, so a
+//! [`Position`] taken at one revision can be resolved against any later
+//! revision, giving the offset the same place in the text has moved to. A
+//! consumer which holds positions, such as bookmarks or diagnostics, need
+//! not adjust them after every edit; it resolves them when it uses them.
+//! Since that history grows with every edit, a long-lived `VersionedRope`
+//! should [`forget_before`] the oldest revision any consumer still holds a
+//! position into.
+//!
+//! [`VersionedRope`]: struct.VersionedRope.html
+//! [`Position`]: struct.Position.html
+//! [`forget_before`]: struct.VersionedRope.html#method.forget_before
+use std::convert;
+use std::ops;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{EditInfo, Rope};
+
+/// The next revision number to hand out.
+///
+/// Revision numbers are drawn from a single global counter, so no two
+/// revisions are ever equal, even if they were made by editing the same
+/// `VersionedRope` in two different ways.
+static NEXT_REVISION: AtomicUsize = AtomicUsize::new(0);
+
+#[inline]
+fn next_revision() -> usize {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// An edit in the history of a `VersionedRope`.
+#[derive(Debug)]
+struct Change { /// The revision which was edited.
+                from: usize
+              , /// The edit, or `None` if it was made with `apply`.
+                edit: Option<EditInfo>
+              , /// The change which produced the revision which was edited.
+                parent: Option<Arc<Change>>
+              }
+
+impl Drop for Change {
+    fn drop(&mut self) {
+        // unlink the history iteratively, since dropping a long chain of
+        // changes recursively could overflow the stack
+        let mut next = self.parent.take();
+        while let Some(change) = next {
+            next = match Arc::try_unwrap(change) {
+                Ok(mut change) => change.parent.take()
+              , Err(_) => None
+            };
+        }
+    }
+}
+
+/// A place in the text of a `VersionedRope`, at a particular revision.
+///
+/// A `Position` is a byte offset together with the revision it is an offset
+/// into. [`VersionedRope::resolve`] maps it to the offset of the same place
+/// in a later revision, by replaying the edits made in between.
+///
+/// [`VersionedRope::resolve`]: struct.VersionedRope.html#method.resolve
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Position { revision: usize
+                    , offset: usize
+                    }
+
+impl Position {
+    /// Returns the revision this `Position` was taken at.
+    #[inline] pub fn revision(&self) -> usize { self.revision }
+
+    /// Returns the byte offset of this `Position` in the revision it was
+    /// taken at.
+    #[inline] pub fn offset(&self) -> usize { self.offset }
+}
+
+/// Returns the offset `offset` is moved to by `edit`.
+///
+/// Offsets before the edited range are unchanged, and those after it are
+/// shifted by the change in length. Offsets within the edited range, or at
+/// either end of it, move to the end of the new text, so a `Position` at an
+/// insertion point stays after the inserted text.
+#[inline]
+fn map_offset(edit: &EditInfo, offset: usize) -> usize {
+    let old = edit.old_range();
+    if offset < old.start { offset }
+    else if offset <= old.end { edit.new_range().end }
+    else { (offset as isize + edit.delta()) as usize }
+}
+
+/// A `Rope` with a revision number that changes on every edit.
+///
+/// Like `Rope`, `VersionedRope` is persistent: each edit returns a new
+/// `VersionedRope`, with a new revision, and leaves the original unchanged.
+/// Revisions only ever increase, and are never reused.
+///
+/// A `VersionedRope` dereferences to its `Rope`, so all of `Rope`'s
+/// non-editing methods may be called on it directly.
+///
+/// # Examples
+///
+/// ```
+/// use an_rope::{Rope, VersionedRope};
+/// let buffer = VersionedRope::new(Rope::from("hello"));
+/// let linted_at = buffer.revision();
+///
+/// let buffer = buffer.insert_str(5, " world");
+/// assert!(buffer.revision() > linted_at);
+/// assert_eq!(&*buffer, "hello world");
+/// ```
+#[derive(Clone, Debug)]
+pub struct VersionedRope { rope: Rope
+                         , revision: usize
+                         , history: Option<Arc<Change>>
+                         }
+
+impl VersionedRope {
+    /// Returns a new `VersionedRope` holding `rope`, at a new revision.
+    #[inline]
+    pub fn new(rope: Rope) -> Self {
+        VersionedRope { rope: rope, revision: next_revision(), history: None }
+    }
+
+    /// Returns a new `VersionedRope` holding `rope`, which was produced from
+    /// this one by `edit`, at a new revision.
+    fn edited(&self, rope: Rope, edit: Option<EditInfo>) -> Self {
+        let change = Change { from: self.revision
+                            , edit: edit
+                            , parent: self.history.clone()
+                            };
+        VersionedRope { rope: rope
+                      , revision: next_revision()
+                      , history: Some(Arc::new(change))
+                      }
+    }
+
+    /// Returns the revision number of this `VersionedRope`.
+    #[inline] pub fn revision(&self) -> usize { self.revision }
+
+    /// Returns the `Rope` held by this `VersionedRope`.
+    #[inline] pub fn rope(&self) -> &Rope { &self.rope }
+
+    /// Returns the `Rope` held by this `VersionedRope`, consuming it.
+    #[inline] pub fn into_rope(self) -> Rope { self.rope }
+
+    /// Returns a new `VersionedRope` holding the result of applying `f` to
+    /// this `VersionedRope`'s `Rope`, at a new revision.
+    ///
+    /// This can be used to make any edit which does not have a method of its
+    /// own on `VersionedRope`. Since it is not known which text `f` changed,
+    /// `Position`s taken before it cannot be resolved after it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use an_rope::{Rope, VersionedRope};
+    /// let buffer = VersionedRope::new(Rope::from("a\tb"));
+    /// let expanded = buffer.apply(|rope| rope.expand_tabs(4));
+    /// assert_eq!(&*expanded, "a   b");
+    /// assert!(expanded.revision() > buffer.revision());
+    /// ```
+    #[inline]
+    pub fn apply<F>(&self, f: F) -> Self
+    where F: FnOnce(&Rope) -> Rope {
+        self.edited(f(&self.rope), None)
+    }
+
+    /// Insert `s` at byte index `index`, returning a new `VersionedRope`.
+    ///
+    /// # Panics
+    /// If `index` is greater than the length of the `Rope`.
+    #[inline]
+    pub fn insert_str(&self, index: usize, s: &str) -> Self {
+        self.edit(index..index, s)
+    }
+
+    /// Delete the byte range `range`, returning a new `VersionedRope`.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than the end, or if the end of
+    /// `range` is greater than the length of the `Rope`.
+    #[inline]
+    pub fn delete(&self, range: ops::Range<usize>) -> Self {
+        self.edit(range, "")
+    }
+
+    /// Replace the byte range `range` with `replacement`, returning a new
+    /// `VersionedRope`.
+    ///
+    /// # Panics
+    /// If the start of `range` is greater than the end, or if the end of
+    /// `range` is greater than the length of the `Rope`.
+    #[inline]
+    pub fn edit(&self, range: ops::Range<usize>, replacement: &str) -> Self {
+        let rope = self.rope.edit(range.clone(), replacement);
+        self.edited(rope, Some(EditInfo::new(range, replacement.len())))
+    }
+
+    /// Append `other` to the end of the `Rope`, returning a new
+    /// `VersionedRope`.
+    #[inline]
+    pub fn append(&self, other: &Rope) -> Self {
+        let len = self.rope.len();
+        let edit = EditInfo::new(len..len, other.len());
+        self.edited(self.rope.append(other), Some(edit))
+    }
+
+    /// Returns a `Position` at byte index `offset` of this revision.
+    ///
+    /// # Panics
+    /// If `offset` is greater than the length of the `Rope`.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, VersionedRope};
+    /// let buffer = VersionedRope::new(Rope::from("fn main() {}"));
+    /// let name = buffer.position(3);
+    /// let buffer = buffer.insert_str(0, "pub ");
+    /// let buffer = buffer.edit(11..11, "_x");
+    /// assert_eq!(buffer.resolve(&name), Some(7));
+    /// ```
+    #[inline]
+    pub fn position(&self, offset: usize) -> Position {
+        assert!( offset <= self.rope.len()
+               , "VersionedRope::position: offset {} was > length {}"
+               , offset, self.rope.len());
+        Position { revision: self.revision, offset: offset }
+    }
+
+    /// Returns the byte offset in this revision of the place `position`
+    /// marks, by replaying the edits made since the revision it was taken
+    /// at.
+    ///
+    /// Text inserted at a `Position` goes before it, and a `Position`
+    /// within text which was deleted or replaced moves to the end of the
+    /// replacement.
+    ///
+    /// # Returns
+    /// - `Some` with the resolved offset
+    /// - `None` if this `VersionedRope` was not produced from the revision
+    ///   `position` was taken at, or if it was produced by an edit made
+    ///   with `apply`
+    ///
+    /// # Time complexity
+    /// O(_k_) in the number of edits made since `position` was taken. Since
+    /// revisions only increase, the search gives up as soon as it passes
+    /// `position`'s revision, so resolving a `Position` from an unrelated
+    /// revision doesn't walk the whole history.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, VersionedRope};
+    /// let buffer = VersionedRope::new(Rope::from("abc"));
+    /// let end = buffer.position(3);
+    /// let edited = buffer.delete(0..1).insert_str(2, "d");
+    /// assert_eq!(edited.resolve(&end), Some(3));
+    ///
+    /// // a sibling revision does not descend from `edited`
+    /// let other = buffer.insert_str(0, "z");
+    /// assert_eq!(other.resolve(&edited.position(0)), None);
+    /// ```
+    pub fn resolve(&self, position: &Position) -> Option<usize> {
+        let mut edits = Vec::new();
+        let mut revision = self.revision;
+        let mut history = self.history.as_ref();
+        while revision != position.revision {
+            if revision < position.revision { return None }
+            let change = match history {
+                Some(change) => change
+              , None => return None
+            };
+            match change.edit {
+                Some(ref edit) => edits.push(edit)
+              , None => return None
+            }
+            revision = change.from;
+            history = change.parent.as_ref();
+        }
+        Some(edits.iter().rev().fold(position.offset, |offset, edit|
+            map_offset(edit, offset)))
+    }
+
+    /// Returns a `VersionedRope` with the same `Rope` and revision as this
+    /// one, which only remembers the edits made since `revision`.
+    ///
+    /// The history of a `VersionedRope` otherwise grows with every edit made
+    /// to it, however old. After this, `Position`s taken at `revision` or
+    /// later still resolve, but those taken before it resolve to `None`.
+    /// This `VersionedRope` is unchanged, and the memory of the forgotten
+    /// edits is freed once nothing else refers to them.
+    ///
+    /// # Time complexity
+    /// O(_k_) in the number of edits made since `revision`, which are copied.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::{Rope, VersionedRope};
+    /// let buffer = VersionedRope::new(Rope::from("abc"));
+    /// let old = buffer.position(1);
+    /// let buffer = buffer.insert_str(0, "x");
+    /// let new = buffer.position(1);
+    /// let buffer = buffer.insert_str(0, "y").forget_before(new.revision());
+    /// assert_eq!(buffer.resolve(&new), Some(2));
+    /// assert_eq!(buffer.resolve(&old), None);
+    /// ```
+    pub fn forget_before(&self, revision: usize) -> Self {
+        // the changes made from `revision` on, newest first
+        let mut kept = Vec::new();
+        let mut history = self.history.as_ref();
+        while let Some(change) = history {
+            if change.from < revision { break }
+            kept.push((change.from, change.edit.clone()));
+            history = change.parent.as_ref();
+        }
+        // if nothing is forgotten, the whole history can be shared
+        if history.is_none() { return self.clone() }
+        let history = kept.into_iter().rev().fold(None, |parent, (from, edit)|
+            Some(Arc::new(Change { from: from, edit: edit, parent: parent })));
+        VersionedRope { rope: self.rope.clone()
+                      , revision: self.revision
+                      , history: history
+                      }
+    }
+}
+
+impl Default for VersionedRope {
+    #[inline] fn default() -> Self { VersionedRope::new(Rope::new()) }
+}
+
+impl convert::From<Rope> for VersionedRope {
+    #[inline] fn from(rope: Rope) -> Self { VersionedRope::new(rope) }
+}
+
+impl ops::Deref for VersionedRope {
+    type Target = Rope;
+    #[inline] fn deref(&self) -> &Rope { &self.rope }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use super::VersionedRope;
+
+    #[test]
+    fn revisions_are_never_reused() {
+        let base = VersionedRope::from(Rope::from("abc"));
+        let a = base.insert_str(0, "x");
+        let b = base.insert_str(0, "x");
+        assert_eq!(a.rope(), b.rope());
+        assert!(a.revision() != b.revision());
+        assert!(a.revision() > base.revision());
+        assert!(b.revision() > base.revision());
+    }
+
+    #[test]
+    fn positions_follow_edits() {
+        let base = VersionedRope::from(Rope::from("one two three"));
+        let (two, three) = (base.position(4), base.position(8));
+        let edited = base.edit(4..7, "2")
+                         .insert_str(0, ">> ")
+                         .delete(9..11)
+                         .append(&Rope::from("!"));
+        assert_eq!(&*edited, ">> one 2 ree!");
+        assert_eq!(edited.resolve(&two), Some(8));
+        assert_eq!(edited.resolve(&three), Some(9));
+        assert_eq!(edited.resolve(&edited.position(13)), Some(13));
+        assert_eq!(base.resolve(&edited.position(0)), None);
+        let applied = edited.apply(|rope| rope.clone());
+        assert_eq!(applied.resolve(&two), None);
+        assert_eq!(applied.insert_str(0, "x").resolve(&applied.position(1))
+                  , Some(2));
+    }
+
+    #[test]
+    fn long_histories_drop() {
+        let mut buffer = VersionedRope::default();
+        for _ in 0..200_000 { buffer = buffer.insert_str(0, ""); }
+        let start = buffer.position(0);
+        assert_eq!(buffer.insert_str(0, "a").resolve(&start), Some(1));
+    }
+
+    #[test]
+    fn forgotten_positions_do_not_resolve() {
+        let base = VersionedRope::from(Rope::from("abc"));
+        let first = base.position(3);
+        let edited = base.insert_str(0, "x");
+        let second = edited.position(4);
+        let edited = edited.insert_str(0, "y").delete(0..1);
+        let forgotten = edited.forget_before(second.revision());
+        assert_eq!(forgotten.revision(), edited.revision());
+        assert_eq!(forgotten.rope(), edited.rope());
+        assert_eq!(forgotten.resolve(&second), Some(4));
+        assert_eq!(forgotten.resolve(&first), None);
+        assert_eq!(edited.resolve(&first), Some(4));
+        // later edits still record their history
+        let later = forgotten.insert_str(4, "!");
+        assert_eq!(later.resolve(&second), Some(5));
+        assert_eq!(later.resolve(&forgotten.position(0)), Some(0));
+        // forgetting revisions before any edit keeps everything
+        assert_eq!(edited.forget_before(0).resolve(&first), Some(4));
+    }
+
+    #[test]
+    fn resolve_stops_at_older_revisions() {
+        let mut buffer = VersionedRope::default();
+        for _ in 0..200_000 { buffer = buffer.insert_str(0, ""); }
+        let newer = VersionedRope::default().position(0);
+        assert_eq!(buffer.resolve(&newer), None);
+    }
+
+    #[test]
+    fn clones_share_revision() {
+        let base = VersionedRope::default();
+        let clone = base.clone();
+        assert_eq!(base.revision(), clone.revision());
+        assert!(base.edit(0..0, "a").revision() != clone.revision());
+    }
+}
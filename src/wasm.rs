@@ -0,0 +1,213 @@
+//! Helpers for using `Rope`s from JavaScript, via [`wasm-bindgen`].
+//!
+//! When built with the `wasm` feature, `Rope`s may be converted to and from
+//! [`js_sys::JsString`]s, and offer methods for converting between the byte
+//! offsets used by `Rope` and the UTF-16 code unit offsets used by
+//! JavaScript strings, and so by web editors such as CodeMirror and Monaco.
+//!
+//! With this feature, each node of a `Rope`'s tree caches the length of its
+//! text in UTF-16 code units, so these conversions descend the tree to the
+//! leaf containing the offset rather than counting from the start.
+//!
+//! [`wasm-bindgen`]: https://docs.rs/crate/wasm-bindgen
+//! [`js_sys::JsString`]: https://docs.rs/js-sys/*/js_sys/struct.JsString.html
+
+use js_sys::JsString;
+
+use std::convert;
+
+use super::Rope;
+use super::internals::{Node, NodeLink, Value};
+
+/// Returns the leaf of `node` containing the position after `i` UTF-16 code
+/// units, with the byte index and UTF-16 offset of its start.
+fn utf16_leaf(node: &Node, i: usize) -> (&str, usize, usize) {
+    let (mut node, mut bytes, mut utf16) = (node, 0, 0);
+    loop {
+        match node.value {
+            Value::Leaf(ref s) => return (s.as_ref(), bytes, utf16)
+          , Value::Branch { ref left, ref right } => {
+                let left_utf16 = left.len_utf16();
+                if i - utf16 < left_utf16 {
+                    node = left;
+                } else {
+                    bytes += left.len();
+                    utf16 += left_utf16;
+                    node = right;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the leaf of `node` containing the byte at index `i`, with the
+/// byte index and UTF-16 offset of its start.
+fn byte_leaf(node: &Node, i: usize) -> (&str, usize, usize) {
+    let (mut node, mut bytes, mut utf16) = (node, 0, 0);
+    loop {
+        match node.value {
+            Value::Leaf(ref s) => return (s.as_ref(), bytes, utf16)
+          , Value::Branch { ref left, ref right } => {
+                if i - bytes < left.len() {
+                    node = left;
+                } else {
+                    bytes += left.len();
+                    utf16 += left.len_utf16();
+                    node = right;
+                }
+            }
+        }
+    }
+}
+
+impl Rope {
+    /// Returns the length of this `Rope` in UTF-16 code units.
+    ///
+    /// This is the `length` of the equivalent JavaScript string.
+    ///
+    /// # Time complexity
+    /// O(1), once the length has been cached; O(_n_) in the number of nodes
+    /// whose length has not been cached, after an edit.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\u{1F600}b");
+    /// assert_eq!(rope.len_utf16(), 4);
+    /// ```
+    #[inline]
+    pub fn len_utf16(&self) -> usize { self.root.len_utf16() }
+
+    /// Converts the UTF-16 code unit offset `i` into a byte index in this
+    /// `Rope`.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope` in UTF-16 code
+    /// units, or if `i` falls between the two halves of a surrogate pair.
+    ///
+    /// # Time complexity
+    /// O(log _n_) to find the leaf containing `i`, plus the length of that
+    /// leaf, once the UTF-16 lengths of the nodes have been cached.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\u{1F600}b");
+    /// assert_eq!(rope.utf16_to_byte_index(3), 5);
+    /// assert_eq!(rope.utf16_to_byte_index(4), 6);
+    /// ```
+    pub fn utf16_to_byte_index(&self, i: usize) -> usize {
+        let len = self.len_utf16();
+        assert!( i <= len
+               , "Rope::utf16_to_byte_index: index {} was > length {}"
+               , i, len);
+        let (leaf, mut bytes, mut utf16) = utf16_leaf(&self.root, i);
+        for c in leaf.chars() {
+            if utf16 >= i { break }
+            utf16 += c.len_utf16();
+            bytes += c.len_utf8();
+        }
+        assert!( utf16 == i
+               , "Rope::utf16_to_byte_index: index {} is not a char boundary"
+               , i);
+        bytes
+    }
+
+    /// Converts the byte index `i` in this `Rope` into a UTF-16 code unit
+    /// offset.
+    ///
+    /// # Panics
+    /// If `i` is greater than the length of this `Rope`, or if `i` does not
+    /// fall on a character boundary.
+    ///
+    /// # Time complexity
+    /// O(log _n_) to find the leaf containing `i`, plus the length of that
+    /// leaf, once the UTF-16 lengths of the nodes have been cached.
+    ///
+    /// # Examples
+    /// ```
+    /// use an_rope::Rope;
+    /// let rope = Rope::from("a\u{1F600}b");
+    /// assert_eq!(rope.byte_to_utf16_index(5), 3);
+    /// ```
+    pub fn byte_to_utf16_index(&self, i: usize) -> usize {
+        assert!( i <= self.len()
+               , "Rope::byte_to_utf16_index: index {} was > length {}"
+               , i, self.len());
+        let (leaf, mut bytes, mut utf16) = byte_leaf(&self.root, i);
+        for c in leaf.chars() {
+            if bytes >= i { break }
+            utf16 += c.len_utf16();
+            bytes += c.len_utf8();
+        }
+        assert!( bytes == i
+               , "Rope::byte_to_utf16_index: index {} is not a char boundary"
+               , i);
+        utf16
+    }
+}
+
+impl convert::From<JsString> for NodeLink {
+    #[inline] fn from(that: JsString) -> Self {
+        NodeLink::from(String::from(that))
+    }
+}
+
+impl<'a> convert::From<&'a Rope> for JsString {
+    fn from(that: &'a Rope) -> Self {
+        that.strings().fold(JsString::from(""), |acc, s| acc.concat(&s.into()))
+    }
+}
+
+impl convert::From<Rope> for JsString {
+    #[inline] fn from(that: Rope) -> Self { JsString::from(&that) }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::Rope;
+    use ::test::leaves;
+
+    #[test]
+    fn utf16_offsets_across_leaves() {
+        let rope = Rope::from("\u{3c0}\u{1F600}") + Rope::from("x\u{1F600}");
+        assert_eq!(rope.len_utf16(), 6);
+        let offsets = [(0, 0), (1, 2), (3, 6), (4, 7), (6, 11)];
+        for &(utf16, byte) in offsets.iter() {
+            assert_eq!(rope.utf16_to_byte_index(utf16), byte);
+            assert_eq!(rope.byte_to_utf16_index(byte), utf16);
+        }
+    }
+
+    #[test]
+    fn utf16_offsets_descend_to_leaf() {
+        let rope = leaves(&[ "ab", "\u{1F600}", "\u{3c0}c", ""
+                           , "\u{1F600}\u{1F600}", "d", "\u{e9}\u{1F600}e"]);
+        let s = rope.to_string();
+        let mut utf16 = 0;
+        for (byte, c) in s.char_indices() {
+            assert_eq!(rope.utf16_to_byte_index(utf16), byte);
+            assert_eq!(rope.byte_to_utf16_index(byte), utf16);
+            utf16 += c.len_utf16();
+        }
+        assert_eq!(rope.len_utf16(), utf16);
+        assert_eq!(rope.utf16_to_byte_index(utf16), s.len());
+        assert_eq!(rope.byte_to_utf16_index(s.len()), utf16);
+        // lengths are cached, and recomputed for the nodes an edit creates
+        let edited = rope.insert_str(2, "\u{1F600}");
+        assert_eq!(edited.len_utf16(), utf16 + 2);
+        assert_eq!(edited.byte_to_utf16_index(6), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 is not a char boundary")]
+    fn utf16_inside_surrogate_pair() {
+        Rope::from("a\u{1F600}").utf16_to_byte_index(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 5 was > length 4")]
+    fn utf16_past_end() {
+        Rope::from("a\u{1F600}b").utf16_to_byte_index(5);
+    }
+}